@@ -0,0 +1,211 @@
+use crate::{emergency::country_name, error::TravelError, utils::Date};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use serde::{Deserialize, Serialize};
+
+/// UK FCDO travel advice through the GOV.UK content API. Free and keyless, unlike the MEA
+/// advisories which are only published as web pages.
+const FCDO_ADVICE_URL: &str = "https://www.gov.uk/api/content/foreign-travel-advice";
+const MAX_SUMMARY_LEN: usize = 600;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafetyLevel {
+    Normal,
+    ExerciseCaution,
+    ReconsiderTravel,
+    DoNotTravel,
+}
+
+impl SafetyLevel {
+    /// Maps FCDO alert statuses such as `avoid_all_travel_to_parts`. The most severe wins.
+    fn from_alert_statuses(statuses: &[String]) -> SafetyLevel {
+        statuses
+            .iter()
+            .map(|status| match status.as_str() {
+                "avoid_all_travel_to_whole_country" => SafetyLevel::DoNotTravel,
+                "avoid_all_travel_to_parts" | "avoid_all_but_essential_travel_to_whole_country" => {
+                    SafetyLevel::ReconsiderTravel
+                }
+                _ => SafetyLevel::ExerciseCaution,
+            })
+            .max_by_key(|level| *level as u8)
+            .unwrap_or(SafetyLevel::Normal)
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SafetyAdvisory {
+    pub country: String,
+    pub level: SafetyLevel,
+    pub summary: String,
+    pub last_updated: Date,
+}
+
+#[derive(Deserialize)]
+struct FcdoAdvice {
+    title: String,
+    public_updated_at: String,
+    details: FcdoDetails,
+}
+
+#[derive(Deserialize)]
+struct FcdoDetails {
+    #[serde(default)]
+    alert_status: Vec<String>,
+    #[serde(default)]
+    summary: String,
+}
+
+/// GOV.UK path of a country, e.g. `sri-lanka`. A few countries go by a short name.
+fn fcdo_slug(country: &str) -> String {
+    match country {
+        "United States" => "usa".to_string(),
+        _ => country
+            .to_lowercase()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-"),
+    }
+}
+
+/// Text of an HTML summary, cut at a word near `MAX_SUMMARY_LEN`.
+fn plain_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.len() <= MAX_SUMMARY_LEN {
+        return text;
+    }
+    let cut = text[..text.floor_char_boundary(MAX_SUMMARY_LEN)]
+        .rsplit_once(' ')
+        .map_or(MAX_SUMMARY_LEN, |(kept, _)| kept.len());
+    format!("{}…", &text[..cut])
+}
+
+fn to_advisory(advice: FcdoAdvice) -> Result<SafetyAdvisory, TravelError> {
+    let updated = advice.public_updated_at.get(..10).unwrap_or_default();
+    Ok(SafetyAdvisory {
+        country: advice.title.trim_end_matches(" travel advice").to_string(),
+        level: SafetyLevel::from_alert_statuses(&advice.details.alert_status),
+        summary: plain_text(&advice.details.summary),
+        last_updated: Date::from_yyyy_mm_dd(updated)
+            .map_err(|e| TravelError::Api(format!("Invalid advisory date {updated}: {e}")))?,
+    })
+}
+
+pub async fn safety_advisory(country: &str) -> Result<SafetyAdvisory, TravelError> {
+    let name = country_name(country).unwrap_or(country.trim());
+    if name == "India" {
+        return Err(TravelError::InvalidInput(
+            "Advisories are only for travel abroad".into(),
+        ));
+    }
+    let slug = fcdo_slug(name);
+    if slug.is_empty() || !slug.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(TravelError::InvalidInput(format!(
+            "Invalid country: {country}"
+        )));
+    }
+
+    let resp = reqwest::get(format!("{FCDO_ADVICE_URL}/{slug}"))
+        .await
+        .map_err(|e| TravelError::Api(format!("FCDO advice request failed: {e}")))?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(TravelError::InvalidInput(format!(
+            "No travel advice found for {country}"
+        )));
+    }
+    if !resp.status().is_success() {
+        return Err(TravelError::Api(format!(
+            "FCDO advice error: {}",
+            resp.status()
+        )));
+    }
+    let advice: FcdoAdvice = resp
+        .json()
+        .await
+        .map_err(|e| TravelError::Api(format!("Invalid FCDO advice response: {e}")))?;
+    to_advisory(advice)
+}
+
+#[gemini_function]
+///Get the official travel safety advisory of a country. Call it for every international destination.
+pub async fn destination_safety_advisory(
+    ///Country of the destination (e.g., 'Thailand')
+    country: String,
+) -> Result<SafetyAdvisory, TravelError> {
+    safety_advisory(&country).await
+}
+
+#[test]
+fn safety_level_test() {
+    let level = |statuses: &[&str]| {
+        SafetyLevel::from_alert_statuses(
+            &statuses.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(level(&[]), SafetyLevel::Normal);
+    assert_eq!(
+        level(&["avoid_all_but_essential_travel_to_parts"]),
+        SafetyLevel::ExerciseCaution
+    );
+    assert_eq!(
+        level(&[
+            "avoid_all_but_essential_travel_to_parts",
+            "avoid_all_travel_to_parts"
+        ]),
+        SafetyLevel::ReconsiderTravel
+    );
+    assert_eq!(
+        level(&["avoid_all_but_essential_travel_to_whole_country"]),
+        SafetyLevel::ReconsiderTravel
+    );
+    assert_eq!(
+        level(&[
+            "avoid_all_travel_to_whole_country",
+            "avoid_all_travel_to_parts"
+        ]),
+        SafetyLevel::DoNotTravel
+    );
+
+    assert_eq!(fcdo_slug("Sri Lanka"), "sri-lanka");
+    assert_eq!(fcdo_slug("United Arab Emirates"), "united-arab-emirates");
+    assert_eq!(fcdo_slug("United States"), "usa");
+}
+
+#[test]
+fn to_advisory_test() {
+    let advice: FcdoAdvice = serde_json::from_value(serde_json::json!({
+        "base_path": "/foreign-travel-advice/thailand",
+        "title": "Thailand travel advice",
+        "public_updated_at": "2026-09-30T14:05:12.000+01:00",
+        "details": {
+            "alert_status": ["avoid_all_but_essential_travel_to_parts"],
+            "summary": "<p>FCDO advises against all but essential travel to:</p>\n<ul>\n<li>the provinces of Pattani, Yala and Narathiwat</li>\n</ul>",
+            "parts": []
+        }
+    }))
+    .unwrap();
+    let advisory = to_advisory(advice).unwrap();
+    assert_eq!(advisory.country, "Thailand");
+    assert_eq!(advisory.level, SafetyLevel::ExerciseCaution);
+    assert_eq!(
+        advisory.summary,
+        "FCDO advises against all but essential travel to: the provinces of Pattani, Yala and Narathiwat"
+    );
+    assert_eq!(advisory.last_updated, Date::new(2026, 9, 30).unwrap());
+
+    let long = plain_text(&format!("<p>{}</p>", "Stay alert. ".repeat(100)));
+    assert!(long.len() <= MAX_SUMMARY_LEN + "…".len());
+    assert!(long.ends_with("alert.…"));
+}
@@ -0,0 +1,282 @@
+use crate::{
+    api_requests::rapidapi::{ApiClient, RapidAPIClient},
+    error::TravelError,
+    utils::{Currency, Date, Time, sanitize_query_param},
+};
+use chrono::{NaiveTime, Timelike};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
+use serde::{Deserialize, Serialize};
+
+const REDBUS_HOST: &str = "redbus.p.rapidapi.com";
+const MAX_CITY_LEN: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[gemini_schema]
+pub enum BusType {
+    Sleeper,
+    Seater,
+    SemiSleeper,
+    ///Premium air conditioned multi-axle coach
+    Volvo,
+}
+
+/// Reads RedBus bus type names like `Volvo Multi-Axle A/C Semi Sleeper (2+2)`. A Volvo is
+/// taken as `Volvo` whatever its seats, and anything without berths as `Seater`.
+impl From<&str> for BusType {
+    fn from(bus_type: &str) -> Self {
+        let bus_type = bus_type.to_ascii_lowercase().replace('-', " ");
+        if bus_type.contains("volvo") {
+            BusType::Volvo
+        } else if bus_type.contains("semi sleeper") {
+            BusType::SemiSleeper
+        } else if bus_type.contains("sleeper") {
+            BusType::Sleeper
+        } else {
+            BusType::Seater
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bus {
+    pub operator_name: String,
+    pub bus_type: BusType,
+    pub departure: Time,
+    ///Next day when earlier than `departure`
+    pub arrival: Time,
+    pub duration_minutes: u32,
+    pub fare: Currency,
+    pub available_seats: u8,
+    pub amenities: Vec<String>,
+    ///Out of 5, 0 when the operator isn't rated yet
+    pub rating: f32,
+}
+
+#[derive(Deserialize)]
+struct BusSearchResponse {
+    data: Vec<RedBusTrip>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RedBusTrip {
+    travels: String,
+    bus_type: String,
+    departure_time: String,
+    arrival_time: String,
+    ///Minutes
+    #[serde(default)]
+    duration: Option<u32>,
+    fare: f32,
+    #[serde(default)]
+    available_seats: u32,
+    #[serde(default)]
+    amenities: Vec<String>,
+    #[serde(default)]
+    rating: f32,
+}
+
+/// Times are sent as `20:30`, sometimes with seconds.
+fn parse_time(time: &str) -> Option<Time> {
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time.trim(), "%H:%M:%S"))
+        .ok()?;
+    Time::new(time.hour() as u8, time.minute() as u8, time.second() as u8).ok()
+}
+
+fn minutes_between(departure: &Time, arrival: &Time) -> u32 {
+    let minutes = |time: &Time| time.hour() as u32 * 60 + time.minute() as u32;
+    (minutes(arrival) + 24 * 60 - minutes(departure)) % (24 * 60)
+}
+
+impl TryFrom<RedBusTrip> for Bus {
+    type Error = TravelError;
+
+    fn try_from(trip: RedBusTrip) -> Result<Self, TravelError> {
+        let time = |time: &str| {
+            parse_time(time)
+                .ok_or_else(|| TravelError::ParseError(format!("Invalid bus time: {time}")))
+        };
+        let departure = time(&trip.departure_time)?;
+        let arrival = time(&trip.arrival_time)?;
+        Ok(Bus {
+            operator_name: trip.travels,
+            bus_type: BusType::from(trip.bus_type.as_str()),
+            duration_minutes: trip
+                .duration
+                .unwrap_or_else(|| minutes_between(&departure, &arrival)),
+            departure,
+            arrival,
+            fare: Currency::Inr(trip.fare),
+            available_seats: trip.available_seats.min(u8::MAX as u32) as u8,
+            amenities: trip.amenities,
+            rating: trip.rating,
+        })
+    }
+}
+
+async fn search_buses(
+    client: &impl ApiClient,
+    source: &str,
+    destination: &str,
+    date: &Date,
+) -> Result<Vec<Bus>, TravelError> {
+    if source.trim().is_empty() || destination.trim().is_empty() {
+        return Err(TravelError::InvalidInput(
+            "Source and destination cities are required".into(),
+        ));
+    }
+    let path = format!(
+        "/api/v1/searchBuses?fromCity={}&toCity={}&date={}",
+        sanitize_query_param(source.trim(), MAX_CITY_LEN)?,
+        sanitize_query_param(destination.trim(), MAX_CITY_LEN)?,
+        date.to_yyyy_mm_dd()
+    );
+    let body: BusSearchResponse = client.get(&path).await?;
+    // One trip with an unreadable time shouldn't hide the other buses
+    let mut buses: Vec<Bus> = body
+        .data
+        .into_iter()
+        .filter_map(|trip| {
+            Bus::try_from(trip)
+                .inspect_err(|e| eprintln!("ERROR: Skipping RedBus trip:\n{e}"))
+                .ok()
+        })
+        .collect();
+    buses.sort_by(|a, b| a.departure.cmp(&b.departure));
+    Ok(buses)
+}
+
+/// Intercity buses on RedBus, sorted by departure time.
+pub async fn buses_between(
+    source: &str,
+    destination: &str,
+    date: Date,
+) -> Result<Vec<Bus>, TravelError> {
+    let client = RapidAPIClient::for_host(REDBUS_HOST)?;
+    search_buses(&client, source, destination, &date).await
+}
+
+#[gemini_function]
+///Search for intercity buses between two cities on a date, sorted by departure time. Use it for places without an airport or railway station nearby, e.g. Manali, Spiti Valley or Coorg, and for short trips.
+pub async fn buses_between_cities(
+    ///Name of the city to board in (e.g., 'Delhi')
+    source: String,
+    ///Name of the city to get off in (e.g., 'Manali')
+    destination: String,
+    date: Date,
+) -> Result<Vec<Bus>, TravelError> {
+    buses_between(&source, &destination, date).await
+}
+
+#[test]
+fn bus_type_test() {
+    assert_eq!(
+        BusType::from("Volvo Multi-Axle A/C Semi Sleeper (2+2)"),
+        BusType::Volvo
+    );
+    assert_eq!(
+        BusType::from("Bharat Benz A/C Semi-Sleeper (2+2)"),
+        BusType::SemiSleeper
+    );
+    assert_eq!(BusType::from("A/C Sleeper (2+1)"), BusType::Sleeper);
+    assert_eq!(BusType::from("Non A/C Seater (2+3)"), BusType::Seater);
+    assert_eq!(BusType::from(""), BusType::Seater);
+}
+
+#[tokio::test]
+async fn buses_between_test() {
+    use crate::api_requests::rapidapi::MockApiClient;
+    use serde_json::json;
+
+    let client = MockApiClient::new([(
+        "/api/v1/searchBuses",
+        json!({"status": true, "data": [
+            {
+                "travels": "Zingbus",
+                "busType": "Volvo Multi-Axle A/C Semi Sleeper (2+2)",
+                "departureTime": "21:15",
+                "arrivalTime": "09:45",
+                "duration": 750,
+                "fare": 1299,
+                "availableSeats": 14,
+                "amenities": ["WiFi", "Charging Point", "Blankets"],
+                "rating": 4.4
+            },
+            {
+                "travels": "HRTC",
+                "busType": "Non A/C Seater (2+3)",
+                "departureTime": "18:30:00",
+                "arrivalTime": "07:00",
+                "fare": 780.5,
+                "availableSeats": 400
+            }
+        ]}),
+    )]);
+    let date = Date::new(2026, 5, 1).unwrap();
+    let buses = search_buses(&client, "New Delhi", "Manali", &date)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.requests.lock().unwrap()[0],
+        "/api/v1/searchBuses?fromCity=New%20Delhi&toCity=Manali&date=2026-05-01"
+    );
+
+    assert_eq!(buses.len(), 2);
+    let hrtc = &buses[0];
+    assert_eq!(hrtc.operator_name, "HRTC");
+    assert_eq!(hrtc.bus_type, BusType::Seater);
+    assert_eq!(hrtc.departure, Time::new(18, 30, 0).unwrap());
+    // Overnight, so the duration is counted into the next day
+    assert_eq!(hrtc.duration_minutes, 12 * 60 + 30);
+    assert_eq!(hrtc.fare.to_amount(), 780.5);
+    assert_eq!(hrtc.available_seats, u8::MAX);
+    assert!(hrtc.amenities.is_empty());
+    assert_eq!(hrtc.rating, 0.0);
+
+    let zingbus = &buses[1];
+    assert_eq!(zingbus.bus_type, BusType::Volvo);
+    assert_eq!(zingbus.arrival, Time::new(9, 45, 0).unwrap());
+    assert_eq!(zingbus.duration_minutes, 750);
+    assert_eq!(zingbus.fare.currency_code(), "INR");
+    assert_eq!(zingbus.available_seats, 14);
+    assert_eq!(zingbus.amenities, ["WiFi", "Charging Point", "Blankets"]);
+
+    assert!(matches!(
+        search_buses(&client, " ", "Manali", &date).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+}
+
+#[tokio::test]
+async fn invalid_bus_time_test() {
+    use crate::api_requests::rapidapi::MockApiClient;
+    use serde_json::json;
+
+    let client = MockApiClient::new([(
+        "/api/v1/searchBuses",
+        json!({"status": true, "data": [
+            {
+                "travels": "Zingbus",
+                "busType": "A/C Sleeper (2+1)",
+                "departureTime": "9 PM",
+                "arrivalTime": "09:45",
+                "fare": 1299
+            },
+            {
+                "travels": "HRTC",
+                "busType": "Non A/C Seater (2+3)",
+                "departureTime": "18:30",
+                "arrivalTime": "07:00",
+                "fare": 780.5
+            }
+        ]}),
+    )]);
+    let date = Date::new(2026, 5, 1).unwrap();
+    // The trip with the unreadable time is left out
+    let buses = search_buses(&client, "Delhi", "Manali", &date)
+        .await
+        .unwrap();
+    assert_eq!(buses.len(), 1);
+    assert_eq!(buses[0].operator_name, "HRTC");
+}
@@ -0,0 +1,242 @@
+use crate::{config::CONFIG, emergency::local_currency, error::TravelError, utils::Currency};
+use gemini_client_api::futures::future::try_join;
+use gemini_client_api::gemini::{
+    ask::Gemini,
+    types::sessions::Session,
+    utils::{GeminiSchema, gemini_function, gemini_schema},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Free and keyless, with daily rates of every currency Indians travel with.
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/INR";
+/// The API updates its rates once a day.
+const RATES_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+static RATES_CACHE: LazyLock<RwLock<Option<(Instant, CurrencyConverter)>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[gemini_schema]
+///Money advice for an Indian travelling abroad.
+pub struct CurrencyTips {
+    ///ISO 4217 code, e.g. 'THB'
+    pub local_currency: String,
+    ///Rupees for one unit of the local currency
+    pub current_rate_to_inr: f32,
+    pub best_exchange_method: String,
+    pub atm_availability: String,
+    pub credit_card_acceptance: String,
+    pub carry_cash_recommendation: bool,
+}
+
+#[derive(Deserialize)]
+#[gemini_schema]
+///Practical money advice for a destination.
+struct MoneyAdvice {
+    ///Where to get the local currency at a good rate, e.g. forex card, airport counter or ATM
+    best_exchange_method: String,
+    ///How easy it is to find ATMs that accept Indian cards
+    atm_availability: String,
+    ///Where cards are accepted and where only cash works
+    credit_card_acceptance: String,
+    ///Carrying some cash is needed
+    carry_cash_recommendation: bool,
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    result: String,
+    ///Units of each currency for one rupee
+    #[serde(default)]
+    rates: HashMap<String, f32>,
+}
+
+/// Exchange rates against the rupee.
+#[derive(Debug, Clone)]
+pub struct CurrencyConverter {
+    ///Units of each currency for one rupee
+    rates: HashMap<String, f32>,
+}
+
+impl CurrencyConverter {
+    fn from_response(response: RatesResponse) -> Result<Self, TravelError> {
+        if response.result != "success" || response.rates.is_empty() {
+            return Err(TravelError::Api(format!(
+                "Exchange rate API returned {}",
+                response.result
+            )));
+        }
+        Ok(Self {
+            rates: response.rates,
+        })
+    }
+    /// Cached for `RATES_TTL`.
+    pub async fn latest() -> Result<Self, TravelError> {
+        if let Some((fetched_at, converter)) = RATES_CACHE.read().await.as_ref()
+            && fetched_at.elapsed() < RATES_TTL
+        {
+            return Ok(converter.clone());
+        }
+        let resp = reqwest::get(RATES_URL)
+            .await
+            .map_err(|e| TravelError::Api(format!("Exchange rate request failed: {e}")))?;
+        if !resp.status().is_success() {
+            return Err(TravelError::Api(format!(
+                "Exchange rate error: {}",
+                resp.status()
+            )));
+        }
+        let response: RatesResponse = resp
+            .json()
+            .await
+            .map_err(|e| TravelError::Api(format!("Invalid exchange rate response: {e}")))?;
+        let converter = Self::from_response(response)?;
+        *RATES_CACHE.write().await = Some((Instant::now(), converter.clone()));
+        Ok(converter)
+    }
+    /// Rupees for one unit of `currency_code`.
+    pub fn rate_to_inr(&self, currency_code: &str) -> Option<f32> {
+        self.rates
+            .get(&currency_code.to_ascii_uppercase())
+            .filter(|rate| **rate > 0.0)
+            .map(|rate| 1.0 / rate)
+    }
+    /// `amount` in `currency_code`, which must be one `Currency` supports.
+    pub fn convert_currency(
+        &self,
+        amount: &Currency,
+        currency_code: &str,
+    ) -> Result<Currency, TravelError> {
+        if amount.currency_code().eq_ignore_ascii_case(currency_code) {
+            return Ok(amount.clone());
+        }
+        let rate = |code: &str| {
+            self.rate_to_inr(code)
+                .ok_or_else(|| TravelError::Api(format!("No exchange rate for {code}")))
+        };
+        let inr = amount.to_amount() * rate(amount.currency_code())?;
+        Currency::from_code(currency_code, inr / rate(currency_code)?)
+    }
+    #[cfg(test)]
+    pub fn from_rates(rates: &[(&str, f32)]) -> Self {
+        Self {
+            rates: rates
+                .iter()
+                .map(|(code, rate)| (code.to_string(), *rate))
+                .collect(),
+        }
+    }
+}
+
+async fn money_advice(destination: &str, currency: &str) -> Result<MoneyAdvice, TravelError> {
+    let ai = Gemini::new(
+        CONFIG.gemini_api_key.clone(),
+        CONFIG.gemini_model.clone(),
+        None,
+    )
+    .set_json_mode(MoneyAdvice::gemini_schema());
+    let mut session = Session::new(2);
+    session.ask_string(format!(
+        "In one short sentence each, give practical money advice for an Indian tourist visiting {destination}, where the currency is {currency}."
+    ));
+    ai.ask(&mut session)
+        .await
+        .map_err(|e| TravelError::Api(format!("Gemini money advice failed: {e}")))?
+        .get_json()
+        .map_err(|e| TravelError::Api(format!("Invalid Gemini money advice: {e}")))
+}
+
+pub async fn get_currency_exchange_tips(destination: &str) -> Result<CurrencyTips, TravelError> {
+    let currency = local_currency(destination).ok_or_else(|| {
+        TravelError::InvalidInput(format!("Local currency of {destination} is not known"))
+    })?;
+    if currency == "INR" {
+        return Err(TravelError::InvalidInput(format!(
+            "{destination} uses rupees, no exchange is needed"
+        )));
+    }
+    let (converter, advice) = try_join(
+        CurrencyConverter::latest(),
+        money_advice(destination, currency),
+    )
+    .await?;
+    let rate = converter
+        .rate_to_inr(currency)
+        .ok_or_else(|| TravelError::Api(format!("No exchange rate for {currency}")))?;
+    Ok(CurrencyTips {
+        local_currency: currency.to_string(),
+        current_rate_to_inr: rate,
+        best_exchange_method: advice.best_exchange_method,
+        atm_availability: advice.atm_availability,
+        credit_card_acceptance: advice.credit_card_acceptance,
+        carry_cash_recommendation: advice.carry_cash_recommendation,
+    })
+}
+
+#[gemini_function]
+///Get the exchange rate and practical money advice (cash or card, ATMs, where to exchange) for a trip abroad.
+pub async fn currency_tips(
+    ///Country of the destination (e.g., 'Thailand')
+    destination: String,
+) -> Result<CurrencyTips, TravelError> {
+    get_currency_exchange_tips(&destination).await
+}
+
+#[test]
+fn currency_converter_test() {
+    let response: RatesResponse = serde_json::from_value(serde_json::json!({
+        "result": "success",
+        "base_code": "INR",
+        "time_last_update_unix": 1760486401,
+        "rates": {"INR": 1, "USD": 0.01136, "AED": 0.0418, "THB": 0.3704, "JPY": 1.7241, "XXX": 0}
+    }))
+    .unwrap();
+    let converter = CurrencyConverter::from_response(response).unwrap();
+    let rate = |code: &str| converter.rate_to_inr(code).unwrap();
+    assert!((rate("AED") - 23.92).abs() < 0.01, "{}", rate("AED"));
+    assert!((rate("thb") - 2.70).abs() < 0.01);
+    assert!((rate("JPY") - 0.58).abs() < 0.01);
+    assert_eq!(rate("INR"), 1.0);
+    assert_eq!(converter.rate_to_inr("XXX"), None);
+    assert_eq!(converter.rate_to_inr("ZZZ"), None);
+
+    let rupees = converter
+        .convert_currency(&Currency::Usd(100.0), "inr")
+        .unwrap();
+    assert!(matches!(rupees, Currency::Inr(amount) if (amount - 8800.0).abs() < 100.0));
+    let same = converter
+        .convert_currency(&Currency::Inr(500.0), "INR")
+        .unwrap();
+    assert!(matches!(same, Currency::Inr(amount) if amount == 500.0));
+    assert!(matches!(
+        converter.convert_currency(&Currency::Eur(10.0), "INR"),
+        Err(TravelError::Api(_))
+    ));
+    assert!(matches!(
+        converter.convert_currency(&Currency::Inr(10.0), "THB"),
+        Err(TravelError::Api(_) | TravelError::InvalidInput(_))
+    ));
+
+    let failed: RatesResponse =
+        serde_json::from_value(serde_json::json!({"result": "error"})).unwrap();
+    assert!(matches!(
+        CurrencyConverter::from_response(failed),
+        Err(TravelError::Api(_))
+    ));
+}
+
+#[tokio::test]
+async fn get_currency_exchange_tips_test() {
+    assert!(matches!(
+        get_currency_exchange_tips("Atlantis").await,
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        get_currency_exchange_tips("India").await,
+        Err(TravelError::InvalidInput(msg)) if msg.contains("rupees")
+    ));
+}
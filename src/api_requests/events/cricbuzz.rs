@@ -0,0 +1,324 @@
+use crate::{
+    api_requests::rapidapi::{ApiClient, RapidAPIClient},
+    error::TravelError,
+    utils::{Date, Time, check_param_length},
+};
+use chrono::{DateTime, FixedOffset, Timelike};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use serde::{Deserialize, Serialize};
+
+const CRICBUZZ_HOST: &str = "cricbuzz-cricket.p.rapidapi.com";
+const MAX_CITY_LEN: usize = 100;
+/// CricBuzz only lists upcoming matches a few weeks ahead.
+const MAX_RANGE_DAYS: i64 = 60;
+/// Venues without a time zone are taken to be in India.
+const IST_OFFSET_SECS: i32 = 5 * 3600 + 30 * 60;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CricketMatch {
+    ///Format, e.g. 'T20', 'ODI' or 'TEST'
+    pub match_type: String,
+    ///e.g. 'Indian Premier League 2026'
+    pub series: String,
+    pub teams: (String, String),
+    ///Stadium and city, e.g. 'Wankhede Stadium, Mumbai'
+    pub venue: String,
+    ///Local date and start time at the venue
+    pub date: Date,
+    pub time: Time,
+    pub is_international: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchesResponse {
+    #[serde(default)]
+    type_matches: Vec<TypeMatches>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TypeMatches {
+    ///`International`, `League`, `Domestic` or `Women`
+    match_type: String,
+    #[serde(default)]
+    series_matches: Vec<SeriesMatches>,
+}
+
+/// Entries without `seriesAdWrapper` are ads.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SeriesMatches {
+    #[serde(default)]
+    series_ad_wrapper: Option<SeriesWrapper>,
+}
+
+#[derive(Deserialize)]
+struct SeriesWrapper {
+    #[serde(default)]
+    matches: Vec<MatchWrapper>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchWrapper {
+    match_info: MatchInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchInfo {
+    series_name: String,
+    match_format: String,
+    ///Milliseconds since the epoch as a string
+    start_date: String,
+    team1: Team,
+    team2: Team,
+    venue_info: VenueInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Team {
+    team_name: String,
+}
+
+#[derive(Deserialize)]
+struct VenueInfo {
+    ground: String,
+    city: String,
+    ///Offset like `+05:30`
+    #[serde(default)]
+    timezone: Option<String>,
+}
+
+/// `+05:30` or `-04:00`. IST when missing or unreadable.
+fn venue_offset(timezone: Option<&str>) -> FixedOffset {
+    timezone
+        .and_then(|timezone| {
+            let sign = match timezone.trim().chars().next()? {
+                '+' => 1,
+                '-' => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = timezone.trim()[1..].split_once(':')?;
+            let secs = hours.parse::<i32>().ok()? * 3600 + minutes.parse::<i32>().ok()? * 60;
+            FixedOffset::east_opt(sign * secs)
+        })
+        .unwrap_or_else(|| FixedOffset::east_opt(IST_OFFSET_SECS).unwrap())
+}
+
+/// `None` when the start time can't be read.
+fn to_cricket_match(info: MatchInfo, is_international: bool) -> Option<CricketMatch> {
+    let start = DateTime::from_timestamp_millis(info.start_date.trim().parse().ok()?)?
+        .with_timezone(&venue_offset(info.venue_info.timezone.as_deref()));
+    Some(CricketMatch {
+        match_type: info.match_format,
+        series: info.series_name,
+        teams: (info.team1.team_name, info.team2.team_name),
+        venue: format!("{}, {}", info.venue_info.ground, info.venue_info.city),
+        date: start.date_naive().into(),
+        time: Time::new(start.hour() as u8, start.minute() as u8, 0).ok()?,
+        is_international,
+    })
+}
+
+/// Matches in `city` from `date_from` to `date_to`, both included, in the order they start.
+fn matches_in_city(
+    response: MatchesResponse,
+    city: &str,
+    date_from: &Date,
+    date_to: &Date,
+) -> Vec<CricketMatch> {
+    let city = city.trim();
+    let mut matches: Vec<CricketMatch> = response
+        .type_matches
+        .into_iter()
+        .flat_map(|type_matches| {
+            let is_international = type_matches
+                .match_type
+                .eq_ignore_ascii_case("International");
+            type_matches
+                .series_matches
+                .into_iter()
+                .filter_map(|series| series.series_ad_wrapper)
+                .flat_map(|series| series.matches)
+                .filter(|wrapper| {
+                    wrapper
+                        .match_info
+                        .venue_info
+                        .city
+                        .eq_ignore_ascii_case(city)
+                })
+                .filter_map(move |wrapper| to_cricket_match(wrapper.match_info, is_international))
+        })
+        .filter(|cricket_match| (date_from..=date_to).contains(&&cricket_match.date))
+        .collect();
+    matches.sort_by(|a, b| (&a.date, &a.time).cmp(&(&b.date, &b.time)));
+    matches
+}
+
+async fn search_matches(
+    client: &impl ApiClient,
+    city: &str,
+    date_from: &Date,
+    date_to: &Date,
+) -> Result<Vec<CricketMatch>, TravelError> {
+    let city = check_param_length(city.trim(), MAX_CITY_LEN)?;
+    if city.is_empty() {
+        return Err(TravelError::InvalidInput("City is empty".into()));
+    }
+    let (Some(from), Some(to)) = (date_from.to_naive_date(), date_to.to_naive_date()) else {
+        return Err(TravelError::InvalidInput(format!(
+            "Invalid date range {} to {}",
+            date_from.to_yyyy_mm_dd(),
+            date_to.to_yyyy_mm_dd()
+        )));
+    };
+    let days = (to - from).num_days();
+    if !(0..=MAX_RANGE_DAYS).contains(&days) {
+        return Err(TravelError::InvalidInput(format!(
+            "date_to must be on or up to {MAX_RANGE_DAYS} days after date_from. Found: {days} days"
+        )));
+    }
+    let response: MatchesResponse = client.get("/matches/v1/upcoming").await?;
+    Ok(matches_in_city(response, city, date_from, date_to))
+}
+
+/// Upcoming international, IPL and domestic matches on CricBuzz.
+pub async fn get_cricket_matches_in_city(
+    city: &str,
+    date_from: Date,
+    date_to: Date,
+) -> Result<Vec<CricketMatch>, TravelError> {
+    let client = RapidAPIClient::for_host(CRICBUZZ_HOST)?;
+    search_matches(&client, city, &date_from, &date_to).await
+}
+
+#[gemini_function]
+///Find cricket matches (IPL, Tests, ODIs, T20s) played in a city during the travel dates. Call it for the destination of every trip in India.
+pub async fn cricket_matches_in_city(
+    ///City of the stadium (e.g., 'Mumbai')
+    city: String,
+    ///First day of the stay
+    date_from: Date,
+    ///Last day of the stay
+    date_to: Date,
+) -> Result<Vec<CricketMatch>, TravelError> {
+    get_cricket_matches_in_city(&city, date_from, date_to).await
+}
+
+#[cfg(test)]
+fn upcoming_matches() -> serde_json::Value {
+    use serde_json::json;
+
+    let cricket_match =
+        |series: &str, format: &str, start: &str, teams: [&str; 2], ground: &str, city: &str| {
+            json!({"matchInfo": {
+                "matchId": 1,
+                "seriesName": series,
+                "matchDesc": "1st Match",
+                "matchFormat": format,
+                "startDate": start,
+                "team1": {"teamId": 1, "teamName": teams[0], "teamSName": "T1"},
+                "team2": {"teamId": 2, "teamName": teams[1], "teamSName": "T2"},
+                "venueInfo": {"ground": ground, "city": city, "timezone": "+05:30"}
+            }})
+        };
+    json!({"typeMatches": [
+        {"matchType": "International", "seriesMatches": [
+            {"seriesAdWrapper": {"seriesId": 1, "seriesName": "England tour of India", "matches": [
+                // 2026-11-20 09:30 IST
+                cricket_match("England tour of India", "TEST", "1795147200000", ["India", "England"], "Wankhede Stadium", "Mumbai"),
+                // 2026-11-28 13:30 IST
+                cricket_match("England tour of India", "ODI", "1795852800000", ["India", "England"], "Eden Gardens", "Kolkata")
+            ]}},
+            {"adDetail": {"name": "native_matches"}}
+        ]},
+        {"matchType": "League", "seriesMatches": [
+            {"seriesAdWrapper": {"seriesId": 2, "seriesName": "Indian Premier League 2026", "matches": [
+                // 2026-11-18 19:30 IST
+                cricket_match("Indian Premier League 2026", "T20", "1795010400000", ["Mumbai Indians", "Chennai Super Kings"], "Wankhede Stadium", "Mumbai"),
+                cricket_match("Indian Premier League 2026", "T20", "soon", ["Mumbai Indians", "Delhi Capitals"], "Wankhede Stadium", "Mumbai")
+            ]}}
+        ]}
+    ]})
+}
+
+#[tokio::test]
+async fn cricket_matches_in_city_test() {
+    use crate::api_requests::rapidapi::MockApiClient;
+
+    let client = MockApiClient::new([("/matches/v1/upcoming", upcoming_matches())]);
+    let date = |day| Date::new(2026, 11, day).unwrap();
+    let matches = search_matches(&client, " mumbai ", &date(15), &date(25))
+        .await
+        .unwrap();
+    assert_eq!(client.requests.lock().unwrap()[0], "/matches/v1/upcoming");
+    assert_eq!(
+        matches,
+        [
+            CricketMatch {
+                match_type: "T20".into(),
+                series: "Indian Premier League 2026".into(),
+                teams: ("Mumbai Indians".into(), "Chennai Super Kings".into()),
+                venue: "Wankhede Stadium, Mumbai".into(),
+                date: date(18),
+                time: Time::new(19, 30, 0).unwrap(),
+                is_international: false,
+            },
+            CricketMatch {
+                match_type: "TEST".into(),
+                series: "England tour of India".into(),
+                teams: ("India".into(), "England".into()),
+                venue: "Wankhede Stadium, Mumbai".into(),
+                date: date(20),
+                time: Time::new(9, 30, 0).unwrap(),
+                is_international: true,
+            },
+        ]
+    );
+
+    // Both ends of the range are included
+    let matches = search_matches(&client, "Mumbai", &date(20), &date(20))
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+    let kolkata = search_matches(&client, "Kolkata", &date(1), &date(27))
+        .await
+        .unwrap();
+    assert!(kolkata.is_empty());
+    assert!(
+        search_matches(&client, "Goa", &date(1), &date(30))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn invalid_cricket_search_test() {
+    use crate::api_requests::rapidapi::MockApiClient;
+
+    let client = MockApiClient::new([("/matches/v1/upcoming", upcoming_matches())]);
+    let date = |month, day| Date::new(2026, month, day).unwrap();
+    for (city, from, to) in [
+        (" ", date(11, 1), date(11, 5)),
+        ("Mumbai", date(11, 5), date(11, 1)),
+        ("Mumbai", date(11, 1), date(2, 1).add_days(365).unwrap()),
+        ("Mumbai", date(2, 30), date(3, 1)),
+    ] {
+        assert!(matches!(
+            search_matches(&client, city, &from, &to).await,
+            Err(TravelError::InvalidInput(_))
+        ));
+    }
+    assert!(client.requests.lock().unwrap().is_empty());
+
+    assert_eq!(venue_offset(Some("+05:30")), venue_offset(None));
+    assert_eq!(
+        venue_offset(Some("-04:00")),
+        FixedOffset::west_opt(4 * 3600).unwrap()
+    );
+    assert_eq!(venue_offset(Some("IST")), venue_offset(None));
+}
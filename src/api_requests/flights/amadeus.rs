@@ -1,49 +1,419 @@
-use crate::utils::{Currency, Date, IataCode, get_bearer_token};
-use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
-use reqwest::header::AUTHORIZATION;
+use super::{
+    lounges::terminal_map_url,
+    low_cost::{FlightOffer, low_cost_flights_between, merge_flights},
+};
+use crate::{
+    error::TravelError,
+    utils::{AmadeusClient, Currency, Date, IataCode, Time, TravelDuration, check_param_length},
+};
+use chrono::{Datelike, NaiveDateTime};
+use gemini_client_api::futures::future::{join_all, join3};
+use gemini_client_api::futures::{StreamExt, stream};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
 use serde::{Deserialize, Serialize};
-use std::env;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
 
-const BASE_URL: &str = "https://test.api.amadeus.com/v2/shopping/flight-offers";
+const FLIGHT_OFFERS_PATH: &str = "/v2/shopping/flight-offers";
+const PRICING_PATH: &str = "/v1/shopping/flight-offers/pricing";
+const SEATMAPS_PATH: &str = "/v1/shopping/seatmaps";
+const UPSELLING_PATH: &str = "/v1/shopping/flight-offers/upselling";
+const PRICE_METRICS_PATH: &str = "/v1/analytics/itinerary-price-metrics";
+/// Amadeus has price metrics per departure date, so a month is sampled once a week.
+const PRICE_HISTORY_DAYS: [u8; 4] = [1, 8, 15, 22];
+const MAX_CODE_LEN: usize = 3;
+const MAX_OFFER_ID_LEN: usize = 64;
+/// Longest date range searched by `cheapest_flexible_date`, each date is a search of its own.
+const MAX_FLEXIBLE_DAYS: i64 = 30;
+/// Dates of a flexible search searched at once, each is a full flight search.
+const MAX_CONCURRENT_DATE_SEARCHES: usize = 5;
+/// Amadeus offers can't be priced or booked for long after the search.
+const RAW_OFFER_TTL: Duration = Duration::from_secs(30 * 60);
+/// Flights longer than this need planning for lounges, transit hotels and rest.
+pub const LONG_HAUL_ABOVE_MINUTES: u32 = 5 * 60;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[gemini_schema]
 pub struct Flight {
     pub id: String,
     pub price: Currency,
     pub itineraries: Vec<Itinerary>,
+    ///IATA code of the airline selling the ticket, e.g. '6E'
+    #[serde(default)]
+    pub carrier_code: String,
+    ///Departs at or after 21:00 or lands before 06:00
+    #[serde(default)]
+    pub overnight: bool,
+    ///Departs before 06:00
+    #[serde(default)]
+    pub early_morning: bool,
+    ///Takes more than 5 hours
+    #[serde(default)]
+    pub long_haul: bool,
+    ///Outbound journey time including layovers, e.g. '5h 30m'
+    pub journey_time: Option<String>,
+    ///Fare of the first traveller split into base fare and taxes
+    #[serde(default)]
+    pub fare_breakdown: Option<FareBreakdown>,
+    ///Terminals of the outbound journey, only set when asked for
+    #[serde(default)]
+    pub terminal_info: Option<AirportTerminalInfo>,
+    ///Every airport of the journey is in India, so an Aadhaar or other photo ID does instead
+    ///of a passport
+    #[serde(default)]
+    pub domestic: bool,
+    ///Cabin of the first segment
+    #[serde(default)]
+    pub booking_class: BookingClass,
+    ///Price per passenger is below the median of the route's `price_history`
+    #[serde(default)]
+    pub is_good_price: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[gemini_schema]
+pub struct PricePoint {
+    pub date: Date,
+    ///Median fare paid for departures on `date`
+    pub price: Currency,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[gemini_schema]
+pub enum BookingClass {
+    #[default]
+    Economy,
+    PremiumEconomy,
+    Business,
+    First,
+}
+
+impl BookingClass {
+    /// Amadeus cabin code, e.g. `PREMIUM_ECONOMY`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BookingClass::Economy => "ECONOMY",
+            BookingClass::PremiumEconomy => "PREMIUM_ECONOMY",
+            BookingClass::Business => "BUSINESS",
+            BookingClass::First => "FIRST",
+        }
+    }
+}
+
+/// Unknown cabin codes are taken as economy, the cabin nearly every offer is in.
+impl From<&str> for BookingClass {
+    fn from(cabin: &str) -> Self {
+        match cabin.trim().to_ascii_uppercase().as_str() {
+            "PREMIUM_ECONOMY" => BookingClass::PremiumEconomy,
+            "BUSINESS" => BookingClass::Business,
+            "FIRST" => BookingClass::First,
+            _ => BookingClass::Economy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[gemini_schema]
+pub struct FareBreakdown {
+    pub base_fare: Currency,
+    ///Taxes, airport charges and supplier and ticketing fees
+    pub taxes_and_fees: Currency,
+    pub total: Currency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[gemini_schema]
+pub struct AirportTerminalInfo {
+    ///Terminal at the departure airport, e.g. '3' for Delhi T3
+    pub departure_terminal: Option<String>,
+    pub arrival_terminal: Option<String>,
+    ///Google Maps link to the departure terminal
+    pub departure_map_url: String,
+    pub arrival_map_url: String,
+}
+
+impl Flight {
+    pub fn departure(&self) -> Option<&Endpoint> {
+        Some(&self.itineraries.first()?.segments.first()?.departure)
+    }
+    pub fn arrival(&self) -> Option<&Endpoint> {
+        Some(&self.itineraries.first()?.segments.last()?.arrival)
+    }
+    /// Uses the outbound itinerary only.
+    pub fn is_overnight(&self) -> bool {
+        let departs_late = self
+            .departure()
+            .and_then(Endpoint::time)
+            .is_some_and(|time| time.hour() >= 21);
+        let lands_early = self
+            .arrival()
+            .and_then(Endpoint::time)
+            .is_some_and(|time| time.hour() < 6);
+        departs_late || lands_early
+    }
+    pub fn is_early_morning(&self) -> bool {
+        self.departure()
+            .and_then(Endpoint::time)
+            .is_some_and(|time| time.hour() < 6)
+    }
+    /// Offer ids are only unique within one search, so the first segment is part of the key.
+    fn offer_key(&self) -> String {
+        let departure = self
+            .itineraries
+            .first()
+            .and_then(|itinerary| itinerary.segments.first())
+            .map(|segment| {
+                format!(
+                    "{}{}@{}",
+                    segment.carrier_code, segment.number, segment.departure.at
+                )
+            })
+            .unwrap_or_default();
+        format!("{}:{departure}", self.id)
+    }
+    /// Duration of the outbound itinerary, layovers included.
+    pub fn duration(&self) -> Option<TravelDuration> {
+        TravelDuration::from_iso8601(&self.itineraries.first()?.duration)
+    }
+    /// `false` when the duration is unknown.
+    pub fn is_long_haul(&self) -> bool {
+        self.duration()
+            .is_some_and(|duration| duration.total_minutes() > LONG_HAUL_ABOVE_MINUTES)
+    }
+    /// Terminals where the outbound journey starts and ends. `None` when the airports are
+    /// unknown.
+    pub fn airport_terminal_info(&self) -> Option<AirportTerminalInfo> {
+        let (departure, arrival) = (self.departure()?, self.arrival()?);
+        let map_url = |endpoint: &Endpoint| {
+            let code = IataCode::new(endpoint.iata_code.clone()).ok()?;
+            Some(terminal_map_url(&code, endpoint.terminal.as_deref()))
+        };
+        Some(AirportTerminalInfo {
+            departure_terminal: departure.terminal.clone(),
+            arrival_terminal: arrival.terminal.clone(),
+            departure_map_url: map_url(departure)?,
+            arrival_map_url: map_url(arrival)?,
+        })
+    }
+    /// `false` when any airport is unknown or outside India.
+    pub fn is_domestic(&self) -> bool {
+        let mut endpoints = self
+            .itineraries
+            .iter()
+            .flat_map(|itinerary| &itinerary.segments)
+            .flat_map(|segment| [&segment.departure, &segment.arrival])
+            .peekable();
+        endpoints.peek().is_some()
+            && endpoints.all(|endpoint| {
+                IataCode::new(endpoint.iata_code.clone()).is_ok_and(|code| code.is_domestic_india())
+            })
+    }
+    /// Sets the fields computed from the itineraries.
+    pub(super) fn with_derived_fields(mut self) -> Self {
+        self.overnight = self.is_overnight();
+        self.early_morning = self.is_early_morning();
+        self.long_haul = self.is_long_haul();
+        self.journey_time = self.duration().map(TravelDuration::to_human_string);
+        self
+    }
+    /// Carrier and number of every segment, e.g. `6E2131`. The same flight found by different
+    /// sources has the same flight numbers.
+    pub(super) fn flight_numbers(&self) -> String {
+        self.itineraries
+            .iter()
+            .flat_map(|itinerary| &itinerary.segments)
+            .map(|segment| format!("{}{}", segment.carrier_code, segment.number))
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+    pub(super) fn is_sold_by(&self, airline_code: &str) -> bool {
+        self.carrier_code.eq_ignore_ascii_case(airline_code.trim())
+    }
+    /// Sets `is_good_price` for a price paid for `passengers`, as the history is of single
+    /// fares. The price is never good against a history in another currency.
+    pub fn compare_with_history(&mut self, history: &[PricePoint], passengers: u8) {
+        let code = self.price.currency_code();
+        let mut prices: Vec<f32> = history
+            .iter()
+            .filter(|point| point.price.currency_code() == code)
+            .map(|point| point.price.to_amount())
+            .collect();
+        prices.sort_by(f32::total_cmp);
+        let fare = self.price.to_amount() / f32::from(passengers.max(1));
+        self.is_good_price = median(&prices).is_some_and(|median| fare < median);
+    }
+}
+
+/// Middle of sorted `prices`. `None` when empty.
+fn median(prices: &[f32]) -> Option<f32> {
+    let middle = prices.len() / 2;
+    if prices.is_empty() {
+        None
+    } else if prices.len().is_multiple_of(2) {
+        Some((prices[middle - 1] + prices[middle]) / 2.0)
+    } else {
+        Some(prices[middle])
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Flights of one search.
+#[derive(Debug, Clone, Default)]
+pub struct FlightCollection(pub Vec<FlightOffer>);
+
+impl FlightCollection {
+    /// `airline_code` is an IATA code like `AI`, matched against `Flight::carrier_code`.
+    pub fn filter_by_airline(&self, airline_code: &str) -> Vec<&Flight> {
+        self.0
+            .iter()
+            .map(FlightOffer::flight)
+            .filter(|flight| flight.is_sold_by(airline_code))
+            .collect()
+    }
+    pub fn exclude_airlines(self, codes: &[&str]) -> FlightCollection {
+        FlightCollection(
+            self.0
+                .into_iter()
+                .filter(|offer| !codes.iter().any(|code| offer.flight().is_sold_by(code)))
+                .collect(),
+        )
+    }
+}
+
+/// Spread of the prices of one search, so a fare can be called cheap or expensive.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlightPriceSummary {
+    pub min_price: Currency,
+    pub max_price: Currency,
+    pub mean_price: Currency,
+    pub median_price: Currency,
+    ///`max_price` less `min_price`
+    pub price_range: Currency,
+}
+
+/// Uses the flights priced in the currency of the first flight. `None` without flights.
+pub fn compare_prices(flights: &[Flight]) -> Option<FlightPriceSummary> {
+    let code = flights.first()?.price.currency_code();
+    let mut prices: Vec<f32> = flights
+        .iter()
+        .filter(|flight| flight.price.currency_code() == code)
+        .map(|flight| flight.price.to_amount())
+        .collect();
+    prices.sort_by(f32::total_cmp);
+    let (min, max) = (*prices.first()?, *prices.last()?);
+    let median = median(&prices)?;
+    let price = |amount: f32| Currency::from_code(code, amount).ok();
+    Some(FlightPriceSummary {
+        min_price: price(min)?,
+        max_price: price(max)?,
+        mean_price: price(prices.iter().sum::<f32>() / prices.len() as f32)?,
+        median_price: price(median)?,
+        price_range: price(max - min)?,
+    })
+}
+
+/// Adds the `price_summary` of its flights to a `flights_between` response. Other responses,
+/// like errors, are returned as is.
+pub fn with_price_summary(mut response: Value) -> Value {
+    let Ok(offers) = serde_json::from_value::<Vec<FlightOffer>>(response["flights"].clone()) else {
+        return response;
+    };
+    let flights: Vec<Flight> = offers.iter().map(|offer| offer.flight().clone()).collect();
+    if let Some(summary) = compare_prices(&flights) {
+        response["price_summary"] = json!(summary);
+    }
+    response
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[gemini_schema]
 pub struct Itinerary {
     pub duration: String,
     pub segments: Vec<Segment>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[gemini_schema]
 pub struct Segment {
     pub departure: Endpoint,
     pub arrival: Endpoint,
+    ///Airline marketing the flight
     pub carrier_code: String,
     pub number: String,
     pub duration: String,
+    ///Airline flying the plane, when it differs from `carrier_code` the flight is a codeshare
+    #[serde(default)]
+    pub operating_carrier: Option<String>,
+    #[serde(default)]
+    pub operating_flight_number: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[gemini_schema]
 pub struct Endpoint {
     pub iata_code: String,
+    ///Local time as `YYYY-MM-DDTHH:MM:SS`
     pub at: String,
+    ///Airport terminal, e.g. '3' for Delhi T3
+    #[serde(default)]
+    pub terminal: Option<String>,
+}
+
+impl Endpoint {
+    pub fn time(&self) -> Option<Time> {
+        let (_, time) = self.at.split_once('T')?;
+        Time::from_hh_mm_ss(time).ok()
+    }
+    pub fn date_time(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.at, "%Y-%m-%dT%H:%M:%S").ok()
+    }
+}
+
+/// Raw Amadeus offers by `Flight::offer_key`, with when they were received. The pricing API
+/// needs the complete offer, which `Flight` only keeps a summary of. Offers older than
+/// `RAW_OFFER_TTL` are dropped whenever new ones are stored.
+static RAW_OFFERS: LazyLock<RwLock<HashMap<String, (Value, Instant)>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn is_raw_offer_fresh(received: &Instant) -> bool {
+    received.elapsed() < RAW_OFFER_TTL
+}
+
+async fn store_raw_offers(offers: impl IntoIterator<Item = (String, Value)>) {
+    let mut raw_offers = RAW_OFFERS.write().await;
+    raw_offers.retain(|_, (_, received)| is_raw_offer_fresh(received));
+    let now = Instant::now();
+    raw_offers.extend(offers.into_iter().map(|(key, raw)| (key, (raw, now))));
 }
 
 #[derive(Deserialize)]
 struct AmadeusFlightResponse {
-    data: Vec<AmadeusFlightOffer>,
+    data: Vec<Value>,
 }
 
 #[derive(Deserialize)]
+struct AmadeusPricingResponse {
+    data: AmadeusPricingData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmadeusPricingData {
+    flight_offers: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct AmadeusFlightOffer {
     id: String,
     price: AmadeusPrice,
     itineraries: Vec<AmadeusItinerary>,
+    #[serde(default)]
+    validating_airline_codes: Vec<String>,
+    #[serde(default)]
+    traveler_pricings: Vec<AmadeusTravelerPricing>,
 }
 
 #[derive(Deserialize)]
@@ -52,6 +422,26 @@ struct AmadeusPrice {
     total: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmadeusTravelerPricing {
+    price: AmadeusTravelerPrice,
+    #[serde(default)]
+    fare_details_by_segment: Vec<AmadeusFareDetails>,
+}
+
+#[derive(Deserialize)]
+struct AmadeusFareDetails {
+    cabin: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AmadeusTravelerPrice {
+    currency: String,
+    total: String,
+    base: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct AmadeusItinerary {
     duration: String,
@@ -66,6 +456,49 @@ struct AmadeusSegment {
     carrier_code: String,
     number: String,
     duration: String,
+    #[serde(default)]
+    operating: Option<AmadeusOperating>,
+}
+
+#[derive(Deserialize)]
+struct AmadeusPriceMetricsResponse {
+    data: Vec<AmadeusPriceMetrics>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmadeusPriceMetrics {
+    departure_date: String,
+    currency_code: String,
+    price_metrics: Vec<AmadeusPriceMetric>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmadeusPriceMetric {
+    amount: String,
+    ///`MINIMUM`, `FIRST`, `MEDIUM`, `THIRD` or `MAXIMUM`
+    quartile_ranking: String,
+}
+
+impl AmadeusPriceMetrics {
+    fn median_price(&self) -> Option<PricePoint> {
+        let metric = self
+            .price_metrics
+            .iter()
+            .find(|metric| metric.quartile_ranking == "MEDIUM")?;
+        Some(PricePoint {
+            date: Date::from_yyyy_mm_dd(&self.departure_date).ok()?,
+            price: Currency::parse_currency(&self.currency_code, &metric.amount).ok()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmadeusOperating {
+    carrier_code: Option<String>,
+    number: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -73,10 +506,21 @@ struct AmadeusSegment {
 struct AmadeusEndpoint {
     iata_code: String,
     at: String,
+    terminal: Option<String>,
+}
+
+/// Output of `flights_between`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlightSearchResults {
+    pub flights: Vec<FlightOffer>,
+    ///Median fares paid on the route on a few days of the month of departure, oldest first.
+    ///Only there when asked for
+    pub price_history: Option<Vec<PricePoint>>,
 }
 
 #[gemini_function]
 ///Search for flight offers between two cities on a specific date.
+#[allow(clippy::too_many_arguments)]
 pub async fn flights_between(
     ///IATA origin city code (e.g., 'JFK')
     source: IataCode,
@@ -87,72 +531,640 @@ pub async fn flights_between(
     adult_count: u8,
     ///3-letter currency code (e.g., 'USD')
     currency_code: String,
+    ///Maximum price per traveller in `currency_code`. Offers above it are not returned.
+    max_price: Option<f32>,
+    ///Add the departure and arrival terminals with map links. Set it only once the user is choosing between a few flights, as it makes the results longer.
+    include_terminal_info: Option<bool>,
+    ///Only return flights in this cabin. Leave it out unless the user asks for a cabin.
+    preferred_class: Option<BookingClass>,
+    ///Add the route's price history and mark flights with `is_good_price`. Set it only when the user asks whether a fare is a good deal, as it takes 4 more searches.
+    include_price_history: Option<bool>,
+) -> Result<FlightSearchResults, Box<dyn std::error::Error + Send + Sync>> {
+    check_param_length(&currency_code, MAX_CODE_LEN)?;
+    let history = async {
+        match include_price_history {
+            Some(true) => Some(
+                departure_month_price_history(
+                    &source,
+                    &destination,
+                    &least_departure,
+                    &currency_code,
+                )
+                .await,
+            ),
+            _ => None,
+        }
+    };
+    let (amadeus, low_cost, history) = join3(
+        amadeus_flights_between(
+            &source,
+            &destination,
+            &least_departure,
+            adult_count,
+            &currency_code,
+            max_price,
+            preferred_class,
+        ),
+        low_cost_flights_between(
+            &source,
+            &destination,
+            &least_departure,
+            adult_count,
+            &currency_code,
+            max_price,
+        ),
+        history,
+    )
+    .await;
+    // Budget carriers are a bonus, the search still works without them
+    let low_cost = low_cost.unwrap_or_else(|e| {
+        eprintln!("ERROR: Low cost flight search failed:\n{e}");
+        Vec::new()
+    });
+    let mut offers = merge_flights(amadeus?, low_cost);
+    if let Some(class) = preferred_class {
+        offers.retain(|offer| offer.flight().booking_class == class);
+    }
+    // Like budget carriers, the price history only adds to the results
+    let price_history = history.and_then(|history| {
+        history
+            .inspect_err(|e| eprintln!("ERROR: Flight price history failed:\n{e}"))
+            .ok()
+    });
+    for offer in &mut offers {
+        let flight = offer.flight_mut();
+        flight.domestic = flight.is_domestic();
+        if let Some(history) = &price_history {
+            flight.compare_with_history(history, adult_count);
+        }
+        if include_terminal_info.unwrap_or(false) {
+            flight.terminal_info = flight.airport_terminal_info();
+        }
+    }
+    Ok(FlightSearchResults {
+        flights: offers,
+        price_history,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheapestFlightResult {
+    pub date: Date,
+    pub flight: FlightOffer,
+    ///Dates of the range whose search failed, a cheaper flight may be on them
+    pub failed_dates: u32,
+}
+
+/// Cheapest flight across every date, the earlier date on a tie. `None` when no date has a
+/// flight.
+fn cheapest_by_date(
+    by_date: Vec<(Date, Vec<FlightOffer>)>,
+    failed_dates: u32,
+) -> Option<CheapestFlightResult> {
+    by_date
+        .into_iter()
+        .flat_map(|(date, offers)| offers.into_iter().map(move |offer| (date.clone(), offer)))
+        .min_by(|(date_a, a), (date_b, b)| {
+            a.flight()
+                .price
+                .to_amount()
+                .total_cmp(&b.flight().price.to_amount())
+                .then_with(|| date_a.cmp(date_b))
+        })
+        .map(|(date, flight)| CheapestFlightResult {
+            date,
+            flight,
+            failed_dates,
+        })
+}
+
+/// Searches `dates` with `search`, `MAX_CONCURRENT_DATE_SEARCHES` at a time. Failed searches
+/// are logged and counted instead.
+async fn search_dates<F, Fut>(dates: Vec<Date>, search: F) -> (Vec<(Date, Vec<FlightOffer>)>, u32)
+where
+    F: Fn(Date) -> Fut,
+    Fut: Future<Output = Result<Vec<FlightOffer>, Box<dyn std::error::Error + Send + Sync>>>,
+{
+    let search = &search;
+    let results: Vec<(Date, Result<Vec<FlightOffer>, _>)> = stream::iter(dates)
+        .map(|date| async move {
+            let offers = search(date.clone()).await;
+            (date, offers)
+        })
+        .buffer_unordered(MAX_CONCURRENT_DATE_SEARCHES)
+        .collect()
+        .await;
+    let mut failed_dates = 0;
+    let by_date = results
+        .into_iter()
+        .filter_map(|(date, offers)| match offers {
+            Ok(offers) => Some((date, offers)),
+            Err(e) => {
+                eprintln!(
+                    "ERROR: Could not search flights on {}:\n{e}",
+                    date.to_yyyy_mm_dd()
+                );
+                failed_dates += 1;
+                None
+            }
+        })
+        .collect();
+    (by_date, failed_dates)
+}
+
+/// Searches every date from `from_date` to `to_date`, both included, without the price
+/// history. Dates whose search fails are skipped and counted in `failed_dates`.
+pub async fn cheapest_flexible_date(
+    source: &IataCode,
+    destination: &IataCode,
+    from_date: &Date,
+    to_date: &Date,
+    adults: u8,
+    currency_code: &str,
+) -> Result<CheapestFlightResult, TravelError> {
+    let (Some(from), Some(to)) = (from_date.to_naive_date(), to_date.to_naive_date()) else {
+        return Err(TravelError::InvalidInput("Invalid date range".into()));
+    };
+    let days = (to - from).num_days() + 1;
+    if !(1..=MAX_FLEXIBLE_DAYS).contains(&days) {
+        return Err(TravelError::InvalidInput(format!(
+            "The date range must be 1 to {MAX_FLEXIBLE_DAYS} days. Found: {days} days from {} to {}",
+            from_date.to_yyyy_mm_dd(),
+            to_date.to_yyyy_mm_dd()
+        )));
+    }
+    let dates: Vec<Date> = (0..days as u64)
+        .map_while(|offset| from_date.add_days(offset))
+        .collect();
+    let (by_date, failed_dates) = search_dates(dates, |date| async move {
+        flights_between(
+            source.clone(),
+            destination.clone(),
+            date,
+            adults,
+            currency_code.to_string(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .map(|results| results.flights)
+    })
+    .await;
+    let range = format!(
+        "between {} and {}",
+        from_date.to_yyyy_mm_dd(),
+        to_date.to_yyyy_mm_dd()
+    );
+    if i64::from(failed_dates) == days {
+        return Err(TravelError::Api(format!(
+            "Could not search flights from {source} to {destination} on any date {range}"
+        )));
+    }
+    cheapest_by_date(by_date, failed_dates).ok_or_else(|| {
+        let failed = match failed_dates {
+            0 => String::new(),
+            failed => format!(", {failed} of the {days} dates could not be searched"),
+        };
+        TravelError::NoResults(format!(
+            "No flights from {source} to {destination} {range}{failed}"
+        ))
+    })
+}
+
+#[gemini_function]
+///Find the cheapest day to fly when the user's travel date is flexible, e.g. "any day in the first week of March". Searches every date in the range, so keep the range as short as the user allows.
+pub async fn cheapest_flight_in_range(
+    ///IATA origin city code (e.g., 'DEL')
+    source: IataCode,
+    ///IATA destination city code (e.g., 'GOI')
+    destination: IataCode,
+    ///Earliest date the user can fly
+    from_date: Date,
+    ///Latest date the user can fly, at most 30 days after `from_date`
+    to_date: Date,
+    ///Number of adult passengers
+    adult_count: u8,
+    ///3-letter currency code (e.g., 'INR')
+    currency_code: String,
+) -> Result<CheapestFlightResult, TravelError> {
+    cheapest_flexible_date(
+        &source,
+        &destination,
+        &from_date,
+        &to_date,
+        adult_count,
+        &currency_code,
+    )
+    .await
+}
+
+async fn amadeus_flights_between(
+    source: &IataCode,
+    destination: &IataCode,
+    least_departure: &Date,
+    adult_count: u8,
+    currency_code: &str,
+    max_price: Option<f32>,
+    travel_class: Option<BookingClass>,
 ) -> Result<Vec<Flight>, Box<dyn std::error::Error + Send + Sync>> {
-    let client_id = env::var("AMADEUS_API_KEY")?;
-    let client_secret = env::var("AMADEUS_API_SECRET")?;
-
-    let token = get_bearer_token(&client_id, &client_secret).await?;
-
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(BASE_URL)
-        .header(AUTHORIZATION, format!("Bearer {}", token))
-        .query(&[
-            ("originLocationCode", source.to_string()),
-            ("destinationLocationCode", destination.to_string()),
-            ("departureDate", least_departure.to_yyyy_mm_dd()),
-            ("adults", adult_count.to_string()),
-            ("currencyCode", currency_code.to_string()),
-            ("max", "10".to_string()),
-        ])
-        .send()
-        .await?;
+    let mut query = vec![
+        ("originLocationCode", source.to_string()),
+        ("destinationLocationCode", destination.to_string()),
+        ("departureDate", least_departure.to_yyyy_mm_dd()),
+        ("adults", adult_count.to_string()),
+        ("currencyCode", currency_code.to_string()),
+        ("max", "10".to_string()),
+    ];
+    if let Some(max_price) = max_price {
+        // Amadeus only accepts whole numbers for maxPrice
+        query.push(("maxPrice", (max_price.floor() as u32).to_string()));
+    }
+    if let Some(class) = travel_class {
+        query.push(("travelClass", class.as_str().to_string()));
+    }
+
+    let response: AmadeusFlightResponse =
+        AmadeusClient::new().get(FLIGHT_OFFERS_PATH, &query).await?;
 
-    if !resp.status().is_success() {
-        let error_text = resp.text().await?;
-        return Err(format!("Amadeus API error: {}", error_text).into());
+    let mut raw_offers = Vec::new();
+    let mut flights = Vec::new();
+    for raw in response.data {
+        let flight = to_flight(serde_json::from_value(raw.clone())?);
+        raw_offers.push((flight.offer_key(), raw));
+        flights.push(flight);
     }
+    store_raw_offers(raw_offers).await;
 
-    let response: AmadeusFlightResponse = resp.json().await?;
+    Ok(flights)
+}
 
-    let flights = response
+async fn price_metrics_on(
+    client: &AmadeusClient,
+    source: &IataCode,
+    destination: &IataCode,
+    date: &Date,
+    currency_code: &str,
+) -> Result<Option<PricePoint>, TravelError> {
+    let response: AmadeusPriceMetricsResponse = client
+        .get(
+            PRICE_METRICS_PATH,
+            &[
+                ("originIataCode", source.to_string()),
+                ("destinationIataCode", destination.to_string()),
+                ("departureDate", date.to_yyyy_mm_dd()),
+                ("currencyCode", currency_code.to_string()),
+                ("oneWay", "true".to_string()),
+            ],
+        )
+        .await?;
+    Ok(response
         .data
+        .first()
+        .and_then(AmadeusPriceMetrics::median_price))
+}
+
+/// Median fares paid on the route on a few days of the month, oldest first. Days Amadeus has
+/// no fares for are left out.
+pub async fn fetch_price_history(
+    source: &IataCode,
+    destination: &IataCode,
+    departure_month: u8,
+    departure_year: u16,
+    currency_code: &str,
+) -> Result<Vec<PricePoint>, TravelError> {
+    check_param_length(currency_code, MAX_CODE_LEN)?;
+    let dates = PRICE_HISTORY_DAYS
+        .iter()
+        .map(|day| Date::new(departure_year, departure_month, *day))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(TravelError::InvalidInput)?;
+    if dates[0].to_naive_date().is_none() {
+        return Err(TravelError::InvalidInput(format!(
+            "Invalid month: {departure_month}/{departure_year}"
+        )));
+    }
+    let client = AmadeusClient::new();
+    let points = join_all(
+        dates
+            .iter()
+            .map(|date| price_metrics_on(&client, source, destination, date, currency_code)),
+    )
+    .await;
+    Ok(points
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?
         .into_iter()
-        .map(|offer| {
-            let currency = Currency::parse_currency(&offer.price.currency, &offer.price.total)
-                .unwrap_or(Currency::Usd(0.0));
-            Flight {
-                id: offer.id,
-                price: currency,
-                itineraries: offer
-                    .itineraries
+        .flatten()
+        .collect())
+}
+
+async fn departure_month_price_history(
+    source: &IataCode,
+    destination: &IataCode,
+    departure: &Date,
+    currency_code: &str,
+) -> Result<Vec<PricePoint>, TravelError> {
+    let departure = departure.to_naive_date().ok_or_else(|| {
+        TravelError::InvalidInput(format!("Invalid date: {}", departure.to_yyyy_mm_dd()))
+    })?;
+    fetch_price_history(
+        source,
+        destination,
+        departure.month() as u8,
+        departure.year() as u16,
+        currency_code,
+    )
+    .await
+}
+
+/// Taxes and fees are whatever the total has over the base fare, as Amadeus lists only some of
+/// them.
+fn fare_breakdown(offer: &AmadeusFlightOffer) -> Option<FareBreakdown> {
+    let price = &offer.traveler_pricings.first()?.price;
+    let total = Currency::parse_currency(&price.currency, &price.total).ok()?;
+    let base_fare = Currency::parse_currency(&price.currency, price.base.as_deref()?).ok()?;
+    let taxes_and_fees =
+        Currency::from_code(&price.currency, total.to_amount() - base_fare.to_amount()).ok()?;
+    Some(FareBreakdown {
+        base_fare,
+        taxes_and_fees,
+        total,
+    })
+}
+
+fn to_flight(offer: AmadeusFlightOffer) -> Flight {
+    let fare_breakdown = fare_breakdown(&offer);
+    let booking_class = offer
+        .traveler_pricings
+        .first()
+        .and_then(|pricing| pricing.fare_details_by_segment.first()?.cabin.as_deref())
+        .map(BookingClass::from)
+        .unwrap_or_default();
+    let currency = Currency::parse_currency(&offer.price.currency, &offer.price.total)
+        .unwrap_or(Currency::Usd(0.0));
+    let carrier_code = offer
+        .validating_airline_codes
+        .into_iter()
+        .next()
+        .or_else(|| {
+            let segment = offer.itineraries.first()?.segments.first()?;
+            Some(segment.carrier_code.clone())
+        })
+        .unwrap_or_default();
+    Flight {
+        id: offer.id,
+        carrier_code,
+        price: currency,
+        itineraries: offer
+            .itineraries
+            .into_iter()
+            .map(|iti| Itinerary {
+                duration: iti.duration,
+                segments: iti
+                    .segments
                     .into_iter()
-                    .map(|iti| Itinerary {
-                        duration: iti.duration,
-                        segments: iti
-                            .segments
-                            .into_iter()
-                            .map(|seg| Segment {
-                                departure: Endpoint {
-                                    iata_code: seg.departure.iata_code,
-                                    at: seg.departure.at,
-                                },
-                                arrival: Endpoint {
-                                    iata_code: seg.arrival.iata_code,
-                                    at: seg.arrival.at,
-                                },
-                                carrier_code: seg.carrier_code,
-                                number: seg.number,
-                                duration: seg.duration,
-                            })
-                            .collect(),
+                    .map(|seg| Segment {
+                        departure: Endpoint {
+                            iata_code: seg.departure.iata_code,
+                            at: seg.departure.at,
+                            terminal: seg.departure.terminal,
+                        },
+                        arrival: Endpoint {
+                            iata_code: seg.arrival.iata_code,
+                            at: seg.arrival.at,
+                            terminal: seg.arrival.terminal,
+                        },
+                        carrier_code: seg.carrier_code,
+                        number: seg.number,
+                        duration: seg.duration,
+                        operating_carrier: seg
+                            .operating
+                            .as_ref()
+                            .and_then(|operating| operating.carrier_code.clone()),
+                        operating_flight_number: seg
+                            .operating
+                            .and_then(|operating| operating.number),
                     })
                     .collect(),
-            }
+            })
+            .collect(),
+        overnight: false,
+        early_morning: false,
+        long_haul: false,
+        journey_time: None,
+        fare_breakdown,
+        terminal_info: None,
+        domestic: false,
+        booking_class,
+        is_good_price: false,
+    }
+    .with_derived_fields()
+}
+
+/// Updates the `price` and `id` of `offer` from an Amadeus pricing response.
+fn apply_pricing(
+    offer: &Flight,
+    response: AmadeusPricingResponse,
+) -> Result<(Flight, Value), TravelError> {
+    let raw = response
+        .data
+        .flight_offers
+        .into_iter()
+        .next()
+        .ok_or_else(|| TravelError::Api("Pricing response has no flight offer".into()))?;
+    let priced: AmadeusFlightOffer = serde_json::from_value(raw.clone())
+        .map_err(|e| TravelError::Api(format!("Invalid pricing response: {e}")))?;
+    let price = Currency::parse_currency(&priced.price.currency, &priced.price.total)
+        .map_err(|e| TravelError::Api(format!("Invalid price in pricing response: {e}")))?;
+    let mut flight = offer.clone();
+    flight.fare_breakdown = fare_breakdown(&priced).or(flight.fare_breakdown);
+    flight.id = priced.id;
+    flight.price = price;
+    Ok((flight, raw))
+}
+
+/// Offer as Amadeus sent it, which it needs back for follow-up requests. Only offers from a
+/// `flights_between` search served by this instance are known.
+async fn raw_offer(offer: &Flight) -> Result<Value, TravelError> {
+    RAW_OFFERS
+        .read()
+        .await
+        .get(&offer.offer_key())
+        .filter(|(_, received)| is_raw_offer_fresh(received))
+        .map(|(raw, _)| raw.clone())
+        .ok_or_else(|| {
+            TravelError::InvalidInput(format!(
+                "Flight offer {} has expired, search the flights again",
+                offer.id
+            ))
         })
-        .collect();
+}
 
-    Ok(flights)
+/// Like `raw_offer`, but by the Amadeus offer id. Ids repeat across searches, so the offer of
+/// the latest search with the id is taken.
+async fn raw_offer_by_id(offer_id: &str) -> Result<Value, TravelError> {
+    let offer_id = check_param_length(offer_id.trim(), MAX_OFFER_ID_LEN)?;
+    let prefix = format!("{offer_id}:");
+    RAW_OFFERS
+        .read()
+        .await
+        .iter()
+        .filter(|(key, (_, received))| key.starts_with(&prefix) && is_raw_offer_fresh(received))
+        .max_by_key(|(_, (_, received))| *received)
+        .map(|(_, (raw, _))| raw.clone())
+        .ok_or_else(|| {
+            TravelError::InvalidInput(format!(
+                "Flight offer {offer_id} has expired, search the flights again"
+            ))
+        })
+}
+
+/// Confirms the current price of `offer` with Amadeus. `offer` must come from a
+/// `flights_between` search served by this instance.
+pub async fn reprice_flight(offer: &Flight) -> Result<Flight, TravelError> {
+    let raw = raw_offer(offer).await?;
+    let response: AmadeusPricingResponse = AmadeusClient::new()
+        .post(
+            PRICING_PATH,
+            &json!({
+                "data": {
+                    "type": "flight-offers-pricing",
+                    "flightOffers": [raw],
+                }
+            }),
+        )
+        .await?;
+
+    let (flight, raw) = apply_pricing(offer, response)?;
+    store_raw_offers([(flight.offer_key(), raw)]).await;
+    Ok(flight)
+}
+
+#[gemini_function]
+///Confirm the current price of a flight offer from `flights_between`. Call it before presenting a final booking recommendation, offers go stale within minutes.
+pub async fn confirm_flight_price(flight: Flight) -> Result<Flight, TravelError> {
+    reprice_flight(&flight).await
+}
+
+/// What the fare family of a flight offer includes and costs to cancel or change.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlightCancellationInfo {
+    ///Name of the fare family, e.g. 'ECO FLEX'
+    pub fare_family: Option<String>,
+    pub refundable: bool,
+    ///Zero when cancelling is free. `None` when the ticket is not refundable or the airline
+    ///charges a fee Amadeus doesn't state
+    pub cancellation_fee: Option<Currency>,
+    pub changeable: bool,
+    ///Zero when changes are free. `None` when the ticket can't be changed or the fee isn't
+    ///stated
+    pub change_fee: Option<Currency>,
+    pub seat_selection_included: bool,
+    pub meal_included: bool,
+    pub lounge_access: bool,
+}
+
+/// `brandedFare` code of the first segment of an Amadeus offer, e.g. `ECOFLEX`.
+fn branded_fare(offer: &Value) -> Option<&str> {
+    offer["travelerPricings"][0]["fareDetailsBySegment"][0]["brandedFare"].as_str()
+}
+
+/// Amenity descriptions like `NON REFUNDABLE`, `NONREFUNDABLE` or `NO SEAT SELECTION` say the
+/// fare doesn't have it.
+fn is_negated(description: &str) -> bool {
+    description
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| word.starts_with("NON") || matches!(word, "NO" | "NOT" | "WITHOUT"))
+}
+
+/// Reads the fare rules from the amenities of the first segment, as a fare family applies to
+/// the whole ticket. An amenity counts as included when the airline doesn't charge for it.
+fn fare_rules(offer: &Value) -> FlightCancellationInfo {
+    let details = &offer["travelerPricings"][0]["fareDetailsBySegment"][0];
+    let amenities = details["amenities"].as_array().cloned().unwrap_or_default();
+    // `Some(true)` when the amenity is charged for, `None` when the fare doesn't have it
+    let chargeable = |types: &[&str], words: &[&str]| {
+        let description = |amenity: &Value| {
+            amenity["description"]
+                .as_str()
+                .unwrap_or_default()
+                .to_ascii_uppercase()
+        };
+        amenities
+            .iter()
+            .find(|amenity| {
+                let kind = amenity["amenityType"].as_str().unwrap_or_default();
+                let description = description(amenity);
+                types.contains(&kind) || words.iter().any(|word| description.contains(word))
+            })
+            .filter(|amenity| !is_negated(&description(amenity)))
+            .map(|amenity| amenity["isChargeable"].as_bool().unwrap_or(true))
+    };
+    let currency = offer["price"]["currency"].as_str().unwrap_or_default();
+    let free = || Currency::zero(currency).ok();
+    let refund = chargeable(&[], &["REFUND"]);
+    let change = chargeable(&[], &["CHANGE"]);
+    FlightCancellationInfo {
+        fare_family: details["brandedFareLabel"]
+            .as_str()
+            .or(details["brandedFare"].as_str())
+            .map(str::to_string),
+        refundable: refund.is_some(),
+        cancellation_fee: refund.filter(|charged| !charged).and_then(|_| free()),
+        changeable: change.is_some(),
+        change_fee: change.filter(|charged| !charged).and_then(|_| free()),
+        seat_selection_included: chargeable(&["PRE_RESERVED_SEAT"], &["SEAT"]) == Some(false),
+        meal_included: chargeable(&["MEAL"], &["MEAL", "SNACK"]) == Some(false),
+        lounge_access: chargeable(&["LOUNGE"], &["LOUNGE"]) == Some(false),
+    }
+}
+
+/// Amadeus answers with every fare family of the flight, the booked one is picked by its
+/// branded fare code. Offers without one have no known fare family.
+fn booked_fare_rules(
+    offer: &Value,
+    upsell_offers: &[Value],
+) -> Result<FlightCancellationInfo, TravelError> {
+    let booked = branded_fare(offer).ok_or_else(|| {
+        TravelError::Api("The airline publishes no fare family for the flight offer".into())
+    })?;
+    upsell_offers
+        .iter()
+        .find(|upsell| branded_fare(upsell) == Some(booked))
+        .map(fare_rules)
+        .ok_or_else(|| TravelError::Api("No fare rules found for the flight offer".into()))
+}
+
+/// `offer_id` is the `id` of a flight offer from a `flights_between` search served by this
+/// instance.
+pub async fn flight_cancellation_policy(
+    offer_id: &str,
+) -> Result<FlightCancellationInfo, TravelError> {
+    let raw = raw_offer_by_id(offer_id).await?;
+    let response: Value = AmadeusClient::new()
+        .post(
+            UPSELLING_PATH,
+            &json!({
+                "data": {
+                    "type": "flight-offers-upselling",
+                    "flightOffers": [raw],
+                }
+            }),
+        )
+        .await?;
+    let upsell_offers = response["data"].as_array().cloned().unwrap_or_default();
+    booked_fare_rules(&raw, &upsell_offers)
+}
+
+#[gemini_function]
+///Get the fare rules of a flight offer from `flights_between`: whether it can be cancelled or changed and for what fee, and whether seat selection, meals and lounge access are included. Does not work for budget airlines, their offers already carry the fees.
+pub async fn flight_fare_rules(
+    ///`id` of the flight offer
+    offer_id: String,
+) -> Result<FlightCancellationInfo, TravelError> {
+    flight_cancellation_policy(&offer_id).await
 }
 
 #[gemini_function]
@@ -161,25 +1173,10 @@ pub async fn flight_seats_available(
     ///The unique ID of the flight offer
     flight_offer_id: String,
 ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
-    let client_id = env::var("AMADEUS_API_KEY")?;
-    let client_secret = env::var("AMADEUS_API_SECRET")?;
-
-    let token = get_bearer_token(&client_id, &client_secret).await?;
-
-    let client = reqwest::Client::new();
-    let resp = client
-        .get("https://test.api.amadeus.com/v1/shopping/seatmaps")
-        .header(AUTHORIZATION, format!("Bearer {}", token))
-        .query(&[("flight-offerId", flight_offer_id)])
-        .send()
+    check_param_length(&flight_offer_id, MAX_OFFER_ID_LEN)?;
+    let response: serde_json::Value = AmadeusClient::new()
+        .get(SEATMAPS_PATH, &[("flight-offerId", flight_offer_id)])
         .await?;
-
-    if !resp.status().is_success() {
-        let error_text = resp.text().await?;
-        return Err(format!("Amadeus API error: {}", error_text).into());
-    }
-
-    let response: serde_json::Value = resp.json().await?;
     Ok(response)
 }
 
@@ -196,7 +1193,784 @@ async fn flights_between_test() {
     let adult_count = 1;
     let currency = "USD".to_string();
 
-    let result = flights_between(source, destination, departure_date, adult_count, currency).await;
+    let result = flights_between(
+        source,
+        destination,
+        departure_date,
+        adult_count,
+        currency,
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
 
     dbg!(result.unwrap());
 }
+
+#[tokio::test]
+async fn flights_between_max_price_test() {
+    if std::env::var("AMADEUS_API_KEY").is_err() || std::env::var("AMADEUS_API_SECRET").is_err() {
+        println!("Skipping integration test: Amadeus credentials not found in env");
+        return;
+    }
+
+    let max_price = 300.0;
+    let flights = flights_between(
+        IataCode::new("JFK".to_string()).unwrap(),
+        IataCode::new("LAX".to_string()).unwrap(),
+        Date::new(2026, 1, 25).unwrap(),
+        1,
+        "USD".to_string(),
+        Some(max_price),
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    for offer in flights.flights {
+        match &offer.flight().price {
+            Currency::Usd(price) => assert!(*price <= max_price, "{price} > {max_price}"),
+            other => panic!("Expected USD price, found {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn flight_timing_flags_test() {
+    let flight = |departure: &str, arrival: &str| Flight {
+        id: "1".into(),
+        price: Currency::Inr(5000.0),
+        itineraries: vec![Itinerary {
+            duration: "PT2H".into(),
+            segments: vec![Segment {
+                departure: Endpoint {
+                    iata_code: "IXR".into(),
+                    at: format!("2026-02-15T{departure}"),
+                    terminal: None,
+                },
+                arrival: Endpoint {
+                    iata_code: "GOI".into(),
+                    at: format!("2026-02-15T{arrival}"),
+                    terminal: None,
+                },
+                carrier_code: "6E".into(),
+                number: "512".into(),
+                duration: "PT2H".into(),
+                operating_carrier: None,
+                operating_flight_number: None,
+            }],
+        }],
+        carrier_code: "6E".into(),
+        overnight: false,
+        early_morning: false,
+        long_haul: false,
+        journey_time: None,
+        fare_breakdown: None,
+        terminal_info: None,
+        domestic: false,
+        booking_class: BookingClass::Economy,
+        is_good_price: false,
+    };
+
+    assert!(!flight("20:59:00", "23:00:00").is_overnight());
+    assert!(flight("21:00:00", "23:00:00").is_overnight());
+    assert!(flight("23:30:00", "05:59:00").is_overnight());
+    assert!(!flight("08:00:00", "06:00:00").is_overnight());
+
+    assert!(flight("00:00:00", "02:00:00").is_early_morning());
+    assert!(flight("05:59:00", "08:00:00").is_early_morning());
+    assert!(!flight("06:00:00", "08:00:00").is_early_morning());
+
+    assert_eq!(
+        flight("08:00:00", "10:00:00").duration(),
+        Some(TravelDuration::new_from_minutes(120))
+    );
+
+    let mut no_segments = flight("03:00:00", "04:00:00");
+    no_segments.itineraries.clear();
+    assert!(!no_segments.is_overnight() && !no_segments.is_early_morning());
+    assert!(no_segments.duration().is_none());
+}
+
+#[test]
+fn long_haul_test() {
+    let flight = |duration: &str| {
+        serde_json::from_value::<Flight>(json!({
+            "id": "1",
+            "price": {"Inr": 42000.0},
+            "itineraries": [{"duration": duration, "segments": []}],
+            "journey_time": null
+        }))
+        .unwrap()
+        .with_derived_fields()
+    };
+    assert!(!flight("PT2H15M").is_long_haul());
+    assert!(!flight("PT5H").long_haul);
+    assert!(flight("PT5H1M").long_haul);
+    assert!(flight("PT9H40M").is_long_haul());
+    assert!(flight("P1DT2H").is_long_haul());
+    assert!(!flight("unknown").is_long_haul());
+}
+
+#[test]
+fn operating_carrier_test() {
+    let flight = to_flight(
+        serde_json::from_value(json!({
+            "id": "1",
+            "price": {"currency": "INR", "total": "7450.00"},
+            "itineraries": [{
+                "duration": "PT2H10M",
+                "segments": [
+                    {
+                        "departure": {"iataCode": "DEL", "terminal": "3", "at": "2026-02-15T07:00:00"},
+                        "arrival": {"iataCode": "BOM", "terminal": "2", "at": "2026-02-15T09:10:00"},
+                        "carrierCode": "AI",
+                        "number": "9641",
+                        "duration": "PT2H10M",
+                        "operating": {"carrierCode": "6E", "number": "2131"}
+                    },
+                    {
+                        "departure": {"iataCode": "BOM", "at": "2026-02-15T11:00:00"},
+                        "arrival": {"iataCode": "GOI", "at": "2026-02-15T12:10:00"},
+                        "carrierCode": "AI",
+                        "number": "663",
+                        "duration": "PT1H10M",
+                        "operating": {"carrierCode": "AI"}
+                    }
+                ]
+            }],
+            "validatingAirlineCodes": ["AI"]
+        }))
+        .unwrap(),
+    );
+    let segments = &flight.itineraries[0].segments;
+    assert_eq!(segments[0].carrier_code, "AI");
+    assert_eq!(segments[0].operating_carrier.as_deref(), Some("6E"));
+    assert_eq!(segments[0].operating_flight_number.as_deref(), Some("2131"));
+    assert_eq!(segments[1].operating_carrier.as_deref(), Some("AI"));
+    assert_eq!(segments[0].departure.terminal.as_deref(), Some("3"));
+    assert_eq!(segments[1].departure.terminal, None);
+    assert!(segments[1].operating_flight_number.is_none());
+
+    // Older cached flights have no operating carrier
+    let value = json!({
+        "departure": {"iata_code": "DEL", "at": "2026-02-15T07:00:00"},
+        "arrival": {"iata_code": "BOM", "at": "2026-02-15T09:10:00"},
+        "carrier_code": "AI",
+        "number": "9641",
+        "duration": "PT2H10M"
+    });
+    let segment: Segment = serde_json::from_value(value).unwrap();
+    assert!(segment.operating_carrier.is_none());
+}
+
+#[test]
+fn airport_terminal_info_test() {
+    let flight = to_flight(
+        serde_json::from_value(json!({
+            "id": "1",
+            "price": {"currency": "INR", "total": "7450.00"},
+            "itineraries": [{
+                "duration": "PT4H10M",
+                "segments": [
+                    {
+                        "departure": {"iataCode": "DEL", "terminal": "3", "at": "2026-02-15T07:00:00"},
+                        "arrival": {"iataCode": "BOM", "terminal": "2", "at": "2026-02-15T09:10:00"},
+                        "carrierCode": "AI",
+                        "number": "9641",
+                        "duration": "PT2H10M"
+                    },
+                    {
+                        "departure": {"iataCode": "BOM", "terminal": "2", "at": "2026-02-15T10:00:00"},
+                        "arrival": {"iataCode": "GOI", "at": "2026-02-15T11:10:00"},
+                        "carrierCode": "AI",
+                        "number": "663",
+                        "duration": "PT1H10M"
+                    }
+                ]
+            }]
+        }))
+        .unwrap(),
+    );
+    // Only filled in when the search asks for it
+    assert!(flight.terminal_info.is_none());
+
+    let info = flight.airport_terminal_info().unwrap();
+    assert_eq!(info.departure_terminal.as_deref(), Some("3"));
+    assert_eq!(info.arrival_terminal, None);
+    assert_eq!(
+        info.departure_map_url,
+        "https://www.google.com/maps/search/?api=1&query=28.5562%2C77.0870"
+    );
+    assert_eq!(
+        info.arrival_map_url,
+        "https://www.google.com/maps/search/?api=1&query=GOI%20airport"
+    );
+
+    let mut no_segments = flight.clone();
+    no_segments.itineraries.clear();
+    assert!(no_segments.airport_terminal_info().is_none());
+}
+
+#[test]
+fn apply_pricing_test() {
+    let search_fixture = json!({
+        "id": "1",
+        "price": {"currency": "INR", "total": "5120.00", "base": "4300.00"},
+        "itineraries": [{
+            "duration": "PT2H15M",
+            "segments": [{
+                "departure": {"iataCode": "IXR", "at": "2026-02-15T06:30:00"},
+                "arrival": {"iataCode": "GOI", "at": "2026-02-15T08:45:00"},
+                "carrierCode": "6E",
+                "number": "512",
+                "duration": "PT2H15M"
+            }]
+        }],
+        "validatingAirlineCodes": ["6E"]
+    });
+    let offer = to_flight(serde_json::from_value(search_fixture.clone()).unwrap());
+    assert_eq!(offer.offer_key(), "1:6E512@2026-02-15T06:30:00");
+
+    let mut priced_offer = search_fixture;
+    priced_offer["id"] = json!("7");
+    priced_offer["price"]["total"] = json!("5480.50");
+    let response: AmadeusPricingResponse = serde_json::from_value(json!({
+        "data": {"type": "flight-offers-pricing", "flightOffers": [priced_offer]}
+    }))
+    .unwrap();
+
+    let (flight, raw) = apply_pricing(&offer, response).unwrap();
+    assert_eq!(flight.id, "7");
+    assert!(matches!(flight.price, Currency::Inr(total) if total == 5480.5));
+    assert_eq!(flight.journey_time.as_deref(), Some("2h 15m"));
+    assert_eq!(raw["validatingAirlineCodes"][0], "6E");
+
+    let empty: AmadeusPricingResponse =
+        serde_json::from_value(json!({"data": {"flightOffers": []}})).unwrap();
+    assert!(apply_pricing(&offer, empty).is_err());
+}
+
+#[test]
+fn fare_breakdown_test() {
+    let fixture = |traveler_pricings: Value| -> AmadeusFlightOffer {
+        serde_json::from_value(json!({
+            "id": "1",
+            "price": {
+                "currency": "INR",
+                "total": "10240.00",
+                "base": "8600.00",
+                "fees": [
+                    {"amount": "0.00", "type": "SUPPLIER"},
+                    {"amount": "0.00", "type": "TICKETING"}
+                ],
+                "grandTotal": "10240.00"
+            },
+            "itineraries": [{
+                "duration": "PT2H15M",
+                "segments": [{
+                    "departure": {"iataCode": "IXR", "at": "2026-02-15T06:30:00"},
+                    "arrival": {"iataCode": "GOI", "at": "2026-02-15T08:45:00"},
+                    "carrierCode": "6E",
+                    "number": "512",
+                    "duration": "PT2H15M"
+                }]
+            }],
+            "validatingAirlineCodes": ["6E"],
+            "travelerPricings": traveler_pricings
+        }))
+        .unwrap()
+    };
+    let traveler = |id: &str, total: &str, base: &str| {
+        json!({
+            "travelerId": id,
+            "fareOption": "STANDARD",
+            "travelerType": "ADULT",
+            "price": {"currency": "INR", "total": total, "base": base}
+        })
+    };
+
+    let flight = to_flight(fixture(json!([
+        traveler("1", "5120.00", "4300.00"),
+        traveler("2", "5120.00", "4300.00")
+    ])));
+    let breakdown = flight.fare_breakdown.unwrap();
+    assert!(matches!(breakdown.base_fare, Currency::Inr(base) if base == 4300.0));
+    assert!(matches!(breakdown.taxes_and_fees, Currency::Inr(taxes) if taxes == 820.0));
+    assert!(matches!(breakdown.total, Currency::Inr(total) if total == 5120.0));
+    assert_eq!(breakdown.taxes_and_fees.to_string(), "₹820");
+    assert!(matches!(flight.price, Currency::Inr(total) if total == 10240.0));
+
+    assert!(to_flight(fixture(json!([]))).fare_breakdown.is_none());
+    let without_base =
+        json!([{"travelerId": "1", "price": {"currency": "INR", "total": "5120.00"}}]);
+    assert!(to_flight(fixture(without_base)).fare_breakdown.is_none());
+}
+
+#[test]
+fn flight_collection_test() {
+    let offer = |id: &str, validating: &[&str], carriers: &[&str]| {
+        let segments: Vec<Value> = carriers
+            .iter()
+            .enumerate()
+            .map(|(i, carrier)| {
+                json!({
+                    "departure": {"iataCode": "DEL", "at": format!("2026-02-15T0{}:00:00", 6 + i)},
+                    "arrival": {"iataCode": "BOM", "at": format!("2026-02-15T0{}:30:00", 7 + i)},
+                    "carrierCode": carrier,
+                    "number": "100",
+                    "duration": "PT1H30M"
+                })
+            })
+            .collect();
+        to_flight(
+            serde_json::from_value(json!({
+                "id": id,
+                "price": {"currency": "INR", "total": "6000.00"},
+                "itineraries": [{"duration": "PT2H", "segments": segments}],
+                "validatingAirlineCodes": validating
+            }))
+            .unwrap(),
+        )
+    };
+    let flights = FlightCollection(
+        vec![
+            offer("1", &["AI"], &["AI"]),
+            offer("2", &["6E"], &["6E"]),
+            // Codeshare sold by Air India and flown by Vistara
+            offer("3", &["AI"], &["UK", "AI"]),
+            offer("4", &[], &["SG"]),
+            offer("5", &["UK"], &["UK"]),
+        ]
+        .into_iter()
+        .map(FlightOffer::from)
+        .collect(),
+    );
+    assert_eq!(flights.0[3].flight().carrier_code, "SG");
+
+    let ids = |flights: Vec<&Flight>| -> Vec<String> {
+        flights
+            .into_iter()
+            .map(|flight| flight.id.clone())
+            .collect()
+    };
+    assert_eq!(ids(flights.filter_by_airline("AI")), ["1", "3"]);
+    assert_eq!(ids(flights.filter_by_airline(" 6e ")), ["2"]);
+    assert!(flights.filter_by_airline("EK").is_empty());
+
+    let excluded = flights.exclude_airlines(&["ai", "SG"]);
+    assert_eq!(
+        ids(excluded.0.iter().map(FlightOffer::flight).collect()),
+        ["2", "5"]
+    );
+    assert_eq!(excluded.exclude_airlines(&[]).0.len(), 2);
+}
+
+#[test]
+fn compare_prices_test() {
+    let flight = |id: &str, price: Currency| {
+        serde_json::from_value::<Flight>(json!({
+            "id": id,
+            "price": price,
+            "itineraries": [],
+            "journey_time": null
+        }))
+        .unwrap()
+    };
+    let prices = [
+        6200.0, 4850.0, 7300.0, 5100.0, 9900.0, 5600.0, 6800.0, 12400.0, 5300.0, 6000.0,
+    ];
+    let flights: Vec<Flight> = prices
+        .iter()
+        .enumerate()
+        .map(|(i, price)| flight(&i.to_string(), Currency::Inr(*price)))
+        .collect();
+    assert_eq!(flights.len(), 10);
+
+    let summary = compare_prices(&flights).unwrap();
+    assert!(matches!(summary.min_price, Currency::Inr(min) if min == 4850.0));
+    assert!(matches!(summary.max_price, Currency::Inr(max) if max == 12400.0));
+    assert!(matches!(summary.mean_price, Currency::Inr(mean) if (mean - 6945.0).abs() < 0.01));
+    // Middle of 6000 and 6200
+    assert!(matches!(summary.median_price, Currency::Inr(median) if median == 6100.0));
+    assert!(matches!(summary.price_range, Currency::Inr(range) if range == 7550.0));
+
+    let odd = compare_prices(&flights[..3]).unwrap();
+    assert_eq!(odd.median_price.to_amount(), 6200.0);
+    let single = compare_prices(&flights[..1]).unwrap();
+    assert_eq!(single.median_price.to_amount(), 6200.0);
+    assert_eq!(single.price_range.to_amount(), 0.0);
+    assert!(compare_prices(&[]).is_none());
+
+    // Flights in another currency are left out
+    let mut mixed = flights[..2].to_vec();
+    mixed.push(flight("usd", Currency::Usd(90.0)));
+    let summary = compare_prices(&mixed).unwrap();
+    assert_eq!(summary.min_price.to_amount(), 4850.0);
+    assert_eq!(summary.price_range.to_amount(), 1350.0);
+
+    let response = serde_json::to_value(
+        flights
+            .iter()
+            .cloned()
+            .map(FlightOffer::from)
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    let response = json!({"flights": response, "price_history": null});
+    let wrapped = with_price_summary(response.clone());
+    assert_eq!(wrapped["flights"], response["flights"]);
+    assert_eq!(wrapped["price_history"], Value::Null);
+    assert_eq!(wrapped["price_summary"]["median_price"]["Inr"], 6100.0);
+    let error = json!({"Error": "No flights"});
+    assert_eq!(with_price_summary(error.clone()), error);
+    let empty = json!({"flights": [], "price_history": null});
+    assert_eq!(with_price_summary(empty.clone()), empty);
+}
+
+#[test]
+fn cheapest_by_date_test() {
+    let offer = |id: &str, price: f32| {
+        FlightOffer::from(
+            serde_json::from_value::<Flight>(json!({
+                "id": id,
+                "price": {"Inr": price},
+                "itineraries": [],
+                "journey_time": null
+            }))
+            .unwrap(),
+        )
+    };
+    let date = |day: u8| Date::new(2026, 3, day).unwrap();
+    let cheapest = cheapest_by_date(
+        vec![
+            (date(4), vec![offer("e", 4850.0)]),
+            (date(1), vec![offer("a", 6200.0), offer("b", 5400.0)]),
+            (date(2), vec![]),
+            (date(3), vec![offer("c", 4850.0), offer("d", 7100.0)]),
+        ],
+        1,
+    )
+    .unwrap();
+    assert_eq!(cheapest.date, date(3));
+    assert_eq!(cheapest.flight.flight().id, "c");
+    assert_eq!(cheapest.failed_dates, 1);
+
+    assert!(cheapest_by_date(vec![(date(1), vec![]), (date(2), vec![])], 0).is_none());
+    assert!(cheapest_by_date(vec![], 0).is_none());
+}
+
+#[tokio::test]
+async fn search_dates_test() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let date = |day: u8| Date::new(2026, 3, day).unwrap();
+    let (running, most_running) = (&AtomicUsize::new(0), &AtomicUsize::new(0));
+    let search = |date: Date| async move {
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        most_running.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        running.fetch_sub(1, Ordering::SeqCst);
+        match date.to_yyyy_mm_dd().as_str() {
+            "2026-03-02" | "2026-03-05" => Err("Amadeus error: 500".into()),
+            _ => Ok(Vec::new()),
+        }
+    };
+    let (by_date, failed_dates) = search_dates((1..=12).map(date).collect(), search).await;
+    assert_eq!(by_date.len(), 10);
+    assert_eq!(failed_dates, 2);
+    assert_eq!(
+        most_running.load(Ordering::SeqCst),
+        MAX_CONCURRENT_DATE_SEARCHES
+    );
+}
+
+#[tokio::test]
+async fn cheapest_flexible_date_range_test() {
+    let code = |code: &str| IataCode::new(code.to_string()).unwrap();
+    let (del, goi) = (code("DEL"), code("GOI"));
+    let search = |from: Date, to: Date| {
+        let (del, goi) = (del.clone(), goi.clone());
+        async move { cheapest_flexible_date(&del, &goi, &from, &to, 1, "INR").await }
+    };
+    assert!(matches!(
+        search(
+            Date::new(2026, 3, 10).unwrap(),
+            Date::new(2026, 3, 9).unwrap()
+        )
+        .await,
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        search(Date::new(2026, 3, 1).unwrap(), Date::new(2026, 3, 31).unwrap()).await,
+        Err(TravelError::InvalidInput(msg)) if msg.contains("Found: 31 days")
+    ));
+}
+
+#[test]
+fn domestic_flight_test() {
+    let flight = |airports: &[&str]| {
+        let segments: Vec<Value> = airports
+            .windows(2)
+            .map(|pair| {
+                json!({
+                    "departure": {"iataCode": pair[0], "at": "2026-02-15T07:00:00"},
+                    "arrival": {"iataCode": pair[1], "at": "2026-02-15T09:00:00"},
+                    "carrierCode": "AI",
+                    "number": "101",
+                    "duration": "PT2H"
+                })
+            })
+            .collect();
+        to_flight(
+            serde_json::from_value(json!({
+                "id": "1",
+                "price": {"currency": "INR", "total": "7450.00"},
+                "itineraries": [{"duration": "PT4H", "segments": segments}]
+            }))
+            .unwrap(),
+        )
+    };
+    assert!(flight(&["DEL", "BOM"]).is_domestic());
+    assert!(flight(&["IXR", "DEL", "GOI"]).is_domestic());
+    assert!(!flight(&["DEL", "JFK"]).is_domestic());
+    // A layover abroad needs a passport
+    assert!(!flight(&["DEL", "DXB", "BOM"]).is_domestic());
+    assert!(!flight(&["DEL"]).is_domestic());
+}
+
+#[test]
+fn fare_rules_test() {
+    let amenity = |description: &str, kind: &str, chargeable: bool| {
+        json!({
+            "description": description,
+            "isChargeable": chargeable,
+            "amenityType": kind,
+            "amenityProvider": {"name": "BrandedFare"}
+        })
+    };
+    let offer = |fare: &str, label: &str, amenities: Vec<Value>| {
+        json!({
+            "type": "flight-offer",
+            "id": "1",
+            "price": {"currency": "INR", "total": "9850.00"},
+            "travelerPricings": [{
+                "travelerId": "1",
+                "fareDetailsBySegment": [{
+                    "segmentId": "1",
+                    "cabin": "ECONOMY",
+                    "brandedFare": fare,
+                    "brandedFareLabel": label,
+                    "amenities": amenities
+                }]
+            }]
+        })
+    };
+    let saver = offer(
+        "ECOSAVER",
+        "ECO SAVER",
+        vec![
+            amenity("CHANGEABLE TICKET", "BRANDED_FARES", true),
+            amenity("PRE RESERVED SEAT ASSIGNMENT", "PRE_RESERVED_SEAT", true),
+            amenity("MEAL SERVICES", "MEAL", false),
+        ],
+    );
+    let light = offer(
+        "ECOLIGHT",
+        "ECO LIGHT",
+        vec![
+            amenity("NON REFUNDABLE TICKET", "BRANDED_FARES", true),
+            amenity("NON-CHANGEABLE TICKET", "BRANDED_FARES", true),
+            amenity("NO SEAT SELECTION", "PRE_RESERVED_SEAT", false),
+        ],
+    );
+    let flex = offer(
+        "ECOFLEX",
+        "ECO FLEX",
+        vec![
+            amenity("REFUNDABLE TICKET", "BRANDED_FARES", false),
+            amenity("CHANGEABLE TICKET", "BRANDED_FARES", false),
+            amenity("PRE RESERVED SEAT ASSIGNMENT", "PRE_RESERVED_SEAT", false),
+            amenity("MEAL SERVICES", "MEAL", false),
+            amenity("LOUNGE ACCESS", "LOUNGE", false),
+        ],
+    );
+    let upsell = [light.clone(), saver.clone(), flex.clone()];
+
+    let rules = booked_fare_rules(&saver, &upsell).unwrap();
+    assert_eq!(rules.fare_family.as_deref(), Some("ECO SAVER"));
+    assert!(!rules.refundable && rules.cancellation_fee.is_none());
+    // Changes cost a fee Amadeus doesn't give
+    assert!(rules.changeable && rules.change_fee.is_none());
+    assert!(!rules.seat_selection_included);
+    assert!(rules.meal_included);
+    assert!(!rules.lounge_access);
+
+    let rules = booked_fare_rules(&flex, &upsell).unwrap();
+    assert_eq!(rules.fare_family.as_deref(), Some("ECO FLEX"));
+    assert!(rules.refundable);
+    assert_eq!(rules.cancellation_fee.map(|fee| fee.to_amount()), Some(0.0));
+    assert_eq!(
+        rules.change_fee.map(|fee| fee.currency_code().to_string()),
+        Some("INR".into())
+    );
+    assert!(rules.seat_selection_included && rules.lounge_access);
+
+    let rules = booked_fare_rules(&light, &upsell).unwrap();
+    assert_eq!(rules.fare_family.as_deref(), Some("ECO LIGHT"));
+    assert!(!rules.refundable && rules.cancellation_fee.is_none());
+    assert!(!rules.changeable && rules.change_fee.is_none());
+    assert!(!rules.seat_selection_included);
+
+    // Without a fare family of its own no upsell offer is taken for it
+    let unbranded = json!({"id": "1", "price": {"currency": "INR", "total": "5000.00"}});
+    assert!(booked_fare_rules(&unbranded, &upsell).is_err());
+    let business = offer("BUSINESS", "BUSINESS", vec![]);
+    assert!(booked_fare_rules(&business, &upsell).is_err());
+    assert!(booked_fare_rules(&saver, &[]).is_err());
+}
+
+#[tokio::test]
+async fn raw_offer_by_id_test() {
+    let older = Instant::now();
+    let newer = older + Duration::from_secs(1);
+    RAW_OFFERS.write().await.extend([
+        (
+            "RAW-TEST:AI101@2026-02-15T06:00:00".to_string(),
+            (json!({"search": 1}), older),
+        ),
+        (
+            "RAW-TEST:6E202@2026-02-16T09:00:00".to_string(),
+            (json!({"search": 2}), newer),
+        ),
+        (
+            "RAW-TEST-2:AI101@2026-02-15T06:00:00".to_string(),
+            (json!({"search": 3}), newer),
+        ),
+    ]);
+    assert_eq!(
+        raw_offer_by_id(" RAW-TEST ").await.unwrap(),
+        json!({"search": 2})
+    );
+    assert!(matches!(
+        raw_offer_by_id("RAW").await,
+        Err(TravelError::InvalidInput(_))
+    ));
+
+    // Expired offers can't be found and are dropped once new ones come in
+    let expired = Instant::now() - RAW_OFFER_TTL;
+    RAW_OFFERS.write().await.insert(
+        "RAW-EXPIRED:AI101@2026-02-15T06:00:00".to_string(),
+        (json!({"search": 4}), expired),
+    );
+    assert!(raw_offer_by_id("RAW-EXPIRED").await.is_err());
+    store_raw_offers([(
+        "RAW-TEST-3:AI101@2026-02-15T06:00:00".to_string(),
+        json!({"search": 5}),
+    )])
+    .await;
+    let raw_offers = RAW_OFFERS.read().await;
+    assert!(!raw_offers.contains_key("RAW-EXPIRED:AI101@2026-02-15T06:00:00"));
+    assert!(raw_offers.contains_key("RAW-TEST-3:AI101@2026-02-15T06:00:00"));
+}
+
+#[test]
+fn booking_class_test() {
+    assert_eq!(BookingClass::from("ECONOMY"), BookingClass::Economy);
+    assert_eq!(
+        BookingClass::from("PREMIUM_ECONOMY"),
+        BookingClass::PremiumEconomy
+    );
+    assert_eq!(BookingClass::from("BUSINESS"), BookingClass::Business);
+    assert_eq!(BookingClass::from("first"), BookingClass::First);
+    assert_eq!(BookingClass::from("SUITE"), BookingClass::Economy);
+    for class in [
+        BookingClass::Economy,
+        BookingClass::PremiumEconomy,
+        BookingClass::Business,
+        BookingClass::First,
+    ] {
+        assert_eq!(BookingClass::from(class.as_str()), class);
+    }
+
+    let offer = |pricings: Value| -> AmadeusFlightOffer {
+        serde_json::from_value(json!({
+            "id": "1",
+            "price": {"currency": "INR", "total": "54000.00"},
+            "itineraries": [],
+            "travelerPricings": pricings
+        }))
+        .unwrap()
+    };
+    let business = to_flight(offer(json!([{
+        "price": {"currency": "INR", "total": "54000.00"},
+        "fareDetailsBySegment": [{"segmentId": "1", "cabin": "BUSINESS"}]
+    }])));
+    assert_eq!(business.booking_class, BookingClass::Business);
+    assert_eq!(
+        to_flight(offer(json!([]))).booking_class,
+        BookingClass::Economy
+    );
+
+    let cached: Flight = serde_json::from_value(serde_json::to_value(&business).unwrap()).unwrap();
+    assert_eq!(cached.booking_class, BookingClass::Business);
+}
+
+#[test]
+fn price_history_test() {
+    let response: AmadeusPriceMetricsResponse = serde_json::from_value(json!({
+        "data": [{
+            "type": "itinerary-price-metric",
+            "origin": {"iataCode": "DEL"},
+            "destination": {"iataCode": "BOM"},
+            "departureDate": "2026-03-08",
+            "transportType": "FLIGHT",
+            "currencyCode": "INR",
+            "oneWay": true,
+            "priceMetrics": [
+                {"amount": "3890.00", "quartileRanking": "MINIMUM"},
+                {"amount": "4720.00", "quartileRanking": "FIRST"},
+                {"amount": "5480.50", "quartileRanking": "MEDIUM"},
+                {"amount": "6900.00", "quartileRanking": "THIRD"},
+                {"amount": "11250.00", "quartileRanking": "MAXIMUM"}
+            ]
+        }]
+    }))
+    .unwrap();
+    let point = response.data[0].median_price().unwrap();
+    assert_eq!(point.date, Date::new(2026, 3, 8).unwrap());
+    assert!(matches!(point.price, Currency::Inr(price) if price == 5480.5));
+
+    let history: Vec<PricePoint> = [(1, 5200.0), (8, 5480.5), (15, 6100.0), (22, 5900.0)]
+        .into_iter()
+        .map(|(day, price)| PricePoint {
+            date: Date::new(2026, 3, day).unwrap(),
+            price: Currency::Inr(price),
+        })
+        .collect();
+    let flight = |price: Currency, passengers: u8| {
+        let mut flight = serde_json::from_value::<Flight>(json!({
+            "id": "1",
+            "price": price,
+            "itineraries": [],
+            "journey_time": null
+        }))
+        .unwrap();
+        flight.compare_with_history(&history, passengers);
+        flight
+    };
+    // The median is the middle of 5480.5 and 5900
+    assert!(flight(Currency::Inr(5600.0), 1).is_good_price);
+    assert!(!flight(Currency::Inr(5690.25), 1).is_good_price);
+    assert!(!flight(Currency::Usd(60.0), 1).is_good_price);
+    // Compared per passenger
+    assert!(flight(Currency::Inr(11200.0), 2).is_good_price);
+    assert!(!flight(Currency::Inr(11200.0), 1).is_good_price);
+    let mut flight = flight(Currency::Inr(5600.0), 1);
+    flight.compare_with_history(&[], 1);
+    assert!(!flight.is_good_price);
+}
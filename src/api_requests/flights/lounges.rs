@@ -0,0 +1,247 @@
+use crate::{error::TravelError, utils::IataCode};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use phf::phf_map;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Lounge {
+    pub airport: &'static str,
+    pub name: &'static str,
+    pub terminal: &'static str,
+    ///IATA codes of the airlines whose premium passengers get in. Empty when open to passengers
+    ///of every airline
+    pub airlines: &'static [&'static str],
+    ///How else to get in, e.g. with a credit card programme or by paying at the door
+    pub access: &'static str,
+    pub open_24_hours: bool,
+}
+
+/// Lounges at the airports most of our travellers fly long haul from or through. There is no
+/// free lounge API, so this is kept by hand.
+static LOUNGES: &[Lounge] = &[
+    Lounge {
+        airport: "DEL",
+        name: "Air India Maharaja Lounge",
+        terminal: "T3",
+        airlines: &["AI"],
+        access: "Business and first class, Star Alliance Gold",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "DEL",
+        name: "Encalm Lounge",
+        terminal: "T3",
+        airlines: &[],
+        access: "Priority Pass, DreamFolks, eligible credit cards or paid entry",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "BOM",
+        name: "Air India Lounge",
+        terminal: "T2",
+        airlines: &["AI"],
+        access: "Business and first class, Star Alliance Gold",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "BOM",
+        name: "Adani Lounge",
+        terminal: "T2",
+        airlines: &[],
+        access: "Priority Pass, DreamFolks, eligible credit cards or paid entry",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "BLR",
+        name: "080 International Lounge",
+        terminal: "T1",
+        airlines: &[],
+        access: "Priority Pass, DreamFolks, eligible credit cards or paid entry",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "HYD",
+        name: "Encalm Lounge",
+        terminal: "Main",
+        airlines: &[],
+        access: "Priority Pass, DreamFolks, eligible credit cards or paid entry",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "MAA",
+        name: "Travel Club Lounge",
+        terminal: "T2",
+        airlines: &[],
+        access: "Priority Pass, DreamFolks, eligible credit cards or paid entry",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "DXB",
+        name: "Emirates Business Class Lounge",
+        terminal: "T3",
+        airlines: &["EK"],
+        access: "Business class, Skywards Gold",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "DXB",
+        name: "Marhaba Lounge",
+        terminal: "T3",
+        airlines: &[],
+        access: "Priority Pass or paid entry",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "SIN",
+        name: "SilverKris Lounge",
+        terminal: "T3",
+        airlines: &["SQ"],
+        access: "Business and first class, Star Alliance Gold",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "SIN",
+        name: "Ambassador Transit Lounge",
+        terminal: "T3",
+        airlines: &[],
+        access: "Priority Pass or paid entry",
+        open_24_hours: true,
+    },
+    Lounge {
+        airport: "LHR",
+        name: "Plaza Premium Lounge",
+        terminal: "T2",
+        airlines: &[],
+        access: "Priority Pass or paid entry",
+        open_24_hours: false,
+    },
+];
+
+/// Lounges at `airport` that passengers of `airline` can use. Without an airline every lounge
+/// is returned.
+pub fn lounges_at(airport: &str, airline: Option<&str>) -> Vec<Lounge> {
+    LOUNGES
+        .iter()
+        .filter(|lounge| lounge.airport.eq_ignore_ascii_case(airport))
+        .filter(|lounge| {
+            lounge.airlines.is_empty()
+                || airline.is_none_or(|airline| {
+                    lounge
+                        .airlines
+                        .iter()
+                        .any(|code| code.eq_ignore_ascii_case(airline.trim()))
+                })
+        })
+        .cloned()
+        .collect()
+}
+
+const MAPS_SEARCH_URL: &str = "https://www.google.com/maps/search/?api=1&query=";
+
+/// Terminal buildings of the busiest Indian airports, keyed by `{airport}/{terminal}`. A search
+/// for the terminal's name can land on the wrong building or a parking lot, so these link to
+/// the building itself.
+static MAP_URLS: phf::Map<&'static str, &'static str> = phf_map! {
+    "DEL/3" => "https://www.google.com/maps/search/?api=1&query=28.5562%2C77.0870",
+    "BOM/2" => "https://www.google.com/maps/search/?api=1&query=19.0987%2C72.8747",
+    "MAA/1" => "https://www.google.com/maps/search/?api=1&query=12.9823%2C80.1640",
+    "MAA/2" => "https://www.google.com/maps/search/?api=1&query=12.9789%2C80.1652",
+};
+
+/// Terminals are written as Amadeus sends them, e.g. `3` or `T3`.
+fn terminal_number(terminal: &str) -> &str {
+    let terminal = terminal.trim();
+    terminal
+        .strip_prefix(['T', 't'])
+        .filter(|number| !number.is_empty())
+        .unwrap_or(terminal)
+}
+
+/// Google Maps link to `terminal` of `airport`, or to the airport without a terminal.
+pub fn terminal_map_url(airport: &IataCode, terminal: Option<&str>) -> String {
+    let airport = airport.to_string();
+    let terminal = terminal.map(terminal_number).filter(|t| !t.is_empty());
+    let query = match terminal {
+        Some(terminal) => {
+            let key = format!("{}/{}", airport.to_uppercase(), terminal.to_uppercase());
+            if let Some(url) = MAP_URLS.get(key.as_str()) {
+                return url.to_string();
+            }
+            format!("{airport} airport terminal {terminal}")
+        }
+        None => format!("{airport} airport"),
+    };
+    format!("{MAPS_SEARCH_URL}{}", urlencoding::encode(&query))
+}
+
+#[gemini_function]
+///Get a Google Maps link to an airport terminal, to share once the user picks a flight. The terminal is in the flight's `departure` or `arrival`.
+pub async fn get_terminal_map_url(
+    ///IATA code of the airport (e.g., 'DEL')
+    iata_code: IataCode,
+    ///Terminal (e.g., '3' or 'T3')
+    terminal: Option<String>,
+) -> Result<String, TravelError> {
+    Ok(terminal_map_url(&iata_code, terminal.as_deref()))
+}
+
+#[gemini_function]
+///Get the airport lounges a passenger can use. Call it for the departure and layover airports of every long haul flight you recommend.
+pub async fn get_airport_lounge_info(
+    ///IATA code of the airport (e.g., 'DEL')
+    iata_code: IataCode,
+    ///IATA code of the airline flown (e.g., 'AI'), to include the airline's own lounges
+    airline: Option<String>,
+) -> Result<Vec<Lounge>, TravelError> {
+    Ok(lounges_at(&iata_code.to_string(), airline.as_deref()))
+}
+
+#[test]
+fn lounges_at_test() {
+    let names = |airport: &str, airline: Option<&str>| {
+        lounges_at(airport, airline)
+            .into_iter()
+            .map(|lounge| lounge.name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names("DEL", None),
+        ["Air India Maharaja Lounge", "Encalm Lounge"]
+    );
+    assert_eq!(
+        names("del", Some("ai ")),
+        ["Air India Maharaja Lounge", "Encalm Lounge"]
+    );
+    assert_eq!(names("DEL", Some("6E")), ["Encalm Lounge"]);
+    assert_eq!(names("DXB", Some("EK")).len(), 2);
+    assert!(names("IXR", None).is_empty());
+}
+
+#[test]
+fn terminal_map_url_test() {
+    let code = |code: &str| IataCode::new(code.to_string()).unwrap();
+    assert_eq!(
+        terminal_map_url(&code("DEL"), Some("3")),
+        "https://www.google.com/maps/search/?api=1&query=28.5562%2C77.0870"
+    );
+    assert_eq!(
+        terminal_map_url(&code("DEL"), Some(" t3 ")),
+        terminal_map_url(&code("DEL"), Some("3"))
+    );
+    assert_eq!(
+        terminal_map_url(&code("MAA"), Some("T2")),
+        "https://www.google.com/maps/search/?api=1&query=12.9789%2C80.1652"
+    );
+    assert_eq!(
+        terminal_map_url(&code("DEL"), Some("1")),
+        "https://www.google.com/maps/search/?api=1&query=DEL%20airport%20terminal%201"
+    );
+    assert_eq!(
+        terminal_map_url(&code("IXR"), None),
+        "https://www.google.com/maps/search/?api=1&query=IXR%20airport"
+    );
+    assert_eq!(
+        terminal_map_url(&code("IXR"), Some("")),
+        terminal_map_url(&code("IXR"), None)
+    );
+}
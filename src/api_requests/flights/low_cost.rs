@@ -0,0 +1,432 @@
+use super::amadeus::{BookingClass, Endpoint, Flight, Itinerary, Segment};
+use crate::{
+    api_requests::currency::CurrencyConverter,
+    config::CONFIG,
+    error::TravelError,
+    utils::{Currency, Date, IataCode, TravelDuration},
+};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const SEARCH_URL: &str = "https://flights-sky.p.rapidapi.com/flights/search-one-way";
+const RAPIDAPI_HOST: &str = "flights-sky.p.rapidapi.com";
+
+struct CarrierFees {
+    code: &'static str,
+    airline: &'static str,
+    cancellation_fee: f32,
+    change_fee: f32,
+    ///`None` when a checked bag is part of the fare
+    checked_bag_fee: Option<f32>,
+}
+
+/// Published domestic fees in INR. Only these carriers are taken from the low cost search,
+/// Amadeus covers the rest.
+static LOW_COST_CARRIERS: &[CarrierFees] = &[
+    CarrierFees {
+        code: "6E",
+        airline: "IndiGo",
+        cancellation_fee: 3500.0,
+        change_fee: 2999.0,
+        checked_bag_fee: None,
+    },
+    CarrierFees {
+        code: "SG",
+        airline: "SpiceJet",
+        cancellation_fee: 3399.0,
+        change_fee: 2999.0,
+        checked_bag_fee: None,
+    },
+    CarrierFees {
+        code: "QP",
+        airline: "Akasa Air",
+        cancellation_fee: 3499.0,
+        change_fee: 2999.0,
+        checked_bag_fee: None,
+    },
+    CarrierFees {
+        code: "IX",
+        airline: "Air India Express",
+        cancellation_fee: 3500.0,
+        change_fee: 3000.0,
+        checked_bag_fee: None,
+    },
+    CarrierFees {
+        code: "I5",
+        airline: "AIX Connect",
+        cancellation_fee: 3500.0,
+        change_fee: 3000.0,
+        checked_bag_fee: None,
+    },
+    CarrierFees {
+        code: "AK",
+        airline: "AirAsia",
+        cancellation_fee: 4000.0,
+        change_fee: 3500.0,
+        checked_bag_fee: Some(2800.0),
+    },
+];
+
+/// A budget carrier flight, which Amadeus doesn't return.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowCostFlight {
+    #[serde(flatten)]
+    pub flight: Flight,
+    pub airline: String,
+    ///In the currency of the search, like the fare
+    pub cancellation_fee: Currency,
+    pub change_fee: Currency,
+    ///Fee for a checked bag. Absent when one is included in the fare.
+    pub baggage_fee: Option<Currency>,
+}
+
+/// Output of `flights_between`. Low cost flights are plain flights with their fees added.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FlightOffer {
+    LowCost(LowCostFlight),
+    Amadeus(Flight),
+}
+
+impl FlightOffer {
+    pub fn flight(&self) -> &Flight {
+        match self {
+            FlightOffer::LowCost(low_cost) => &low_cost.flight,
+            FlightOffer::Amadeus(flight) => flight,
+        }
+    }
+    pub fn flight_mut(&mut self) -> &mut Flight {
+        match self {
+            FlightOffer::LowCost(low_cost) => &mut low_cost.flight,
+            FlightOffer::Amadeus(flight) => flight,
+        }
+    }
+}
+
+impl From<Flight> for FlightOffer {
+    fn from(flight: Flight) -> Self {
+        FlightOffer::Amadeus(flight)
+    }
+}
+
+#[derive(Deserialize)]
+struct SkyResponse {
+    data: SkyData,
+}
+
+#[derive(Deserialize)]
+struct SkyData {
+    #[serde(default)]
+    itineraries: Vec<SkyItinerary>,
+}
+
+#[derive(Deserialize)]
+struct SkyItinerary {
+    id: String,
+    price: SkyPrice,
+    legs: Vec<SkyLeg>,
+}
+
+#[derive(Deserialize)]
+struct SkyPrice {
+    raw: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkyLeg {
+    duration_in_minutes: u32,
+    segments: Vec<SkySegment>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkySegment {
+    origin: SkyPlace,
+    destination: SkyPlace,
+    departure: String,
+    arrival: String,
+    duration_in_minutes: u32,
+    flight_number: String,
+    marketing_carrier: SkyCarrier,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkyPlace {
+    display_code: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkyCarrier {
+    alternate_id: String,
+}
+
+fn get_headers() -> Result<HeaderMap, TravelError> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "X-RapidAPI-Key",
+        HeaderValue::from_str(&CONFIG.rapidapi_key)
+            .map_err(|_| TravelError::ApiKeyMissing("Invalid RAPIDAPI_KEY".into()))?,
+    );
+    headers.insert("X-RapidAPI-Host", HeaderValue::from_static(RAPIDAPI_HOST));
+    Ok(headers)
+}
+
+fn find_carrier(code: &str) -> Option<&'static CarrierFees> {
+    LOW_COST_CARRIERS
+        .iter()
+        .find(|carrier| carrier.code.eq_ignore_ascii_case(code))
+}
+
+/// `None` for itineraries of carriers missing from `LOW_COST_CARRIERS`, or when the rupee fees
+/// of the carrier can't be converted to `currency_code` with `rates`.
+fn to_low_cost_flight(
+    itinerary: SkyItinerary,
+    currency_code: &str,
+    rates: Option<&CurrencyConverter>,
+) -> Option<LowCostFlight> {
+    let carrier = find_carrier(
+        &itinerary
+            .legs
+            .first()?
+            .segments
+            .first()?
+            .marketing_carrier
+            .alternate_id,
+    )?;
+    let price = Currency::parse_currency(currency_code, &itinerary.price.raw.to_string()).ok()?;
+    let fee = |inr: f32| {
+        let fee = Currency::Inr(inr);
+        if currency_code.eq_ignore_ascii_case(fee.currency_code()) {
+            Some(fee)
+        } else {
+            rates?.convert_currency(&fee, currency_code).ok()
+        }
+    };
+    let baggage_fee = match carrier.checked_bag_fee {
+        Some(inr) => Some(fee(inr)?),
+        None => None,
+    };
+    let flight = Flight {
+        id: itinerary.id,
+        price,
+        itineraries: itinerary
+            .legs
+            .into_iter()
+            .map(|leg| Itinerary {
+                duration: TravelDuration::new_from_minutes(leg.duration_in_minutes).to_iso8601(),
+                segments: leg
+                    .segments
+                    .into_iter()
+                    .map(|seg| Segment {
+                        departure: Endpoint {
+                            iata_code: seg.origin.display_code,
+                            at: seg.departure,
+                            terminal: None,
+                        },
+                        arrival: Endpoint {
+                            iata_code: seg.destination.display_code,
+                            at: seg.arrival,
+                            terminal: None,
+                        },
+                        carrier_code: seg.marketing_carrier.alternate_id,
+                        number: seg.flight_number,
+                        duration: TravelDuration::new_from_minutes(seg.duration_in_minutes)
+                            .to_iso8601(),
+                        operating_carrier: None,
+                        operating_flight_number: None,
+                    })
+                    .collect(),
+            })
+            .collect(),
+        carrier_code: carrier.code.to_string(),
+        overnight: false,
+        early_morning: false,
+        long_haul: false,
+        journey_time: None,
+        fare_breakdown: None,
+        terminal_info: None,
+        domestic: false,
+        booking_class: BookingClass::Economy,
+        is_good_price: false,
+    }
+    .with_derived_fields();
+    Some(LowCostFlight {
+        flight,
+        airline: carrier.airline.to_string(),
+        cancellation_fee: fee(carrier.cancellation_fee)?,
+        change_fee: fee(carrier.change_fee)?,
+        baggage_fee,
+    })
+}
+
+/// Searches budget carriers like IndiGo and SpiceJet, which the Amadeus test API misses.
+pub async fn low_cost_flights_between(
+    source: &IataCode,
+    destination: &IataCode,
+    date: &Date,
+    adult_count: u8,
+    currency_code: &str,
+    max_price: Option<f32>,
+) -> Result<Vec<LowCostFlight>, TravelError> {
+    // The carrier fees are published in rupees
+    let rates = if currency_code.eq_ignore_ascii_case("INR") {
+        None
+    } else {
+        Some(CurrencyConverter::latest().await?)
+    };
+    let resp = reqwest::Client::new()
+        .get(SEARCH_URL)
+        .headers(get_headers()?)
+        .query(&[
+            ("fromEntityId", source.to_string()),
+            ("toEntityId", destination.to_string()),
+            ("departDate", date.to_yyyy_mm_dd()),
+            ("adults", adult_count.to_string()),
+            ("currency", currency_code.to_string()),
+        ])
+        .send()
+        .await
+        .map_err(|e| TravelError::Api(format!("Low cost flight search failed: {e}")))?;
+    if !resp.status().is_success() {
+        return Err(TravelError::Api(format!(
+            "Low cost flight search error: {}",
+            resp.status()
+        )));
+    }
+    let response: SkyResponse = resp
+        .json()
+        .await
+        .map_err(|e| TravelError::Api(format!("Invalid low cost flight response: {e}")))?;
+    Ok(response
+        .data
+        .itineraries
+        .into_iter()
+        .filter(|itinerary| max_price.is_none_or(|max_price| itinerary.price.raw <= max_price))
+        .filter_map(|itinerary| to_low_cost_flight(itinerary, currency_code, rates.as_ref()))
+        .collect())
+}
+
+/// Amadeus flights come first. Low cost flights Amadeus also found are dropped, as only
+/// Amadeus offers can be repriced.
+pub fn merge_flights(amadeus: Vec<Flight>, low_cost: Vec<LowCostFlight>) -> Vec<FlightOffer> {
+    let mut seen: HashSet<String> = amadeus.iter().map(Flight::flight_numbers).collect();
+    let mut offers: Vec<FlightOffer> = amadeus.into_iter().map(FlightOffer::from).collect();
+    for flight in low_cost {
+        if seen.insert(flight.flight.flight_numbers()) {
+            offers.push(FlightOffer::LowCost(flight));
+        }
+    }
+    offers
+}
+
+#[cfg(test)]
+fn sky_fixture() -> SkyResponse {
+    let itinerary = |id: &str, carrier: &str, number: &str, departure: &str, price: f32| {
+        serde_json::json!({
+            "id": id,
+            "price": {"raw": price, "formatted": format!("₹{price}")},
+            "legs": [{
+                "origin": {"displayCode": "DEL"},
+                "destination": {"displayCode": "BOM"},
+                "durationInMinutes": 135,
+                "segments": [{
+                    "origin": {"displayCode": "DEL"},
+                    "destination": {"displayCode": "BOM"},
+                    "departure": format!("2026-02-15T{departure}"),
+                    "arrival": "2026-02-15T08:15:00",
+                    "durationInMinutes": 135,
+                    "flightNumber": number,
+                    "marketingCarrier": {"name": carrier, "alternateId": carrier}
+                }]
+            }]
+        })
+    };
+    serde_json::from_value(serde_json::json!({
+        "status": true,
+        "data": {"itineraries": [
+            itinerary("13554-2602150600", "6E", "2131", "06:00:00", 5120.0),
+            itinerary("13554-2602150515", "AK", "39", "05:15:00", 4300.0),
+            itinerary("13554-2602150700", "AI", "887", "07:00:00", 7300.0),
+            itinerary("13554-2602150630", "SG", "8701", "06:30:00", 4890.0)
+        ]}
+    }))
+    .unwrap()
+}
+
+#[test]
+fn to_low_cost_flight_test() {
+    let flights: Vec<LowCostFlight> = sky_fixture()
+        .data
+        .itineraries
+        .into_iter()
+        .filter_map(|itinerary| to_low_cost_flight(itinerary, "INR", None))
+        .collect();
+    // Air India is left to Amadeus
+    assert_eq!(flights.len(), 3);
+
+    let indigo = &flights[0];
+    assert_eq!(indigo.airline, "IndiGo");
+    assert_eq!(indigo.flight.carrier_code, "6E");
+    assert_eq!(indigo.flight.flight_numbers(), "6E2131");
+    assert_eq!(indigo.flight.itineraries[0].duration, "PT2H15M");
+    assert_eq!(indigo.flight.journey_time.as_deref(), Some("2h 15m"));
+    assert!(matches!(indigo.flight.price, Currency::Inr(price) if price == 5120.0));
+    assert!(matches!(indigo.change_fee, Currency::Inr(fee) if fee == 2999.0));
+    assert!(indigo.baggage_fee.is_none());
+
+    let air_asia = &flights[1];
+    assert!(air_asia.flight.early_morning);
+    assert!(matches!(air_asia.baggage_fee, Some(Currency::Inr(fee)) if fee == 2800.0));
+
+    // Fees follow the currency of the fare
+    let rates = CurrencyConverter::from_rates(&[("INR", 1.0), ("USD", 0.012)]);
+    let usd: Vec<LowCostFlight> = sky_fixture()
+        .data
+        .itineraries
+        .into_iter()
+        .filter_map(|itinerary| to_low_cost_flight(itinerary, "USD", Some(&rates)))
+        .collect();
+    assert_eq!(usd.len(), 3);
+    assert!(matches!(usd[0].flight.price, Currency::Usd(price) if price == 5120.0));
+    assert!(matches!(usd[0].cancellation_fee, Currency::Usd(fee) if (fee - 42.0).abs() < 0.01));
+    assert!(matches!(usd[1].baggage_fee, Some(Currency::Usd(fee)) if (fee - 33.6).abs() < 0.01));
+    // Without rates the fees can't be shown in dollars
+    assert!(
+        sky_fixture()
+            .data
+            .itineraries
+            .into_iter()
+            .all(|itinerary| to_low_cost_flight(itinerary, "USD", None).is_none())
+    );
+}
+
+#[test]
+fn merge_flights_test() {
+    let low_cost: Vec<LowCostFlight> = sky_fixture()
+        .data
+        .itineraries
+        .into_iter()
+        .filter_map(|itinerary| to_low_cost_flight(itinerary, "INR", None))
+        .collect();
+    let mut amadeus_indigo = low_cost[0].flight.clone();
+    amadeus_indigo.id = "1".into();
+
+    let offers = merge_flights(vec![amadeus_indigo], low_cost);
+    let ids: Vec<&str> = offers
+        .iter()
+        .map(|offer| offer.flight().id.as_str())
+        .collect();
+    assert_eq!(ids, ["1", "13554-2602150515", "13554-2602150630"]);
+    assert!(matches!(offers[0], FlightOffer::Amadeus(_)));
+    assert!(matches!(offers[1], FlightOffer::LowCost(_)));
+
+    // Both kinds survive a round trip through Gemini
+    let round_trip: Vec<FlightOffer> =
+        serde_json::from_value(serde_json::to_value(&offers).unwrap()).unwrap();
+    assert!(matches!(round_trip[0], FlightOffer::Amadeus(_)));
+    assert!(matches!(&round_trip[2], FlightOffer::LowCost(flight) if flight.airline == "SpiceJet"));
+}
@@ -1,3 +1,3 @@
 pub mod amadeus;
-
-
+pub mod lounges;
+pub mod low_cost;
@@ -0,0 +1,82 @@
+use crate::{
+    api_requests::places::google::GooglePlacesClient,
+    error::TravelError,
+    utils::{Coordinates, IataCode, indian_airport_city},
+};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use tokio::sync::RwLock;
+
+/// Airports cached in `AIRPORT_COORDS`, far more than India has.
+const MAX_CACHED_AIRPORTS: usize = 500;
+
+/// Airports don't move, so they are looked up once per Lambda instance. Emptied when it
+/// reaches `MAX_CACHED_AIRPORTS`, as any code is looked up.
+static AIRPORT_COORDS: LazyLock<RwLock<HashMap<IataCode, Coordinates>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Coordinates of the best match for `address` using Google Places text search.
+pub async fn geocode(address: &str) -> Result<Coordinates, TravelError> {
+    GooglePlacesClient::from_config()
+        .text_search(address)
+        .await?
+        .into_iter()
+        .find_map(|place| place.location)
+        .ok_or_else(|| TravelError::Api(format!("No location found for '{address}'")))
+}
+
+/// Coordinates of the centre of the city with IATA code `city`, e.g. 'GOI' for Goa.
+pub async fn city_center_coords(city: &IataCode) -> Result<Coordinates, TravelError> {
+    geocode(&city_center_query(city)).await
+}
+
+/// A code alone is ambiguous, e.g. 'GOI' also stands for Government of India, so the city
+/// name is searched when the airport data has it. Other codes are searched as they are.
+fn city_center_query(city: &IataCode) -> String {
+    match indian_airport_city(&city.to_string()) {
+        Some(name) => format!("{name} city centre, India"),
+        None => format!("{city} city centre"),
+    }
+}
+
+/// Coordinates of the airport with IATA code `airport`, cached in `AIRPORT_COORDS`.
+/// `InvalidInput` when no airport is found for the code.
+pub async fn airport_coords(airport: &IataCode) -> Result<Coordinates, TravelError> {
+    if let Some(coords) = AIRPORT_COORDS.read().await.get(airport) {
+        return Ok(*coords);
+    }
+    let coords = GooglePlacesClient::from_config()
+        .text_search(&format!("{airport} airport"))
+        .await?
+        .into_iter()
+        .find_map(|place| place.location)
+        .ok_or_else(|| TravelError::InvalidInput(format!("Unknown airport: {airport}")))?;
+    let mut cache = AIRPORT_COORDS.write().await;
+    if cache.len() >= MAX_CACHED_AIRPORTS {
+        cache.clear();
+    }
+    cache.insert(airport.clone(), coords);
+    Ok(coords)
+}
+
+#[cfg(test)]
+pub async fn cache_airport_coords(airport: IataCode, coords: Coordinates) {
+    AIRPORT_COORDS.write().await.insert(airport, coords);
+}
+
+#[tokio::test]
+async fn airport_coords_cache_test() {
+    let code = IataCode::new("XAA".into()).unwrap();
+    let known = Coordinates::new(28.5562, 77.1);
+    AIRPORT_COORDS.write().await.insert(code.clone(), known);
+    // A cached airport is not geocoded again
+    assert_eq!(airport_coords(&code).await.unwrap(), known);
+}
+
+#[test]
+fn city_center_query_test() {
+    let code = |code: &str| IataCode::new(code.to_string()).unwrap();
+    assert_eq!(city_center_query(&code("GOI")), "Goa city centre, India");
+    assert_eq!(city_center_query(&code("IXR")), "Ranchi city centre, India");
+    assert_eq!(city_center_query(&code("DXB")), "DXB city centre");
+}
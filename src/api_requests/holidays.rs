@@ -0,0 +1,138 @@
+use crate::{error::TravelError, utils::Date};
+use chrono::Datelike;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use tokio::sync::RwLock;
+
+const BASE_URL: &str = "https://date.nager.at/api/v3/PublicHolidays";
+
+/// `(year, country_code)`
+type HolidayKey = (u16, String);
+
+/// Public holidays don't change once published, so they are kept for the Lambda's lifetime.
+static HOLIDAY_CACHE: LazyLock<RwLock<HashMap<HolidayKey, Vec<Holiday>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Holiday {
+    pub date: Date,
+    pub name: String,
+    ///Observed in the whole country rather than in some states only
+    pub is_national: bool,
+}
+
+#[derive(Deserialize)]
+struct NagerHoliday {
+    date: String,
+    name: String,
+    global: bool,
+}
+
+fn to_holidays(response: Vec<NagerHoliday>) -> Result<Vec<Holiday>, TravelError> {
+    response
+        .into_iter()
+        .map(|holiday| {
+            Ok(Holiday {
+                date: Date::from_yyyy_mm_dd(&holiday.date).map_err(|e| {
+                    TravelError::Api(format!("Invalid holiday date {}: {e}", holiday.date))
+                })?,
+                name: holiday.name,
+                is_national: holiday.global,
+            })
+        })
+        .collect()
+}
+
+/// `country_code` is an ISO 3166-1 alpha-2 code like `IN`.
+pub async fn get_holiday_list(year: u16, country_code: &str) -> Result<Vec<Holiday>, TravelError> {
+    if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(TravelError::InvalidInput(format!(
+            "Country code must be 2 letters. Found: {country_code}"
+        )));
+    }
+    let key = (year, country_code.to_ascii_uppercase());
+    if let Some(holidays) = HOLIDAY_CACHE.read().await.get(&key) {
+        return Ok(holidays.clone());
+    }
+
+    let resp = reqwest::get(format!("{BASE_URL}/{year}/{}", key.1))
+        .await
+        .map_err(|e| TravelError::Api(format!("Nager.Date request failed: {e}")))?;
+    if !resp.status().is_success() {
+        return Err(TravelError::Api(format!(
+            "Nager.Date error: {}",
+            resp.status()
+        )));
+    }
+    let response: Vec<NagerHoliday> = resp
+        .json()
+        .await
+        .map_err(|e| TravelError::Api(format!("Invalid Nager.Date response: {e}")))?;
+
+    let holidays = to_holidays(response)?;
+    HOLIDAY_CACHE.write().await.insert(key, holidays.clone());
+    Ok(holidays)
+}
+
+impl Date {
+    /// `false` when the holidays can't be fetched.
+    pub async fn is_holiday(&self, country_code: &str) -> bool {
+        let Some(year) = self.to_naive_date().map(|date| date.year() as u16) else {
+            return false;
+        };
+        get_holiday_list(year, country_code)
+            .await
+            .is_ok_and(|holidays| holidays.iter().any(|holiday| holiday.date == *self))
+    }
+}
+
+#[gemini_function]
+///Get the public holidays of a country in a year. Use it to warn the user when travel dates fall on festivals like Diwali or Holi.
+pub async fn get_holidays(
+    year: u16,
+    ///ISO 3166-1 alpha-2 country code (e.g., 'IN')
+    country_code: String,
+) -> Result<Vec<Holiday>, TravelError> {
+    get_holiday_list(year, &country_code).await
+}
+
+#[test]
+fn to_holidays_test() {
+    let fixture = r#"[
+        {"date": "2026-01-26", "localName": "Republic Day", "name": "Republic Day",
+         "countryCode": "IN", "fixed": true, "global": true, "counties": null,
+         "launchYear": null, "types": ["Public"]},
+        {"date": "2026-03-04", "localName": "Holi", "name": "Holi",
+         "countryCode": "IN", "fixed": false, "global": false, "counties": ["IN-UP"],
+         "launchYear": null, "types": ["Public"]}
+    ]"#;
+    let holidays = to_holidays(serde_json::from_str(fixture).unwrap()).unwrap();
+    assert_eq!(holidays.len(), 2);
+    assert_eq!(holidays[0].date, Date::new(2026, 1, 26).unwrap());
+    assert!(holidays[0].is_national);
+    assert_eq!(holidays[1].name, "Holi");
+    assert!(!holidays[1].is_national);
+
+    let invalid: Vec<NagerHoliday> =
+        serde_json::from_str(r#"[{"date": "26/01/2026", "name": "Republic Day", "global": true}]"#)
+            .unwrap();
+    assert!(to_holidays(invalid).is_err());
+}
+
+#[tokio::test]
+async fn is_holiday_test() {
+    let republic_day = Date::new(2026, 1, 26).unwrap();
+    HOLIDAY_CACHE.write().await.insert(
+        (2026, "IN".into()),
+        vec![Holiday {
+            date: republic_day.clone(),
+            name: "Republic Day".into(),
+            is_national: true,
+        }],
+    );
+    assert!(republic_day.is_holiday("in").await);
+    assert!(!Date::new(2026, 1, 27).unwrap().is_holiday("IN").await);
+    assert!(!republic_day.is_holiday("IND").await);
+}
@@ -1,13 +1,53 @@
-use crate::utils::{Date, IataCode, get_bearer_token};
+use crate::{
+    api_requests::{
+        currency::CurrencyConverter,
+        geocode::{airport_coords, city_center_coords, geocode},
+    },
+    error::TravelError,
+    utils::{
+        AmadeusClient, Coordinates, Currency, Date, IataCode, NO_PARAMS, Time, check_param_length,
+        sanitize_query_param, sorting::sort_hotels_by_distance_to,
+    },
+};
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use gemini_client_api::futures::future::join;
 use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
-use reqwest::header::AUTHORIZATION;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::env;
+use std::collections::HashMap;
+
+const HOTEL_OFFERS_PATH: &str = "/v3/shopping/hotel-offers";
+const HOTEL_LIST_PATH: &str = "/v1/reference-data/locations/hotels/by-city";
+const HOTEL_GEOCODE_LIST_PATH: &str = "/v1/reference-data/locations/hotels/by-geocode";
+const HOTEL_SENTIMENTS_PATH: &str = "/v2/e-reputation/hotel-sentiments";
+const MAX_CODE_LEN: usize = 3;
+const MAX_OFFER_ID_LEN: usize = 64;
+const MAX_ROOM_CODE_LEN: usize = 16;
+const DEFAULT_AREA_RADIUS_KM: f32 = 5.0;
+const DEFAULT_AIRPORT_RADIUS_KM: f32 = 5.0;
+const MAX_AIRPORT_RADIUS_KM: u8 = 50;
+const DEFAULT_MAX_RESULTS: u8 = 20;
+const DEFAULT_CLOSEST_COUNT: u8 = 5;
+// Amadeus accepts 1 to 9 adults per room
+const MAX_ADULTS: u8 = 9;
+const BEST_VALUE_TAG: &str = "⭐ Best Value";
+const LUXURY_TAG: &str = "💎 Luxury";
+const FREE_CANCELLATION_TAG: &str = "✅ Free cancellation until";
+
+/// Typical price per night in INR of a hotel of each star rating in the major Indian cities.
+static MEDIAN_PRICE_BY_STARS: [(u8, f32); 5] = [
+    (1, 1500.0),
+    (2, 2500.0),
+    (3, 4500.0),
+    (4, 8000.0),
+    (5, 15000.0),
+];
+/// Cities `MEDIAN_PRICE_BY_STARS` holds for. Elsewhere hotels are cheaper, so a price below
+/// these medians says little about value.
+static MAJOR_INDIAN_CITIES: &[&str] = &[
+    "DEL", "BOM", "BLR", "MAA", "CCU", "HYD", "PNQ", "AMD", "GOI", "JAI",
+];
 
-const BASE_URL: &str = "https://test.api.amadeus.com/v3/shopping/hotel-offers";
-const HOTEL_LIST_URL: &str =
-    "https://test.api.amadeus.com/v1/reference-data/locations/hotels/by-city";
 #[derive(Deserialize)]
 struct AmadeusHotelListResponse {
     data: Vec<AmadeusHotelReference>,
@@ -17,110 +57,973 @@ struct AmadeusHotelListResponse {
 struct AmadeusHotelReference {
     #[serde(rename = "hotelId")]
     hotel_id: String,
+    ///Star rating
+    #[serde(default)]
+    rating: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct AmadeusSentimentsResponse {
+    #[serde(default)]
+    data: Vec<AmadeusHotelSentiment>,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmadeusHotelSentiment {
+    hotel_id: String,
+    ///0 to 100
+    overall_rating: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[gemini_schema]
-#[derive(Debug, Deserialize)]
-pub struct Rating {
-    start: u8,
-    end: u8,
+pub enum HotelAmenity {
+    SwimmingPool,
+    Spa,
+    FitnessCenter,
+    AirConditioning,
+    Restaurant,
+    Parking,
+    PetsAllowed,
+    AirportShuttle,
+    Wifi,
+    RoomService,
+    KidsWelcome,
+    Beach,
 }
 
-#[gemini_function]
-///Find hotel offers in a city by IATA code with check-in date and budget constraints.
-pub async fn hotels_in_city(
-    city_code: IataCode,
-    check_in_date: Date,
-    ///Number of adult guests
-    adults: u8,
+impl HotelAmenity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HotelAmenity::SwimmingPool => "SWIMMING_POOL",
+            HotelAmenity::Spa => "SPA",
+            HotelAmenity::FitnessCenter => "FITNESS_CENTER",
+            HotelAmenity::AirConditioning => "AIR_CONDITIONING",
+            HotelAmenity::Restaurant => "RESTAURANT",
+            HotelAmenity::Parking => "PARKING",
+            HotelAmenity::PetsAllowed => "PETS_ALLOWED",
+            HotelAmenity::AirportShuttle => "AIRPORT_SHUTTLE",
+            HotelAmenity::Wifi => "WIFI",
+            HotelAmenity::RoomService => "ROOM_SERVICE",
+            HotelAmenity::KidsWelcome => "KIDS_WELCOME",
+            HotelAmenity::Beach => "BEACH",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[gemini_schema]
+///Search constraints for hotel offers.
+pub struct HotelFilter {
+    ///IATA code of the city
+    pub city_code: IataCode,
+    pub check_in: Date,
+    pub check_out: Date,
+    ///Number of adult guests (1-9)
+    pub adults: u8,
     ///3-letter currency code (e.g., 'INR')
-    currency_code: String,
-    ///Rating range of hotels
-    rating: Rating,
-    ///Number of hotels to see hotels
-    hotels_count: u8,
-) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-    let client_id = env::var("AMADEUS_API_KEY")?;
-    let client_secret = env::var("AMADEUS_API_SECRET")?;
-
-    let token = get_bearer_token(&client_id, &client_secret).await?;
-    let client = reqwest::Client::new();
-
-    // 1. Get hotels by city
-    let resp = client
-        .get(HOTEL_LIST_URL)
-        .header(AUTHORIZATION, format!("Bearer {}", token))
-        .query(&[
-            ("cityCode", city_code.to_string()),
-            (
-                "ratings",
-                (rating.start..=rating.end)
-                    .map(|v| v.to_string())
-                    .collect::<Vec<String>>()
-                    .join(","),
+    pub currency_code: String,
+    ///Maximum price per night in `currency_code`
+    pub max_price: Option<f32>,
+    ///Amenities every returned hotel must have
+    pub amenities: Vec<HotelAmenity>,
+    ///Minimum star rating (1-5)
+    pub min_stars: Option<f32>,
+    ///Maximum number of hotels to return
+    pub max_results: u8,
+    ///Search this many km around the city centre instead of only within the city limits, e.g. to
+    ///include beach resorts outside the city
+    pub search_radius_km: Option<u8>,
+    ///Time the guests reach the hotel on the check-in day, e.g. after a late flight. Hotels whose
+    ///check-in closes earlier are left out
+    pub late_checkin_hotels: Option<Time>,
+}
+
+impl HotelFilter {
+    pub fn builder(city_code: IataCode, check_in: Date, check_out: Date) -> HotelFilterBuilder {
+        HotelFilterBuilder {
+            filter: HotelFilter {
+                city_code,
+                check_in,
+                check_out,
+                adults: 1,
+                currency_code: "INR".to_string(),
+                max_price: None,
+                amenities: Vec::new(),
+                min_stars: None,
+                max_results: DEFAULT_MAX_RESULTS,
+                search_radius_km: None,
+                late_checkin_hotels: None,
+            },
+        }
+    }
+    pub fn validate(&self) -> Result<(), TravelError> {
+        if self.check_out <= self.check_in {
+            return Err(TravelError::InvalidInput(format!(
+                "Check-out {} must be after check-in {}",
+                self.check_out.to_yyyy_mm_dd(),
+                self.check_in.to_yyyy_mm_dd()
+            )));
+        }
+        if !(1..=MAX_ADULTS).contains(&self.adults) {
+            return Err(TravelError::InvalidInput(format!(
+                "Adults must be between 1 and {MAX_ADULTS}. Found: {}",
+                self.adults
+            )));
+        }
+        check_param_length(&self.currency_code, MAX_CODE_LEN)?;
+        if self.search_radius_km == Some(0) {
+            return Err(TravelError::InvalidInput(
+                "Search radius must be at least 1 km".to_string(),
+            ));
+        }
+        Ok(())
+    }
+    /// Star ratings accepted by the Amadeus hotel list, e.g. `"4,5"` for `min_stars = 3.5`.
+    fn ratings(&self) -> String {
+        let min_stars = self.min_stars.unwrap_or(1.0).ceil().clamp(1.0, 5.0) as u8;
+        (min_stars..=5)
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+    /// Path and query of the Amadeus hotel list. With a `search_radius_km` the hotels around
+    /// `center` are listed, otherwise the ones registered in the city.
+    fn hotel_list_request(
+        &self,
+        center: Option<&Coordinates>,
+    ) -> (&'static str, Vec<(&'static str, String)>) {
+        let (path, mut query) = match (self.search_radius_km, center) {
+            (Some(radius), Some(center)) => (
+                HOTEL_GEOCODE_LIST_PATH,
+                vec![
+                    ("latitude", center.latitude.to_string()),
+                    ("longitude", center.longitude.to_string()),
+                    ("radius", radius.to_string()),
+                    ("radiusUnit", "KM".to_string()),
+                    ("hotelSource", "ALL".to_string()),
+                ],
             ),
-        ])
-        .send()
-        .await?;
+            _ => (
+                HOTEL_LIST_PATH,
+                vec![("cityCode", self.city_code.to_string())],
+            ),
+        };
+        query.push(("ratings", self.ratings()));
+        if !self.amenities.is_empty() {
+            let amenities: Vec<&str> = self.amenities.iter().map(|a| a.as_str()).collect();
+            query.push(("amenities", amenities.join(",")));
+        }
+        (path, query)
+    }
+}
+
+pub struct HotelFilterBuilder {
+    filter: HotelFilter,
+}
+
+impl HotelFilterBuilder {
+    pub fn adults(mut self, adults: u8) -> Self {
+        self.filter.adults = adults;
+        self
+    }
+    pub fn currency_code(mut self, currency_code: impl Into<String>) -> Self {
+        self.filter.currency_code = currency_code.into();
+        self
+    }
+    pub fn max_price(mut self, max_price: f32) -> Self {
+        self.filter.max_price = Some(max_price);
+        self
+    }
+    pub fn amenities(mut self, amenities: Vec<HotelAmenity>) -> Self {
+        self.filter.amenities = amenities;
+        self
+    }
+    pub fn min_stars(mut self, min_stars: f32) -> Self {
+        self.filter.min_stars = Some(min_stars);
+        self
+    }
+    pub fn max_results(mut self, max_results: u8) -> Self {
+        self.filter.max_results = max_results;
+        self
+    }
+    pub fn search_radius_km(mut self, search_radius_km: u8) -> Self {
+        self.filter.search_radius_km = Some(search_radius_km);
+        self
+    }
+    pub fn build(self) -> Result<HotelFilter, TravelError> {
+        self.filter.validate()?;
+        Ok(self.filter)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hotel {
+    pub hotel_id: String,
+    pub name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    ///Offers as returned by https://api.amadeus.com/v3/shopping/hotel-offers
+    pub offers: Vec<Value>,
+    ///Room of each offer, in the same order as `offers`
+    #[serde(default)]
+    pub rooms: Vec<RoomType>,
+    ///Set by `confirm_hotel_price` when the hotel guarantees the offer price at booking
+    #[serde(default)]
+    pub is_price_guaranteed: bool,
+    ///Straight line distance to the city centre
+    pub distance_to_center_km: Option<f32>,
+    ///Straight line distance to the city's airport
+    pub distance_to_airport_km: Option<f32>,
+    ///An offer mentions vegetarian or Jain meals. `false` means unknown, not unavailable
+    #[serde(default)]
+    pub vegetarian_meals_available: bool,
+    ///IATA code of the city the hotel was searched in
+    #[serde(default)]
+    pub city_code: Option<String>,
+    #[serde(default)]
+    pub stars: Option<u8>,
+    ///Guest rating out of 5
+    #[serde(default)]
+    pub rating: Option<f32>,
+    ///Cheapest room for the stay divided by the nights
+    #[serde(default)]
+    pub price_per_night: Option<Currency>,
+    ///e.g. '⭐ Best Value'
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    ///Earliest check-in, hotel local time
+    #[serde(default)]
+    pub check_in_time: Option<Time>,
+    ///Latest check-in, hotel local time
+    #[serde(default)]
+    pub latest_check_in_time: Option<Time>,
+    ///Latest check-out, hotel local time
+    #[serde(default)]
+    pub check_out_time: Option<Time>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomType {
+    ///`id` of the offer the room is booked through
+    pub offer_id: String,
+    ///Amadeus room type code, e.g. 'A1K'
+    pub code: String,
+    pub description: String,
+    ///e.g. '1 KING'
+    pub bed_configuration: String,
+    pub max_occupancy: u8,
+    ///Total for the stay
+    pub price: Currency,
+    pub refundable: bool,
+    ///`None` when the offer doesn't say
+    #[serde(default)]
+    pub cancellation_policy: Option<CancellationPolicy>,
+    pub breakfast_included: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CancellationPolicy {
+    ///Cancelling before `deadline`, hotel local time as `YYYY-MM-DDTHH:MM:SS`, is free. Later
+    ///cancellations get `refund_pct_after` percent of the price back
+    FreeCancellation {
+        deadline: String,
+        #[serde(default)]
+        refund_pct_after: u8,
+    },
+    ///Cancelling before `before`, the check-in, refunds `refund_pct` percent of the price
+    PartialRefund {
+        refund_pct: u8,
+        before: String,
+    },
+    NonRefundable,
+}
+
+impl CancellationPolicy {
+    /// From the first of `policies.cancellations`. Its `amount`, `percentage` or
+    /// `numberOfNights` is the penalty for cancelling after its `deadline`, so cancelling
+    /// before the deadline is free. `total` is the price of the offer.
+    fn from_offer(offer: &Value, total: f32) -> Option<CancellationPolicy> {
+        let policies = &offer["policies"];
+        if policies["refundable"]["cancellationRefund"] == "NON_REFUNDABLE" {
+            return Some(CancellationPolicy::NonRefundable);
+        }
+        let cancellation = &policies["cancellations"][0];
+        let penalty_pct = penalty_pct(cancellation, total, stay_nights(offer));
+        let refund_pct = |penalty_pct: f32| (100.0 - penalty_pct).clamp(0.0, 100.0).floor() as u8;
+        match (cancellation["deadline"].as_str(), penalty_pct) {
+            (Some(deadline), penalty_pct) => Some(CancellationPolicy::FreeCancellation {
+                deadline: local_date_time(deadline)?
+                    .format("%Y-%m-%dT%H:%M:%S")
+                    .to_string(),
+                // A penalty that can't be read is taken as the whole price
+                refund_pct_after: refund_pct(penalty_pct.unwrap_or(100.0)),
+            }),
+            (None, Some(penalty_pct)) if penalty_pct >= 100.0 => {
+                Some(CancellationPolicy::NonRefundable)
+            }
+            (None, Some(penalty_pct)) if penalty_pct > 0.0 => {
+                Some(CancellationPolicy::PartialRefund {
+                    refund_pct: refund_pct(penalty_pct),
+                    before: format!("{}T00:00:00", offer["checkInDate"].as_str()?),
+                })
+            }
+            _ => None,
+        }
+    }
+    pub fn is_free_before(&self, date: &NaiveDateTime) -> bool {
+        match self {
+            CancellationPolicy::FreeCancellation { deadline, .. } => {
+                local_date_time(deadline).is_some_and(|deadline| deadline >= *date)
+            }
+            _ => false,
+        }
+    }
+}
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let error_text = resp.text().await?;
-        return Err(format!("Amadeus Hotel List error: {} - {}", status, error_text).into());
+/// Penalty of a cancellation in percent of `total`. `None` when it can't be read, e.g. a number
+/// of nights without the length of the stay.
+fn penalty_pct(cancellation: &Value, total: f32, nights: Option<u32>) -> Option<f32> {
+    if let Some(percentage) = cancellation["percentage"].as_str() {
+        return percentage.parse().ok();
+    }
+    if let Some(amount) = cancellation["amount"].as_str() {
+        return Some(amount.parse::<f32>().ok()? / total * 100.0).filter(|pct| pct.is_finite());
     }
+    let penalty_nights = cancellation["numberOfNights"].as_u64()?;
+    Some(penalty_nights as f32 / nights.filter(|nights| *nights > 0)? as f32 * 100.0)
+}
 
-    let list_response: AmadeusHotelListResponse = resp.json().await?;
+/// Nights between the `checkInDate` and `checkOutDate` of an offer.
+fn stay_nights(offer: &Value) -> Option<u32> {
+    let date = |key: &str| NaiveDate::parse_from_str(offer[key].as_str()?, "%Y-%m-%d").ok();
+    u32::try_from((date("checkOutDate")? - date("checkInDate")?).num_days()).ok()
+}
+
+/// Amadeus deadlines may carry a UTC offset, which is left out as the hotel's local time is what
+/// the guest cares about.
+fn local_date_time(at: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(at.get(..19)?, "%Y-%m-%dT%H:%M:%S").ok()
+}
+
+impl RoomType {
+    /// `None` for offers without a room code or a valid price.
+    fn from_offer(offer: &Value) -> Option<RoomType> {
+        let room = &offer["room"];
+        let price = Currency::parse_currency(
+            offer["price"]["currency"].as_str()?,
+            offer["price"]["total"].as_str()?,
+        )
+        .ok()?;
+        let beds = &room["typeEstimated"];
+        let bed_configuration = match (beds["beds"].as_u64(), beds["bedType"].as_str()) {
+            (Some(count), Some(bed_type)) => format!("{count} {bed_type}"),
+            (None, Some(bed_type)) => bed_type.to_string(),
+            _ => String::new(),
+        };
+        let policies = &offer["policies"];
+        let refundable = match policies["refundable"]["cancellationRefund"].as_str() {
+            Some(refund) => refund != "NON_REFUNDABLE",
+            None => policies["cancellations"]
+                .as_array()
+                .is_some_and(|cancellations| {
+                    cancellations.iter().any(|c| c.get("deadline").is_some())
+                }),
+        };
+        let cancellation_policy = CancellationPolicy::from_offer(offer, price.to_amount());
+        Some(RoomType {
+            offer_id: offer["id"].as_str()?.to_string(),
+            code: room["type"].as_str()?.to_string(),
+            description: room["description"]["text"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            bed_configuration,
+            max_occupancy: offer["guests"]["adults"]
+                .as_u64()
+                .map_or(1, |adults| adults.min(u64::from(u8::MAX)) as u8),
+            price,
+            refundable,
+            cancellation_policy,
+            breakfast_included: matches!(
+                offer["boardType"].as_str(),
+                Some("BREAKFAST" | "HALF_BOARD" | "FULL_BOARD" | "ALL_INCLUSIVE")
+            ),
+        })
+    }
+}
+
+impl Hotel {
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        Some(Coordinates::new(self.latitude?, self.longitude?))
+    }
+    /// Distances stay `None` when the hotel or the place has no known location.
+    pub fn set_distances(&mut self, center: Option<&Coordinates>, airport: Option<&Coordinates>) {
+        let location = self.coordinates();
+        let distance_to = |place: Option<&Coordinates>| Some(location?.distance_km(place?) as f32);
+        self.distance_to_center_km = distance_to(center);
+        self.distance_to_airport_km = distance_to(airport);
+    }
+    /// Rooms priced in another currency than `currency_code`, which Amadeus sometimes returns
+    /// despite the requested one.
+    pub fn has_prices_in_other_currency(&self, currency_code: &str) -> bool {
+        self.rooms.iter().any(|room| {
+            !room
+                .price
+                .currency_code()
+                .eq_ignore_ascii_case(currency_code)
+        })
+    }
+    /// Prices of `rooms` in `currency_code`. Rooms without an exchange rate keep their price.
+    pub fn convert_prices(&mut self, converter: &CurrencyConverter, currency_code: &str) {
+        for room in &mut self.rooms {
+            match converter.convert_currency(&room.price, currency_code) {
+                Ok(price) => room.price = price,
+                Err(e) => eprintln!(
+                    "ERROR: Could not convert the price of {} to {currency_code}:\n{e}",
+                    room.offer_id
+                ),
+            }
+        }
+    }
+    pub fn set_price_per_night(&mut self, nights: u32) {
+        self.price_per_night = self
+            .rooms
+            .iter()
+            .map(|room| &room.price)
+            .min_by(|a, b| a.to_amount().total_cmp(&b.to_amount()))
+            .filter(|_| nights > 0)
+            .and_then(|price| {
+                Currency::from_code(price.currency_code(), price.to_amount() / nights as f32).ok()
+            });
+    }
+    /// Well rated and priced at least 20% below the usual price of its star rating. Only known
+    /// for INR prices in `MAJOR_INDIAN_CITIES`.
+    pub fn is_value_for_money(&self) -> bool {
+        let (Some(stars), Some(rating), Some(Currency::Inr(price)), Some(city)) = (
+            self.stars,
+            self.rating,
+            &self.price_per_night,
+            &self.city_code,
+        ) else {
+            return false;
+        };
+        let median = MEDIAN_PRICE_BY_STARS
+            .iter()
+            .find(|(category, _)| *category == stars)
+            .map(|(_, median)| *median);
+        MAJOR_INDIAN_CITIES.contains(&city.as_str())
+            && median.is_some_and(|median| *price < median * 0.8)
+            && rating >= 3.5
+    }
+    pub fn is_luxury(&self) -> bool {
+        self.rating.is_some_and(|rating| rating >= 4.5)
+            && self.stars.is_some_and(|stars| stars >= 4)
+    }
+    /// Replaces `tags` with the ones the hotel qualifies for at `now`, hotel local time.
+    pub fn set_tags(&mut self, now: &NaiveDateTime) {
+        self.tags = [
+            (self.is_value_for_money(), BEST_VALUE_TAG),
+            (self.is_luxury(), LUXURY_TAG),
+        ]
+        .into_iter()
+        .filter(|(qualifies, _)| *qualifies)
+        .map(|(_, tag)| tag.to_string())
+        .collect();
+        if let Some(deadline) = self.free_cancellation_until(now) {
+            self.tags.push(format!(
+                "{FREE_CANCELLATION_TAG} {}",
+                deadline.format("%-d %b, %-I:%M %p")
+            ));
+        }
+    }
+    /// Guests arriving at `arrival` can still check in, i.e. check-in hasn't closed by then.
+    /// Unknown closing times count as yes, most hotels take guests round the clock.
+    pub fn can_checkin_after(&self, arrival: Time) -> bool {
+        self.latest_check_in_time
+            .as_ref()
+            .is_none_or(|latest| arrival <= *latest)
+    }
+    /// A room can still be cancelled for free at `date`, hotel local time.
+    pub fn can_cancel_free_before(&self, date: &NaiveDateTime) -> bool {
+        self.free_cancellation_until(date).is_some()
+    }
+    /// Latest deadline of the rooms that can still be cancelled for free at `now`.
+    pub fn free_cancellation_until(&self, now: &NaiveDateTime) -> Option<NaiveDateTime> {
+        self.rooms
+            .iter()
+            .filter_map(|room| room.cancellation_policy.as_ref())
+            .filter(|policy| policy.is_free_before(now))
+            .filter_map(|policy| match policy {
+                CancellationPolicy::FreeCancellation { deadline, .. } => local_date_time(deadline),
+                _ => None,
+            })
+            .max()
+    }
+}
+
+#[derive(Deserialize)]
+struct AmadeusHotelOffersResponse {
+    data: Vec<AmadeusHotelOffers>,
+}
+
+#[derive(Deserialize)]
+struct AmadeusHotelOfferResponse {
+    data: AmadeusHotelOffers,
+}
+
+#[derive(Deserialize)]
+struct AmadeusHotelOffers {
+    hotel: AmadeusHotel,
+    #[serde(default)]
+    offers: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmadeusHotel {
+    hotel_id: String,
+    name: String,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+/// Bins every hotel into the nearest of `areas` given as `(name, center, radius_km)`.
+/// Hotels outside every radius, or without coordinates, are left out.
+pub fn group_by_area<'a>(
+    hotels: &'a [Hotel],
+    areas: &[(String, Coordinates, f32)],
+) -> HashMap<String, Vec<&'a Hotel>> {
+    let mut grouped: HashMap<String, Vec<&Hotel>> = HashMap::new();
+    for hotel in hotels {
+        let Some(coordinates) = hotel.coordinates() else {
+            continue;
+        };
+        let nearest = areas
+            .iter()
+            .map(|(name, center, radius)| (name, coordinates.distance_km(center), radius))
+            .filter(|(_, distance, radius)| *distance <= f64::from(**radius))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((name, _, _)) = nearest {
+            grouped.entry(name.clone()).or_default().push(hotel);
+        }
+    }
+    grouped
+}
+
+#[gemini_function]
+///Find hotel offers in a city by IATA code with stay dates and budget constraints.
+pub async fn hotels_in_city(
+    filter: HotelFilter,
+) -> Result<Vec<Hotel>, Box<dyn std::error::Error + Send + Sync>> {
+    filter.validate()?;
+    let center = match filter.search_radius_km {
+        Some(_) => Some(city_center_coords(&filter.city_code).await?),
+        None => None,
+    };
+    search_hotels(&filter, center.as_ref(), center).await
+}
+
+/// Hotels of `filter` within its `search_radius_km` of `around`, or registered in its city
+/// without `around`. `city_center` saves looking the centre up again when already known.
+async fn search_hotels(
+    filter: &HotelFilter,
+    around: Option<&Coordinates>,
+    city_center: Option<Coordinates>,
+) -> Result<Vec<Hotel>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = AmadeusClient::new();
+
+    // 1. Get hotels by city, or around a point when a radius is given
+    let city = &filter.city_code;
+    let (path, query) = filter.hotel_list_request(around);
+    let list_response: AmadeusHotelListResponse = client.get(path, &query).await?;
     let hotel_ids: Vec<String> = list_response
         .data
         .iter()
-        .take(hotels_count.into())
+        .take(filter.max_results.into())
         .map(|h| h.hotel_id.clone())
         .collect();
+    let stars: HashMap<&str, u8> = list_response
+        .data
+        .iter()
+        .filter_map(|h| Some((h.hotel_id.as_str(), h.rating?)))
+        .collect();
 
     if hotel_ids.is_empty() {
         return Err("No hotels found in given city_code".into());
     }
 
     // 2. Get offers for those hotels
-    let resp = client
-        .get(BASE_URL)
-        .header(AUTHORIZATION, format!("Bearer {}", token))
-        .query(&[
-            ("hotelIds", hotel_ids.join(",")),
-            ("checkInDate", check_in_date.to_yyyy_mm_dd()),
-            ("adults", adults.to_string()),
-            ("currency", currency_code.to_string()),
-        ])
-        .send()
-        .await?;
+    let mut query = vec![
+        ("hotelIds", hotel_ids.join(",")),
+        ("checkInDate", filter.check_in.to_yyyy_mm_dd()),
+        ("checkOutDate", filter.check_out.to_yyyy_mm_dd()),
+        ("adults", filter.adults.to_string()),
+        ("currency", filter.currency_code.clone()),
+    ];
+    if let Some(max_price) = filter.max_price {
+        query.push(("priceRange", format!("-{}", max_price.floor() as u32)));
+    }
+    let Some(offers_response): Option<AmadeusHotelOffersResponse> =
+        client.get_optional(HOTEL_OFFERS_PATH, &query).await?
+    else {
+        return Err("No hotels found in given city_code".into());
+    };
 
-    if !resp.status().is_success() {
-        if resp.status() == 404 {
-            return Err("No hotels found in given city_code".into());
+    // 3. Distances and guest ratings are a nice to have, so a failed lookup leaves them out
+    let locations = async {
+        match city_center {
+            Some(center) => (Ok(center), airport_coords(city).await),
+            None => join(city_center_coords(city), airport_coords(city)).await,
         }
-        let status = resp.status();
-        let error_text = resp.text().await?;
-        return Err(format!("Amadeus Hotel Offers error: {} - {}", status, error_text).into());
+    };
+    let ((center, airport), ratings) = join(locations, guest_ratings(&client, &hotel_ids)).await;
+    let center = center
+        .inspect_err(|e| eprintln!("ERROR: Could not locate the centre of {city}:\n{e}"))
+        .ok();
+    let airport = airport
+        .inspect_err(|e| eprintln!("ERROR: Could not locate the airport of {city}:\n{e}"))
+        .ok();
+    let ratings = ratings
+        .inspect_err(|e| eprintln!("ERROR: Could not fetch guest ratings:\n{e}"))
+        .unwrap_or_default();
+    let mut hotels: Vec<Hotel> = offers_response.data.into_iter().map(to_hotel).collect();
+    let nights = filter
+        .check_in
+        .to_naive_date()
+        .zip(filter.check_out.to_naive_date())
+        .map(|(check_in, check_out)| (check_out - check_in).num_days() as u32)
+        .unwrap_or_default();
+    if hotels
+        .iter()
+        .any(|hotel| hotel.has_prices_in_other_currency(&filter.currency_code))
+    {
+        match CurrencyConverter::latest().await {
+            Ok(converter) => hotels
+                .iter_mut()
+                .for_each(|hotel| hotel.convert_prices(&converter, &filter.currency_code)),
+            Err(e) => eprintln!("ERROR: Could not convert hotel prices:\n{e}"),
+        }
+    }
+    if let Some(arrival) = &filter.late_checkin_hotels {
+        hotels.retain(|hotel| hotel.can_checkin_after(arrival.clone()));
+    }
+    let now = Utc::now()
+        .with_timezone(&FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap())
+        .naive_local();
+    for hotel in &mut hotels {
+        hotel.set_distances(center.as_ref(), airport.as_ref());
+        hotel.city_code = Some(city.to_string());
+        hotel.stars = stars.get(hotel.hotel_id.as_str()).copied();
+        hotel.rating = ratings.get(&hotel.hotel_id).copied();
+        hotel.set_price_per_night(nights);
+        hotel.set_tags(&now);
     }
+    Ok(hotels)
+}
+
+/// Guest ratings out of 5 by hotel id, from the Amadeus hotel sentiments.
+async fn guest_ratings(
+    client: &AmadeusClient,
+    hotel_ids: &[String],
+) -> Result<HashMap<String, f32>, TravelError> {
+    let sentiments: AmadeusSentimentsResponse = client
+        .get(HOTEL_SENTIMENTS_PATH, &[("hotelIds", hotel_ids.join(","))])
+        .await?;
+    Ok(sentiments
+        .data
+        .into_iter()
+        .filter_map(|s| Some((s.hotel_id, s.overall_rating? / 20.0)))
+        .collect())
+}
 
-    Ok(resp.json().await?)
+/// Amadeus has no meal options, so this relies on the offer text.
+fn mentions_vegetarian_meals(offer: &Value) -> bool {
+    let text = offer
+        .to_string()
+        .to_ascii_lowercase()
+        .replace("non-vegetarian", "")
+        .replace("non vegetarian", "");
+    ["vegetarian", "jain meal", "jain food"]
+        .iter()
+        .any(|word| text.contains(word))
+}
+
+fn to_hotel(offers: AmadeusHotelOffers) -> Hotel {
+    Hotel {
+        hotel_id: offers.hotel.hotel_id,
+        name: offers.hotel.name,
+        latitude: offers.hotel.latitude,
+        longitude: offers.hotel.longitude,
+        rooms: offers
+            .offers
+            .iter()
+            .filter_map(RoomType::from_offer)
+            .collect(),
+        vegetarian_meals_available: offers.offers.iter().any(mentions_vegetarian_meals),
+        check_in_time: policy_time(&offers.offers, "checkInTime", "checkIn"),
+        latest_check_in_time: policy_time(&offers.offers, "latestCheckInTime", "latestCheckIn"),
+        check_out_time: policy_time(&offers.offers, "checkOutTime", "checkOut"),
+        offers: offers.offers,
+        is_price_guaranteed: false,
+        distance_to_center_km: None,
+        distance_to_airport_km: None,
+        city_code: None,
+        stars: None,
+        rating: None,
+        price_per_night: None,
+        tags: Vec::new(),
+    }
+}
+
+/// Time from the `policies` of the first offer that has it, as `key` or under `checkInOut`.
+/// Amadeus sends `HH:MM:SS` or `HH:MM`.
+fn policy_time(offers: &[Value], key: &str, check_in_out_key: &str) -> Option<Time> {
+    offers.iter().find_map(|offer| {
+        let policies = &offer["policies"];
+        let time = policies[key]
+            .as_str()
+            .or(policies["checkInOut"][check_in_out_key].as_str())?
+            .trim();
+        Time::from_hh_mm_ss(time)
+            .or_else(|_| Time::from_hh_mm_ss(&format!("{time}:00")))
+            .ok()
+    })
+}
+
+/// Amadeus has no explicit flag for this. An offer whose payment policy is a guarantee (card
+/// held by the hotel, paid at the property) is charged at the priced rate.
+fn is_price_guaranteed(offer: &Value) -> bool {
+    let policies = &offer["policies"];
+    policies["paymentType"] == "guarantee" || policies.get("guarantee").is_some()
+}
+
+fn to_priced_hotel(response: AmadeusHotelOfferResponse, guests: u8) -> Result<Hotel, TravelError> {
+    let mut hotel = to_hotel(response.data);
+    let offer = hotel
+        .offers
+        .first()
+        .ok_or_else(|| TravelError::Api("Offer is no longer available".into()))?;
+    if let Some(adults) = offer["guests"]["adults"].as_u64()
+        && adults < u64::from(guests)
+    {
+        return Err(TravelError::InvalidInput(format!(
+            "Offer is for {adults} adults but {guests} guests are travelling"
+        )));
+    }
+    hotel.is_price_guaranteed = is_price_guaranteed(offer);
+    Ok(hotel)
+}
+
+async fn fetch_hotel_offer(offer_id: &str) -> Result<AmadeusHotelOfferResponse, TravelError> {
+    let offer_id = sanitize_query_param(offer_id, MAX_OFFER_ID_LEN)?;
+    AmadeusClient::new()
+        .get(&format!("{HOTEL_OFFERS_PATH}/{offer_id}"), NO_PARAMS)
+        .await
+}
+
+/// Fetches the current price of a hotel offer returned by `hotels_in_city`.
+pub async fn reprice_hotel_offer(offer_id: &str, guests: u8) -> Result<Hotel, TravelError> {
+    to_priced_hotel(fetch_hotel_offer(offer_id).await?, guests)
+}
+
+/// Keeps only `room_type_code` in `rooms` of the priced hotel.
+fn to_selected_room(
+    response: AmadeusHotelOfferResponse,
+    hotel_id: &str,
+    room_type_code: &str,
+) -> Result<Hotel, TravelError> {
+    let mut hotel = to_hotel(response.data);
+    if !hotel.hotel_id.eq_ignore_ascii_case(hotel_id) {
+        return Err(TravelError::InvalidInput(format!(
+            "Offer belongs to hotel {} and not {hotel_id}",
+            hotel.hotel_id
+        )));
+    }
+    let room = hotel
+        .rooms
+        .iter()
+        .find(|room| room.code.eq_ignore_ascii_case(room_type_code))
+        .cloned()
+        .ok_or_else(|| {
+            TravelError::InvalidInput(format!(
+                "Room type {room_type_code} is not available in this offer"
+            ))
+        })?;
+    hotel.is_price_guaranteed = hotel.offers.first().is_some_and(is_price_guaranteed);
+    hotel.rooms = vec![room];
+    Ok(hotel)
+}
+
+#[gemini_function]
+///Confirm the current price of a hotel offer. Call it before recommending a specific hotel so the shown price is current.
+pub async fn confirm_hotel_price(
+    ///`id` of an offer from `hotels_in_city` or `hotels_near_area`
+    offer_id: String,
+    ///Number of adult guests staying
+    guests: u8,
+) -> Result<Hotel, TravelError> {
+    reprice_hotel_offer(&offer_id, guests).await
+}
+
+#[gemini_function]
+///Select a room of a hotel offer after browsing with `hotels_in_city`. Returns the room with its current price, so the user can be asked about upgrades like a sea view room.
+pub async fn select_hotel_room(
+    hotel_id: String,
+    ///`offer_id` of a room from `hotels_in_city` or `hotels_near_area`
+    offer_id: String,
+    ///`code` of the room
+    room_type_code: String,
+) -> Result<Hotel, TravelError> {
+    check_param_length(&room_type_code, MAX_ROOM_CODE_LEN)?;
+    to_selected_room(
+        fetch_hotel_offer(&offer_id).await?,
+        &hotel_id,
+        &room_type_code,
+    )
+}
+
+#[gemini_function]
+///Find hotel offers within a named area of a city, e.g. hotels near 'Baga Beach' in Goa.
+pub async fn hotels_near_area(
+    ///Area along with its city (e.g., 'Baga Beach, Goa')
+    area: String,
+    ///Hotel search in the city the area is in
+    filter: HotelFilter,
+    ///Radius around the area center in km. Defaults to 5.
+    radius_km: Option<f32>,
+) -> Result<Vec<Hotel>, Box<dyn std::error::Error + Send + Sync>> {
+    let center = geocode(&area).await?;
+    let hotels = hotels_in_city(filter).await?;
+
+    let areas = [(
+        area.clone(),
+        center,
+        radius_km.unwrap_or(DEFAULT_AREA_RADIUS_KM),
+    )];
+    let hotels_in_area = group_by_area(&hotels, &areas)
+        .remove(&area)
+        .unwrap_or_default()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    if hotels_in_area.is_empty() {
+        return Err(TravelError::Api(format!("No hotels found near {area}")).into());
+    }
+    Ok(hotels_in_area)
+}
+
+/// Hotels within `max_km` of `airport`, closest first, with their `distance_to_airport_km`
+/// set. Hotels without a known location are left out. The airport's location is taken from
+/// the geocode cache, an unknown IATA code is an error.
+pub async fn filter_hotels_near_airport(
+    hotels: Vec<Hotel>,
+    airport: IataCode,
+    max_km: f32,
+) -> Result<Vec<Hotel>, TravelError> {
+    let coords = airport_coords(&airport).await?;
+    Ok(hotels_within_km_of(hotels, &coords, max_km))
+}
+
+fn hotels_within_km_of(hotels: Vec<Hotel>, airport: &Coordinates, max_km: f32) -> Vec<Hotel> {
+    let mut nearby: Vec<Hotel> = hotels
+        .into_iter()
+        .filter_map(|mut hotel| {
+            let distance = hotel.coordinates()?.distance_km(airport) as f32;
+            hotel.distance_to_airport_km = Some(distance);
+            (distance <= max_km).then_some(hotel)
+        })
+        .collect();
+    nearby.sort_by(|a, b| {
+        a.distance_to_airport_km
+            .unwrap_or_default()
+            .total_cmp(&b.distance_to_airport_km.unwrap_or_default())
+    });
+    nearby
+}
+
+/// Radius of the Amadeus hotel list that covers `max_km`, which it only takes in whole km.
+fn airport_search_radius_km(max_km: f32) -> Result<u8, TravelError> {
+    if max_km.is_nan() || max_km <= 0.0 || max_km > MAX_AIRPORT_RADIUS_KM as f32 {
+        return Err(TravelError::InvalidInput(format!(
+            "max_km must be above 0 and at most {MAX_AIRPORT_RADIUS_KM}. Found: {max_km}"
+        )));
+    }
+    Ok(max_km.ceil() as u8)
+}
+
+#[gemini_function]
+///Find hotels close to an airport, for travellers with an early morning flight or a long layover. Closest first.
+pub async fn hotels_near_airport(
+    ///IATA code of the airport (e.g., 'DEL')
+    airport: IataCode,
+    ///Hotel search in the city the airport serves
+    filter: HotelFilter,
+    ///Maximum straight line distance to the airport in km. Defaults to 5, at most 50.
+    max_km: Option<f32>,
+) -> Result<Vec<Hotel>, Box<dyn std::error::Error + Send + Sync>> {
+    let max_km = max_km.unwrap_or(DEFAULT_AIRPORT_RADIUS_KM);
+    let mut filter = filter;
+    filter.search_radius_km = Some(airport_search_radius_km(max_km)?);
+    filter.validate()?;
+    // Listed around the airport, as hotels close to it are often outside the city limits
+    let coords = airport_coords(&airport).await?;
+    let hotels = search_hotels(&filter, Some(&coords), None).await?;
+    let hotels = filter_hotels_near_airport(hotels, airport.clone(), max_km).await?;
+    if hotels.is_empty() {
+        return Err(TravelError::Api(format!("No hotels found near {airport} airport")).into());
+    }
+    Ok(hotels)
+}
+
+/// A hotel found by `hotels_closest_to`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HotelNearAttraction {
+    ///Straight line distance to the attraction. `None` when the hotel's location is unknown
+    pub distance_km: Option<f32>,
+    #[serde(flatten)]
+    pub hotel: Hotel,
+}
+
+#[gemini_function]
+///Find the hotels in a city closest to an attraction, e.g. the beach, a temple or the business district. Closest first.
+pub async fn hotels_closest_to(
+    ///Attraction along with its city (e.g., 'Baga Beach, Goa')
+    attraction_name: String,
+    ///Hotel search in the city the attraction is in
+    filter: HotelFilter,
+    ///Number of hotels to return. Defaults to 5.
+    count: Option<u8>,
+) -> Result<Vec<HotelNearAttraction>, Box<dyn std::error::Error + Send + Sync>> {
+    let attraction = geocode(&attraction_name).await?;
+    let mut hotels = hotels_in_city(filter).await?;
+    sort_hotels_by_distance_to(&mut hotels, attraction);
+    Ok(hotels
+        .into_iter()
+        .take(count.unwrap_or(DEFAULT_CLOSEST_COUNT).into())
+        .map(|hotel| HotelNearAttraction {
+            distance_km: hotel
+                .coordinates()
+                .map(|location| location.distance_km(&attraction) as f32),
+            hotel,
+        })
+        .collect())
 }
 
 #[tokio::test]
 async fn hotels_in_city_test() {
-    let city_code = IataCode::new("DEL".to_string()).unwrap();
-    let check_in_date = Date::new(2026, 1, 30).unwrap();
-    let currency = "INR";
-
-    let result = hotels_in_city(
-        city_code,
-        check_in_date,
-        2,
-        currency.into(),
-        Rating { start: 1, end: 5 },
-        20,
+    let filter = HotelFilter::builder(
+        IataCode::new("DEL".to_string()).unwrap(),
+        Date::new(2026, 1, 30).unwrap(),
+        Date::new(2026, 2, 1).unwrap(),
     )
-    .await;
+    .adults(2)
+    .currency_code("INR")
+    .build()
+    .unwrap();
+
+    let result = hotels_in_city(filter).await;
 
     match result {
         Ok(hotels) => {
@@ -131,3 +1034,640 @@ async fn hotels_in_city_test() {
         }
     }
 }
+
+#[test]
+fn group_by_area_test() {
+    let hotel = |id: &str, coordinates: Option<(f64, f64)>| Hotel {
+        hotel_id: id.into(),
+        name: format!("Hotel {id}"),
+        latitude: coordinates.map(|c| c.0),
+        longitude: coordinates.map(|c| c.1),
+        offers: vec![],
+        rooms: vec![],
+        is_price_guaranteed: false,
+        distance_to_center_km: None,
+        distance_to_airport_km: None,
+        vegetarian_meals_available: false,
+        city_code: None,
+        stars: None,
+        rating: None,
+        price_per_night: None,
+        tags: Vec::new(),
+        check_in_time: None,
+        latest_check_in_time: None,
+        check_out_time: None,
+    };
+    let hotels = vec![
+        hotel("BAGA1", Some((15.5560, 73.7517))),
+        hotel("CAL1", Some((15.5439, 73.7553))),
+        hotel("PANJIM1", Some((15.4989, 73.8278))),
+        hotel("FAR1", Some((15.0, 74.0))),
+        hotel("NOGEO", None),
+    ];
+    let areas = [
+        ("Baga".to_string(), Coordinates::new(15.5553, 73.7517), 1.0),
+        (
+            "Calangute".to_string(),
+            Coordinates::new(15.5439, 73.7553),
+            1.0,
+        ),
+        (
+            "Panjim".to_string(),
+            Coordinates::new(15.4909, 73.8278),
+            3.0,
+        ),
+    ];
+
+    let grouped = group_by_area(&hotels, &areas);
+    let ids = |area: &str| {
+        grouped[area]
+            .iter()
+            .map(|h| h.hotel_id.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids("Baga"), vec!["BAGA1"]);
+    assert_eq!(ids("Calangute"), vec!["CAL1"]);
+    assert_eq!(ids("Panjim"), vec!["PANJIM1"]);
+    assert_eq!(grouped.values().map(Vec::len).sum::<usize>(), 3);
+}
+
+#[test]
+fn vegetarian_meals_test() {
+    let offer = |text: &str| serde_json::json!({"id": "1", "description": {"text": text}});
+    assert!(mentions_vegetarian_meals(&offer(
+        "Pure Vegetarian breakfast buffet included"
+    )));
+    assert!(mentions_vegetarian_meals(&offer("Jain meals on request")));
+    assert!(!mentions_vegetarian_meals(&offer(
+        "Non-vegetarian dinner included"
+    )));
+    assert!(!mentions_vegetarian_meals(&offer("Room only")));
+}
+
+#[test]
+fn hotel_distances_test() {
+    let mut hotel = Hotel {
+        hotel_id: "PANJIM1".into(),
+        name: "Hotel Mandovi".into(),
+        latitude: Some(15.4989),
+        longitude: Some(73.8278),
+        offers: vec![],
+        rooms: vec![],
+        is_price_guaranteed: false,
+        distance_to_center_km: None,
+        distance_to_airport_km: None,
+        vegetarian_meals_available: false,
+        city_code: None,
+        stars: None,
+        rating: None,
+        price_per_night: None,
+        tags: Vec::new(),
+        check_in_time: None,
+        latest_check_in_time: None,
+        check_out_time: None,
+    };
+    let panjim = Coordinates::new(15.4909, 73.8278);
+    let dabolim = Coordinates::new(15.3808, 73.8314);
+
+    hotel.set_distances(Some(&panjim), Some(&dabolim));
+    let center = hotel.distance_to_center_km.unwrap();
+    let airport = hotel.distance_to_airport_km.unwrap();
+    assert!((center - 0.89).abs() < 0.05, "{center}");
+    assert!((airport - 13.1).abs() < 0.2, "{airport}");
+
+    hotel.set_distances(None, Some(&dabolim));
+    assert_eq!(hotel.distance_to_center_km, None);
+    assert!(hotel.distance_to_airport_km.is_some());
+
+    hotel.latitude = None;
+    hotel.set_distances(Some(&panjim), Some(&dabolim));
+    assert_eq!(hotel.distance_to_center_km, None);
+    assert_eq!(hotel.distance_to_airport_km, None);
+}
+
+#[test]
+fn hotel_filter_builder_test() {
+    let city = || IataCode::new("GOI".to_string()).unwrap();
+    let filter = HotelFilter::builder(
+        city(),
+        Date::new(2026, 2, 15).unwrap(),
+        Date::new(2026, 2, 22).unwrap(),
+    )
+    .adults(2)
+    .currency_code("INR")
+    .max_price(8000.0)
+    .amenities(vec![HotelAmenity::Wifi, HotelAmenity::SwimmingPool])
+    .min_stars(3.5)
+    .max_results(10)
+    .build()
+    .unwrap();
+    assert_eq!(filter.ratings(), "4,5");
+    assert_eq!(filter.max_results, 10);
+
+    let same_day = HotelFilter::builder(
+        city(),
+        Date::new(2026, 2, 15).unwrap(),
+        Date::new(2026, 2, 15).unwrap(),
+    )
+    .build();
+    assert!(matches!(same_day, Err(TravelError::InvalidInput(_))));
+
+    let reversed = HotelFilter::builder(
+        city(),
+        Date::new(2026, 3, 1).unwrap(),
+        Date::new(2026, 2, 28).unwrap(),
+    )
+    .build();
+    assert!(reversed.is_err());
+
+    let check_out = Date::new(2026, 1, 2).unwrap();
+    let new_year =
+        || HotelFilter::builder(city(), Date::new(2025, 12, 31).unwrap(), check_out.clone());
+    assert_eq!(new_year().build().unwrap().ratings(), "1,2,3,4,5");
+    assert!(new_year().adults(0).build().is_err());
+    assert!(new_year().adults(10).build().is_err());
+    assert!(new_year().search_radius_km(0).build().is_err());
+}
+
+#[test]
+fn hotel_list_request_test() {
+    let filter = || {
+        HotelFilter::builder(
+            IataCode::new("GOI".to_string()).unwrap(),
+            Date::new(2026, 2, 15).unwrap(),
+            Date::new(2026, 2, 22).unwrap(),
+        )
+        .amenities(vec![HotelAmenity::Beach])
+        .min_stars(4.0)
+    };
+    let goa = Coordinates::new(15.4909, 73.8278);
+
+    let (path, query) = filter().build().unwrap().hotel_list_request(None);
+    assert_eq!(path, HOTEL_LIST_PATH);
+    assert_eq!(
+        query,
+        [
+            ("cityCode", "GOI".to_string()),
+            ("ratings", "4,5".to_string()),
+            ("amenities", "BEACH".to_string()),
+        ]
+    );
+
+    let radius = filter().search_radius_km(25).build().unwrap();
+    let (path, query) = radius.hotel_list_request(Some(&goa));
+    assert_eq!(path, HOTEL_GEOCODE_LIST_PATH);
+    assert_eq!(
+        query,
+        [
+            ("latitude", "15.4909".to_string()),
+            ("longitude", "73.8278".to_string()),
+            ("radius", "25".to_string()),
+            ("radiusUnit", "KM".to_string()),
+            ("hotelSource", "ALL".to_string()),
+            ("ratings", "4,5".to_string()),
+            ("amenities", "BEACH".to_string()),
+        ]
+    );
+    // Without a centre the city search is the fallback
+    assert_eq!(radius.hotel_list_request(None).0, HOTEL_LIST_PATH);
+}
+
+#[test]
+fn to_priced_hotel_test() {
+    let fixture = |policies: Value| -> AmadeusHotelOfferResponse {
+        serde_json::from_value(serde_json::json!({
+            "data": {
+                "type": "hotel-offers",
+                "hotel": {
+                    "hotelId": "MCGOITAJ",
+                    "name": "Taj Fort Aguada",
+                    "latitude": 15.4966,
+                    "longitude": 73.7735
+                },
+                "available": true,
+                "offers": [{
+                    "id": "TSXOJ6LFQ2",
+                    "checkInDate": "2026-02-15",
+                    "checkOutDate": "2026-02-22",
+                    "guests": {"adults": 2},
+                    "price": {"currency": "INR", "total": "84000.00"},
+                    "policies": policies
+                }]
+            }
+        }))
+        .unwrap()
+    };
+
+    let hotel =
+        to_priced_hotel(fixture(serde_json::json!({"paymentType": "guarantee"})), 2).unwrap();
+    assert_eq!(hotel.hotel_id, "MCGOITAJ");
+    assert_eq!(hotel.offers[0]["price"]["total"], "84000.00");
+    assert!(hotel.is_price_guaranteed);
+
+    let deposit = serde_json::json!({"paymentType": "deposit", "deposit": {"amount": "8400.00"}});
+    assert!(
+        !to_priced_hotel(fixture(deposit), 1)
+            .unwrap()
+            .is_price_guaranteed
+    );
+
+    let too_many_guests = to_priced_hotel(fixture(serde_json::json!({})), 3);
+    assert!(matches!(too_many_guests, Err(TravelError::InvalidInput(_))));
+}
+
+#[test]
+fn check_in_time_test() {
+    let hotel = |offers: Value| {
+        to_hotel(
+            serde_json::from_value(serde_json::json!({
+                "hotel": {"hotelId": "MCGOITAJ", "name": "Taj Fort Aguada"},
+                "offers": offers
+            }))
+            .unwrap(),
+        )
+    };
+    let taj = hotel(serde_json::json!([
+        {"id": "NOPOLICY"},
+        {
+            "id": "TSXOJ6LFQ2",
+            "policies": {
+                "checkInTime": "14:00:00",
+                "latestCheckInTime": "22:00",
+                "checkOutTime": "12:00"
+            }
+        }
+    ]));
+    assert_eq!(taj.check_in_time, Some(Time::new(14, 0, 0).unwrap()));
+    assert_eq!(taj.latest_check_in_time, Some(Time::new(22, 0, 0).unwrap()));
+    assert_eq!(taj.check_out_time, Some(Time::new(12, 0, 0).unwrap()));
+    // Landing late, the guests reach the hotel after check-in has closed
+    assert!(!taj.can_checkin_after(Time::new(23, 0, 0).unwrap()));
+    assert!(taj.can_checkin_after(Time::new(22, 0, 0).unwrap()));
+    assert!(taj.can_checkin_after(Time::new(9, 30, 0).unwrap()));
+
+    let check_in_out = hotel(serde_json::json!([{
+        "id": "A1",
+        "policies": {"checkInOut": {
+            "checkIn": "13:00:00",
+            "latestCheckIn": "23:30",
+            "checkOut": "11:00:00"
+        }}
+    }]));
+    assert_eq!(
+        check_in_out.check_in_time,
+        Some(Time::new(13, 0, 0).unwrap())
+    );
+    assert_eq!(
+        check_in_out.check_out_time,
+        Some(Time::new(11, 0, 0).unwrap())
+    );
+    assert!(check_in_out.can_checkin_after(Time::new(23, 0, 0).unwrap()));
+
+    let unknown = hotel(serde_json::json!([{"id": "B1", "policies": {"checkInTime": "noon"}}]));
+    assert_eq!(unknown.check_in_time, None);
+    assert_eq!(unknown.latest_check_in_time, None);
+    assert!(unknown.can_checkin_after(Time::new(23, 0, 0).unwrap()));
+}
+
+#[test]
+fn room_types_test() {
+    let offer = |id: &str, code: &str, board: &str, refund: &str, total: &str| {
+        serde_json::json!({
+            "id": id,
+            "room": {
+                "type": code,
+                "typeEstimated": {"category": "DELUXE_ROOM", "beds": 1, "bedType": "KING"},
+                "description": {"text": format!("{code} room with balcony"), "lang": "EN"}
+            },
+            "guests": {"adults": 2},
+            "boardType": board,
+            "price": {"currency": "INR", "total": total},
+            "policies": {"paymentType": "guarantee", "refundable": {"cancellationRefund": refund}}
+        })
+    };
+    let fixture = serde_json::json!({
+        "data": {
+            "hotel": {"hotelId": "MCGOITAJ", "name": "Taj Fort Aguada"},
+            "offers": [
+                offer("OFFER1", "A1K", "ROOM_ONLY", "NON_REFUNDABLE", "84000.00"),
+                offer("OFFER2", "SVK", "BREAKFAST", "REFUNDABLE_UP_TO_DEADLINE", "87500.00"),
+                {"id": "OFFER3", "price": {"currency": "INR", "total": "1.00"}}
+            ]
+        }
+    });
+    let response =
+        || -> AmadeusHotelOfferResponse { serde_json::from_value(fixture.clone()).unwrap() };
+
+    let hotel = to_hotel(response().data);
+    assert_eq!(hotel.rooms.len(), 2);
+    assert!(!hotel.vegetarian_meals_available);
+    let standard = &hotel.rooms[0];
+    assert_eq!(standard.offer_id, "OFFER1");
+    assert_eq!(standard.bed_configuration, "1 KING");
+    assert_eq!(standard.max_occupancy, 2);
+    assert_eq!(standard.description, "A1K room with balcony");
+    assert!(!standard.refundable && !standard.breakfast_included);
+    let sea_view = &hotel.rooms[1];
+    assert!(sea_view.refundable && sea_view.breakfast_included);
+    assert!(matches!(sea_view.price, Currency::Inr(total) if total == 87500.0));
+
+    let selected = to_selected_room(response(), "MCGOITAJ", "svk").unwrap();
+    assert_eq!(selected.rooms.len(), 1);
+    assert_eq!(selected.rooms[0].offer_id, "OFFER2");
+    assert!(selected.is_price_guaranteed);
+
+    assert!(matches!(
+        to_selected_room(response(), "MCGOIXYZ", "SVK"),
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        to_selected_room(response(), "MCGOITAJ", "PRES"),
+        Err(TravelError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn cancellation_policy_test() {
+    let offer = |policies: Value| {
+        serde_json::json!({
+            "id": "OFFER1",
+            "room": {"type": "A1K"},
+            "price": {"currency": "INR", "total": "20000.00"},
+            "checkInDate": "2026-02-15",
+            "checkOutDate": "2026-02-17",
+            "policies": policies
+        })
+    };
+    let policy = |policies: Value| {
+        RoomType::from_offer(&offer(policies))
+            .unwrap()
+            .cancellation_policy
+    };
+
+    // The penalty is charged after the deadline, before it cancelling is free
+    let free = |deadline: &str, refund_pct_after| {
+        Some(CancellationPolicy::FreeCancellation {
+            deadline: deadline.into(),
+            refund_pct_after,
+        })
+    };
+    assert_eq!(
+        policy(serde_json::json!({
+            "cancellations": [{"deadline": "2026-02-13T23:59:00+05:30", "amount": "20000.00"}]
+        })),
+        free("2026-02-13T23:59:00", 0)
+    );
+    assert_eq!(
+        policy(serde_json::json!({
+            "cancellations": [{"deadline": "2026-02-13T18:00:00"}]
+        })),
+        free("2026-02-13T18:00:00", 0)
+    );
+    assert_eq!(
+        policy(serde_json::json!({
+            "cancellations": [{"deadline": "2026-02-10T12:00:00", "amount": "5000.00"}]
+        })),
+        free("2026-02-10T12:00:00", 75)
+    );
+    assert_eq!(
+        policy(serde_json::json!({
+            "cancellations": [{"deadline": "2026-02-10T12:00:00", "percentage": "50"}]
+        })),
+        free("2026-02-10T12:00:00", 50)
+    );
+    // One of the two nights
+    assert_eq!(
+        policy(serde_json::json!({
+            "cancellations": [{"deadline": "2026-02-13T12:00:00", "numberOfNights": 1}]
+        })),
+        free("2026-02-13T12:00:00", 50)
+    );
+    assert_eq!(
+        policy(serde_json::json!({"cancellations": [{"numberOfNights": 1}]})),
+        Some(CancellationPolicy::PartialRefund {
+            refund_pct: 50,
+            before: "2026-02-15T00:00:00".into()
+        })
+    );
+    assert_eq!(
+        policy(serde_json::json!({"refundable": {"cancellationRefund": "NON_REFUNDABLE"}})),
+        Some(CancellationPolicy::NonRefundable)
+    );
+    assert_eq!(
+        policy(serde_json::json!({"cancellations": [{"amount": "20000.00"}]})),
+        Some(CancellationPolicy::NonRefundable)
+    );
+    assert_eq!(
+        policy(serde_json::json!({"cancellations": [{"numberOfNights": 2}]})),
+        Some(CancellationPolicy::NonRefundable)
+    );
+    assert_eq!(policy(serde_json::json!({})), None);
+    assert_eq!(
+        policy(serde_json::json!({"cancellations": [{"deadline": "soon"}]})),
+        None
+    );
+
+    let date = |at: &str| local_date_time(at).unwrap();
+    let mut hotel = to_hotel(
+        serde_json::from_value(serde_json::json!({
+            "hotel": {"hotelId": "MCGOITAJ", "name": "Taj Fort Aguada"},
+            "offers": [offer(serde_json::json!({
+                "cancellations": [{"deadline": "2026-02-13T23:59:00+05:30"}]
+            }))]
+        }))
+        .unwrap(),
+    );
+    let deadline = date("2026-02-13T23:59:00");
+    assert_eq!(
+        hotel.free_cancellation_until(&date("2026-02-12T10:00:00")),
+        Some(deadline)
+    );
+    assert_eq!(hotel.free_cancellation_until(&deadline), Some(deadline));
+    assert_eq!(
+        hotel.free_cancellation_until(&date("2026-02-14T00:00:00")),
+        None
+    );
+    assert!(hotel.can_cancel_free_before(&date("2026-02-12T10:00:00")));
+    assert!(hotel.can_cancel_free_before(&deadline));
+    assert!(!hotel.can_cancel_free_before(&date("2026-02-14T00:00:00")));
+    hotel.set_tags(&date("2026-02-12T10:00:00"));
+    assert_eq!(hotel.tags, ["✅ Free cancellation until 13 Feb, 11:59 PM"]);
+    hotel.set_tags(&date("2026-02-14T00:00:00"));
+    assert!(hotel.tags.is_empty());
+    hotel.rooms[0].cancellation_policy = Some(CancellationPolicy::NonRefundable);
+    assert_eq!(
+        hotel.free_cancellation_until(&date("2026-02-12T10:00:00")),
+        None
+    );
+}
+
+#[test]
+fn hotel_value_test() {
+    let room = |price: f32| -> RoomType {
+        serde_json::from_value(serde_json::json!({
+            "offer_id": "OFFER1",
+            "code": "A1K",
+            "description": "Deluxe room",
+            "bed_configuration": "1 KING",
+            "max_occupancy": 2,
+            "price": {"Inr": price},
+            "refundable": true,
+            "breakfast_included": false
+        }))
+        .unwrap()
+    };
+    let now = local_date_time("2026-02-01T10:00:00").unwrap();
+    let hotel = |city: &str, stars: u8, rating: f32, total: f32| {
+        let mut hotel: Hotel = serde_json::from_value(serde_json::json!({
+            "hotel_id": "HOTEL1",
+            "name": "Hotel",
+            "latitude": null,
+            "longitude": null,
+            "offers": [],
+            "distance_to_center_km": null,
+            "distance_to_airport_km": null,
+            "city_code": city,
+            "stars": stars,
+            "rating": rating
+        }))
+        .unwrap();
+        hotel.rooms = vec![room(total * 2.0), room(total)];
+        hotel.set_price_per_night(2);
+        hotel.set_tags(&now);
+        hotel
+    };
+
+    // 3 star median is ₹4500, so ₹3500 a night is a good deal
+    let deal = hotel("BOM", 3, 4.0, 7000.0);
+    assert_eq!(
+        deal.price_per_night.as_ref().map(Currency::to_amount),
+        Some(3500.0)
+    );
+    assert!(deal.is_value_for_money());
+    assert!(!deal.is_luxury());
+    assert_eq!(deal.tags, [BEST_VALUE_TAG]);
+    assert!(!hotel("BOM", 3, 4.0, 7400.0).is_value_for_money());
+    assert!(!hotel("BOM", 3, 3.4, 7000.0).is_value_for_money());
+    assert!(!hotel("IXR", 3, 4.0, 7000.0).is_value_for_money());
+    assert!(hotel("DEL", 5, 3.5, 20000.0).is_value_for_money());
+
+    let palace = hotel("JAI", 5, 4.7, 60000.0);
+    assert!(palace.is_luxury());
+    assert_eq!(palace.tags, [LUXURY_TAG]);
+    assert!(!hotel("JAI", 3, 4.7, 6000.0).is_luxury());
+    assert_eq!(
+        hotel("DEL", 4, 4.6, 8000.0).tags,
+        [BEST_VALUE_TAG, LUXURY_TAG]
+    );
+
+    let mut unknown = hotel("BOM", 3, 4.0, 7000.0);
+    unknown.stars = None;
+    unknown.set_tags(&now);
+    assert!(unknown.tags.is_empty());
+    unknown.set_price_per_night(0);
+    assert!(unknown.price_per_night.is_none());
+}
+
+#[test]
+fn hotel_price_conversion_test() {
+    let offer = |id: &str, currency: &str, total: &str| {
+        serde_json::json!({
+            "id": id,
+            "room": {"type": "A1K"},
+            "price": {"currency": currency, "total": total}
+        })
+    };
+    let fixture = serde_json::json!({
+        "hotel": {"hotelId": "HINYC123", "name": "Pod Times Square"},
+        "offers": [offer("OFFER1", "USD", "300.00"), offer("OFFER2", "INR", "20000.00")]
+    });
+    let mut hotel = to_hotel(serde_json::from_value(fixture).unwrap());
+    assert!(hotel.has_prices_in_other_currency("INR"));
+    assert!(
+        !to_hotel(
+            serde_json::from_value(serde_json::json!({
+                "hotel": {"hotelId": "HINYC123", "name": "Pod Times Square"},
+                "offers": [offer("OFFER1", "usd", "300.00")]
+            }))
+            .unwrap()
+        )
+        .has_prices_in_other_currency("USD")
+    );
+
+    let converter = CurrencyConverter::from_rates(&[("INR", 1.0), ("USD", 0.0125)]);
+    hotel.convert_prices(&converter, "INR");
+    assert!(!hotel.has_prices_in_other_currency("INR"));
+    assert!(matches!(hotel.rooms[0].price, Currency::Inr(total) if (total - 24000.0).abs() < 0.5));
+    assert!(matches!(hotel.rooms[1].price, Currency::Inr(total) if total == 20000.0));
+    hotel.set_price_per_night(2);
+    assert!(
+        matches!(hotel.price_per_night, Some(Currency::Inr(price)) if price == 10000.0),
+        "{:?}",
+        hotel.price_per_night
+    );
+
+    // Without a rate the price is left as Amadeus sent it
+    let mut euro = hotel.clone();
+    euro.rooms[1].price = Currency::Eur(150.0);
+    euro.convert_prices(&converter, "INR");
+    assert!(matches!(euro.rooms[1].price, Currency::Eur(total) if total == 150.0));
+}
+
+#[tokio::test]
+async fn hotels_near_airport_test() {
+    use crate::api_requests::geocode::cache_airport_coords;
+
+    let hotel = |id: &str, location: Option<(f64, f64)>| Hotel {
+        hotel_id: id.into(),
+        name: id.into(),
+        latitude: location.map(|(latitude, _)| latitude),
+        longitude: location.map(|(_, longitude)| longitude),
+        offers: vec![],
+        rooms: vec![],
+        is_price_guaranteed: false,
+        distance_to_center_km: None,
+        distance_to_airport_km: None,
+        vegetarian_meals_available: false,
+        city_code: None,
+        stars: None,
+        rating: None,
+        price_per_night: None,
+        tags: Vec::new(),
+        check_in_time: None,
+        latest_check_in_time: None,
+        check_out_time: None,
+    };
+    // Not a real airport, so the test never clashes with a geocoded one
+    let delhi_airport = IataCode::new("XDL".into()).unwrap();
+    cache_airport_coords(delhi_airport.clone(), Coordinates::new(28.5562, 77.1000)).await;
+    let hotels = vec![
+        hotel("CONNAUGHT", Some((28.6315, 77.2167))),
+        hotel("MAHIPALPUR", Some((28.5450, 77.1260))),
+        hotel("AEROCITY", Some((28.5503, 77.1205))),
+        hotel("UNKNOWN", None),
+    ];
+
+    let nearby = filter_hotels_near_airport(hotels.clone(), delhi_airport.clone(), 5.0)
+        .await
+        .unwrap();
+    let ids: Vec<&str> = nearby.iter().map(|hotel| hotel.hotel_id.as_str()).collect();
+    assert_eq!(ids, ["AEROCITY", "MAHIPALPUR"]);
+    let closest = nearby[0].distance_to_airport_km.unwrap();
+    assert!((closest - 2.1).abs() < 0.2, "{closest}");
+    assert_eq!(
+        filter_hotels_near_airport(hotels.clone(), delhi_airport, 20.0)
+            .await
+            .unwrap()
+            .len(),
+        3
+    );
+
+    assert_eq!(airport_search_radius_km(5.0).unwrap(), 5);
+    assert_eq!(airport_search_radius_km(2.5).unwrap(), 3);
+    for max_km in [0.0, -3.0, 51.0, f32::NAN] {
+        assert!(matches!(
+            airport_search_radius_km(max_km),
+            Err(TravelError::InvalidInput(_))
+        ));
+    }
+}
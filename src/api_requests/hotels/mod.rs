@@ -1 +1,2 @@
 pub mod amadeus;
+pub mod reviews;
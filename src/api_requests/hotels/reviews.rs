@@ -0,0 +1,209 @@
+use crate::{
+    config::CONFIG, error::TravelError, gemini_interface::GeminiInterface,
+    utils::check_param_length,
+};
+use gemini_client_api::gemini::{
+    ask::Gemini,
+    types::{request::Tool, sessions::Session},
+    utils::{GeminiSchema, gemini_function, gemini_schema},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Fast enough to answer while the planner waits, and grounded in search results anyway.
+const REVIEW_MODEL: &str = "gemini-flash-latest";
+// Reviews change slowly, but hotels do get renovated or change hands
+const REVIEW_TTL: Duration = Duration::from_secs(60 * 60);
+const MAX_NAME_LEN: usize = 100;
+const MAX_LIST_ITEMS: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[gemini_schema]
+pub enum Sentiment {
+    VeryPositive,
+    Positive,
+    Mixed,
+    Negative,
+    VeryNegative,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[gemini_schema]
+///What guests say about a hotel in recent reviews.
+pub struct ReviewSummary {
+    pub overall_sentiment: Sentiment,
+    ///What guests praise most, e.g. 'Helpful staff'
+    pub top_pros: Vec<String>,
+    ///What guests complain about most, e.g. 'Thin walls'
+    pub top_cons: Vec<String>,
+    ///Kinds of travellers the hotel suits, e.g. 'Families with young children'
+    pub best_for: Vec<String>,
+}
+
+/// Summaries by lowercase hotel name and location.
+static REVIEW_CACHE: LazyLock<RwLock<HashMap<String, (ReviewSummary, Instant)>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn review_prompt(hotel_name: &str, location: &str) -> String {
+    let example = json!({
+        "overall_sentiment": "Positive",
+        "top_pros": ["Helpful staff"],
+        "top_cons": ["Slow Wi-Fi"],
+        "best_for": ["Couples"]
+    });
+    format!(
+        "Search for recent guest reviews of the hotel {hotel_name} in {location} on Google, TripAdvisor, Booking.com and MakeMyTrip. Summarise them as a single JSON object like {example} and nothing else. `overall_sentiment` is one of VeryPositive, Positive, Mixed, Negative or VeryNegative. Give at most {MAX_LIST_ITEMS} short items in each list, most mentioned first."
+    )
+}
+
+/// Reads the JSON object out of Gemini's reply, which may be wrapped in a markdown code block
+/// or text, as JSON mode can't be used along with Google Search.
+fn parse_review_summary(reply: &str) -> Result<ReviewSummary, TravelError> {
+    let json = reply
+        .find('{')
+        .zip(reply.rfind('}'))
+        .filter(|(start, end)| start < end)
+        .map(|(start, end)| &reply[start..=end])
+        .ok_or_else(|| TravelError::ParseError(format!("No review summary in reply: {reply}")))?;
+    let mut summary: ReviewSummary = serde_json::from_str(json)
+        .map_err(|e| TravelError::ParseError(format!("Invalid review summary: {e}")))?;
+    for list in [
+        &mut summary.top_pros,
+        &mut summary.top_cons,
+        &mut summary.best_for,
+    ] {
+        list.retain(|item| !item.trim().is_empty());
+        list.truncate(MAX_LIST_ITEMS);
+    }
+    Ok(summary)
+}
+
+/// Summary from `ai`, cached for `REVIEW_TTL`.
+pub async fn review_summary(
+    ai: &impl GeminiInterface,
+    hotel_name: &str,
+    location: &str,
+) -> Result<ReviewSummary, TravelError> {
+    let hotel_name = check_param_length(hotel_name.trim(), MAX_NAME_LEN)?;
+    let location = check_param_length(location.trim(), MAX_NAME_LEN)?;
+    if hotel_name.is_empty() || location.is_empty() {
+        return Err(TravelError::InvalidInput(
+            "Hotel name and location are required".into(),
+        ));
+    }
+    let key = format!("{hotel_name}|{location}").to_lowercase();
+    if let Some((summary, fetched_at)) = REVIEW_CACHE.read().await.get(&key)
+        && fetched_at.elapsed() < REVIEW_TTL
+    {
+        return Ok(summary.clone());
+    }
+
+    let mut session = Session::new(2);
+    session.ask_string(review_prompt(hotel_name, location));
+    let reply = ai
+        .ask(&mut session)
+        .await
+        .map_err(|e| TravelError::Api(format!("Gemini review summary failed: {e}")))?;
+    let summary = parse_review_summary(&reply.get_chat().get_text_no_think(""))?;
+    REVIEW_CACHE
+        .write()
+        .await
+        .insert(key, (summary.clone(), Instant::now()));
+    Ok(summary)
+}
+
+/// Summary of the reviews Google Search finds for the hotel.
+pub async fn get_hotel_reviews_summary(
+    hotel_name: &str,
+    location: &str,
+) -> Result<ReviewSummary, TravelError> {
+    let ai = Gemini::new(CONFIG.gemini_api_key.clone(), REVIEW_MODEL, None)
+        .set_tools(vec![Tool::GoogleSearch(json!({}))]);
+    review_summary(&ai, hotel_name, location).await
+}
+
+#[gemini_function]
+///Summarise what guests say about a hotel in recent reviews: overall sentiment, top pros and cons, and who it suits best. Call it for the hotels the user is deciding between.
+pub async fn hotel_review_summary(
+    ///Name of the hotel (e.g., 'Taj Fort Aguada Resort & Spa')
+    hotel_name: String,
+    ///City or area of the hotel (e.g., 'Candolim, Goa')
+    location: String,
+) -> Result<ReviewSummary, TravelError> {
+    get_hotel_reviews_summary(&hotel_name, &location).await
+}
+
+#[test]
+fn parse_review_summary_test() {
+    let reply = r#"Here is the summary:
+```json
+{
+  "overall_sentiment": "VeryPositive",
+  "top_pros": ["Sea view", "Breakfast", " ", "Pool", "Spa", "Staff", "Location"],
+  "top_cons": ["Expensive"],
+  "best_for": ["Couples", "Families"]
+}
+```"#;
+    let summary = parse_review_summary(reply).unwrap();
+    assert_eq!(summary.overall_sentiment, Sentiment::VeryPositive);
+    assert_eq!(
+        summary.top_pros,
+        ["Sea view", "Breakfast", "Pool", "Spa", "Staff"]
+    );
+    assert_eq!(summary.top_cons, ["Expensive"]);
+    assert_eq!(summary.best_for, ["Couples", "Families"]);
+
+    for reply in [
+        "I couldn't find reviews for this hotel.",
+        "} {",
+        r#"{"overall_sentiment": "Great", "top_pros": [], "top_cons": [], "best_for": []}"#,
+    ] {
+        assert!(
+            matches!(parse_review_summary(reply), Err(TravelError::ParseError(_))),
+            "{reply}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn review_summary_test() {
+    use crate::gemini_interface::MockGemini;
+
+    let prompt = review_prompt("Zostel", "Manali");
+    assert!(prompt.starts_with("Search for recent guest reviews of the hotel Zostel in Manali"));
+    assert!(prompt.contains(r#""overall_sentiment":"Positive""#));
+
+    let ai = MockGemini::new([
+        r#"{"overall_sentiment": "Mixed", "top_pros": ["Cheap"], "top_cons": ["Noisy"], "best_for": ["Backpackers"]}"#,
+    ]);
+    for (name, location) in [
+        (" ", "Manali"),
+        ("Zostel", ""),
+        (&*"x".repeat(101), "Manali"),
+    ] {
+        assert!(matches!(
+            review_summary(&ai, name, location).await,
+            Err(TravelError::InvalidInput(_))
+        ));
+    }
+    let summary = review_summary(&ai, "Review Test Hostel", "Manali")
+        .await
+        .unwrap();
+    assert_eq!(summary.overall_sentiment, Sentiment::Mixed);
+    assert_eq!(summary.best_for, ["Backpackers"]);
+    assert_eq!(ai.remaining(), 0);
+
+    // Cached, so Gemini isn't asked again
+    let cached = review_summary(&ai, " review test hostel ", "MANALI")
+        .await
+        .unwrap();
+    assert_eq!(cached, summary);
+    assert!(matches!(
+        review_summary(&ai, "Review Test Hostel", "Kasol").await,
+        Err(TravelError::Api(_))
+    ));
+}
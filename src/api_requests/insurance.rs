@@ -0,0 +1,209 @@
+use crate::{
+    config::CONFIG,
+    error::TravelError,
+    utils::{Currency, Date, check_param_length},
+};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const MAX_DESTINATION_LEN: usize = 100;
+const MAX_TRAVELERS: u8 = 9;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct InsuranceQuote {
+    pub provider: String,
+    pub plan_name: String,
+    ///Premium for all travellers
+    pub premium: Currency,
+    ///Most the plan pays out per traveller
+    pub coverage_amount: Currency,
+    pub includes_medical: bool,
+    pub includes_trip_cancellation: bool,
+    pub includes_baggage: bool,
+}
+
+#[derive(Deserialize)]
+struct QuoteResponse {
+    quotes: Vec<PartnerQuote>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PartnerQuote {
+    provider: String,
+    plan_name: String,
+    currency: String,
+    premium: String,
+    coverage_amount: String,
+    #[serde(default)]
+    benefits: Vec<String>,
+}
+
+fn to_quote(quote: PartnerQuote) -> Result<InsuranceQuote, TravelError> {
+    let amount = |amount: &str| {
+        Currency::parse_currency(&quote.currency, amount).map_err(|e| {
+            TravelError::Api(format!("Invalid amount in {} quote: {e}", quote.plan_name))
+        })
+    };
+    let covers = |benefit: &str| {
+        quote
+            .benefits
+            .iter()
+            .any(|b| b.eq_ignore_ascii_case(benefit))
+    };
+    Ok(InsuranceQuote {
+        premium: amount(&quote.premium)?,
+        coverage_amount: amount(&quote.coverage_amount)?,
+        includes_medical: covers("MEDICAL"),
+        includes_trip_cancellation: covers("TRIP_CANCELLATION"),
+        includes_baggage: covers("BAGGAGE"),
+        provider: quote.provider,
+        plan_name: quote.plan_name,
+    })
+}
+
+/// Quotes whose amounts can't be read, e.g. in an unsupported currency, are left out.
+fn to_quotes(response: QuoteResponse) -> Vec<InsuranceQuote> {
+    response
+        .quotes
+        .into_iter()
+        .filter_map(|quote| {
+            to_quote(quote)
+                .inspect_err(|e| eprintln!("ERROR: Skipping insurance quote:\n{e}"))
+                .ok()
+        })
+        .collect()
+}
+
+/// Quotes come from the insurance partner API at `INSURANCE_API_URL`. There is no free travel
+/// insurance API, so without the partner's URL and key no quotes are available.
+pub async fn get_insurance_quotes(
+    destination: &str,
+    departure: Date,
+    return_date: Date,
+    travelers: u8,
+) -> Result<Vec<InsuranceQuote>, TravelError> {
+    check_param_length(destination, MAX_DESTINATION_LEN)?;
+    if return_date < departure {
+        return Err(TravelError::InvalidInput(format!(
+            "Return date {} is before departure {}",
+            return_date.to_yyyy_mm_dd(),
+            departure.to_yyyy_mm_dd()
+        )));
+    }
+    if !(1..=MAX_TRAVELERS).contains(&travelers) {
+        return Err(TravelError::InvalidInput(format!(
+            "Travelers must be between 1 and {MAX_TRAVELERS}. Found: {travelers}"
+        )));
+    }
+    let (Some(url), Some(key)) = (&CONFIG.insurance_api_url, &CONFIG.insurance_api_key) else {
+        return Err(TravelError::ApiKeyMissing(
+            "INSURANCE_API_URL and INSURANCE_API_KEY are needed for insurance quotes".into(),
+        ));
+    };
+
+    let resp = reqwest::Client::new()
+        .post(url)
+        .header("x-api-key", key)
+        .json(&json!({
+            "destination": destination,
+            "departureDate": departure.to_yyyy_mm_dd(),
+            "returnDate": return_date.to_yyyy_mm_dd(),
+            "travellers": travelers,
+        }))
+        .send()
+        .await
+        .map_err(|e| TravelError::Api(format!("Insurance quote request failed: {e}")))?;
+    if !resp.status().is_success() {
+        return Err(TravelError::Api(format!(
+            "Insurance quote error: {}",
+            resp.status()
+        )));
+    }
+    let response: QuoteResponse = resp
+        .json()
+        .await
+        .map_err(|e| TravelError::Api(format!("Invalid insurance quote response: {e}")))?;
+    Ok(to_quotes(response))
+}
+
+#[gemini_function]
+///Get travel insurance quotes for a trip. Offer them for international trips and expensive bookings.
+pub async fn travel_insurance_quotes(
+    ///City or country of the trip (e.g., 'Dubai')
+    destination: String,
+    departure: Date,
+    return_date: Date,
+    ///Number of travellers (1-9)
+    travelers: u8,
+) -> Result<Vec<InsuranceQuote>, TravelError> {
+    get_insurance_quotes(&destination, departure, return_date, travelers).await
+}
+
+#[cfg(test)]
+fn quote_fixture() -> serde_json::Value {
+    json!({
+        "quotes": [
+            {
+                "provider": "Cover-More",
+                "planName": "International Comprehensive",
+                "currency": "INR",
+                "premium": "2450.00",
+                "coverageAmount": "3500000.00",
+                "benefits": ["MEDICAL", "TRIP_CANCELLATION", "BAGGAGE"]
+            },
+            {
+                "provider": "Cover-More",
+                "planName": "Medical Only",
+                "currency": "INR",
+                "premium": "980.00",
+                "coverageAmount": "2000000.00",
+                "benefits": ["medical"]
+            }
+        ]
+    })
+}
+
+#[test]
+fn to_quotes_test() {
+    let quotes = to_quotes(serde_json::from_value(quote_fixture()).unwrap());
+    assert_eq!(quotes.len(), 2);
+    assert_eq!(quotes[0].plan_name, "International Comprehensive");
+    assert!(matches!(quotes[0].premium, Currency::Inr(premium) if premium == 2450.0));
+    assert!(quotes[0].includes_medical);
+    assert!(quotes[0].includes_trip_cancellation);
+    assert!(quotes[0].includes_baggage);
+    assert!(quotes[1].includes_medical);
+    assert!(!quotes[1].includes_trip_cancellation);
+    assert!(!quotes[1].includes_baggage);
+
+    // Only the quote in an unsupported currency is left out
+    let mut unsupported = quote_fixture();
+    unsupported["quotes"][1]["currency"] = json!("AUD");
+    let quotes = to_quotes(serde_json::from_value(unsupported).unwrap());
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[0].plan_name, "International Comprehensive");
+
+    let mut response: QuoteResponse = serde_json::from_value(quote_fixture()).unwrap();
+    response.quotes[1].currency = "AUD".into();
+    let error = to_quote(response.quotes.remove(1));
+    assert!(matches!(error, Err(TravelError::Api(msg)) if msg.contains("Medical Only")));
+}
+
+#[tokio::test]
+async fn get_insurance_quotes_validation_test() {
+    let date = |day| Date::new(2026, 3, day).unwrap();
+    assert!(matches!(
+        get_insurance_quotes("Dubai", date(10), date(5), 2).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        get_insurance_quotes("Dubai", date(5), date(10), 0).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        get_insurance_quotes(&"x".repeat(101), date(5), date(10), 2).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+}
@@ -1,4 +1,13 @@
+pub mod advisory;
+pub mod buses;
+pub mod currency;
+pub mod events;
 pub mod flights;
-pub mod site_seen;
+pub mod geocode;
+pub mod holidays;
 pub mod hotels;
+pub mod insurance;
+pub mod places;
+pub mod rapidapi;
+pub mod site_seen;
 pub mod trains;
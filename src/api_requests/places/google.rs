@@ -0,0 +1,525 @@
+use crate::{
+    config::CONFIG,
+    error::TravelError,
+    utils::{Coordinates, check_param_length, sanitize_query_param},
+};
+use reqwest::{
+    RequestBuilder,
+    header::{CONTENT_TYPE, HeaderMap, HeaderValue},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value, json};
+use std::sync::LazyLock;
+
+const BASE_URL: &str = "https://places.googleapis.com/v1";
+const DIRECTIONS_URL: &str = "https://maps.google.com/maps/dir";
+const ROUTES_API_URL: &str = "https://routes.googleapis.com/directions/v2:computeRoutes";
+const DEFAULT_FIELD_MASK: &str = "places.id,places.displayName,places.formattedAddress,places.location,places.rating,places.photos,places.types";
+const DEFAULT_MAX_RESULTS: u8 = 10;
+const MAX_QUERY_LEN: usize = 200;
+const MAX_PLACE_ID_LEN: usize = 256;
+
+/// Connection pool shared by every `GooglePlacesClient`.
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizedText {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacePhoto {
+    ///Resource name used to fetch the photo, e.g. `places/{id}/photos/{ref}`
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningHours {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_now: Option<bool>,
+    ///e.g. 'Monday: 6:00 AM – 6:30 PM'
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weekday_descriptions: Vec<String>,
+}
+
+/// What a place is, from its Places API types.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaceType {
+    NaturalLandmark,
+    HistoricalSite,
+    ReligiousSite,
+    Museum,
+    Park,
+    Beach,
+    ShoppingArea,
+    EntertainmentVenue,
+    Restaurant,
+    ///Places API type without a category, e.g. 'lodging'
+    Other(String),
+}
+
+/// Types every place has, which say nothing about it.
+const GENERIC_PLACE_TYPES: &[&str] = &["point_of_interest", "establishment", "tourist_attraction"];
+
+impl From<&str> for PlaceType {
+    fn from(google_type: &str) -> Self {
+        match google_type {
+            "natural_feature" | "hiking_area" | "mountain_peak" | "waterfall" => {
+                PlaceType::NaturalLandmark
+            }
+            "historical_landmark" | "historical_place" | "monument" | "cultural_landmark" => {
+                PlaceType::HistoricalSite
+            }
+            "place_of_worship" | "hindu_temple" | "church" | "mosque" | "synagogue" => {
+                PlaceType::ReligiousSite
+            }
+            "museum" | "art_gallery" => PlaceType::Museum,
+            "park" | "national_park" | "state_park" | "garden" | "botanical_garden"
+            | "wildlife_park" | "zoo" => PlaceType::Park,
+            "beach" => PlaceType::Beach,
+            "shopping_mall" | "market" | "department_store" | "gift_shop" => {
+                PlaceType::ShoppingArea
+            }
+            "amusement_park"
+            | "water_park"
+            | "aquarium"
+            | "casino"
+            | "movie_theater"
+            | "night_club"
+            | "performing_arts_theater"
+            | "concert_hall"
+            | "stadium"
+            | "event_venue"
+            | "bowling_alley" => PlaceType::EntertainmentVenue,
+            "restaurant" | "cafe" | "bar" | "bakery" | "food_court" => PlaceType::Restaurant,
+            // e.g. 'south_indian_restaurant'
+            cuisine if cuisine.ends_with("_restaurant") => PlaceType::Restaurant,
+            other => PlaceType::Other(other.to_string()),
+        }
+    }
+}
+
+impl PlaceType {
+    /// `None` for `Other`.
+    pub fn label(&self) -> Option<&'static str> {
+        Some(match self {
+            PlaceType::NaturalLandmark => "Natural landmark",
+            PlaceType::HistoricalSite => "Historical site",
+            PlaceType::ReligiousSite => "Religious site",
+            PlaceType::Museum => "Museum",
+            PlaceType::Park => "Park",
+            PlaceType::Beach => "Beach",
+            PlaceType::ShoppingArea => "Shopping",
+            PlaceType::EntertainmentVenue => "Entertainment",
+            PlaceType::Restaurant => "Restaurant",
+            PlaceType::Other(_) => return None,
+        })
+    }
+}
+
+/// A place from the Places API (New). Fields not modelled here are kept in `other`, so
+/// serializing gives back whatever the field mask asked for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<LocalizedText>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Coordinates>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_rating_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub international_phone_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub website_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regular_opening_hours: Option<OpeningHours>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub photos: Vec<PlacePhoto>,
+    ///Places API types, e.g. `hindu_temple`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<String>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl PlaceResult {
+    /// Resource name of the first photo, ready for `GooglePlacesClient::photo_url`.
+    pub fn photo_reference(&self) -> Option<&str> {
+        self.photos.first().map(|photo| photo.name.as_str())
+    }
+    /// Categories of the place in the order of `types`, without duplicates or the types
+    /// every place has.
+    pub fn place_types(&self) -> Vec<PlaceType> {
+        let mut place_types = Vec::new();
+        for google_type in &self.types {
+            if GENERIC_PLACE_TYPES.contains(&google_type.as_str()) {
+                continue;
+            }
+            let place_type = PlaceType::from(google_type.as_str());
+            if !place_types.contains(&place_type) {
+                place_types.push(place_type);
+            }
+        }
+        place_types
+    }
+}
+
+#[derive(Deserialize)]
+struct PlacesResponse {
+    places: Option<Vec<PlaceResult>>,
+}
+
+#[derive(Deserialize)]
+struct RoutesResponse {
+    ///Empty when there's no road between the places, e.g. across the sea
+    #[serde(default)]
+    routes: Vec<Route>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Route {
+    distance_meters: u64,
+}
+
+impl RoutesResponse {
+    /// Length of the first route.
+    fn distance_km(&self) -> Result<f32, TravelError> {
+        self.routes
+            .first()
+            .map(|route| route.distance_meters as f32 / 1000.0)
+            .ok_or_else(|| TravelError::Api("No driving route found".into()))
+    }
+}
+
+/// Place Photo response with `skipHttpRedirect=true`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PhotoMedia {
+    ///Short-lived googleusercontent.com URL of the image
+    photo_uri: String,
+}
+
+fn route_request(from: Coordinates, to: Coordinates) -> Value {
+    let waypoint = |place: Coordinates| json!({"location": {"latLng": {"latitude": place.latitude, "longitude": place.longitude}}});
+    json!({
+        "origin": waypoint(from),
+        "destination": waypoint(to),
+        "travelMode": "DRIVE",
+    })
+}
+
+/// Asks for the image URL as JSON instead of a redirect to the image.
+fn photo_media_url(reference: &str, max_width: u32) -> String {
+    format!("{BASE_URL}/{reference}/media?maxWidthPx={max_width}&skipHttpRedirect=true")
+}
+
+#[derive(Clone)]
+pub struct GooglePlacesClient {
+    http_client: reqwest::Client,
+    api_key: String,
+}
+
+impl GooglePlacesClient {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            http_client: HTTP_CLIENT.clone(),
+            api_key,
+        }
+    }
+    pub fn from_config() -> Self {
+        Self::new(CONFIG.google_maps_api_key.clone())
+    }
+
+    /// The key goes in a header rather than the URL, so it never ends up in links or error
+    /// messages.
+    fn key_headers(&self) -> Result<HeaderMap, TravelError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-Goog-Api-Key",
+            HeaderValue::from_str(&self.api_key)
+                .map_err(|_| TravelError::ApiKeyMissing("Invalid GOOGLE_MAPS_API_KEY".into()))?,
+        );
+        Ok(headers)
+    }
+
+    fn headers(&self, field_mask: &str) -> Result<HeaderMap, TravelError> {
+        let mut headers = self.key_headers()?;
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(
+            "X-Goog-FieldMask",
+            HeaderValue::from_str(field_mask)
+                .map_err(|e| TravelError::InvalidInput(format!("Invalid field mask: {e}")))?,
+        );
+        Ok(headers)
+    }
+
+    async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, TravelError> {
+        let resp = request.send().await.map_err(|e| {
+            TravelError::Api(format!("Places API request failed: {}", e.without_url()))
+        })?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await.unwrap_or_default();
+            return Err(TravelError::Api(format!("Places API error: {error_text}")));
+        }
+        resp.json().await.map_err(|e| {
+            TravelError::Api(format!("Invalid Places API response: {}", e.without_url()))
+        })
+    }
+
+    async fn search<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        field_mask: &str,
+        body: Value,
+    ) -> Result<T, TravelError> {
+        let request = self
+            .http_client
+            .post(format!("{BASE_URL}/places:{method}"))
+            .headers(self.headers(field_mask)?)
+            .json(&body);
+        Self::send(request).await
+    }
+
+    pub async fn text_search(&self, query: &str) -> Result<Vec<PlaceResult>, TravelError> {
+        self.text_search_with_fields(query, DEFAULT_MAX_RESULTS, DEFAULT_FIELD_MASK)
+            .await
+    }
+
+    /// `field_mask` is a comma separated list like `places.id,places.rating`.
+    pub async fn text_search_with_fields(
+        &self,
+        query: &str,
+        max_results: u8,
+        field_mask: &str,
+    ) -> Result<Vec<PlaceResult>, TravelError> {
+        let body = json!({
+            "textQuery": check_param_length(query, MAX_QUERY_LEN)?,
+            "maxResultCount": max_results,
+        });
+        let response: PlacesResponse = self.search("searchText", field_mask, body).await?;
+        Ok(response.places.unwrap_or_default())
+    }
+
+    /// `radius` is in meters and `place_type` is a Places type like `tourist_attraction`.
+    pub async fn nearby_search(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius: u32,
+        place_type: &str,
+        max_results: u8,
+    ) -> Result<Vec<PlaceResult>, TravelError> {
+        let body = json!({
+            "includedTypes": [check_param_length(place_type, MAX_QUERY_LEN)?],
+            "maxResultCount": max_results,
+            "locationRestriction": {
+                "circle": {
+                    "center": {"latitude": lat, "longitude": lon},
+                    "radius": radius,
+                }
+            }
+        });
+        let response: PlacesResponse = self
+            .search("searchNearby", DEFAULT_FIELD_MASK, body)
+            .await?;
+        Ok(response.places.unwrap_or_default())
+    }
+
+    /// Place Details for `place_id`. Unlike the search masks, `field_mask` has no `places.`
+    /// prefix, e.g. `id,photos`.
+    pub async fn place_details(
+        &self,
+        place_id: &str,
+        field_mask: &str,
+    ) -> Result<PlaceResult, TravelError> {
+        let place_id = sanitize_query_param(place_id, MAX_PLACE_ID_LEN)?;
+        let request = self
+            .http_client
+            .get(format!("{BASE_URL}/places/{place_id}"))
+            .headers(self.headers(field_mask)?);
+        Self::send(request).await
+    }
+
+    /// Length of the fastest driving route from `from` to `to` using the Routes API.
+    pub async fn driving_distance_km(
+        &self,
+        from: Coordinates,
+        to: Coordinates,
+    ) -> Result<f32, TravelError> {
+        let request = self
+            .http_client
+            .post(ROUTES_API_URL)
+            .headers(self.headers("routes.distanceMeters")?)
+            .json(&route_request(from, to));
+        let response: RoutesResponse = Self::send(request).await?;
+        response.distance_km()
+    }
+
+    /// Public URL of the photo, without the API key, that can be shown to users. `reference`
+    /// is the `name` of a `PlacePhoto`.
+    pub async fn photo_url(&self, reference: &str, max_width: u32) -> Result<String, TravelError> {
+        let request = self
+            .http_client
+            .get(photo_media_url(reference, max_width))
+            .headers(self.key_headers()?);
+        let media: PhotoMedia = Self::send(request).await?;
+        Ok(media.photo_uri)
+    }
+}
+
+/// Google Maps directions from `from` to `to`.
+pub fn get_directions_url(from: Coordinates, to: Coordinates) -> String {
+    format!(
+        "{DIRECTIONS_URL}/{},{}/{},{}",
+        from.latitude, from.longitude, to.latitude, to.longitude
+    )
+}
+
+/// Google Maps directions to `to` from wherever the link is opened.
+pub fn directions_to_url(to: Coordinates) -> String {
+    format!("{DIRECTIONS_URL}//{},{}", to.latitude, to.longitude)
+}
+
+#[test]
+fn place_result_fixture_test() {
+    let fixture = r#"{
+        "places": [{
+            "id": "ChIJ5y3",
+            "displayName": {"text": "Baga Beach", "languageCode": "en"},
+            "formattedAddress": "Baga, Goa 403516, India",
+            "location": {"latitude": 15.5553, "longitude": 73.7517},
+            "rating": 4.4,
+            "photos": [{"name": "places/ChIJ5y3/photos/AUc7", "widthPx": 4032}],
+            "regularOpeningHours": {"openNow": true}
+        }]
+    }"#;
+    let response: PlacesResponse = serde_json::from_str(fixture).unwrap();
+    let place = &response.places.unwrap()[0];
+    assert_eq!(place.display_name.as_ref().unwrap().text, "Baga Beach");
+    assert_eq!(place.location, Some(Coordinates::new(15.5553, 73.7517)));
+    assert_eq!(place.photos[0].name, "places/ChIJ5y3/photos/AUc7");
+
+    // Unmodelled fields are passed through untouched
+    let value = serde_json::to_value(place).unwrap();
+    assert_eq!(value["regularOpeningHours"]["openNow"], true);
+    assert_eq!(value["displayName"]["languageCode"], "en");
+
+    let empty: PlacesResponse = serde_json::from_str("{}").unwrap();
+    assert!(empty.places.is_none());
+}
+
+#[test]
+fn photo_url_test() {
+    assert_eq!(
+        photo_media_url("places/ChIJ5y3/photos/AUc7", 800),
+        "https://places.googleapis.com/v1/places/ChIJ5y3/photos/AUc7/media?maxWidthPx=800&skipHttpRedirect=true"
+    );
+    let client = GooglePlacesClient::new("test-key".into());
+    let headers = client.key_headers().unwrap();
+    assert_eq!(headers["X-Goog-Api-Key"], "test-key");
+    assert!(!headers.contains_key("X-Goog-FieldMask"));
+
+    let media: PhotoMedia = serde_json::from_value(json!({
+        "name": "places/ChIJ5y3/photos/AUc7/media",
+        "photoUri": "https://lh3.googleusercontent.com/place-photos/AUc7=s1600-w800"
+    }))
+    .unwrap();
+    assert_eq!(
+        media.photo_uri,
+        "https://lh3.googleusercontent.com/place-photos/AUc7=s1600-w800"
+    );
+}
+
+#[test]
+fn place_types_test() {
+    for (google_type, expected) in [
+        ("natural_feature", PlaceType::NaturalLandmark),
+        ("historical_landmark", PlaceType::HistoricalSite),
+        ("hindu_temple", PlaceType::ReligiousSite),
+        ("mosque", PlaceType::ReligiousSite),
+        ("art_gallery", PlaceType::Museum),
+        ("national_park", PlaceType::Park),
+        ("beach", PlaceType::Beach),
+        ("market", PlaceType::ShoppingArea),
+        ("amusement_park", PlaceType::EntertainmentVenue),
+        ("south_indian_restaurant", PlaceType::Restaurant),
+        ("lodging", PlaceType::Other("lodging".into())),
+    ] {
+        assert_eq!(PlaceType::from(google_type), expected, "{google_type}");
+    }
+
+    let place: PlaceResult = serde_json::from_value(json!({
+        "displayName": {"text": "Golden Temple"},
+        "types": [
+            "tourist_attraction",
+            "sikh_temple",
+            "place_of_worship",
+            "hindu_temple",
+            "point_of_interest",
+            "establishment"
+        ]
+    }))
+    .unwrap();
+    assert_eq!(
+        place.place_types(),
+        [
+            PlaceType::Other("sikh_temple".into()),
+            PlaceType::ReligiousSite
+        ]
+    );
+    assert_eq!(PlaceType::ReligiousSite.label(), Some("Religious site"));
+    assert_eq!(PlaceType::Other("lodging".into()).label(), None);
+    let untyped: PlaceResult = serde_json::from_value(json!({"id": "1"})).unwrap();
+    assert!(untyped.place_types().is_empty());
+}
+
+#[test]
+fn directions_url_test() {
+    let hotel = Coordinates::new(27.1751, 78.0421);
+    assert_eq!(
+        get_directions_url(hotel, Coordinates::new(27.1795, 78.0211)),
+        "https://maps.google.com/maps/dir/27.1751,78.0421/27.1795,78.0211"
+    );
+    assert_eq!(
+        directions_to_url(Coordinates::new(15.5553, -73.7517)),
+        "https://maps.google.com/maps/dir//15.5553,-73.7517"
+    );
+}
+
+#[test]
+fn routes_response_test() {
+    let body = route_request(
+        Coordinates::new(19.076, 72.8777),
+        Coordinates::new(18.5204, 73.8567),
+    );
+    assert_eq!(body["travelMode"], "DRIVE");
+    assert_eq!(body["origin"]["location"]["latLng"]["latitude"], 19.076);
+    assert_eq!(
+        body["destination"]["location"]["latLng"]["longitude"],
+        73.8567
+    );
+
+    let response: RoutesResponse = serde_json::from_value(json!({
+        "routes": [{"distanceMeters": 148642}]
+    }))
+    .unwrap();
+    assert!((response.distance_km().unwrap() - 148.642).abs() < 0.001);
+
+    // Across the sea
+    let response: RoutesResponse = serde_json::from_value(json!({})).unwrap();
+    assert!(matches!(response.distance_km(), Err(TravelError::Api(_))));
+}
@@ -0,0 +1,234 @@
+use crate::{config::CONFIG, error::TravelError};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::de::DeserializeOwned;
+#[cfg(test)]
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// A warning is logged when a RapidAPI plan has fewer calls left.
+const LOW_RATE_LIMIT_WARNING: u32 = 10;
+/// How long to stop calling after a 429 that doesn't say when the quota resets.
+const DEFAULT_RATE_LIMIT_RESET: Duration = Duration::from_secs(60);
+
+/// Quota of each RapidAPI host, as of its last response.
+static RAPIDAPI_RATE_LIMITS: LazyLock<RwLock<HashMap<&'static str, RateLimitInfo>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Sends GET requests to a RapidAPI host, so the train, bus and cricket functions can be
+/// tested without calling it.
+pub trait ApiClient: Send + Sync {
+    /// `path` starts with `/` and carries the query string, e.g.
+    /// `/api/v1/getTrainDetails?trainNo=12951`.
+    fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> impl Future<Output = Result<T, TravelError>> + Send;
+}
+
+pub struct RapidAPIClient {
+    client: reqwest::Client,
+    headers: HeaderMap,
+    host: &'static str,
+    base_url: String,
+}
+
+/// Calls left on a RapidAPI plan, from the headers of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    ///`X-RateLimit-Requests-Remaining`
+    pub remaining: Option<u32>,
+    ///From `X-RateLimit-Requests-Reset`, the seconds until the quota is topped up
+    pub resets_at: Option<Instant>,
+}
+
+impl RateLimitInfo {
+    /// `None` when the response has no rate limit headers.
+    fn from_headers(headers: &HeaderMap, now: Instant) -> Option<Self> {
+        let number =
+            |name: &str| -> Option<u64> { headers.get(name)?.to_str().ok()?.trim().parse().ok() };
+        let remaining = number("X-RateLimit-Requests-Remaining")
+            .map(|remaining| u32::try_from(remaining).unwrap_or(u32::MAX));
+        let resets_at =
+            number("X-RateLimit-Requests-Reset").map(|secs| now + Duration::from_secs(secs));
+        (remaining.is_some() || resets_at.is_some()).then_some(Self {
+            remaining,
+            resets_at,
+        })
+    }
+    /// Errs while no calls are left and the quota hasn't been topped up. Without a known reset
+    /// time the call is let through, RapidAPI answers it with a 429 if need be.
+    pub fn check_before_request(&self, now: Instant) -> Result<(), TravelError> {
+        match (self.remaining, self.resets_at) {
+            (Some(0), Some(resets_at)) if now < resets_at => {
+                Err(TravelError::RateLimited { resets_at })
+            }
+            _ => Ok(()),
+        }
+    }
+    pub fn is_low(&self) -> bool {
+        self.remaining
+            .is_some_and(|remaining| remaining < LOW_RATE_LIMIT_WARNING)
+    }
+}
+
+async fn record_rate_limit(host: &'static str, limit: RateLimitInfo) {
+    if limit.is_low() {
+        eprintln!(
+            "WARNING: {} RapidAPI calls left on {host}",
+            limit.remaining.unwrap_or_default()
+        );
+    }
+    RAPIDAPI_RATE_LIMITS.write().await.insert(host, limit);
+}
+
+impl RapidAPIClient {
+    /// Client for an API on RapidAPI, e.g. `redbus.p.rapidapi.com`.
+    pub fn for_host(host: &'static str) -> Result<Self, TravelError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-RapidAPI-Key",
+            HeaderValue::from_str(&CONFIG.rapidapi_key)
+                .map_err(|_| TravelError::ApiKeyMissing("Invalid RAPIDAPI_KEY".into()))?,
+        );
+        headers.insert("X-RapidAPI-Host", HeaderValue::from_static(host));
+        Ok(Self {
+            client: reqwest::Client::new(),
+            headers,
+            host,
+            base_url: format!("https://{host}"),
+        })
+    }
+}
+
+impl ApiClient for RapidAPIClient {
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, TravelError> {
+        if let Some(limit) = RAPIDAPI_RATE_LIMITS.read().await.get(self.host) {
+            limit.check_before_request(Instant::now())?;
+        }
+        let resp = self
+            .client
+            .get(format!("{}{path}", self.base_url))
+            .headers(self.headers.clone())
+            .send()
+            .await
+            .map_err(|e| TravelError::Api(format!("RapidAPI request failed: {e}")))?;
+        let limit = RateLimitInfo::from_headers(resp.headers(), Instant::now());
+        if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let resets_at = limit
+                .and_then(|limit| limit.resets_at)
+                .unwrap_or_else(|| Instant::now() + DEFAULT_RATE_LIMIT_RESET);
+            let exhausted = RateLimitInfo {
+                remaining: Some(0),
+                resets_at: Some(resets_at),
+            };
+            record_rate_limit(self.host, exhausted).await;
+            return Err(TravelError::RateLimited { resets_at });
+        }
+        if let Some(limit) = limit {
+            record_rate_limit(self.host, limit).await;
+        }
+        if !resp.status().is_success() {
+            let remaining = limit
+                .and_then(|limit| limit.remaining)
+                .map(|remaining| format!(" ({remaining} calls left)"))
+                .unwrap_or_default();
+            return Err(TravelError::Api(format!(
+                "RapidAPI error: {}{remaining}",
+                resp.status()
+            )));
+        }
+        resp.json()
+            .await
+            .map_err(|e| TravelError::Api(format!("Invalid RapidAPI response: {e}")))
+    }
+}
+
+/// Answers each path, without its query string, with a fixture, and records the paths asked
+/// for.
+#[cfg(test)]
+pub struct MockApiClient {
+    responses: HashMap<&'static str, Value>,
+    pub requests: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl MockApiClient {
+    pub fn new(responses: impl IntoIterator<Item = (&'static str, Value)>) -> Self {
+        Self {
+            responses: responses.into_iter().collect(),
+            requests: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ApiClient for MockApiClient {
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, TravelError> {
+        self.requests.lock().unwrap().push(path.to_string());
+        let route = path.split('?').next().unwrap_or_default();
+        let response = self
+            .responses
+            .get(route)
+            .ok_or_else(|| TravelError::Api("RapidAPI error: 404 Not Found".into()))?;
+        serde_json::from_value(response.clone())
+            .map_err(|e| TravelError::Api(format!("Invalid RapidAPI response: {e}")))
+    }
+}
+
+#[test]
+fn rate_limit_test() {
+    let now = Instant::now();
+    let mut headers = HeaderMap::new();
+    assert_eq!(RateLimitInfo::from_headers(&headers, now), None);
+
+    headers.insert(
+        "X-RateLimit-Requests-Remaining",
+        HeaderValue::from_static("7"),
+    );
+    headers.insert(
+        "X-RateLimit-Requests-Reset",
+        HeaderValue::from_static("3600"),
+    );
+    let limit = RateLimitInfo::from_headers(&headers, now).unwrap();
+    assert_eq!(limit.remaining, Some(7));
+    assert_eq!(limit.resets_at, Some(now + Duration::from_secs(3600)));
+    assert!(limit.is_low());
+    assert!(limit.check_before_request(now).is_ok());
+
+    let exhausted = RateLimitInfo {
+        remaining: Some(0),
+        ..limit
+    };
+    let error = exhausted.check_before_request(now).unwrap_err();
+    assert!(
+        matches!(error, TravelError::RateLimited { resets_at } if resets_at == now + Duration::from_secs(3600))
+    );
+    assert!(
+        error
+            .to_string()
+            .starts_with("Rate limited: no API calls left, try again in 3")
+    );
+    // Topped up again
+    assert!(
+        exhausted
+            .check_before_request(now + Duration::from_secs(3600))
+            .is_ok()
+    );
+    let unknown_reset = RateLimitInfo {
+        remaining: Some(0),
+        resets_at: None,
+    };
+    assert!(unknown_reset.check_before_request(now).is_ok());
+
+    headers.insert(
+        "X-RateLimit-Requests-Remaining",
+        HeaderValue::from_static("9500"),
+    );
+    headers.remove("X-RateLimit-Requests-Reset");
+    let plenty = RateLimitInfo::from_headers(&headers, now).unwrap();
+    assert_eq!(plenty.remaining, Some(9500));
+    assert!(!plenty.is_low() && plenty.resets_at.is_none());
+}
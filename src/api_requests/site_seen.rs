@@ -1,106 +1,1016 @@
-use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
+use crate::api_requests::{
+    geocode::geocode,
+    hotels::amadeus::Hotel,
+    places::google::{
+        GooglePlacesClient, OpeningHours, PlaceResult, PlaceType, directions_to_url,
+        get_directions_url,
+    },
+};
+use crate::error::TravelError;
+use crate::utils::{
+    Coordinates, format_inr,
+    optimizer::{nearest_neighbor_order, route_distance_km},
+};
+use gemini_client_api::futures::future::join_all;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[gemini_schema]
-pub enum PlaceField {
-    Id,
-    DisplayName,
-    FormattedAddress,
-    Location,
-    Rating,
-    UserRatingCount,
-    PriceLevel,
-    Types,
-    WebsiteUri,
-    RegularOpeningHours,
-    EditorialSummary,
-    Photos,
-    InternationalPhoneNumber,
-    Reviews,
-}
-
-impl PlaceField {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            PlaceField::Id => "places.id",
-            PlaceField::DisplayName => "places.displayName",
-            PlaceField::FormattedAddress => "places.formattedAddress",
-            PlaceField::Location => "places.location",
-            PlaceField::Rating => "places.rating",
-            PlaceField::UserRatingCount => "places.userRatingCount",
-            PlaceField::PriceLevel => "places.priceLevel",
-            PlaceField::Types => "places.types",
-            PlaceField::WebsiteUri => "places.websiteUri",
-            PlaceField::RegularOpeningHours => "places.regularOpeningHours",
-            PlaceField::EditorialSummary => "places.editorialSummary",
-            PlaceField::Photos => "places.photos",
-            PlaceField::InternationalPhoneNumber => "places.internationalPhoneNumber",
-            PlaceField::Reviews => "places.reviews",
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+const DEFAULT_IMAGE_WIDTH: u32 = 800;
+const DEFAULT_MAX_SITES: u8 = 10;
+///Most results the Places API returns for one search
+const MAX_SITES: u8 = 20;
+const VERIFY_FEE_NOTE: &str = "verify entrance fee";
+///Most sites ordered into one sightseeing route
+const MAX_ROUTE_SITES: usize = 20;
+
+const PLACE_DETAILS_FIELDS: &str = "id,displayName,formattedAddress,rating,userRatingCount,internationalPhoneNumber,websiteUri,regularOpeningHours,photos,types,location";
+// Ratings and opening hours change slowly
+const PLACE_DETAILS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct CachedPlace {
+    details: PlaceDetails,
+    fetched_at: Instant,
+    ///Set by `invalidate_site_cache`, the next lookup fetches fresh details
+    invalidated: bool,
+}
+
+impl CachedPlace {
+    fn is_fresh(&self) -> bool {
+        !self.invalidated && self.fetched_at.elapsed() < PLACE_DETAILS_TTL
+    }
+}
+
+#[derive(Deserialize)]
+struct MonumentFee {
+    name: String,
+    ///Ticket for an Indian citizen at the counter. Online tickets are ₹5 cheaper.
+    fee_inr: f32,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+/// Entrance fees of the ticketed ASI monuments, which the Places API doesn't return. World
+/// Heritage Sites are ₹40, the Taj Mahal ₹50 and the others ₹25. Kept by hand.
+static MONUMENT_FEES: LazyLock<Vec<MonumentFee>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../../data/india_monument_fees.json"))
+        .expect("Invalid data/india_monument_fees.json")
+});
+
+/// `get_about_place` results by normalized query.
+static PLACE_DETAILS_CACHE: LazyLock<RwLock<HashMap<String, CachedPlace>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaceDetails {
+    pub name: String,
+    pub address: String,
+    ///Average Google rating out of 5
+    pub rating: Option<f32>,
+    pub total_reviews: Option<u32>,
+    pub phone: Option<String>,
+    ///Official website, often where tickets can be booked in advance
+    pub website: Option<String>,
+    ///Public googleusercontent.com photo, without the API key
+    pub image_url: Option<String>,
+    pub opening_hours: Option<OpeningHours>,
+    ///Ticket price for Indian citizens, known for ASI monuments only
+    pub entrance_fee_inr: Option<f32>,
+    ///Set when searched with a budget, e.g. 'verify entrance fee' when the fee is unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    ///What kind of place it is, e.g. a historical site or a beach
+    #[serde(default)]
+    pub place_types: Vec<PlaceType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Coordinates>,
+}
+
+impl PlaceDetails {
+    /// Heading, address, rating, entrance fee and photo of the place, leaving out what's
+    /// unknown.
+    pub fn formatted_markdown(&self) -> String {
+        let mut lines = vec![format!("### {}", self.name)];
+        if !self.address.is_empty() {
+            lines.push(self.address.clone());
+        }
+        match (self.rating, self.total_reviews) {
+            (Some(rating), Some(reviews)) => lines.push(format!("⭐ {rating} ({reviews} reviews)")),
+            (Some(rating), None) => lines.push(format!("⭐ {rating}")),
+            _ => {}
+        }
+        let labels: Vec<&str> = self
+            .place_types
+            .iter()
+            .filter_map(PlaceType::label)
+            .collect();
+        if !labels.is_empty() {
+            lines.push(format!("🏷️ {}", labels.join(", ")));
         }
+        if let Some(fee) = self.entrance_fee_inr {
+            lines.push(format!("🎟️ Entry {} for Indian citizens", format_inr(fee)));
+        }
+        if let Some(note) = &self.note {
+            lines.push(format!("⚠️ {note}"));
+        }
+        if let Some(location) = self.location {
+            lines.push(format!(
+                "[📍 Get Directions]({})",
+                directions_to_url(location)
+            ));
+        }
+        if let Some(image_url) = &self.image_url {
+            lines.push(format!("![{}]({image_url})", self.name));
+        }
+        lines.join("\n")
+    }
+    /// Google Maps directions to the place from `from`.
+    pub fn get_directions(&self, from: Coordinates) -> Result<String, TravelError> {
+        let to = self
+            .location
+            .ok_or_else(|| TravelError::Api(format!("Location of '{}' is not known", self.name)))?;
+        Ok(get_directions_url(from, to))
     }
+    /// `None` when the location of the hotel or the place is not known.
+    pub fn get_directions_from_hotel(&self, hotel: &Hotel) -> Option<String> {
+        self.get_directions(hotel.coordinates()?).ok()
+    }
+}
+
+pub fn sites_to_markdown(sites: &[PlaceDetails]) -> String {
+    sites
+        .iter()
+        .map(PlaceDetails::formatted_markdown)
+        .collect::<Vec<_>>()
+        .join("\n\n")
 }
 
-#[derive(Deserialize, Debug)]
-struct TextSearchResponse {
-    places: Option<Vec<Value>>,
+/// Wraps a `get_about_place` response as `{"details": {...}, "markdown": "..."}`, so every
+/// place is shown the same way. Other responses, like errors, are returned as is.
+pub fn with_markdown(response: Value) -> Value {
+    let sites = match &response {
+        Value::Array(_) => serde_json::from_value::<Vec<PlaceDetails>>(response.clone()),
+        _ => serde_json::from_value::<PlaceDetails>(response.clone()).map(|site| vec![site]),
+    };
+    match sites {
+        Ok(sites) if !sites.is_empty() => {
+            json!({"details": response, "markdown": sites_to_markdown(&sites)})
+        }
+        _ => response,
+    }
 }
 
 #[gemini_function]
-///Get detailed information about a specific location or point of interest using Google Places API (New).
+///Get details of a place or point of interest like its address, Google rating, phone number, website and opening hours.
 pub async fn get_about_place(
     ///The name of the place to search for (e.g., 'Eiffel Tower', 'Manali')
     query: String,
-    ///Maximum number of results to return (1-20).
-    max_results: u8,
-    ///Specific fields to include in the response. If empty, returns basic fields (id, displayName, formattedAddress).
-    fields: Vec<PlaceField>,
-) -> Result<Vec<Value>, Box<dyn std::error::Error + Send + Sync>> {
-    let api_key = std::env::var("GOOGLE_MAPS_API_KEY")?;
-
-    let client = reqwest::Client::new();
-    let url = "https://places.googleapis.com/v1/places:searchText";
-
-    let field_mask = if fields.is_empty() {
-        "places.id,places.displayName,places.formattedAddress".to_string()
+    ///Most the user wants to spend per person on an activity, in INR
+    budget_per_activity_inr: Option<f32>,
+) -> Result<PlaceDetails, TravelError> {
+    check_budget(budget_per_activity_inr)?;
+    let mut details = cached_place_details(&query, || fetch_place_details(&query)).await?;
+    details.note = fee_note(details.entrance_fee_inr, budget_per_activity_inr);
+    Ok(details)
+}
+
+fn check_budget(budget: Option<f32>) -> Result<(), TravelError> {
+    match budget {
+        Some(budget) if budget.is_nan() || budget < 0.0 => Err(TravelError::InvalidInput(format!(
+            "budget_per_activity_inr can't be negative. Found: {budget}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Fees are only known for the monuments in `MONUMENT_FEES`, so sites with an unknown fee are
+/// kept.
+fn is_within_budget(fee: Option<f32>, budget: Option<f32>) -> bool {
+    budget.is_none_or(|budget| fee.is_none_or(|fee| fee <= budget))
+}
+
+/// `None` without a budget or when the fee is known to be within it.
+fn fee_note(fee: Option<f32>, budget: Option<f32>) -> Option<String> {
+    match (fee, budget?) {
+        (None, _) => Some(VERIFY_FEE_NOTE.to_string()),
+        (Some(fee), budget) if fee > budget => Some(format!(
+            "Entrance fee {} is above the budget of {}",
+            format_inr(fee),
+            format_inr(budget)
+        )),
+        _ => None,
+    }
+}
+
+async fn fetch_place_details(query: &str) -> Result<PlaceDetails, TravelError> {
+    let client = GooglePlacesClient::from_config();
+    let details = client
+        .place_details(&place_id(&client, query).await?, PLACE_DETAILS_FIELDS)
+        .await?;
+    // The details are still worth showing without a photo
+    let image_url = image_url(&client, &details, DEFAULT_IMAGE_WIDTH)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("ERROR: Could not fetch the photo of {query}:\n{e}");
+            None
+        });
+    Ok(to_place_details(details, image_url))
+}
+
+fn cache_key(location: &str) -> String {
+    location.trim().to_lowercase()
+}
+
+async fn cached_place_details<F, Fut>(query: &str, fetch: F) -> Result<PlaceDetails, TravelError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<PlaceDetails, TravelError>>,
+{
+    let key = cache_key(query);
+    if let Some(cached) = PLACE_DETAILS_CACHE.read().await.get(&key)
+        && cached.is_fresh()
+    {
+        return Ok(cached.details.clone());
+    }
+
+    let details = fetch().await?;
+    let mut cache = PLACE_DETAILS_CACHE.write().await;
+    // Stale entries would otherwise stay for as long as the Lambda is warm
+    cache.retain(|_, cached| cached.is_fresh());
+    cache.insert(
+        key,
+        CachedPlace {
+            details: details.clone(),
+            fetched_at: Instant::now(),
+            invalidated: false,
+        },
+    );
+    Ok(details)
+}
+
+/// Makes the next `get_about_place` for `location` fetch fresh details. `false` when nothing
+/// was cached for it.
+pub async fn invalidate_site_cache(location: &str) -> bool {
+    let mut cache = PLACE_DETAILS_CACHE.write().await;
+    let Some(cached) = cache.get_mut(&cache_key(location)) else {
+        return false;
+    };
+    cached.invalidated = true;
+    eprintln!(
+        "INFO: Cleared the cached details of '{location}', fetched {}s ago",
+        cached.fetched_at.elapsed().as_secs()
+    );
+    true
+}
+
+#[gemini_function]
+///Clear the cached details of a place when the user says they look outdated. Call `get_about_place` again afterwards to get fresh details.
+pub async fn refresh_destination_info(
+    ///The place exactly as it was passed to `get_about_place`
+    location: String,
+) -> Result<String, TravelError> {
+    Ok(if invalidate_site_cache(&location).await {
+        format!("Cached details of '{location}' cleared")
     } else {
-        fields
+        format!("Nothing cached for '{location}', get_about_place already fetches fresh details")
+    })
+}
+
+fn to_place_details(details: PlaceResult, image_url: Option<String>) -> PlaceDetails {
+    let place_types = details.place_types();
+    let name = details
+        .display_name
+        .map(|name| name.text)
+        .unwrap_or_default();
+    PlaceDetails {
+        image_url,
+        entrance_fee_inr: look_up_monument_fee(&name),
+        name,
+        address: details.formatted_address.unwrap_or_default(),
+        rating: details.rating,
+        total_reviews: details.user_rating_count,
+        phone: details.international_phone_number,
+        website: details.website_uri,
+        opening_hours: details.regular_opening_hours,
+        note: None,
+        place_types,
+        location: details.location,
+    }
+}
+
+/// Lowercase words of `name` without punctuation, so `Humayun's Tomb` is `humayun tomb`.
+fn name_words(name: &str) -> Vec<String> {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !matches!(*word, "" | "s" | "the"))
+        .map(str::to_string)
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Entrance fee of the monument `name` refers to, e.g. `Taj Mahal, Agra` or `Qutab Minar`. A
+/// monument named within `name` wins, the longest name first. One word names like `Hampi` are
+/// often the town around the monument, so they have to be the whole part before the first
+/// comma. Otherwise that part may be misspelt by a letter in five.
+pub fn look_up_monument_fee(name: &str) -> Option<f32> {
+    let words = name_words(name);
+    let place = name_words(name.split(',').next().unwrap_or_default());
+    let names = || {
+        MONUMENT_FEES.iter().flat_map(|monument| {
+            std::iter::once(&monument.name)
+                .chain(&monument.aliases)
+                .map(move |name| (name_words(name), monument.fee_inr))
+        })
+    };
+    let named = names()
+        .filter(|(monument, _)| match monument.len() {
+            1 => place == *monument,
+            len => words.windows(len).any(|window| window == monument),
+        })
+        .max_by_key(|(monument, _)| monument.len());
+    if let Some((_, fee)) = named {
+        return Some(fee);
+    }
+
+    let place = place.join(" ");
+    names()
+        .map(|(monument, fee)| {
+            let monument = monument.join(" ");
+            (edit_distance(&place, &monument), monument.len(), fee)
+        })
+        .filter(|(distance, len, _)| *distance <= (len / 5).max(1))
+        .min_by_key(|(distance, ..)| *distance)
+        .map(|(.., fee)| fee)
+}
+
+/// Id of the best match for `query`, for use with `GooglePlacesClient::place_details`.
+async fn place_id(client: &GooglePlacesClient, query: &str) -> Result<String, TravelError> {
+    client
+        .text_search_with_fields(query, 1, "places.id")
+        .await?
+        .into_iter()
+        .find_map(|place| place.id)
+        .ok_or_else(|| TravelError::Api(format!("No place found for '{query}'")))
+}
+
+/// A place found by `nearby_attractions`.
+#[derive(Debug, Clone, Serialize)]
+pub struct NearbyAttraction {
+    #[serde(flatten)]
+    pub place: PlaceResult,
+    ///Ticket price for Indian citizens, known for ASI monuments only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrance_fee_inr: Option<f32>,
+    ///'verify entrance fee' when searched with a budget and the fee is unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub place_types: Vec<PlaceType>,
+}
+
+/// Leaves out the places with a known entrance fee above `budget`.
+fn attractions_within_budget(
+    places: Vec<PlaceResult>,
+    budget: Option<f32>,
+) -> Vec<NearbyAttraction> {
+    places
+        .into_iter()
+        .map(|place| {
+            let name = place.display_name.as_ref().map(|name| name.text.as_str());
+            let entrance_fee_inr = name.and_then(look_up_monument_fee);
+            NearbyAttraction {
+                note: fee_note(entrance_fee_inr, budget),
+                entrance_fee_inr,
+                place_types: place.place_types(),
+                place,
+            }
+        })
+        .filter(|attraction| is_within_budget(attraction.entrance_fee_inr, budget))
+        .collect()
+}
+
+#[gemini_function]
+///Find places of a given type around a location, e.g. attractions near the user's hotel.
+pub async fn nearby_attractions(
+    latitude: f64,
+    longitude: f64,
+    ///Search radius in meters (max 50000)
+    radius_meters: u32,
+    ///Google Places type (e.g., 'tourist_attraction', 'restaurant', 'museum')
+    place_type: String,
+    ///Maximum number of places to return (1-20). Defaults to 10.
+    max_sites: Option<u8>,
+    ///Most the user wants to spend per person on an activity, in INR. Places with a higher entrance fee are left out.
+    budget_per_activity_inr: Option<f32>,
+) -> Result<Vec<NearbyAttraction>, Box<dyn std::error::Error + Send + Sync>> {
+    let max_sites = site_limit(max_sites)?;
+    check_budget(budget_per_activity_inr)?;
+    let places = GooglePlacesClient::from_config()
+        .nearby_search(latitude, longitude, radius_meters, &place_type, max_sites)
+        .await?;
+    let mut attractions = attractions_within_budget(places, budget_per_activity_inr);
+    // Every place adds to the context, so never pass on more than asked for
+    attractions.truncate(max_sites.into());
+    Ok(attractions)
+}
+
+fn site_limit(max_sites: Option<u8>) -> Result<u8, TravelError> {
+    let max_sites = max_sites.unwrap_or(DEFAULT_MAX_SITES);
+    if !(1..=MAX_SITES).contains(&max_sites) {
+        return Err(TravelError::InvalidInput(format!(
+            "max_sites must be between 1 and {MAX_SITES}. Found: {max_sites}"
+        )));
+    }
+    Ok(max_sites)
+}
+
+#[gemini_function]
+///Get a Google Maps link with directions to a place, e.g. from the hotel the user picked.
+pub async fn get_directions_to_place(
+    ///Name of the place along with its city (e.g., 'Taj Mahal, Agra')
+    query: String,
+    ///Where the user starts from, e.g. the hotel's latitude and longitude
+    from: Coordinates,
+) -> Result<String, TravelError> {
+    cached_place_details(&query, || fetch_place_details(&query))
+        .await?
+        .get_directions(from)
+}
+
+/// `sites` with a known location in the order of the nearest neighbour route from `start`.
+fn route_from(start: Coordinates, sites: Vec<PlaceDetails>) -> Vec<PlaceDetails> {
+    let locations: Vec<Coordinates> = sites.iter().filter_map(|site| site.location).collect();
+    let mut sites: Vec<Option<PlaceDetails>> = sites
+        .into_iter()
+        .filter(|site| site.location.is_some())
+        .map(Some)
+        .collect();
+    nearest_neighbor_order(start, &locations)
+        .into_iter()
+        .filter_map(|i| sites[i].take())
+        .collect()
+}
+
+/// Reorders `sites` so that each is followed by the closest one not visited yet, starting from
+/// the hotel, or from the first site when the hotel's location isn't known. Sites without a
+/// known location are taken out of `sites` and returned.
+pub fn optimize_sites_for_hotel(hotel: &Hotel, sites: &mut Vec<PlaceDetails>) -> Vec<PlaceDetails> {
+    let (located, unlocated): (Vec<_>, Vec<_>) =
+        sites.drain(..).partition(|site| site.location.is_some());
+    *sites = match hotel.coordinates().or_else(|| located.first()?.location) {
+        Some(start) => route_from(start, located),
+        None => located,
+    };
+    unlocated
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RouteStop {
+    pub name: String,
+    pub address: String,
+    ///Straight line distance from the hotel or the stop before
+    pub distance_from_previous_km: f32,
+    ///Google Maps directions from the hotel or the stop before
+    pub directions_url: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SightseeingRoute {
+    pub stops: Vec<RouteStop>,
+    pub total_distance_km: f32,
+    ///Sites whose location couldn't be found, to be fitted in by hand
+    pub not_found: Vec<String>,
+}
+
+/// `sites` visited in the order given, starting from `start`.
+fn sightseeing_route(
+    start: Coordinates,
+    sites: &[PlaceDetails],
+    not_found: Vec<String>,
+) -> SightseeingRoute {
+    let locations: Vec<Coordinates> = sites.iter().filter_map(|site| site.location).collect();
+    let stops = std::iter::once(&start)
+        .chain(&locations)
+        .zip(sites.iter().zip(&locations))
+        .map(|(from, (site, to))| RouteStop {
+            name: site.name.clone(),
+            address: site.address.clone(),
+            distance_from_previous_km: from.distance_km(to) as f32,
+            directions_url: get_directions_url(*from, *to),
+        })
+        .collect();
+    SightseeingRoute {
+        stops,
+        total_distance_km: route_distance_km(start, &locations) as f32,
+        not_found,
+    }
+}
+
+#[gemini_function]
+///Order the sites the user wants to see into a short route from their hotel, so nearby sites are visited one after another. Call it when planning several sites over one or more days and split the days along the route.
+pub async fn optimize_sightseeing_route(
+    ///Name of the hotel along with its city (e.g., 'Taj Fort Aguada Resort & Spa, Goa')
+    hotel: String,
+    ///Names of the sites along with their city (e.g., ['Basilica of Bom Jesus, Goa', 'Chapora Fort, Goa'])
+    sites: Vec<String>,
+) -> Result<SightseeingRoute, TravelError> {
+    if sites.is_empty() || sites.len() > MAX_ROUTE_SITES {
+        return Err(TravelError::InvalidInput(format!(
+            "Between 1 and {MAX_ROUTE_SITES} sites can be ordered. Found: {}",
+            sites.len()
+        )));
+    }
+    let start = geocode(&hotel).await?;
+    let details = join_all(
+        sites
             .iter()
-            .map(|f| f.as_str())
+            .map(|site| cached_place_details(site, || fetch_place_details(site))),
+    )
+    .await;
+    let mut not_found = vec![];
+    let mut found = vec![];
+    for (site, details) in sites.into_iter().zip(details) {
+        match details {
+            Ok(details) if details.location.is_some() => found.push(details),
+            _ => not_found.push(site),
+        }
+    }
+    Ok(sightseeing_route(
+        start,
+        &route_from(start, found),
+        not_found,
+    ))
+}
+
+#[gemini_function]
+///Get a public photo URL of a hotel, monument or any other place to show to the user.
+pub async fn get_place_image_url(
+    ///Name of the place along with its city (e.g., 'Taj Mahal, Agra')
+    query: String,
+    ///Width of the image in pixels. Defaults to 800.
+    max_width: Option<u32>,
+) -> Result<String, TravelError> {
+    let client = GooglePlacesClient::from_config();
+    // Text Search photos are often of the surrounding area, so the photos are taken from the
+    // place's own details record instead
+    let details = client
+        .place_details(&place_id(&client, &query).await?, "id,photos")
+        .await?;
+    image_url(&client, &details, max_width.unwrap_or(DEFAULT_IMAGE_WIDTH))
+        .await?
+        .ok_or_else(|| TravelError::Api(format!("No photos found for '{query}'")))
+}
+
+/// URL of the first photo of the place. `None` when it has no photos.
+async fn image_url(
+    client: &GooglePlacesClient,
+    details: &PlaceResult,
+    max_width: u32,
+) -> Result<Option<String>, TravelError> {
+    match details.photo_reference() {
+        Some(reference) => client.photo_url(reference, max_width).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+#[tokio::test]
+async fn image_url_from_place_details_test() {
+    let client = GooglePlacesClient::new("test-key".into());
+    let fixture = r#"{
+        "id": "ChIJbf8C1yFxdDkR3n12P4DkKt0",
+        "photos": [
+            {"name": "places/ChIJbf8C1yFxdDkR3n12P4DkKt0/photos/AWU5", "widthPx": 4800},
+            {"name": "places/ChIJbf8C1yFxdDkR3n12P4DkKt0/photos/BXq2", "widthPx": 1200}
+        ]
+    }"#;
+    let details: PlaceResult = serde_json::from_str(fixture).unwrap();
+    assert_eq!(
+        details.photo_reference(),
+        Some("places/ChIJbf8C1yFxdDkR3n12P4DkKt0/photos/AWU5")
+    );
+
+    // Places without photos don't call the Places API
+    let no_photos: PlaceResult =
+        serde_json::from_str(r#"{"id": "ChIJbf8C1yFxdDkR3n12P4DkKt0"}"#).unwrap();
+    assert_eq!(image_url(&client, &no_photos, 400).await.unwrap(), None);
+}
+
+#[test]
+fn site_limit_test() {
+    assert_eq!(site_limit(None).unwrap(), DEFAULT_MAX_SITES);
+    assert_eq!(site_limit(Some(1)).unwrap(), 1);
+    assert_eq!(site_limit(Some(20)).unwrap(), 20);
+    assert!(matches!(
+        site_limit(Some(0)),
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        site_limit(Some(21)),
+        Err(TravelError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn place_details_test() {
+    let fixture = r#"{
+        "id": "ChIJbf8C1yFxdDkR3n12P4DkKt0",
+        "displayName": {"text": "Taj Mahal", "languageCode": "en"},
+        "formattedAddress": "Dharmapuri, Forest Colony, Tajganj, Agra, Uttar Pradesh 282001, India",
+        "rating": 4.6,
+        "userRatingCount": 312456,
+        "internationalPhoneNumber": "+91 562 222 6431",
+        "websiteUri": "https://www.tajmahal.gov.in/",
+        "regularOpeningHours": {
+            "openNow": false,
+            "weekdayDescriptions": ["Monday: 6:00 AM – 6:30 PM", "Friday: Closed"]
+        },
+        "photos": [{"name": "places/ChIJbf8C1yFxdDkR3n12P4DkKt0/photos/AWU5"}],
+        "types": ["historical_landmark", "tourist_attraction", "point_of_interest"]
+    }"#;
+    let photo = "https://lh3.googleusercontent.com/place-photos/AWU5=s1600-w800";
+    let details = to_place_details(serde_json::from_str(fixture).unwrap(), Some(photo.into()));
+    assert_eq!(details.name, "Taj Mahal");
+    assert_eq!(details.rating, Some(4.6));
+    assert_eq!(details.total_reviews, Some(312456));
+    assert_eq!(details.phone.as_deref(), Some("+91 562 222 6431"));
+    assert_eq!(
+        details.website.as_deref(),
+        Some("https://www.tajmahal.gov.in/")
+    );
+    assert_eq!(details.image_url.as_deref(), Some(photo));
+    let hours = details.opening_hours.unwrap();
+    assert_eq!(hours.open_now, Some(false));
+    assert_eq!(hours.weekday_descriptions[1], "Friday: Closed");
+    assert_eq!(details.entrance_fee_inr, Some(50.0));
+    assert_eq!(details.place_types, [PlaceType::HistoricalSite]);
+
+    let unrated = to_place_details(
+        serde_json::from_str(r#"{"displayName": {"text": "Hidden Cove"}}"#).unwrap(),
+        None,
+    );
+    assert_eq!(unrated.name, "Hidden Cove");
+    assert_eq!(unrated.address, "");
+    assert!(unrated.rating.is_none() && unrated.image_url.is_none());
+    assert!(unrated.entrance_fee_inr.is_none());
+}
+
+#[test]
+fn formatted_markdown_test() {
+    let site = |name: &str, image_url: Option<&str>| PlaceDetails {
+        name: name.into(),
+        address: "Tajganj, Agra, Uttar Pradesh 282001, India".into(),
+        rating: Some(4.6),
+        total_reviews: Some(312456),
+        phone: None,
+        website: None,
+        image_url: image_url.map(Into::into),
+        opening_hours: None,
+        entrance_fee_inr: look_up_monument_fee(name),
+        note: None,
+        place_types: vec![],
+        location: None,
+    };
+    let taj = site(
+        "Taj Mahal",
+        Some("https://places.googleapis.com/v1/taj/media"),
+    );
+    assert_eq!(
+        taj.formatted_markdown(),
+        "### Taj Mahal\nTajganj, Agra, Uttar Pradesh 282001, India\n⭐ 4.6 (312456 reviews)\n🎟️ Entry ₹50 for Indian citizens\n![Taj Mahal](https://places.googleapis.com/v1/taj/media)"
+    );
+    let mut cove = site("Hidden Cove", None);
+    cove.address.clear();
+    cove.total_reviews = None;
+    assert_eq!(cove.formatted_markdown(), "### Hidden Cove\n⭐ 4.6");
+    cove.rating = None;
+    assert_eq!(cove.formatted_markdown(), "### Hidden Cove");
+    cove.note = Some(VERIFY_FEE_NOTE.into());
+    assert_eq!(
+        cove.formatted_markdown(),
+        "### Hidden Cove\n⚠️ verify entrance fee"
+    );
+    cove.note = None;
+    cove.place_types = vec![PlaceType::Beach, PlaceType::Other("lodging".into())];
+    assert_eq!(cove.formatted_markdown(), "### Hidden Cove\n🏷️ Beach");
+    cove.place_types.clear();
+    assert!(cove.get_directions(Coordinates::new(15.55, 73.75)).is_err());
+    cove.location = Some(Coordinates::new(15.5553, 73.7517));
+    assert_eq!(
+        cove.formatted_markdown(),
+        "### Hidden Cove\n[📍 Get Directions](https://maps.google.com/maps/dir//15.5553,73.7517)"
+    );
+    assert_eq!(
+        cove.get_directions(Coordinates::new(15.5439, 73.7553))
+            .unwrap(),
+        "https://maps.google.com/maps/dir/15.5439,73.7553/15.5553,73.7517"
+    );
+    let hotel: Hotel = serde_json::from_value(json!({
+        "hotel_id": "GOABAGA1",
+        "name": "Baga Beach Resort",
+        "latitude": 15.5439,
+        "longitude": 73.7553,
+        "offers": [],
+        "distance_to_center_km": null,
+        "distance_to_airport_km": null
+    }))
+    .unwrap();
+    assert_eq!(
+        cove.get_directions_from_hotel(&hotel).as_deref(),
+        Some("https://maps.google.com/maps/dir/15.5439,73.7553/15.5553,73.7517")
+    );
+    cove.location = None;
+    assert_eq!(cove.get_directions_from_hotel(&hotel), None);
+
+    assert_eq!(
+        sites_to_markdown(&[taj.clone(), cove.clone()]),
+        format!("{}\n\n### Hidden Cove", taj.formatted_markdown())
+    );
+    assert_eq!(sites_to_markdown(&[]), "");
+
+    let response = json!(taj);
+    let wrapped = with_markdown(response.clone());
+    assert_eq!(wrapped["details"], response);
+    assert_eq!(wrapped["markdown"], taj.formatted_markdown());
+    let error = json!({"Error": "No place found for 'Atlantis'"});
+    assert_eq!(with_markdown(error.clone()), error);
+}
+
+#[test]
+fn budget_per_activity_test() {
+    let place = |name: &str| -> PlaceResult {
+        serde_json::from_value(json!({
+            "id": name.to_lowercase(),
+            "displayName": {"text": name},
+            "rating": 4.5
+        }))
+        .unwrap()
+    };
+    let places = || {
+        vec![
+            place("Taj Mahal"),
+            place("Agra Fort"),
+            place("Mehtab Bagh"),
+            place("Agra Helicopter Ride"),
+        ]
+    };
+    let names = |attractions: &[NearbyAttraction]| {
+        attractions
+            .iter()
+            .map(|attraction| attraction.place.display_name.as_ref().unwrap().text.clone())
             .collect::<Vec<_>>()
-            .join(",")
     };
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert("Content-Type", "application/json".parse()?);
-    headers.insert("X-Goog-Api-Key", api_key.parse()?);
-    headers.insert("X-Goog-FieldMask", field_mask.parse()?);
+    let all = attractions_within_budget(places(), None);
+    assert_eq!(all.len(), 4);
+    assert!(all.iter().all(|attraction| attraction.note.is_none()));
+    assert_eq!(all[0].entrance_fee_inr, Some(50.0));
 
-    let body = serde_json::json!({
-        "textQuery": query,
-        "maxResultCount": max_results,
-    });
+    // The Taj Mahal is ₹50 and Agra Fort ₹40, the helicopter ride's fee is unknown
+    let budget = attractions_within_budget(places(), Some(45.0));
+    assert_eq!(
+        names(&budget),
+        ["Agra Fort", "Mehtab Bagh", "Agra Helicopter Ride"]
+    );
+    assert_eq!(budget[0].note, None);
+    assert_eq!(budget[2].note.as_deref(), Some(VERIFY_FEE_NOTE));
+    let json = serde_json::to_value(&budget[2]).unwrap();
+    assert_eq!(json["displayName"]["text"], "Agra Helicopter Ride");
+    assert_eq!(json["note"], VERIFY_FEE_NOTE);
+    assert!(json.get("entrance_fee_inr").is_none());
 
-    let resp = client.post(url).headers(headers).json(&body).send().await?;
+    assert_eq!(
+        fee_note(Some(50.0), Some(30.0)).as_deref(),
+        Some("Entrance fee ₹50 is above the budget of ₹30")
+    );
+    assert_eq!(fee_note(Some(25.0), Some(25.0)), None);
+    assert_eq!(fee_note(None, None), None);
+    assert!(check_budget(Some(0.0)).is_ok());
+    assert!(check_budget(None).is_ok());
+    assert!(check_budget(Some(-1.0)).is_err());
+    assert!(check_budget(Some(f32::NAN)).is_err());
+}
 
-    if !resp.status().is_success() {
-        let error_text = resp.text().await?;
-        return Err(format!("Places API error: {}", error_text).into());
+#[test]
+fn monument_fee_test() {
+    let known = [
+        ("Taj Mahal", 50.0),
+        ("Red Fort, Delhi", 40.0),
+        ("Qutub Minar", 40.0),
+        ("Humayun's Tomb", 40.0),
+        ("Agra Fort", 40.0),
+        ("Konark Sun Temple", 40.0),
+        ("Modhera Sun Temple", 25.0),
+        ("Golconda Fort, Hyderabad", 25.0),
+        ("Mattancherry Palace (Dutch Palace)", 25.0),
+        ("Charminar", 25.0),
+    ];
+    for (name, fee) in known {
+        assert_eq!(look_up_monument_fee(name), Some(fee), "{name}");
     }
+    // Aliases, case and misspellings
+    assert_eq!(look_up_monument_fee("lal qila"), Some(40.0));
+    assert_eq!(look_up_monument_fee("Humayun Tomb"), Some(40.0));
+    assert_eq!(look_up_monument_fee("Qutab Minaar, New Delhi"), Some(40.0));
+    assert_eq!(look_up_monument_fee("Itmad-ud-Daulah, Agra"), Some(25.0));
+
+    assert_eq!(look_up_monument_fee("Hampi, Karnataka"), Some(40.0));
+    assert_eq!(look_up_monument_fee("Charminar, Hyderabad"), Some(25.0));
+    // Towns named after their monument
+    assert_eq!(look_up_monument_fee("Hampi Bazaar Restaurant"), None);
+    assert_eq!(look_up_monument_fee("Hotel Mahabalipuram, Chennai"), None);
 
-    let payload: TextSearchResponse = resp.json().await?;
-    Ok(payload.places.unwrap_or_default())
+    assert_eq!(look_up_monument_fee("Baga Beach"), None);
+    assert_eq!(look_up_monument_fee("Fort"), None);
+    assert_eq!(look_up_monument_fee(""), None);
+    assert!(MONUMENT_FEES.len() >= 100);
 }
+
+#[tokio::test]
+async fn place_details_cache_test() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = AtomicUsize::new(0);
+    let fetch = || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(PlaceDetails {
+            name: "Cache Test Fort".into(),
+            address: "Jaipur".into(),
+            rating: Some(4.5),
+            total_reviews: None,
+            phone: None,
+            website: None,
+            image_url: None,
+            opening_hours: None,
+            entrance_fee_inr: None,
+            note: None,
+            place_types: vec![],
+            location: None,
+        })
+    };
+
+    cached_place_details("Cache Test Fort, Jaipur", fetch)
+        .await
+        .unwrap();
+    cached_place_details(" cache test fort, JAIPUR", fetch)
+        .await
+        .unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    assert!(invalidate_site_cache("Cache Test Fort, Jaipur").await);
+    cached_place_details("Cache Test Fort, Jaipur", fetch)
+        .await
+        .unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    cached_place_details("Cache Test Fort, Jaipur", fetch)
+        .await
+        .unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    assert!(!invalidate_site_cache("Never Fetched Palace").await);
+
+    // Invalidated entries are dropped on the next insert
+    assert!(invalidate_site_cache("Cache Test Fort, Jaipur").await);
+    cached_place_details("Cache Test Palace, Jaipur", fetch)
+        .await
+        .unwrap();
+    let cache = PLACE_DETAILS_CACHE.read().await;
+    assert!(!cache.contains_key(&cache_key("Cache Test Fort, Jaipur")));
+    assert!(cache.contains_key(&cache_key("Cache Test Palace, Jaipur")));
+}
+
 #[tokio::test]
 async fn get_about_place_test() {
-    dbg!(
-        get_about_place(
-            "Kashmir, manali".into(),
-            1,
-            vec![PlaceField::DisplayName, PlaceField::FormattedAddress]
-        )
+    let place = get_about_place("Taj Mahal, Agra".into(), None)
         .await
+        .unwrap();
+    assert!(place.name.contains("Taj Mahal"), "{place:?}");
+    assert!(place.rating.is_some());
+}
+
+#[test]
+fn optimize_sites_for_hotel_test() {
+    let site = |name: &str, location: Option<(f64, f64)>| PlaceDetails {
+        name: name.into(),
+        address: format!("{name}, Goa"),
+        rating: None,
+        total_reviews: None,
+        phone: None,
+        website: None,
+        image_url: None,
+        opening_hours: None,
+        entrance_fee_inr: None,
+        note: None,
+        place_types: vec![],
+        location: location.map(|(latitude, longitude)| Coordinates::new(latitude, longitude)),
+    };
+    let hotel = |latitude: Option<f64>, longitude: Option<f64>| -> Hotel {
+        serde_json::from_value(json!({
+            "hotel_id": "GOACAL01",
+            "name": "Calangute Residency",
+            "latitude": latitude,
+            "longitude": longitude,
+            "offers": [],
+            "distance_to_center_km": null,
+            "distance_to_airport_km": null
+        }))
         .unwrap()
+    };
+    let names = |sites: &[PlaceDetails]| -> Vec<String> {
+        sites.iter().map(|site| site.name.clone()).collect()
+    };
+    let goa = vec![
+        site("Basilica of Bom Jesus", Some((15.5009, 73.9116))),
+        site("Hidden Cove", None),
+        site("Fort Aguada", Some((15.4925, 73.7737))),
+        site("Chapora Fort", Some((15.6062, 73.7364))),
+        site("Baga Beach", Some((15.5553, 73.7517))),
+    ];
+
+    let mut sites = goa.clone();
+    let unlocated = optimize_sites_for_hotel(&hotel(Some(15.5439), Some(73.7553)), &mut sites);
+    assert_eq!(
+        names(&sites),
+        [
+            "Baga Beach",
+            "Chapora Fort",
+            "Fort Aguada",
+            "Basilica of Bom Jesus"
+        ]
     );
+    assert_eq!(names(&unlocated), ["Hidden Cove"]);
+
+    // Without the hotel's location the route starts at the first site
+    let mut sites = goa.clone();
+    optimize_sites_for_hotel(&hotel(None, None), &mut sites);
+    assert_eq!(
+        names(&sites),
+        [
+            "Basilica of Bom Jesus",
+            "Fort Aguada",
+            "Baga Beach",
+            "Chapora Fort"
+        ]
+    );
+    let mut nowhere = vec![site("Hidden Cove", None)];
+    assert_eq!(
+        optimize_sites_for_hotel(&hotel(None, None), &mut nowhere).len(),
+        1
+    );
+    assert!(nowhere.is_empty());
+
+    let calangute = Coordinates::new(15.5439, 73.7553);
+    let route = sightseeing_route(
+        calangute,
+        &route_from(calangute, goa),
+        vec!["Hidden Cove".into()],
+    );
+    assert_eq!(route.stops.len(), 4);
+    assert_eq!(route.stops[0].name, "Baga Beach");
+    assert_eq!(
+        route.stops[0].directions_url,
+        "https://maps.google.com/maps/dir/15.5439,73.7553/15.5553,73.7517"
+    );
+    assert_eq!(
+        route.stops[1].directions_url,
+        "https://maps.google.com/maps/dir/15.5553,73.7517/15.6062,73.7364"
+    );
+    assert!((route.stops[0].distance_from_previous_km - 1.3).abs() < 0.1);
+    let total: f32 = route
+        .stops
+        .iter()
+        .map(|stop| stop.distance_from_previous_km)
+        .sum();
+    assert!((route.total_distance_km - total).abs() < 0.01);
+    assert_eq!(route.not_found, ["Hidden Cove"]);
+}
+
+#[tokio::test]
+async fn optimize_sightseeing_route_test() {
+    for sites in [
+        vec![],
+        vec!["Baga Beach, Goa".to_string(); MAX_ROUTE_SITES + 1],
+    ] {
+        assert!(matches!(
+            optimize_sightseeing_route("Calangute, Goa".into(), sites).await,
+            Err(TravelError::InvalidInput(_))
+        ));
+    }
 }
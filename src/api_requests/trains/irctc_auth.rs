@@ -0,0 +1,341 @@
+use crate::{
+    config::CONFIG,
+    error::TravelError,
+    utils::{Currency, Date},
+};
+use reqwest::header::AUTHORIZATION;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+
+const BASE_URL: &str = "https://partner.irctc.co.in";
+const TOKEN_PATH: &str = "/oauth/token";
+const BOOKINGS_PATH: &str = "/api/v1/bookings";
+///IRCTC allows at most 6 passengers on one ticket
+const MAX_PASSENGERS: usize = 6;
+const TRAVEL_CLASSES: &[&str] = &["1A", "2A", "3A", "3E", "SL", "CC", "EC", "2S"];
+///General, Tatkal, Premium Tatkal, Ladies and Senior Citizen
+const QUOTAS: &[&str] = &["GN", "TQ", "PT", "LD", "SS"];
+
+#[derive(Deserialize)]
+struct IrctcTokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+/// OAuth client of the IRCTC partner API. Unlike the RapidAPI wrapper it can book tickets, so
+/// it is only available to registered partners.
+pub struct IrctcAuthClient {
+    client_id: String,
+    client_secret: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: Instant,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Passenger {
+    pub name: String,
+    pub age: u8,
+    ///'M', 'F' or 'T'
+    pub gender: String,
+    ///'LB', 'MB', 'UB', 'SL' or 'SU'
+    pub berth_preference: Option<String>,
+}
+
+/// A booking held by IRCTC until it is confirmed or `expires_at` passes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BookingSession {
+    pub booking_id: String,
+    pub total_fare: Currency,
+    pub status: String,
+    ///Local time as `YYYY-MM-DDTHH:MM:SS`
+    pub expires_at: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IrctcBookingResponse {
+    booking_id: String,
+    total_fare: f32,
+    status: String,
+    expires_at: String,
+}
+
+#[cfg(feature = "irctc_booking")]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BookingConfirmation {
+    pub pnr: String,
+    ///'CNF', 'RAC' or a waitlist position like 'WL 12'
+    pub status: String,
+}
+
+impl IrctcAuthClient {
+    /// Signs in with `IRCTC_CLIENT_ID` and `IRCTC_CLIENT_SECRET`.
+    pub async fn from_env() -> Result<Self, TravelError> {
+        let var = |key: &str| {
+            std::env::var(key).map_err(|_| TravelError::ApiKeyMissing(format!("{key} not found")))
+        };
+        let client_id = var("IRCTC_CLIENT_ID")?;
+        let client_secret = var("IRCTC_CLIENT_SECRET")?;
+        let token = request_token(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            ("client_secret", &client_secret),
+        ])
+        .await?;
+        Ok(Self::from_token(client_id, client_secret, token))
+    }
+
+    fn from_token(client_id: String, client_secret: String, token: IrctcTokenResponse) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expires_at: Instant::now() + Duration::from_secs(token.expires_in),
+        }
+    }
+
+    fn expires_within(&self, buffer: Duration) -> bool {
+        self.expires_at <= Instant::now() + buffer
+    }
+
+    async fn bearer_token(&mut self) -> Result<String, TravelError> {
+        if self.expires_within(Duration::from_secs(CONFIG.token_refresh_buffer_secs)) {
+            let token = request_token(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &self.refresh_token),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+            ])
+            .await?;
+            *self = Self::from_token(self.client_id.clone(), self.client_secret.clone(), token);
+        }
+        Ok(format!("Bearer {}", self.access_token))
+    }
+
+    /// Starts the booking flow. The seats are held but not booked until the session is
+    /// confirmed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn init_booking(
+        &mut self,
+        train_number: &str,
+        from: &str,
+        to: &str,
+        date: &Date,
+        passengers: &[Passenger],
+        class: &str,
+        quota: &str,
+    ) -> Result<BookingSession, TravelError> {
+        let body = booking_request(train_number, from, to, date, passengers, class, quota)?;
+        let resp = reqwest::Client::new()
+            .post(format!("{BASE_URL}{BOOKINGS_PATH}"))
+            .header(AUTHORIZATION, self.bearer_token().await?)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| TravelError::Api(format!("IRCTC booking request failed: {e}")))?;
+        if !resp.status().is_success() {
+            return Err(TravelError::Api(format!(
+                "IRCTC booking error: {}",
+                resp.status()
+            )));
+        }
+        let response: IrctcBookingResponse = resp
+            .json()
+            .await
+            .map_err(|e| TravelError::Api(format!("Invalid IRCTC booking response: {e}")))?;
+        Ok(to_booking_session(response))
+    }
+
+    /// Books the tickets held by `session` and charges the partner account.
+    #[cfg(feature = "irctc_booking")]
+    pub async fn confirm_booking(
+        &mut self,
+        session: &BookingSession,
+    ) -> Result<BookingConfirmation, TravelError> {
+        let resp = reqwest::Client::new()
+            .post(format!(
+                "{BASE_URL}{BOOKINGS_PATH}/{}/confirm",
+                urlencoding::encode(&session.booking_id)
+            ))
+            .header(AUTHORIZATION, self.bearer_token().await?)
+            .send()
+            .await
+            .map_err(|e| TravelError::Api(format!("IRCTC confirmation request failed: {e}")))?;
+        if !resp.status().is_success() {
+            return Err(TravelError::Api(format!(
+                "IRCTC confirmation error: {}",
+                resp.status()
+            )));
+        }
+        resp.json()
+            .await
+            .map_err(|e| TravelError::Api(format!("Invalid IRCTC confirmation response: {e}")))
+    }
+}
+
+async fn request_token(params: &[(&str, &str)]) -> Result<IrctcTokenResponse, TravelError> {
+    let resp = reqwest::Client::new()
+        .post(format!("{BASE_URL}{TOKEN_PATH}"))
+        .form(params)
+        .send()
+        .await
+        .map_err(|e| TravelError::Api(format!("IRCTC token request failed: {e}")))?;
+    if !resp.status().is_success() {
+        return Err(TravelError::Api(format!(
+            "IRCTC token error: {}",
+            resp.status()
+        )));
+    }
+    resp.json()
+        .await
+        .map_err(|e| TravelError::Api(format!("Invalid IRCTC token response: {e}")))
+}
+
+fn booking_request(
+    train_number: &str,
+    from: &str,
+    to: &str,
+    date: &Date,
+    passengers: &[Passenger],
+    class: &str,
+    quota: &str,
+) -> Result<Value, TravelError> {
+    if train_number.len() != 5 || !train_number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(TravelError::InvalidInput(format!(
+            "Train number must be 5 digits. Found: {train_number}"
+        )));
+    }
+    if passengers.is_empty() || passengers.len() > MAX_PASSENGERS {
+        return Err(TravelError::InvalidInput(format!(
+            "A ticket needs 1 to {MAX_PASSENGERS} passengers. Found: {}",
+            passengers.len()
+        )));
+    }
+    let class = class.to_ascii_uppercase();
+    if !TRAVEL_CLASSES.contains(&class.as_str()) {
+        return Err(TravelError::InvalidInput(format!(
+            "Unknown travel class: {class}"
+        )));
+    }
+    let quota = quota.to_ascii_uppercase();
+    if !QUOTAS.contains(&quota.as_str()) {
+        return Err(TravelError::InvalidInput(format!("Unknown quota: {quota}")));
+    }
+    Ok(json!({
+        "trainNumber": train_number,
+        "fromStationCode": from.to_ascii_uppercase(),
+        "toStationCode": to.to_ascii_uppercase(),
+        "journeyDate": date.to_yyyy_mm_dd(),
+        "travelClass": class,
+        "quota": quota,
+        "passengers": passengers,
+    }))
+}
+
+fn to_booking_session(response: IrctcBookingResponse) -> BookingSession {
+    BookingSession {
+        booking_id: response.booking_id,
+        total_fare: Currency::Inr(response.total_fare),
+        status: response.status,
+        expires_at: response.expires_at,
+    }
+}
+
+#[test]
+fn irctc_token_test() {
+    let token: IrctcTokenResponse = serde_json::from_value(json!({
+        "access_token": "access",
+        "refresh_token": "refresh",
+        "token_type": "Bearer",
+        "expires_in": 1800
+    }))
+    .unwrap();
+    let client = IrctcAuthClient::from_token("id".into(), "secret".into(), token);
+    assert_eq!(client.access_token, "access");
+    assert_eq!(client.refresh_token, "refresh");
+    assert!(!client.expires_within(Duration::from_secs(30)));
+    assert!(client.expires_within(Duration::from_secs(1800)));
+}
+
+#[test]
+fn init_booking_request_test() {
+    let date = Date::new(2026, 2, 15).unwrap();
+    let passenger = Passenger {
+        name: "Asha Verma".into(),
+        age: 34,
+        gender: "F".into(),
+        berth_preference: Some("LB".into()),
+    };
+    let passengers = vec![passenger; 2];
+
+    let body = booking_request("12301", "hwh", "ndls", &date, &passengers, "3a", "gn").unwrap();
+    assert_eq!(body["fromStationCode"], "HWH");
+    assert_eq!(body["travelClass"], "3A");
+    assert_eq!(body["quota"], "GN");
+    assert_eq!(body["journeyDate"], "2026-02-15");
+    assert_eq!(body["passengers"][1]["berth_preference"], "LB");
+
+    let invalid = |result: Result<Value, TravelError>| {
+        assert!(matches!(result, Err(TravelError::InvalidInput(_))))
+    };
+    invalid(booking_request(
+        "1230",
+        "HWH",
+        "NDLS",
+        &date,
+        &passengers,
+        "3A",
+        "GN",
+    ));
+    invalid(booking_request(
+        "12301",
+        "HWH",
+        "NDLS",
+        &date,
+        &[],
+        "3A",
+        "GN",
+    ));
+    invalid(booking_request(
+        "12301",
+        "HWH",
+        "NDLS",
+        &date,
+        &vec![passengers[0].clone(); 7],
+        "3A",
+        "GN",
+    ));
+    invalid(booking_request(
+        "12301",
+        "HWH",
+        "NDLS",
+        &date,
+        &passengers,
+        "4A",
+        "GN",
+    ));
+    invalid(booking_request(
+        "12301",
+        "HWH",
+        "NDLS",
+        &date,
+        &passengers,
+        "3A",
+        "XX",
+    ));
+
+    let response: IrctcBookingResponse = serde_json::from_value(json!({
+        "bookingId": "BK123456",
+        "totalFare": 2450.5,
+        "status": "PENDING_CONFIRMATION",
+        "expiresAt": "2026-02-10T10:15:00"
+    }))
+    .unwrap();
+    let session = to_booking_session(response);
+    assert_eq!(session.booking_id, "BK123456");
+    assert!(matches!(session.total_fare, Currency::Inr(fare) if fare == 2450.5));
+}
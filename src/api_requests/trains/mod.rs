@@ -1 +1,5 @@
+// Partner only client, nothing books tickets through it yet
+#[allow(dead_code)]
+pub mod irctc_auth;
 pub mod rapidapi;
+pub mod stations;
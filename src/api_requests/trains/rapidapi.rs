@@ -1,10 +1,97 @@
-use crate::utils::Date;
-use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
-use reqwest::header::{HeaderMap, HeaderValue};
+#[cfg(test)]
+use crate::api_requests::rapidapi::MockApiClient;
+use crate::{
+    api_requests::rapidapi::{ApiClient, RapidAPIClient},
+    error::TravelError,
+    utils::{Date, TravelDuration, sanitize_query_param},
+};
+use chrono::{NaiveTime, Timelike, Weekday};
+use gemini_client_api::futures::future::join_all;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::env;
+use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore};
+
+pub(crate) const IRCTC_HOST: &str = "irctc1.p.rapidapi.com";
+pub(crate) const MAX_CODE_LEN: usize = 10;
+const DEFAULT_TRAINS_PER_PAGE: u8 = 10;
+const MAX_TRAINS_PER_PAGE: u8 = 20;
+/// Most seat availability checks sent to RapidAPI at once, which rate limits bursts.
+const MAX_CONCURRENT_AVAILABILITY_CALLS: usize = 10;
+/// Trains of one search whose seats are checked, the rest are listed without availability.
+const MAX_TRAINS_WITH_AVAILABILITY: usize = 10;
+/// Classes of one train checked at once by `train_all_class_availability`.
+const MAX_CONCURRENT_CLASS_CHECKS: usize = 4;
+const MAX_FLEXIBLE_DAYS: u8 = 7;
+// Train schedules rarely change
+const TRAIN_DETAILS_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const PANTRY_CAR_NOTE: &str = "🍽️ Pantry car available";
+const AC_LOUNGE_NOTE: &str = "🛋️ AC waiting lounge available";
+const E_CATERING_NOTE: &str = "🍱 E-catering available via IRCTC";
+
+/// Long distance trains with a pantry car, used when IRCTC doesn't say. Kept by hand.
+static KNOWN_PANTRY_TRAINS: &[&str] = &[
+    "12301", "12302", "12309", "12310", "12313", "12314", "12431", "12432", "12951", "12952",
+    "12953", "12954", "12213", "12214", "12259", "12260", "12615", "12616", "12621", "12622",
+    "12625", "12626", "12627", "12628", "12723", "12724", "12801", "12802",
+];
+
+/// IRCTC `train_type` codes and what they mean for a traveller.
+static TRAIN_TYPE_DESCRIPTIONS: &[(&str, &str)] = &[
+    (
+        "RAJ",
+        "Rajdhani Express, fully air conditioned with meals included in the fare",
+    ),
+    (
+        "SHT",
+        "Shatabdi Express, air conditioned day train with chair cars",
+    ),
+    (
+        "JSHT",
+        "Jan Shatabdi Express, day train with AC and non-AC chair cars",
+    ),
+    (
+        "DRNT",
+        "Duronto Express, long distance train with few stops",
+    ),
+    (
+        "VB",
+        "Vande Bharat Express, semi high speed chair car train",
+    ),
+    ("TEJ", "Tejas Express, premium air conditioned train"),
+    ("HSF", "Humsafar Express, AC 3 tier sleeper train"),
+    ("GR", "Garib Rath, economical AC 3 tier sleeper train"),
+    ("ANT", "Antyodaya Express, unreserved long distance train"),
+    ("SUV", "Suvidha Express, train with dynamic fares"),
+    ("SF", "Superfast Express, averages above 55 km/h"),
+    ("EXP", "Express"),
+    ("MEX", "Mail Express"),
+    ("PAS", "Passenger train, stops at every station"),
+    ("PASS", "Passenger train, stops at every station"),
+    ("EMU", "Suburban local train"),
+    ("MEMU", "Mainline local train"),
+    ("DEMU", "Diesel local train"),
+];
+
+/// Description of an IRCTC `train_type` code. Unknown codes are returned as sent.
+pub fn train_type_description(code: &str) -> String {
+    let code = code.trim();
+    TRAIN_TYPE_DESCRIPTIONS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code))
+        .map_or_else(
+            || code.to_string(),
+            |(_, description)| description.to_string(),
+        )
+}
+
+static TRAIN_DETAILS_CACHE: LazyLock<RwLock<HashMap<String, (TrainDetails, Instant)>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Station(String);
@@ -33,7 +120,8 @@ impl Display for Station {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[gemini_schema]
 pub struct Train {
     pub train_number: String,
     pub train_name: String,
@@ -41,79 +129,539 @@ pub struct Train {
     pub to_sta: String,
     pub run_days: Vec<String>,
     pub train_type: String,
+    ///Category derived from `train_type`, e.g. 'Rajdhani' trains skip most intermediate stations
+    #[serde(default)]
+    pub category: Option<TrainCategory>,
+    ///Minutes between the searched stations. Only set when searched with details
+    #[serde(default)]
+    pub journey_minutes: Option<u32>,
+    ///Km between the searched stations. Only set when searched with details
+    #[serde(default)]
+    pub journey_distance_km: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum TrainCategory {
+    Rajdhani,
+    Shatabdi,
+    DurotoExpress,
+    SuperFast,
+    Express,
+    Passenger,
+    Local,
+    ///Unknown `train_type` code
+    Other(String),
+}
+
+impl GeminiSchema for TrainCategory {
+    fn gemini_schema() -> serde_json::Value {
+        json!({"type": "STRING"})
+    }
+}
+
+impl TrainCategory {
+    /// Maps an IRCTC `train_type` code such as `RAJ`, `SF` or `EXP`.
+    pub fn from_code(code: &str) -> Self {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "RAJ" => TrainCategory::Rajdhani,
+            "SHT" | "JSHT" => TrainCategory::Shatabdi,
+            "DRNT" => TrainCategory::DurotoExpress,
+            "SF" => TrainCategory::SuperFast,
+            "EXP" | "MEX" => TrainCategory::Express,
+            "PAS" | "PASS" => TrainCategory::Passenger,
+            "EMU" | "MEMU" | "DEMU" | "SUB" => TrainCategory::Local,
+            _ => TrainCategory::Other(code.to_string()),
+        }
+    }
+    pub fn name(&self) -> &str {
+        match self {
+            TrainCategory::Rajdhani => "Rajdhani",
+            TrainCategory::Shatabdi => "Shatabdi",
+            TrainCategory::DurotoExpress => "Duronto Express",
+            TrainCategory::SuperFast => "SuperFast",
+            TrainCategory::Express => "Express",
+            TrainCategory::Passenger => "Passenger",
+            TrainCategory::Local => "Local",
+            TrainCategory::Other(code) => code,
+        }
+    }
+    /// Rough average speed including halts, for estimating journey times.
+    pub fn typical_speed_kmh(&self) -> Option<u32> {
+        match self {
+            TrainCategory::Rajdhani => Some(80),
+            TrainCategory::Shatabdi => Some(85),
+            TrainCategory::DurotoExpress => Some(75),
+            TrainCategory::SuperFast => Some(60),
+            TrainCategory::Express => Some(50),
+            TrainCategory::Passenger => Some(35),
+            TrainCategory::Local => Some(40),
+            TrainCategory::Other(_) => None,
+        }
+    }
+}
+
+impl From<String> for TrainCategory {
+    fn from(category: String) -> Self {
+        [
+            TrainCategory::Rajdhani,
+            TrainCategory::Shatabdi,
+            TrainCategory::DurotoExpress,
+            TrainCategory::SuperFast,
+            TrainCategory::Express,
+            TrainCategory::Passenger,
+            TrainCategory::Local,
+        ]
+        .into_iter()
+        .find(|known| known.name().eq_ignore_ascii_case(&category))
+        .unwrap_or_else(|| TrainCategory::from_code(&category))
+    }
+}
+
+impl From<TrainCategory> for String {
+    fn from(category: TrainCategory) -> Self {
+        category.name().to_string()
+    }
+}
+
+impl Train {
+    pub fn category(&self) -> TrainCategory {
+        TrainCategory::from_code(&self.train_type)
+    }
+    /// Checks `run_days` (e.g. `["Mon", "Wed"]`) against the weekday of `date`.
+    pub fn runs_on(&self, date: &Date) -> bool {
+        date.weekday().is_some_and(|day| self.runs_on_day(day))
+    }
+    pub fn runs_on_day(&self, day: Weekday) -> bool {
+        let day = day.to_string();
+        self.run_days.iter().any(|run_day| {
+            run_day
+                .get(..3)
+                .is_some_and(|run_day| run_day.eq_ignore_ascii_case(&day))
+        })
+    }
+    /// First date from `from` onwards the train runs on. `None` when `run_days` has no valid
+    /// weekday.
+    pub fn next_departure_on_or_after(&self, from: &Date) -> Option<Date> {
+        from.to_naive_date()?
+            .iter_days()
+            .take(7)
+            .map(Date::from)
+            .find(|date| self.runs_on(date))
+    }
+}
+
+/// Trains of `trains_between`, whichever day they run on.
+#[derive(Debug, Clone, Default)]
+pub struct TrainsBetween(pub Vec<Train>);
+
+impl TrainsBetween {
+    pub fn filter_by_run_day(&self, day: Weekday) -> Vec<&Train> {
+        self.0
+            .iter()
+            .filter(|train| train.runs_on_day(day))
+            .collect()
+    }
+    /// Trains running on the weekday of `date`, none when the date doesn't exist.
+    pub fn running_on(&self, date: &Date) -> Vec<Train> {
+        date.weekday()
+            .map(|day| self.filter_by_run_day(day).into_iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+pub fn filter_trains_by_date(trains: Vec<Train>, date: &Date) -> Vec<Train> {
+    trains
+        .into_iter()
+        .filter(|train| train.runs_on(date))
+        .collect()
+}
+
+#[derive(Serialize, Debug)]
+pub struct TrainPage {
+    pub trains: Vec<Train>,
+    pub page: u8,
+    pub total_trains: u32,
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+    ///Which pages to ask for to see earlier or later trains
+    pub navigation: String,
+}
+
+/// Trains with an unparseable departure time go last.
+pub fn sort_trains_by_departure(trains: &mut [Train]) {
+    trains.sort_by_key(|train| minutes_of_day(&train.from_sta).unwrap_or(u32::MAX));
+}
+
+/// Fastest first. Trains without `journey_minutes` keep their order and go last.
+pub fn sort_trains_by_duration(trains: &mut [Train]) {
+    trains.sort_by_key(|train| train.journey_minutes.unwrap_or(u32::MAX));
+}
+
+/// Sorts `trains` by departure time and returns the zero based `page`. `unlisted_trains` are
+/// trains IRCTC counted in its `total` but left out of the response. They are added to
+/// `total_trains` but can't be paged to.
+pub fn paginate_trains(
+    mut trains: Vec<Train>,
+    page: u8,
+    per_page: u8,
+    unlisted_trains: u32,
+) -> TrainPage {
+    sort_trains_by_departure(&mut trains);
+    let listed = trains.len();
+    let start = (usize::from(page) * usize::from(per_page)).min(listed);
+    let end = (start + usize::from(per_page)).min(listed);
+    let has_next_page = end < listed;
+    let has_previous_page = page > 0 && listed > 0;
+    let total_trains = listed as u32 + unlisted_trains;
+
+    let mut navigation = if listed == 0 {
+        "No trains listed.".to_string()
+    } else if start == end {
+        format!("Page {page} is past the last of the {listed} trains.")
+    } else {
+        format!("Trains {} to {end} of {listed}.", start + 1)
+    };
+    if has_previous_page {
+        navigation += &format!(" Ask for page {} for earlier departures.", page - 1);
+    }
+    if has_next_page {
+        navigation += &format!(" Ask for page {} for later departures.", page + 1);
+    }
+    if unlisted_trains > 0 {
+        navigation += &format!(
+            " IRCTC didn't list {unlisted_trains} more trains on this route, check them on the IRCTC website."
+        );
+    }
+    TrainPage {
+        trains: trains.drain(start..end).collect(),
+        page,
+        total_trains,
+        has_next_page,
+        has_previous_page,
+        navigation,
+    }
 }
 
 #[derive(Deserialize)]
 struct TrainBetweenResponse {
     data: Vec<Train>,
+    ///Trains on the route, which can be more than `data` holds
+    #[serde(default, rename = "total")]
+    total_count: u32,
 }
 
-fn get_headers() -> HeaderMap {
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "X-RapidAPI-Key",
-        HeaderValue::from_str(&env::var("RAPIDAPI_KEY").expect("RAPIDAPI_KEY not found")).unwrap(),
-    );
-    headers.insert(
-        "X-RapidAPI-Host",
-        HeaderValue::from_static("irctc1.p.rapidapi.com"),
-    );
-    headers
+/// Trains IRCTC sent for a route along with how many it has in all.
+struct RouteTrains {
+    trains: Vec<Train>,
+    total_count: u32,
 }
-#[gemini_function]
-/// Search for trains running between two stations on a specific date.
+
+impl RouteTrains {
+    /// Trains counted in `total_count` but not sent.
+    fn unlisted(&self) -> u32 {
+        self.total_count.saturating_sub(self.trains.len() as u32)
+    }
+}
+
+/// Search for trains between two stations on a specific date, sorted by departure time. Trains
+/// not running on the date are kept, `TrainsBetween::filter_by_run_day` drops them. With
+/// `include_details` they are sorted fastest first instead, which is expensive as the route of
+/// every train is fetched.
 pub async fn trains_between(
-    ///Source station code (e.g., 'NDLS')
     source: Station,
-    ///Destination station code (e.g., 'BCT')
     destination: Station,
     date: Date,
-) -> Result<Vec<Train>, Box<dyn std::error::Error + Send + Sync>> {
-    let url = format!(
-        "https://irctc1.p.rapidapi.com/api/v3/trainBetweenStations?fromStationCode={}&toStationCode={}&dateOfJourney={}",
-        source,
-        destination,
-        date.to_yyyy_mm_dd()
-    );
-
-    let client = reqwest::Client::new();
-    let resp = client.get(url).headers(get_headers()).send().await?;
-
-    if !resp.status().is_success() {
-        return Err(format!("RapidAPI error: {}", resp.status()).into());
+    include_details: bool,
+) -> Result<TrainsBetween, Box<dyn std::error::Error + Send + Sync>> {
+    let client = RapidAPIClient::for_host(IRCTC_HOST)?;
+    let mut trains = all_trains_between(&client, &source, &destination, &date)
+        .await?
+        .trains;
+    sort_trains_by_departure(&mut trains);
+    if include_details {
+        trains = with_journey_times(&client, trains, &source, &destination).await;
+        sort_trains_by_duration(&mut trains);
     }
+    Ok(TrainsBetween(trains))
+}
 
-    let body: TrainBetweenResponse = resp.json().await?;
-    let trains = body
+/// Sets `journey_minutes` and `journey_distance_km` from each train's route. Trains whose route can't be fetched are
+/// left without it.
+async fn with_journey_times(
+    client: &impl ApiClient,
+    trains: Vec<Train>,
+    source: &Station,
+    destination: &Station,
+) -> Vec<Train> {
+    let details = join_all(trains.iter().map(|train| {
+        cached_train_details(&train.train_number, || {
+            fetch_train_details(client, &train.train_number)
+        })
+    }))
+    .await;
+    trains
+        .into_iter()
+        .zip(details)
+        .map(|(train, details)| {
+            let details = details
+                .inspect_err(|e| {
+                    eprintln!(
+                        "ERROR: Could not fetch details of train {}:\n{e}",
+                        train.train_number
+                    )
+                })
+                .ok();
+            let (from, to) = (source.to_string(), destination.to_string());
+            Train {
+                journey_minutes: details
+                    .as_ref()
+                    .and_then(|details| details.duration_between(&from, &to))
+                    .map(TravelDuration::total_minutes),
+                journey_distance_km: details
+                    .as_ref()
+                    .and_then(|details| details.journey_distance_km(&from, &to)),
+                ..train
+            }
+        })
+        .collect()
+}
+
+/// Every train on the route, including those not running on `date`.
+async fn all_trains_between(
+    client: &impl ApiClient,
+    source: &Station,
+    destination: &Station,
+    date: &Date,
+) -> Result<RouteTrains, TravelError> {
+    let path = format!(
+        "/api/v3/trainBetweenStations?fromStationCode={}&toStationCode={}&dateOfJourney={}",
+        sanitize_query_param(&source.to_string(), MAX_CODE_LEN)?,
+        sanitize_query_param(&destination.to_string(), MAX_CODE_LEN)?,
+        date.to_yyyy_mm_dd()
+    );
+    let body: TrainBetweenResponse = client.get(&path).await?;
+    let trains: Vec<Train> = body
         .data
         .into_iter()
         .map(|d| Train {
+            category: Some(d.category()),
             train_number: d.train_number,
             train_name: d.train_name,
             from_sta: d.from_sta,
             to_sta: d.to_sta,
             run_days: d.run_days,
             train_type: d.train_type,
+            journey_minutes: None,
+            journey_distance_km: None,
         })
         .collect();
 
-    Ok(trains)
+    Ok(RouteTrains {
+        trains,
+        total_count: body.total_count,
+    })
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[gemini_function]
+///Search for trains running between two stations on a specific date, sorted by departure time. Ask for the next page when `has_next_page` is set and the user wants more options.
+pub async fn get_train_between_paged(
+    ///Source station code (e.g., 'NDLS')
+    source: Station,
+    ///Destination station code (e.g., 'BCT')
+    destination: Station,
+    date: Date,
+    ///Zero based page number. Defaults to 0.
+    page: Option<u8>,
+    ///Trains per page (1-20). Defaults to 10.
+    per_page: Option<u8>,
+    ///Adds `journey_minutes` and `journey_distance_km` and sorts the page fastest first. Slow, as the route of every train on the page is fetched, so only set it when the user asks for the fastest train.
+    include_details: Option<bool>,
+) -> Result<TrainPage, TravelError> {
+    let per_page = per_page.unwrap_or(DEFAULT_TRAINS_PER_PAGE);
+    if !(1..=MAX_TRAINS_PER_PAGE).contains(&per_page) {
+        return Err(TravelError::InvalidInput(format!(
+            "per_page must be between 1 and {MAX_TRAINS_PER_PAGE}. Found: {per_page}"
+        )));
+    }
+    let client = RapidAPIClient::for_host(IRCTC_HOST)?;
+    let route = all_trains_between(&client, &source, &destination, &date).await?;
+    let unlisted = route.unlisted();
+    let trains = filter_trains_by_date(route.trains, &date);
+    let mut page = paginate_trains(trains, page.unwrap_or_default(), per_page, unlisted);
+    if include_details.unwrap_or_default() {
+        page.trains = with_journey_times(&client, page.trains, &source, &destination).await;
+        sort_trains_by_duration(&mut page.trains);
+    }
+    Ok(page)
+}
+
+#[derive(Serialize, Debug)]
+pub struct NextTrain {
+    pub train: Train,
+    pub next_departure: Date,
+}
+
+/// Trains without valid run days are left out. Sorted by `next_departure`, then by departure
+/// time.
+pub fn next_available_trains(trains: Vec<Train>, from: &Date) -> Vec<NextTrain> {
+    let mut next_trains: Vec<NextTrain> = trains
+        .into_iter()
+        .filter_map(|train| {
+            Some(NextTrain {
+                next_departure: train.next_departure_on_or_after(from)?,
+                train,
+            })
+        })
+        .collect();
+    next_trains.sort_by_key(|next| {
+        (
+            next.next_departure.clone(),
+            minutes_of_day(&next.train.from_sta).unwrap_or(u32::MAX),
+        )
+    });
+    next_trains
+}
+
+#[gemini_function]
+///Find when each train between two stations next runs on or after a date. Use it for trains that don't run daily, e.g. "when does the next Rajdhani run after Feb 15?"
+pub async fn trains_between_next_available(
+    ///Source station code (e.g., 'NDLS')
+    source: Station,
+    ///Destination station code (e.g., 'HWH')
+    destination: Station,
+    ///Earliest date the user can travel
+    date: Date,
+) -> Result<Vec<NextTrain>, TravelError> {
+    let route = all_trains_between(
+        &RapidAPIClient::for_host(IRCTC_HOST)?,
+        &source,
+        &destination,
+        &date,
+    )
+    .await?;
+    Ok(next_available_trains(route.trains, &date))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TrainDetails {
     pub train_number: String,
     pub train_name: String,
     pub station_list: Vec<StationArrival>,
+    ///Time from the first to the last station, e.g. '15h 32m'
+    pub journey_time: Option<String>,
+    ///Km from the first to the last station
+    #[serde(default)]
+    pub total_route_distance_km: Option<u32>,
+    ///The train has a pantry car selling meals on board
+    #[serde(default)]
+    pub buffet_car_available: bool,
+    #[serde(default)]
+    pub ac_lounge_available: bool,
+    ///e.g. 'Rajdhani Express, fully air conditioned with meals included in the fare'. Empty
+    ///when IRCTC doesn't send the type
+    #[serde(default)]
+    pub train_type_description: String,
+    ///e.g. '🍽️ Pantry car available'
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub facilities: Vec<String>,
+    ///Codes of the stations where food ordered on the way is picked up by the pantry car
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pantry_car_stations: Vec<String>,
+    ///Meals can be ordered to the seat through IRCTC e-catering
+    #[serde(default)]
+    pub food_ordering_available: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl TrainDetails {
+    /// Time from the departure at the first station to the arrival at the last one.
+    pub fn journey_duration(&self) -> Option<TravelDuration> {
+        elapsed(&self.station_list)
+    }
+    /// Runs past midnight between the first and the last station.
+    pub fn is_overnight(&self) -> bool {
+        let departure = self
+            .station_list
+            .first()
+            .and_then(|station| minutes_of_day(&station.departure_time));
+        match (departure, self.journey_duration()) {
+            (Some(departure), Some(journey)) => departure + journey.total_minutes() >= 24 * 60,
+            _ => false,
+        }
+    }
+    /// Positions of stations `from` and `to` in `station_list`. `None` when the train doesn't
+    /// stop at both in that order.
+    fn stops_between(&self, from: &str, to: &str) -> Option<(usize, usize)> {
+        let position = |code: &str| {
+            self.station_list
+                .iter()
+                .position(|station| station.station_code.eq_ignore_ascii_case(code))
+        };
+        let (start, end) = (position(from)?, position(to)?);
+        (start < end).then_some((start, end))
+    }
+    /// Time from the departure at station `from` to the arrival at station `to`. `None` when
+    /// the train doesn't stop at both in that order.
+    pub fn duration_between(&self, from: &str, to: &str) -> Option<TravelDuration> {
+        let (start, end) = self.stops_between(from, to)?;
+        let mut stops = self.station_list[start..=end].to_vec();
+        stops[0].arrival_time.clear();
+        stops[end - start].departure_time.clear();
+        elapsed(&stops)
+    }
+    /// Sum of the distances of the legs between stations `from` and `to`. `None` when the train
+    /// doesn't stop at both in that order or a distance is missing.
+    pub fn journey_distance_km(&self, from: &str, to: &str) -> Option<u32> {
+        let (start, end) = self.stops_between(from, to)?;
+        self.station_list[start..=end]
+            .windows(2)
+            .map(|leg| leg[1].distance?.checked_sub(leg[0].distance?))
+            .sum()
+    }
+}
+
+/// Time from the first to the last clock time of `stops`. A clock time earlier than the previous
+/// one is taken as the next day.
+fn elapsed(stops: &[StationArrival]) -> Option<TravelDuration> {
+    let times: Vec<u32> = stops
+        .iter()
+        .flat_map(|station| [&station.arrival_time, &station.departure_time])
+        .filter_map(|time| minutes_of_day(time))
+        .collect();
+    // The first arrival and last departure are placeholders like "--" and get skipped
+    let mut minutes = 0;
+    for pair in times.windows(2) {
+        minutes += (pair[1] + 24 * 60 - pair[0]) % (24 * 60);
+    }
+    (times.len() >= 2).then_some(TravelDuration::new_from_minutes(minutes))
+}
+
+fn minutes_of_day(time: &str) -> Option<u32> {
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+        .ok()?;
+    Some(time.hour() * 60 + time.minute())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StationArrival {
     pub station_code: String,
     pub station_name: String,
     pub arrival_time: String,
     pub departure_time: String,
     pub halt_time: String,
+    ///Km from the first station of the train
+    #[serde(default, deserialize_with = "distance_km")]
+    pub distance: Option<u32>,
+}
+
+/// IRCTC sends distances as numbers or strings like `"264"`. Anything else is taken as unknown.
+fn distance_km<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(km) => km.as_u64().and_then(|km| u32::try_from(km).ok()),
+        Value::String(km) => km.trim().parse().ok(),
+        _ => None,
+    })
 }
 
 #[derive(Deserialize)]
@@ -121,11 +669,130 @@ struct TrainDetailsResponse {
     data: TrainDetailsData,
 }
 
+/// IRCTC sends flags as booleans, `"Y"`/`"N"` or `1`/`0`. Anything else is taken as `false`.
+pub(crate) fn flag<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(flag) => flag,
+        Value::Number(flag) => flag.as_u64() == Some(1),
+        Value::String(flag) => matches!(
+            flag.trim().to_ascii_uppercase().as_str(),
+            "Y" | "YES" | "TRUE" | "1"
+        ),
+        _ => false,
+    })
+}
+
 #[derive(Deserialize)]
 struct TrainDetailsData {
     train_number: String,
     train_name: String,
     station_list: Vec<StationArrival>,
+    #[serde(default, deserialize_with = "distance_km")]
+    distance: Option<u32>,
+    #[serde(default, alias = "has_pantry", deserialize_with = "flag")]
+    pantry: bool,
+    #[serde(default, alias = "ac_waiting_lounge", deserialize_with = "flag")]
+    ac_lounge: bool,
+    #[serde(default)]
+    train_type: Option<String>,
+    #[serde(default, deserialize_with = "station_codes")]
+    pantry_stations: Vec<String>,
+}
+
+/// IRCTC sends pantry stations as a list of codes, a list of stations or a comma separated
+/// string. Anything else is taken as none.
+fn station_codes<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    let codes = match Value::deserialize(deserializer)? {
+        Value::String(codes) => codes.split(',').map(str::to_string).collect(),
+        Value::Array(stations) => stations
+            .into_iter()
+            .filter_map(|station| match station {
+                Value::String(code) => Some(code),
+                Value::Object(mut station) => match station.remove("station_code")? {
+                    Value::String(code) => Some(code),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(codes
+        .iter()
+        .map(|code| code.trim().to_ascii_uppercase())
+        .filter(|code| !code.is_empty())
+        .collect())
+}
+
+fn to_train_details(data: TrainDetailsData) -> TrainDetails {
+    let total_route_distance_km = data.distance.or_else(|| data.station_list.last()?.distance);
+    let facilities = [
+        (data.pantry, PANTRY_CAR_NOTE),
+        (data.ac_lounge, AC_LOUNGE_NOTE),
+    ]
+    .into_iter()
+    .filter(|(available, _)| *available)
+    .map(|(_, note)| note.to_string())
+    .collect();
+    let food_ordering_available = data.pantry
+        || !data.pantry_stations.is_empty()
+        || KNOWN_PANTRY_TRAINS.contains(&data.train_number.trim());
+    let mut details = TrainDetails {
+        train_number: data.train_number,
+        train_name: data.train_name,
+        station_list: data.station_list,
+        journey_time: None,
+        total_route_distance_km,
+        buffet_car_available: data.pantry,
+        ac_lounge_available: data.ac_lounge,
+        train_type_description: data
+            .train_type
+            .as_deref()
+            .map(train_type_description)
+            .unwrap_or_default(),
+        facilities,
+        food_ordering_available,
+        pantry_car_stations: data.pantry_stations,
+    };
+    details.journey_time = details
+        .journey_duration()
+        .map(TravelDuration::to_human_string);
+    if details.food_ordering_available && details.is_overnight() {
+        details.facilities.push(E_CATERING_NOTE.to_string());
+    }
+    details
+}
+
+async fn cached_train_details<F, Fut>(
+    train_number: &str,
+    fetch: F,
+) -> Result<TrainDetails, Box<dyn std::error::Error + Send + Sync>>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<TrainDetails, Box<dyn std::error::Error + Send + Sync>>>,
+{
+    {
+        let cache = TRAIN_DETAILS_CACHE.read().await;
+        if let Some((details, fetched_at)) = cache.get(train_number)
+            && fetched_at.elapsed() < TRAIN_DETAILS_TTL
+        {
+            return Ok(details.clone());
+        }
+    }
+
+    // Fetched without holding the lock so lookups of other trains aren't kept waiting. A
+    // concurrent lookup of the same train may have cached it meanwhile, which is kept.
+    let details = fetch().await?;
+    let mut cache = TRAIN_DETAILS_CACHE.write().await;
+    if let Some((cached, fetched_at)) = cache.get(train_number)
+        && fetched_at.elapsed() < TRAIN_DETAILS_TTL
+    {
+        return Ok(cached.clone());
+    }
+    cache.insert(train_number.to_string(), (details.clone(), Instant::now()));
+    Ok(details)
 }
 
 #[gemini_function]
@@ -134,24 +801,115 @@ pub async fn train_details(
     ///Train number (e.g., '12002')
     train_number: String,
 ) -> Result<TrainDetails, Box<dyn std::error::Error + Send + Sync>> {
-    let url = format!(
-        "https://irctc1.p.rapidapi.com/api/v1/getTrainDetails?trainNo={}",
-        train_number
+    let client = RapidAPIClient::for_host(IRCTC_HOST)?;
+    cached_train_details(&train_number, || {
+        fetch_train_details(&client, &train_number)
+    })
+    .await
+}
+
+async fn fetch_train_details(
+    client: &impl ApiClient,
+    train_number: &str,
+) -> Result<TrainDetails, Box<dyn std::error::Error + Send + Sync>> {
+    let path = format!(
+        "/api/v1/getTrainDetails?trainNo={}",
+        sanitize_query_param(train_number, MAX_CODE_LEN)?
     );
+    let body: TrainDetailsResponse = client.get(&path).await?;
+    Ok(to_train_details(body.data))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrainClass {
+    #[serde(rename = "1A")]
+    FirstAc,
+    #[serde(rename = "2A")]
+    SecondAc,
+    #[serde(rename = "3A")]
+    ThirdAc,
+    #[serde(rename = "3E")]
+    ThirdAcEconomy,
+    #[serde(rename = "SL")]
+    Sleeper,
+    #[serde(rename = "CC")]
+    AcChairCar,
+    #[serde(rename = "EC")]
+    ExecutiveChairCar,
+    #[serde(rename = "2S")]
+    SecondSitting,
+}
+
+impl GeminiSchema for TrainClass {
+    fn gemini_schema() -> serde_json::Value {
+        json!({
+            "type": "STRING",
+            "description": "IRCTC class code",
+            "enum": ["1A", "2A", "3A", "3E", "SL", "CC", "EC", "2S"]
+        })
+    }
+}
 
-    let client = reqwest::Client::new();
-    let resp = client.get(url).headers(get_headers()).send().await?;
+impl TrainClass {
+    pub const ALL: [TrainClass; 8] = [
+        TrainClass::FirstAc,
+        TrainClass::SecondAc,
+        TrainClass::ThirdAc,
+        TrainClass::ThirdAcEconomy,
+        TrainClass::Sleeper,
+        TrainClass::AcChairCar,
+        TrainClass::ExecutiveChairCar,
+        TrainClass::SecondSitting,
+    ];
 
-    if !resp.status().is_success() {
-        return Err(format!("RapidAPI error: {}", resp.status()).into());
+    pub fn code(&self) -> &'static str {
+        match self {
+            TrainClass::FirstAc => "1A",
+            TrainClass::SecondAc => "2A",
+            TrainClass::ThirdAc => "3A",
+            TrainClass::ThirdAcEconomy => "3E",
+            TrainClass::Sleeper => "SL",
+            TrainClass::AcChairCar => "CC",
+            TrainClass::ExecutiveChairCar => "EC",
+            TrainClass::SecondSitting => "2S",
+        }
     }
+}
 
-    let body: TrainDetailsResponse = resp.json().await?;
-    Ok(TrainDetails {
-        train_number: body.data.train_number,
-        train_name: body.data.train_name,
-        station_list: body.data.station_list,
-    })
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quota {
+    #[serde(rename = "GN")]
+    General,
+    #[serde(rename = "TQ")]
+    Tatkal,
+    #[serde(rename = "PT")]
+    PremiumTatkal,
+    #[serde(rename = "LD")]
+    Ladies,
+    #[serde(rename = "SS")]
+    SeniorCitizen,
+}
+
+impl GeminiSchema for Quota {
+    fn gemini_schema() -> serde_json::Value {
+        json!({
+            "type": "STRING",
+            "description": "IRCTC quota code, 'GN' for general",
+            "enum": ["GN", "TQ", "PT", "LD", "SS"]
+        })
+    }
+}
+
+impl Quota {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Quota::General => "GN",
+            Quota::Tatkal => "TQ",
+            Quota::PremiumTatkal => "PT",
+            Quota::Ladies => "LD",
+            Quota::SeniorCitizen => "SS",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -160,17 +918,174 @@ pub struct SeatAvailability {
     pub class: String,
     pub quota: String,
     pub availability: Vec<AvailabilityDetail>,
+    ///Waiting list position on the journey date
+    #[serde(default)]
+    pub waitlist_position: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AvailabilityDetail {
     pub date: String,
-    pub status: String,
+    ///IRCTC sends it as `status`, e.g. 'AVAILABLE-0042' or 'GNWL#45/WL#30'
+    #[serde(alias = "status", deserialize_with = "seat_status")]
+    pub seat_status: SeatStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SeatStatus {
+    ///Seats left. `None` when IRCTC doesn't give the count
+    Available(Option<u32>),
+    ///Current waiting list position
+    WaitingList(u32),
+    ///Current RAC position, travelling on a shared berth until a berth is freed
+    ReservationAgainstCancellation(u32),
+    ///No more tickets are sold, e.g. 'REGRET' or 'NOT AVAILABLE'
+    NotAvailable,
+    ///The train doesn't have the class
+    NotApplicable,
+    Unknown(String),
+}
+
+/// Statuses look like `AVAILABLE-0042`, `AVBL 7`, `WL#34`, `RAC-12` or `GNWL#45/WL#30`. With a
+/// `/` the part after it is the current status, the part before the one at booking.
+impl From<&str> for SeatStatus {
+    fn from(status: &str) -> Self {
+        let upper = status.trim().to_ascii_uppercase();
+        let current = upper.rsplit('/').next().unwrap_or_default().trim();
+        let number = |rest: &str| rest.trim_start_matches(['-', ' ', '#']).parse::<u32>().ok();
+        if upper.starts_with("NOT AVAILABLE")
+            || upper.starts_with("NOT AVBL")
+            || upper.starts_with("REGRET")
+        {
+            return SeatStatus::NotAvailable;
+        }
+        if let Some(rest) = ["AVAILABLE", "CURR_AVBL", "AVBL"]
+            .iter()
+            .find_map(|prefix| current.strip_prefix(prefix))
+        {
+            return match rest.trim() {
+                "" => SeatStatus::Available(None),
+                _ => number(rest).map_or_else(
+                    || SeatStatus::Unknown(status.trim().to_string()),
+                    |count| SeatStatus::Available(Some(count)),
+                ),
+            };
+        }
+        if let Some(rest) = current.strip_prefix("RAC")
+            && let Some(position) = number(rest)
+        {
+            return SeatStatus::ReservationAgainstCancellation(position);
+        }
+        // GNWL, PQWL, RLWL, TQWL and plain WL all end in WL before the position
+        if let Some((_, rest)) = current.split_once("WL")
+            && let Some(position) = number(rest)
+        {
+            return SeatStatus::WaitingList(position);
+        }
+        SeatStatus::Unknown(status.trim().to_string())
+    }
+}
+
+impl SeatStatus {
+    pub fn waitlist_position(&self) -> Option<u32> {
+        match self {
+            SeatStatus::WaitingList(position) => Some(*position),
+            _ => None,
+        }
+    }
+}
+
+/// Parses IRCTC's text status, and takes a `SeatStatus` as serialized as is.
+fn seat_status<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<SeatStatus, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(status) => Ok(SeatStatus::from(status.as_str())),
+        status => SeatStatus::deserialize(status).map_err(serde::de::Error::custom),
+    }
+}
+
+impl AvailabilityDetail {
+    /// Seats left when bookable right away, e.g. 42 for `AVAILABLE-0042` or `AVBL 42`. `None`
+    /// when waitlisted, under RAC, not available or the count isn't given.
+    pub fn available_seats(&self) -> Option<u32> {
+        match self.seat_status {
+            SeatStatus::Available(count) => count,
+            _ => None,
+        }
+    }
+    /// Dates are sent as `23-1-2026`.
+    fn is_on(&self, date: &Date) -> bool {
+        let mut parts = self.date.trim().split('-').map(str::parse::<u16>);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(day)), Some(Ok(month)), Some(Ok(year))) => {
+                Date::new(year, month as u8, day as u8).is_ok_and(|d| d == *date)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl SeatAvailability {
+    /// For a class the train doesn't have.
+    fn not_applicable(train_number: &str, class: &str, quota: &str, date: &Date) -> Self {
+        SeatAvailability {
+            train_number: train_number.to_string(),
+            class: class.to_string(),
+            quota: quota.to_string(),
+            availability: vec![AvailabilityDetail {
+                date: date
+                    .to_naive_date()
+                    .map(|date| date.format("%-d-%-m-%Y").to_string())
+                    .unwrap_or_default(),
+                seat_status: SeatStatus::NotApplicable,
+            }],
+            waitlist_position: None,
+        }
+    }
+    pub fn is_applicable(&self) -> bool {
+        self.availability
+            .iter()
+            .all(|detail| detail.seat_status != SeatStatus::NotApplicable)
+    }
+    /// Seats left on `date`. RapidAPI also sends the days after the journey, so `None` when
+    /// no entry is for `date`.
+    pub fn available_seats_on(&self, date: &Date) -> Option<u32> {
+        self.availability
+            .iter()
+            .find(|detail| detail.is_on(date))?
+            .available_seats()
+    }
+    /// Waiting list position on `date`, picked like `available_seats_on`.
+    pub fn waitlist_position_on(&self, date: &Date) -> Option<u32> {
+        self.availability
+            .iter()
+            .find(|detail| detail.is_on(date))?
+            .seat_status
+            .waitlist_position()
+    }
 }
 
+/// Errors come back with `status: false`, a `message` and no `data`.
 #[derive(Deserialize)]
 struct SeatAvailabilityResponse {
-    data: Vec<AvailabilityDetail>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    data: Option<Vec<AvailabilityDetail>>,
+}
+
+/// IRCTC's answer for a class the train doesn't run, e.g. `Class 1A is not available in this
+/// train` or `Class does not exist for this train`.
+fn is_class_not_available(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("class")
+        && [
+            "not available",
+            "not exist",
+            "does not exist",
+            "doesn't exist",
+        ]
+        .iter()
+        .any(|phrase| message.contains(phrase))
 }
 
 #[gemini_function]
@@ -185,29 +1100,343 @@ pub async fn train_seats_available(
     ///Quota code (e.g., 'GN', 'TQ')
     quota: String,
 ) -> Result<SeatAvailability, Box<dyn std::error::Error + Send + Sync>> {
-    let url = format!(
-        "https://irctc1.p.rapidapi.com/api/v1/checkSeatAvailability?classCode={}&quotaCode={}&trainNo={}&dateOfJourney={}&fromStationCode={}&toStationCode={}",
-        class,
-        quota,
-        train_number,
+    seats_available(
+        &RapidAPIClient::for_host(IRCTC_HOST)?,
+        &train_number,
+        &from_station,
+        &to_station,
+        &date,
+        &class,
+        &quota,
+    )
+    .await
+}
+
+async fn seats_available(
+    client: &impl ApiClient,
+    train_number: &str,
+    from_station: &Station,
+    to_station: &Station,
+    date: &Date,
+    class: &str,
+    quota: &str,
+) -> Result<SeatAvailability, Box<dyn std::error::Error + Send + Sync>> {
+    let path = format!(
+        "/api/v1/checkSeatAvailability?classCode={}&quotaCode={}&trainNo={}&dateOfJourney={}&fromStationCode={}&toStationCode={}",
+        sanitize_query_param(class, MAX_CODE_LEN)?,
+        sanitize_query_param(quota, MAX_CODE_LEN)?,
+        sanitize_query_param(train_number, MAX_CODE_LEN)?,
         date.to_yyyy_mm_dd(),
-        from_station,
-        to_station
+        sanitize_query_param(&from_station.to_string(), MAX_CODE_LEN)?,
+        sanitize_query_param(&to_station.to_string(), MAX_CODE_LEN)?
     );
+    let body: SeatAvailabilityResponse = client.get(&path).await?;
+    let Some(availability) = body.data else {
+        let message = body.message.unwrap_or_default();
+        if is_class_not_available(&message) {
+            return Ok(SeatAvailability::not_applicable(
+                train_number,
+                class,
+                quota,
+                date,
+            ));
+        }
+        return Err(TravelError::Api(format!("IRCTC error: {message}")).into());
+    };
+    let mut seats = SeatAvailability {
+        train_number: train_number.to_string(),
+        class: class.to_string(),
+        quota: quota.to_string(),
+        availability,
+        waitlist_position: None,
+    };
+    seats.waitlist_position = seats.waitlist_position_on(date);
+    Ok(seats)
+}
 
-    let client = reqwest::Client::new();
-    let resp = client.get(url).headers(get_headers()).send().await?;
+/// Checks every `TrainClass` with `check`, at most `MAX_CONCURRENT_CLASS_CHECKS` at a time.
+/// Classes the train doesn't have come back `NotApplicable` from `check`, so any error fails
+/// the whole check.
+async fn all_class_availability<F, Fut>(
+    train_number: &str,
+    check: F,
+) -> Result<HashMap<TrainClass, SeatAvailability>, TravelError>
+where
+    F: Fn(TrainClass) -> Fut,
+    Fut: Future<Output = Result<SeatAvailability, Box<dyn std::error::Error + Send + Sync>>>,
+{
+    let (permits, check) = (&Semaphore::new(MAX_CONCURRENT_CLASS_CHECKS), &check);
+    let checks = join_all(TrainClass::ALL.map(|class| async move {
+        let _permit = permits.acquire().await.expect("Semaphore is never closed");
+        (class, check(class).await)
+    }))
+    .await;
+    checks
+        .into_iter()
+        .map(|(class, seats)| {
+            let seats = seats.map_err(|e| match e.downcast::<TravelError>() {
+                Ok(e) => *e,
+                Err(e) => TravelError::Api(format!(
+                    "Could not check {} seats of train {train_number}: {e}",
+                    class.code()
+                )),
+            })?;
+            Ok((class, seats))
+        })
+        .collect()
+}
 
-    if !resp.status().is_success() {
-        return Err(format!("RapidAPI error: {}", resp.status()).into());
+pub async fn train_seats_available_all_classes(
+    train_number: &str,
+    from: &Station,
+    to: &Station,
+    date: &Date,
+    quota: Quota,
+) -> Result<HashMap<TrainClass, SeatAvailability>, TravelError> {
+    let client = &RapidAPIClient::for_host(IRCTC_HOST)?;
+    all_class_availability(train_number, |class| async move {
+        seats_available(
+            client,
+            train_number,
+            from,
+            to,
+            date,
+            class.code(),
+            quota.code(),
+        )
+        .await
+    })
+    .await
+}
+
+#[gemini_function]
+///Check seat availability of a train in every class at once. Classes the train doesn't have are 'NotApplicable'. Use it when the user hasn't picked a class.
+pub async fn train_all_class_availability(
+    ///Train number (e.g., '12002')
+    train_number: String,
+    from_station: Station,
+    to_station: Station,
+    date: Date,
+    quota: Quota,
+) -> Result<HashMap<TrainClass, SeatAvailability>, TravelError> {
+    train_seats_available_all_classes(&train_number, &from_station, &to_station, &date, quota).await
+}
+
+#[derive(Serialize, Debug)]
+pub struct TrainWithAvailability {
+    pub train: Train,
+    ///Availability by class code. Classes the train doesn't have or that couldn't be checked
+    ///are left out
+    pub availability: HashMap<TrainClass, SeatAvailability>,
+}
+
+/// Checks every class of the first `MAX_TRAINS_WITH_AVAILABILITY` trains with `check`, given
+/// the train number, as `permits` allow. Searches share `permits` to keep to
+/// `MAX_CONCURRENT_AVAILABILITY_CALLS` between them. Failed checks are logged and left out.
+async fn with_availability<F, Fut>(
+    trains: Vec<Train>,
+    classes: &[TrainClass],
+    permits: &Semaphore,
+    check: F,
+) -> Vec<TrainWithAvailability>
+where
+    F: Fn(String, TrainClass) -> Fut,
+    Fut: Future<Output = Result<SeatAvailability, Box<dyn std::error::Error + Send + Sync>>>,
+{
+    let check = &check;
+    let checks = trains
+        .iter()
+        .take(MAX_TRAINS_WITH_AVAILABILITY)
+        .flat_map(|train| {
+            classes.iter().map(move |class| async move {
+                let _permit = permits.acquire().await.expect("Semaphore is never closed");
+                let train_number = train.train_number.clone();
+                let availability = check(train_number.clone(), *class).await.inspect_err(|e| {
+                    eprintln!(
+                        "ERROR: Could not check {} seats of train {train_number}:\n{e}",
+                        class.code()
+                    )
+                });
+                (train_number, *class, availability)
+            })
+        });
+    let mut availability: HashMap<String, HashMap<TrainClass, SeatAvailability>> = HashMap::new();
+    for (train_number, class, seats) in join_all(checks).await {
+        if let Ok(seats) = seats
+            && seats.is_applicable()
+        {
+            availability
+                .entry(train_number)
+                .or_default()
+                .insert(class, seats);
+        }
     }
+    trains
+        .into_iter()
+        .map(|train| TrainWithAvailability {
+            availability: availability.remove(&train.train_number).unwrap_or_default(),
+            train,
+        })
+        .collect()
+}
 
-    let body: SeatAvailabilityResponse = resp.json().await?;
-    Ok(SeatAvailability {
-        train_number: train_number.to_string(),
-        class: class.to_string(),
-        quota: quota.to_string(),
-        availability: body.data,
+#[gemini_function]
+///Search for trains between two stations along with their seat availability in the given classes. Only the first 10 trains by departure have their seats checked. Prefer it over calling `train_seats_available` for every train.
+pub async fn trains_between_with_availability(
+    ///Source station code (e.g., 'NDLS')
+    source: Station,
+    ///Destination station code (e.g., 'BCT')
+    destination: Station,
+    date: Date,
+    ///Classes the user is interested in (e.g., ['3A', 'SL'])
+    classes: Vec<TrainClass>,
+    quota: Quota,
+) -> Result<Vec<TrainWithAvailability>, TravelError> {
+    let mut unique = Vec::new();
+    for class in classes {
+        if !unique.contains(&class) {
+            unique.push(class);
+        }
+    }
+    if unique.is_empty() {
+        return Err(TravelError::InvalidInput(
+            "At least one class is needed".into(),
+        ));
+    }
+    let trains = trains_between(source.clone(), destination.clone(), date.clone(), false)
+        .await
+        .map_err(|e| TravelError::Api(e.to_string()))?
+        .running_on(&date);
+    let client = &RapidAPIClient::for_host(IRCTC_HOST)?;
+    let (source, destination, date) = (&source, &destination, &date);
+    let permits = &Semaphore::new(MAX_CONCURRENT_AVAILABILITY_CALLS);
+    Ok(
+        with_availability(trains, &unique, permits, |train_number, class| async move {
+            seats_available(
+                client,
+                &train_number,
+                source,
+                destination,
+                date,
+                class.code(),
+                quota.code(),
+            )
+            .await
+        })
+        .await,
+    )
+}
+
+#[derive(Serialize, Debug)]
+pub struct FlexibleTrainResult {
+    pub best_date: Date,
+    pub train: Train,
+    pub availability: SeatAvailability,
+    ///Train with the most seats on each of the other dates that have any, by date
+    pub alternatives: Vec<(Date, SeatAvailability)>,
+}
+
+/// Picks the train with the most available seats across every date. `None` when no train has
+/// a seat on any date.
+fn best_availability(
+    by_date: Vec<(Date, Vec<TrainWithAvailability>)>,
+    class: TrainClass,
+) -> Option<FlexibleTrainResult> {
+    let mut best_by_date: Vec<(Date, Train, SeatAvailability, u32)> = by_date
+        .into_iter()
+        .filter_map(|(date, trains)| {
+            let (train, availability, seats) = trains
+                .into_iter()
+                .filter_map(|mut train| {
+                    let availability = train.availability.remove(&class)?;
+                    let seats = availability.available_seats_on(&date)?;
+                    Some((train.train, availability, seats))
+                })
+                .max_by_key(|(.., seats)| *seats)?;
+            Some((date, train, availability, seats))
+        })
+        .collect();
+    let best = best_by_date
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, (.., seats))| (*seats, std::cmp::Reverse(*i)))?
+        .0;
+    let (best_date, train, availability, _) = best_by_date.remove(best);
+    Some(FlexibleTrainResult {
+        best_date,
+        train,
+        availability,
+        alternatives: best_by_date
+            .into_iter()
+            .map(|(date, _, availability, _)| (date, availability))
+            .collect(),
+    })
+}
+
+#[gemini_function]
+///Find the date with the most available seats when the user's travel date is flexible, e.g. "any day next week". Searches every train between the stations on each date.
+pub async fn trains_between_flexible(
+    ///Source station code (e.g., 'NDLS')
+    source: Station,
+    ///Destination station code (e.g., 'BCT')
+    destination: Station,
+    ///Earliest date the user can travel
+    from_date: Date,
+    ///Number of dates to search from `from_date` (1-7)
+    days: u8,
+    class: TrainClass,
+    quota: Quota,
+) -> Result<FlexibleTrainResult, TravelError> {
+    if !(1..=MAX_FLEXIBLE_DAYS).contains(&days) {
+        return Err(TravelError::InvalidInput(format!(
+            "Days must be between 1 and {MAX_FLEXIBLE_DAYS}. Found: {days}"
+        )));
+    }
+    let dates: Vec<Date> = (0..u64::from(days))
+        .map_while(|offset| from_date.add_days(offset))
+        .collect();
+    let client = &RapidAPIClient::for_host(IRCTC_HOST)?;
+    let (source, destination) = (&source, &destination);
+    // One limit for all dates, each date searching on its own would multiply it
+    let permits = &Semaphore::new(MAX_CONCURRENT_AVAILABILITY_CALLS);
+    let searches = dates.into_iter().map(|date| async move {
+        let trains = trains_between(source.clone(), destination.clone(), date.clone(), false)
+            .await
+            .inspect_err(|e| {
+                eprintln!(
+                    "ERROR: Could not search trains on {}:\n{e}",
+                    date.to_yyyy_mm_dd()
+                )
+            })
+            .unwrap_or_default()
+            .running_on(&date);
+        let date_ref = &date;
+        let trains = with_availability(
+            trains,
+            &[class],
+            permits,
+            |train_number, class| async move {
+                seats_available(
+                    client,
+                    &train_number,
+                    source,
+                    destination,
+                    date_ref,
+                    class.code(),
+                    quota.code(),
+                )
+                .await
+            },
+        )
+        .await;
+        (date, trains)
+    });
+    best_availability(join_all(searches).await, class).ok_or_else(|| {
+        TravelError::Api(format!(
+            "No train has {} seats available in the {days} days from {}",
+            class.code(),
+            from_date.to_yyyy_mm_dd()
+        ))
     })
 }
 
@@ -218,8 +1447,958 @@ async fn trains_between_test() {
             Station::new("NDLS".into()).unwrap(),
             Station::new("BCT".into()).unwrap(),
             Date::new(2026, 1, 23).unwrap(),
+            false,
         )
         .await
         .unwrap()
     );
 }
+
+#[test]
+fn filter_trains_by_date_test() {
+    let train = |number: &str, run_days: &[&str]| Train {
+        train_number: number.into(),
+        train_name: "Test Express".into(),
+        from_sta: "NDLS".into(),
+        to_sta: "BCT".into(),
+        run_days: run_days.iter().map(|d| d.to_string()).collect(),
+        train_type: "EXP".into(),
+        category: None,
+        journey_minutes: None,
+        journey_distance_km: None,
+    };
+    let sunday = Date::new(2026, 2, 15).unwrap();
+    let friday = Date::new(2026, 1, 23).unwrap();
+    let thursday = Date::new(2024, 2, 29).unwrap();
+
+    let daily = train("12951", &["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]);
+    let weekend = train("12953", &["sat", "SUN"]);
+    assert!(daily.runs_on(&sunday) && daily.runs_on(&thursday));
+    assert!(weekend.runs_on(&sunday));
+    assert!(!weekend.runs_on(&friday));
+    assert!(!daily.runs_on(&Date::new(2026, 2, 30).unwrap()));
+
+    let trains = TrainsBetween(vec![daily, weekend]);
+    let numbers = |trains: Vec<&Train>| -> Vec<String> {
+        trains
+            .into_iter()
+            .map(|train| train.train_number.clone())
+            .collect()
+    };
+    assert_eq!(numbers(trains.filter_by_run_day(Weekday::Fri)), ["12951"]);
+    assert_eq!(
+        numbers(trains.filter_by_run_day(Weekday::Sat)),
+        ["12951", "12953"]
+    );
+    assert_eq!(trains.running_on(&sunday).len(), 2);
+    assert!(
+        trains
+            .running_on(&Date::new(2026, 2, 30).unwrap())
+            .is_empty()
+    );
+
+    let trains = filter_trains_by_date(trains.0, &friday);
+    assert_eq!(trains.len(), 1);
+    assert_eq!(trains[0].train_number, "12951");
+}
+
+#[test]
+fn next_departure_test() {
+    let train = |number: &str, from_sta: &str, run_days: &[&str]| Train {
+        train_number: number.into(),
+        train_name: "Test Express".into(),
+        from_sta: from_sta.into(),
+        to_sta: "BCT".into(),
+        run_days: run_days.iter().map(|d| d.to_string()).collect(),
+        train_type: "EXP".into(),
+        category: None,
+        journey_minutes: None,
+        journey_distance_km: None,
+    };
+    let wednesday_only = train("12951", "16:30", &["Wed"]);
+    // 2026-02-16 is a Monday, 2026-02-18 a Wednesday and 2026-02-25 the Wednesday after
+    let expected = [18, 18, 18, 25, 25, 25, 25];
+    for (offset, day) in expected.into_iter().enumerate() {
+        let from = Date::new(2026, 2, 16 + offset as u8).unwrap();
+        assert_eq!(
+            wednesday_only.next_departure_on_or_after(&from),
+            Date::new(2026, 2, day).ok(),
+            "starting {from:?}"
+        );
+    }
+    let month_end = Date::new(2026, 1, 29).unwrap();
+    assert_eq!(
+        wednesday_only.next_departure_on_or_after(&month_end),
+        Date::new(2026, 2, 4).ok()
+    );
+    let no_run_days = train("12953", "17:00", &["Daily"]);
+    assert_eq!(no_run_days.next_departure_on_or_after(&month_end), None);
+
+    let sunday = Date::new(2026, 2, 15).unwrap();
+    let weekend = train("12955", "18:40", &["Sat", "Sun"]);
+    let early_wednesday = train("12957", "06:10", &["Wed"]);
+    let next = next_available_trains(
+        vec![wednesday_only, no_run_days, weekend, early_wednesday],
+        &sunday,
+    );
+    let numbers: Vec<&str> = next.iter().map(|n| n.train.train_number.as_str()).collect();
+    assert_eq!(numbers, ["12955", "12957", "12951"]);
+    assert_eq!(next[0].next_departure, sunday);
+}
+
+#[tokio::test]
+async fn train_details_cache_test() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let details = |train_number: &str| TrainDetails {
+        train_number: train_number.into(),
+        train_name: "Cache Test Express".into(),
+        station_list: vec![],
+        journey_time: None,
+        total_route_distance_km: None,
+        buffet_car_available: false,
+        ac_lounge_available: false,
+        train_type_description: String::new(),
+        facilities: Vec::new(),
+        pantry_car_stations: Vec::new(),
+        food_ordering_available: false,
+    };
+    let calls = AtomicUsize::new(0);
+    let fetch = || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(details("CACHE1"))
+    };
+
+    let first = cached_train_details("CACHE1", fetch).await.unwrap();
+    let second = cached_train_details("CACHE1", fetch).await.unwrap();
+    assert_eq!(first.train_name, second.train_name);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // A slow fetch doesn't hold up the lookup of another train
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    let slow = cached_train_details("CACHE2", || async {
+        released.await.ok();
+        Ok(details("CACHE2"))
+    });
+    let fast = async {
+        let fast = cached_train_details("CACHE3", || async { Ok(details("CACHE3")) }).await;
+        release.send(()).unwrap();
+        fast
+    };
+    let (slow, fast) =
+        tokio::time::timeout(Duration::from_secs(5), async { tokio::join!(slow, fast) })
+            .await
+            .unwrap();
+    assert_eq!(slow.unwrap().train_number, "CACHE2");
+    assert_eq!(fast.unwrap().train_number, "CACHE3");
+}
+
+#[test]
+fn journey_duration_test() {
+    let station = |code: &str, arrival_time: &str, departure_time: &str| StationArrival {
+        station_code: code.into(),
+        station_name: code.into(),
+        arrival_time: arrival_time.into(),
+        departure_time: departure_time.into(),
+        halt_time: "5m".into(),
+        distance: None,
+    };
+    let mut details = TrainDetails {
+        train_number: "12951".into(),
+        train_name: "Mumbai Rajdhani".into(),
+        station_list: vec![
+            station("BCT", "--", "17:00"),
+            station("ST", "21:08", "21:13"),
+            station("KOTA", "04:15", "04:25"),
+            station("NDLS", "08:32", "--"),
+        ],
+        journey_time: None,
+        total_route_distance_km: None,
+        buffet_car_available: false,
+        ac_lounge_available: false,
+        train_type_description: String::new(),
+        facilities: Vec::new(),
+        pantry_car_stations: Vec::new(),
+        food_ordering_available: false,
+    };
+    assert_eq!(
+        details.journey_duration(),
+        Some(TravelDuration::new_from_minutes(15 * 60 + 32))
+    );
+
+    assert_eq!(
+        details.duration_between("st", "KOTA"),
+        Some(TravelDuration::new_from_minutes(7 * 60 + 2))
+    );
+    assert_eq!(
+        details.duration_between("BCT", "NDLS"),
+        details.journey_duration()
+    );
+    assert!(details.duration_between("KOTA", "ST").is_none());
+    assert!(details.duration_between("ST", "HWH").is_none());
+
+    details.station_list.truncate(1);
+    assert!(details.journey_duration().is_none());
+}
+
+#[test]
+fn train_distance_test() {
+    let response: TrainDetailsResponse = serde_json::from_value(json!({
+        "status": true,
+        "data": {
+            "train_number": "12951",
+            "train_name": "Mumbai Rajdhani",
+            "distance": 1386,
+            "station_list": [
+                {"station_code": "BCT", "station_name": "Mumbai Central", "arrival_time": "--", "departure_time": "17:00", "halt_time": "--", "distance": "0"},
+                {"station_code": "ST", "station_name": "Surat", "arrival_time": "21:08", "departure_time": "21:13", "halt_time": "5m", "distance": "263"},
+                {"station_code": "KOTA", "station_name": "Kota Jn", "arrival_time": "04:15", "departure_time": "04:25", "halt_time": "10m", "distance": 921},
+                {"station_code": "NDLS", "station_name": "New Delhi", "arrival_time": "08:32", "departure_time": "--", "halt_time": "--", "distance": "1386"}
+            ]
+        }
+    }))
+    .unwrap();
+    let mut details = to_train_details(response.data);
+    assert_eq!(details.total_route_distance_km, Some(1386));
+    assert_eq!(details.journey_time.as_deref(), Some("15h 32m"));
+    assert_eq!(details.journey_distance_km("ST", "kota"), Some(658));
+    assert_eq!(details.journey_distance_km("BCT", "NDLS"), Some(1386));
+    assert_eq!(details.journey_distance_km("NDLS", "ST"), None);
+    assert_eq!(details.journey_distance_km("ST", "HWH"), None);
+
+    // Cached details keep their distances
+    let cached: TrainDetails =
+        serde_json::from_value(serde_json::to_value(&details).unwrap()).unwrap();
+    assert_eq!(cached.journey_distance_km("ST", "KOTA"), Some(658));
+
+    details.station_list[2].distance = None;
+    assert_eq!(details.journey_distance_km("BCT", "NDLS"), None);
+    assert_eq!(details.journey_distance_km("BCT", "ST"), Some(263));
+
+    let without_total: TrainDetailsData = serde_json::from_value(json!({
+        "train_number": "12951",
+        "train_name": "Mumbai Rajdhani",
+        "station_list": [
+            {"station_code": "BCT", "station_name": "Mumbai Central", "arrival_time": "--", "departure_time": "17:00", "halt_time": "--", "distance": "0"},
+            {"station_code": "NDLS", "station_name": "New Delhi", "arrival_time": "08:32", "departure_time": "--", "halt_time": "--", "distance": "1386"}
+        ]
+    }))
+    .unwrap();
+    assert_eq!(
+        to_train_details(without_total).total_route_distance_km,
+        Some(1386)
+    );
+}
+
+#[test]
+fn train_facilities_test() {
+    let details = |extra: Value| {
+        let mut data = json!({
+            "train_number": "12951",
+            "train_name": "Mumbai Rajdhani",
+            "station_list": []
+        });
+        data.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        to_train_details(serde_json::from_value(data).unwrap())
+    };
+
+    let rajdhani = details(json!({"pantry": true, "ac_lounge": "Y", "train_type": "RAJ"}));
+    assert!(rajdhani.buffet_car_available);
+    assert!(rajdhani.ac_lounge_available);
+    assert_eq!(rajdhani.facilities, [PANTRY_CAR_NOTE, AC_LOUNGE_NOTE]);
+    assert!(
+        rajdhani
+            .train_type_description
+            .starts_with("Rajdhani Express")
+    );
+    let json = serde_json::to_value(&rajdhani).unwrap();
+    assert_eq!(json["facilities"][0], "🍽️ Pantry car available");
+
+    let local = details(json!({"has_pantry": "N", "ac_lounge": 0, "train_type": " memu "}));
+    assert!(!local.buffet_car_available && !local.ac_lounge_available);
+    assert!(local.facilities.is_empty());
+    assert_eq!(local.train_type_description, "Mainline local train");
+    let json = serde_json::to_value(&local).unwrap();
+    assert!(json.get("facilities").is_none());
+
+    let express = details(json!({"has_pantry": 1, "train_type": "XYZ"}));
+    assert_eq!(express.facilities, [PANTRY_CAR_NOTE]);
+    assert_eq!(express.train_type_description, "XYZ");
+    let unknown = details(json!({"pantry": null}));
+    assert!(!unknown.buffet_car_available);
+    assert_eq!(unknown.train_type_description, "");
+}
+
+#[test]
+fn pantry_stations_test() {
+    let station = |code: &str, arrival: &str, departure: &str| json!({"station_code": code, "station_name": code, "arrival_time": arrival, "departure_time": departure, "halt_time": "5m"});
+    let details = |train_number: &str, extra: Value| {
+        let mut data = json!({
+            "train_number": train_number,
+            "train_name": "Test Express",
+            "station_list": [
+                station("BCT", "--", "17:00"),
+                station("KOTA", "04:15", "04:25"),
+                station("NDLS", "08:32", "--")
+            ]
+        });
+        data.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        to_train_details(serde_json::from_value(data).unwrap())
+    };
+
+    let listed = details(
+        "22209",
+        json!({"pantry_stations": [" st ", {"station_code": "KOTA"}, 12, ""]}),
+    );
+    assert_eq!(listed.pantry_car_stations, ["ST", "KOTA"]);
+    assert!(listed.food_ordering_available);
+    assert!(listed.is_overnight());
+    assert_eq!(listed.facilities, [E_CATERING_NOTE]);
+    let json = serde_json::to_value(&listed).unwrap();
+    assert_eq!(json["facilities"][0], "🍱 E-catering available via IRCTC");
+    assert_eq!(json["pantry_car_stations"][1], "KOTA");
+
+    let comma_separated = details("22209", json!({"pantry_stations": "ST,KOTA"}));
+    assert_eq!(comma_separated.pantry_car_stations, ["ST", "KOTA"]);
+
+    // Known from the fallback list when IRCTC sends nothing
+    let rajdhani = details("12951", json!({}));
+    assert!(rajdhani.pantry_car_stations.is_empty());
+    assert!(rajdhani.food_ordering_available);
+    assert_eq!(rajdhani.facilities, [E_CATERING_NOTE]);
+    assert!(
+        serde_json::to_value(&rajdhani)
+            .unwrap()
+            .get("pantry_car_stations")
+            .is_none()
+    );
+
+    let unknown = details("22209", json!({"pantry_stations": null}));
+    assert!(!unknown.food_ordering_available);
+    assert!(unknown.facilities.is_empty());
+
+    // Day trains get no e-catering note
+    let mut day_train = details("12951", json!({}));
+    day_train.station_list.truncate(2);
+    day_train.station_list[0].departure_time = "06:00".into();
+    day_train.station_list[1].arrival_time = "12:00".into();
+    day_train.station_list[1].departure_time = "--".into();
+    assert!(!day_train.is_overnight());
+}
+
+#[test]
+fn sort_trains_test() {
+    let train = |number: &str, from_sta: &str, journey_minutes: Option<u32>| Train {
+        train_number: number.into(),
+        train_name: "Test Express".into(),
+        from_sta: from_sta.into(),
+        to_sta: "08:00".into(),
+        run_days: vec![],
+        train_type: "EXP".into(),
+        category: None,
+        journey_minutes,
+        journey_distance_km: None,
+    };
+    let mut trains = vec![
+        train("12951", "16:35", Some(15 * 60 + 32)),
+        train("12953", "17:40", Some(16 * 60 + 55)),
+        train("22209", "--", None),
+        train("12909", "06:10", Some(15 * 60 + 32)),
+        train("19019", "00:05", None),
+    ];
+    let numbers = |trains: &Vec<Train>| {
+        trains
+            .iter()
+            .map(|train| train.train_number.clone())
+            .collect::<Vec<_>>()
+    };
+
+    sort_trains_by_departure(&mut trains);
+    assert_eq!(
+        numbers(&trains),
+        ["19019", "12909", "12951", "12953", "22209"]
+    );
+    // Ties and trains without a duration keep their departure order
+    sort_trains_by_duration(&mut trains);
+    assert_eq!(
+        numbers(&trains),
+        ["12909", "12951", "12953", "19019", "22209"]
+    );
+}
+
+#[test]
+fn paginate_trains_test() {
+    let train = |number: u32| Train {
+        train_number: number.to_string(),
+        train_name: "Test Express".into(),
+        // Numbered in reverse departure order so sorting is exercised
+        from_sta: format!("{:02}:00", 22 - number),
+        to_sta: "12:00".into(),
+        run_days: vec![],
+        train_type: "EXP".into(),
+        category: None,
+        journey_minutes: None,
+        journey_distance_km: None,
+    };
+    let trains = || (0..23).map(train).collect::<Vec<Train>>();
+
+    let first = paginate_trains(trains(), 0, 10, 0);
+    assert_eq!(first.trains.len(), 10);
+    assert_eq!(first.trains[0].from_sta, "00:00");
+    assert_eq!(first.trains[9].from_sta, "09:00");
+    assert_eq!(first.total_trains, 23);
+    assert!(first.has_next_page);
+    assert!(!first.has_previous_page);
+    assert_eq!(
+        first.navigation,
+        "Trains 1 to 10 of 23. Ask for page 1 for later departures."
+    );
+
+    let middle = paginate_trains(trains(), 1, 10, 0);
+    assert!(middle.has_next_page && middle.has_previous_page);
+    assert_eq!(
+        middle.navigation,
+        "Trains 11 to 20 of 23. Ask for page 0 for earlier departures. Ask for page 2 for later departures."
+    );
+
+    let last = paginate_trains(trains(), 2, 10, 0);
+    assert_eq!(last.trains.len(), 3);
+    assert_eq!(last.trains[2].from_sta, "22:00");
+    assert!(!last.has_next_page);
+    assert!(last.has_previous_page);
+
+    let past_end = paginate_trains(trains(), 3, 10, 0);
+    assert!(past_end.trains.is_empty() && !past_end.has_next_page);
+    assert_eq!(past_end.page, 3);
+    assert!(
+        past_end
+            .navigation
+            .starts_with("Page 3 is past the last of the 23 trains.")
+    );
+
+    let exact = paginate_trains(trains(), 0, 23, 0);
+    assert_eq!(exact.trains.len(), 23);
+    assert!(!exact.has_next_page);
+
+    let mut unknown = trains();
+    unknown[22].from_sta = "--".into();
+    assert_eq!(paginate_trains(unknown, 2, 10, 0).trains[2].from_sta, "--");
+    assert!(paginate_trains(vec![], 0, 10, 0).trains.is_empty());
+    let empty = paginate_trains(vec![], 1, 10, 0);
+    assert!(!empty.has_previous_page);
+    assert_eq!(empty.navigation, "No trains listed.");
+
+    let truncated = paginate_trains(trains(), 0, 10, 5);
+    assert_eq!(truncated.total_trains, 28);
+    assert!(
+        truncated
+            .navigation
+            .contains("IRCTC didn't list 5 more trains")
+    );
+}
+
+#[test]
+fn train_between_total_test() {
+    let train = json!({
+        "train_number": "12951",
+        "train_name": "Mumbai Rajdhani",
+        "from_sta": "17:00",
+        "to_sta": "08:32",
+        "run_days": ["Mon"],
+        "train_type": "RAJ"
+    });
+    let response: TrainBetweenResponse =
+        serde_json::from_value(json!({"status": true, "data": [train], "total": 3})).unwrap();
+    let route = RouteTrains {
+        trains: response.data,
+        total_count: response.total_count,
+    };
+    assert_eq!(route.total_count, 3);
+    assert_eq!(route.unlisted(), 2);
+
+    let response: TrainBetweenResponse =
+        serde_json::from_value(json!({"status": true, "data": [train]})).unwrap();
+    let route = RouteTrains {
+        trains: response.data,
+        total_count: response.total_count,
+    };
+    assert_eq!(route.unlisted(), 0);
+}
+
+#[test]
+fn train_category_test() {
+    assert_eq!(TrainCategory::from_code("RAJ"), TrainCategory::Rajdhani);
+    assert_eq!(TrainCategory::from_code("sht"), TrainCategory::Shatabdi);
+    assert_eq!(
+        TrainCategory::from_code("DRNT"),
+        TrainCategory::DurotoExpress
+    );
+    assert_eq!(TrainCategory::from_code("SF"), TrainCategory::SuperFast);
+    assert_eq!(TrainCategory::from_code("MEMU"), TrainCategory::Local);
+    assert_eq!(
+        TrainCategory::from_code("GR"),
+        TrainCategory::Other("GR".into())
+    );
+    assert_eq!(TrainCategory::Rajdhani.typical_speed_kmh(), Some(80));
+    assert_eq!(TrainCategory::Other("GR".into()).typical_speed_kmh(), None);
+
+    let train: Train = serde_json::from_value(json!({
+        "train_number": "12951",
+        "train_name": "Mumbai Rajdhani",
+        "from_sta": "17:00",
+        "to_sta": "08:32",
+        "run_days": ["Mon", "Tue"],
+        "train_type": "RAJ"
+    }))
+    .unwrap();
+    assert_eq!(train.category(), TrainCategory::Rajdhani);
+    assert!(train.category.is_none());
+
+    let categorized = Train {
+        category: Some(train.category()),
+        ..train
+    };
+    let value = serde_json::to_value(&categorized).unwrap();
+    assert_eq!(value["category"], "Rajdhani");
+    let round_trip: Train = serde_json::from_value(value).unwrap();
+    assert_eq!(round_trip.category, Some(TrainCategory::Rajdhani));
+    assert_eq!(
+        TrainCategory::from("Duronto Express".to_string()),
+        TrainCategory::DurotoExpress
+    );
+}
+
+#[tokio::test]
+async fn with_availability_test() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let train = |number: u32| Train {
+        train_number: number.to_string(),
+        train_name: "Test Express".into(),
+        from_sta: "06:00".into(),
+        to_sta: "12:00".into(),
+        run_days: vec![],
+        train_type: "EXP".into(),
+        category: None,
+        journey_minutes: None,
+        journey_distance_km: None,
+    };
+    let (running, most_running) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    let check = |train_number: String, class: TrainClass| {
+        let (running, most_running) = (&running, &most_running);
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            // Train 3 has no sleeper class
+            if train_number == "3" && class == TrainClass::Sleeper {
+                return Err("Class not available".into());
+            }
+            Ok(SeatAvailability {
+                train_number,
+                class: class.code().into(),
+                quota: Quota::General.code().into(),
+                availability: vec![AvailabilityDetail {
+                    date: "23-1-2026".into(),
+                    seat_status: SeatStatus::from("AVAILABLE-0042"),
+                }],
+                waitlist_position: None,
+            })
+        }
+    };
+
+    let permits = &Semaphore::new(MAX_CONCURRENT_AVAILABILITY_CALLS);
+    let trains = with_availability(
+        (0..12).map(train).collect(),
+        &[TrainClass::ThirdAc, TrainClass::Sleeper],
+        permits,
+        check,
+    )
+    .await;
+    assert_eq!(trains.len(), 12);
+    // Past the limit trains are listed without checking them
+    assert!(trains[MAX_TRAINS_WITH_AVAILABILITY].availability.is_empty());
+    assert_eq!(
+        most_running.load(Ordering::SeqCst),
+        MAX_CONCURRENT_AVAILABILITY_CALLS
+    );
+    assert_eq!(trains[0].train.train_number, "0");
+    assert_eq!(trains[0].availability.len(), 2);
+    assert_eq!(trains[0].availability[&TrainClass::Sleeper].class, "SL");
+    assert_eq!(trains[3].availability.len(), 1);
+    assert!(trains[3].availability.contains_key(&TrainClass::ThirdAc));
+
+    // Searches sharing the permits stay within the limit together
+    most_running.store(0, Ordering::SeqCst);
+    let search = || {
+        with_availability(
+            (0..12).map(train).collect(),
+            &[TrainClass::ThirdAc],
+            permits,
+            check,
+        )
+    };
+    join_all([search(), search(), search()]).await;
+    assert_eq!(
+        most_running.load(Ordering::SeqCst),
+        MAX_CONCURRENT_AVAILABILITY_CALLS
+    );
+
+    let json = serde_json::to_value(&trains[3]).unwrap();
+    assert_eq!(json["availability"]["3A"]["train_number"], "3");
+    let class: TrainClass = serde_json::from_value(json!("2S")).unwrap();
+    assert_eq!(class, TrainClass::SecondSitting);
+    assert_eq!(
+        serde_json::from_value::<Quota>(json!("TQ")).unwrap(),
+        Quota::Tatkal
+    );
+}
+
+#[test]
+fn best_availability_test() {
+    let train = |number: &str| Train {
+        train_number: number.into(),
+        train_name: "Test Express".into(),
+        from_sta: "06:00".into(),
+        to_sta: "12:00".into(),
+        run_days: vec![],
+        train_type: "EXP".into(),
+        category: None,
+        journey_minutes: None,
+        journey_distance_km: None,
+    };
+    let seats = |number: &str, statuses: &[(&str, &str)]| SeatAvailability {
+        train_number: number.into(),
+        class: "3A".into(),
+        quota: "GN".into(),
+        availability: statuses
+            .iter()
+            .map(|(date, status)| AvailabilityDetail {
+                date: date.to_string(),
+                seat_status: SeatStatus::from(*status),
+            })
+            .collect(),
+        waitlist_position: None,
+    };
+    let with = |number: &str, statuses: &[(&str, &str)]| TrainWithAvailability {
+        train: train(number),
+        availability: HashMap::from([(TrainClass::ThirdAc, seats(number, statuses))]),
+    };
+    let date = |day: u8| Date::new(2026, 2, day).unwrap();
+
+    let detail = |status: &str| AvailabilityDetail {
+        date: "10-2-2026".into(),
+        seat_status: SeatStatus::from(status),
+    };
+    assert_eq!(detail("AVAILABLE-0042").available_seats(), Some(42));
+    assert_eq!(detail("AVBL 7").available_seats(), Some(7));
+    assert_eq!(detail("CURR_AVBL-0003").available_seats(), Some(3));
+    assert_eq!(detail("AVBL").available_seats(), None);
+    assert_eq!(detail("GNWL12/WL10").available_seats(), None);
+    assert_eq!(detail("RAC 5").available_seats(), None);
+    assert_eq!(detail("NOT AVAILABLE").available_seats(), None);
+    // Later days of the reply don't count for the journey date
+    let next_days = seats(
+        "1",
+        &[("9-2-2026", "AVBL-0001"), ("10-2-2026", "AVBL-0009")],
+    );
+    assert_eq!(next_days.available_seats_on(&date(10)), Some(9));
+    assert_eq!(next_days.available_seats_on(&date(9)), Some(1));
+    // No entry for the date, the other days don't stand in for it
+    assert_eq!(next_days.available_seats_on(&date(12)), None);
+    assert_eq!(next_days.waitlist_position_on(&date(12)), None);
+
+    let result = best_availability(
+        vec![
+            (
+                date(10),
+                vec![
+                    with("12951", &[("10-2-2026", "GNWL12/WL10")]),
+                    with("12953", &[("10-2-2026", "AVAILABLE-0005")]),
+                ],
+            ),
+            (date(11), vec![with("12951", &[("11-2-2026", "RAC 3")])]),
+            (
+                date(12),
+                vec![
+                    with("12951", &[("12-2-2026", "AVAILABLE-0040")]),
+                    with("12953", &[("12-2-2026", "AVAILABLE-0012")]),
+                    TrainWithAvailability {
+                        train: train("22209"),
+                        availability: HashMap::new(),
+                    },
+                ],
+            ),
+            (
+                date(13),
+                vec![with("12953", &[("13-2-2026", "AVAILABLE-0040")])],
+            ),
+        ],
+        TrainClass::ThirdAc,
+    )
+    .unwrap();
+    // The earlier of two dates with as many seats wins
+    assert_eq!(result.best_date, date(12));
+    assert_eq!(result.train.train_number, "12951");
+    assert_eq!(result.availability.available_seats_on(&date(12)), Some(40));
+    let alternatives: Vec<(Date, String)> = result
+        .alternatives
+        .iter()
+        .map(|(date, seats)| (date.clone(), seats.train_number.clone()))
+        .collect();
+    assert_eq!(
+        alternatives,
+        [
+            (date(10), "12953".to_string()),
+            (date(13), "12953".to_string())
+        ]
+    );
+
+    assert!(
+        best_availability(
+            vec![(date(11), vec![with("12951", &[("11-2-2026", "RAC 3")])])],
+            TrainClass::ThirdAc
+        )
+        .is_none()
+    );
+    assert!(
+        best_availability(
+            vec![(date(10), vec![with("12951", &[("10-2-2026", "AVBL-0009")])])],
+            TrainClass::Sleeper
+        )
+        .is_none()
+    );
+}
+
+#[tokio::test]
+async fn rapidapi_client_test() {
+    let client = MockApiClient::new([
+        (
+            "/api/v3/trainBetweenStations",
+            json!({"status": true, "data": [{
+                "train_number": "12951",
+                "train_name": "Mumbai Rajdhani",
+                "from_sta": "17:00",
+                "to_sta": "08:32",
+                "run_days": ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+                "train_type": "RAJ"
+            }]}),
+        ),
+        (
+            "/api/v1/getTrainDetails",
+            json!({"status": true, "data": {
+                "train_number": "12951",
+                "train_name": "Mumbai Rajdhani",
+                "station_list": [
+                    {"station_code": "BCT", "station_name": "Mumbai Central", "arrival_time": "--", "departure_time": "17:00", "halt_time": "--", "distance": "0"},
+                    {"station_code": "NDLS", "station_name": "New Delhi", "arrival_time": "08:32", "departure_time": "--", "halt_time": "--", "distance": "1386"}
+                ]
+            }}),
+        ),
+    ]);
+    let station = |code: &str| Station::new(code.into()).unwrap();
+    let date = Date::new(2026, 2, 15).unwrap();
+
+    let route = all_trains_between(&client, &station("BCT"), &station("NDLS"), &date)
+        .await
+        .unwrap();
+    assert_eq!(route.unlisted(), 0);
+    let trains = route.trains;
+    assert_eq!(trains.len(), 1);
+    assert_eq!(trains[0].category, Some(TrainCategory::Rajdhani));
+
+    let trains = with_journey_times(&client, trains, &station("BCT"), &station("NDLS")).await;
+    assert_eq!(trains[0].journey_distance_km, Some(1386));
+    assert_eq!(
+        *client.requests.lock().unwrap(),
+        [
+            "/api/v3/trainBetweenStations?fromStationCode=BCT&toStationCode=NDLS&dateOfJourney=2026-02-15",
+            "/api/v1/getTrainDetails?trainNo=12951"
+        ]
+    );
+
+    // Unknown paths fail like IRCTC errors do
+    let availability = seats_available(
+        &client,
+        "12951",
+        &station("BCT"),
+        &station("NDLS"),
+        &date,
+        "3A",
+        "GN",
+    )
+    .await;
+    assert!(availability.is_err_and(|e| e.to_string().contains("RapidAPI error")));
+}
+
+#[tokio::test]
+async fn all_class_availability_test() {
+    let date = &Date::new(2026, 1, 23).unwrap();
+    // A Rajdhani has only AC sleeper classes
+    let check = |class: TrainClass| async move {
+        if !matches!(
+            class,
+            TrainClass::FirstAc | TrainClass::SecondAc | TrainClass::ThirdAc
+        ) {
+            return Ok(SeatAvailability::not_applicable(
+                "12951",
+                class.code(),
+                Quota::General.code(),
+                date,
+            ));
+        }
+        Ok(SeatAvailability {
+            train_number: "12951".into(),
+            class: class.code().into(),
+            quota: Quota::General.code().into(),
+            availability: vec![AvailabilityDetail {
+                date: "23-1-2026".into(),
+                seat_status: SeatStatus::Available(Some(12)),
+            }],
+            waitlist_position: None,
+        })
+    };
+    let availability = all_class_availability("12951", check).await.unwrap();
+    assert_eq!(availability.len(), TrainClass::ALL.len());
+    assert_eq!(
+        availability[&TrainClass::ThirdAc].available_seats_on(date),
+        Some(12)
+    );
+    let sleeper = &availability[&TrainClass::Sleeper];
+    assert!(!sleeper.is_applicable());
+    assert_eq!(sleeper.class, "SL");
+    assert_eq!(sleeper.availability[0].date, "23-1-2026");
+    assert_eq!(
+        sleeper.availability[0].seat_status,
+        SeatStatus::NotApplicable
+    );
+    assert_eq!(sleeper.available_seats_on(date), None);
+    let json = serde_json::to_value(&availability).unwrap();
+    assert_eq!(
+        json["2S"]["availability"][0]["seat_status"],
+        "NotApplicable"
+    );
+
+    // Errors other than a missing class aren't hidden
+    let check = &check;
+    let failing = |class: TrainClass| async move {
+        match class {
+            TrainClass::Sleeper => Err("RapidAPI error: 500".into()),
+            _ => check(class).await,
+        }
+    };
+    assert!(matches!(
+        all_class_availability("12951", failing).await,
+        Err(TravelError::Api(msg)) if msg.contains("SL") && msg.contains("500")
+    ));
+    let resets_at = std::time::Instant::now();
+    let rate_limited =
+        |_: TrainClass| async move { Err(TravelError::RateLimited { resets_at }.into()) };
+    assert!(matches!(
+        all_class_availability("12951", rate_limited).await,
+        Err(TravelError::RateLimited { .. })
+    ));
+
+    let station = &|code: &str| Station::new(code.into()).unwrap();
+    let seats = |message: &str| {
+        let client = MockApiClient::new([(
+            "/api/v1/checkSeatAvailability",
+            json!({"status": false, "message": message}),
+        )]);
+        async move {
+            seats_available(
+                &client,
+                "12951",
+                &station("BCT"),
+                &station("NDLS"),
+                date,
+                "SL",
+                "GN",
+            )
+            .await
+        }
+    };
+    let sleeper = seats("Class SL is not available in this train")
+        .await
+        .unwrap();
+    assert_eq!(
+        sleeper.availability[0].seat_status,
+        SeatStatus::NotApplicable
+    );
+    assert!(seats("Something went wrong").await.is_err());
+}
+
+#[tokio::test]
+async fn seat_status_test() {
+    use SeatStatus::*;
+
+    for (status, expected) in [
+        ("AVAILABLE-0042", Available(Some(42))),
+        ("AVBL 7", Available(Some(7))),
+        ("CURR_AVBL-0003", Available(Some(3))),
+        ("avbl", Available(None)),
+        ("WL#34", WaitingList(34)),
+        ("WL 8", WaitingList(8)),
+        ("GNWL#45/WL#30", WaitingList(30)),
+        ("GNWL12/WL10", WaitingList(10)),
+        ("PQWL 3", WaitingList(3)),
+        ("RLWL#21/RLWL#19", WaitingList(19)),
+        ("TQWL#5", WaitingList(5)),
+        ("RAC-12", ReservationAgainstCancellation(12)),
+        ("RAC 5", ReservationAgainstCancellation(5)),
+        ("GNWL#4/RAC#2", ReservationAgainstCancellation(2)),
+        ("NOT AVAILABLE", NotAvailable),
+        ("REGRET/WL", NotAvailable),
+        ("TRAIN CANCELLED", Unknown("TRAIN CANCELLED".into())),
+        ("WL", Unknown("WL".into())),
+        ("", Unknown(String::new())),
+    ] {
+        assert_eq!(SeatStatus::from(status), expected, "{status}");
+    }
+    assert_eq!(
+        SeatStatus::from("GNWL#45/WL#30").waitlist_position(),
+        Some(30)
+    );
+    assert_eq!(SeatStatus::from("RAC-12").waitlist_position(), None);
+
+    let client = MockApiClient::new([(
+        "/api/v1/checkSeatAvailability",
+        json!({
+            "status": true,
+            "data": [
+                {"date": "23-1-2026", "status": "GNWL#45/WL#30"},
+                {"date": "24-1-2026", "status": "AVAILABLE-0004"}
+            ]
+        }),
+    )]);
+    let station = |code: &str| Station::new(code.into()).unwrap();
+    let seats = seats_available(
+        &client,
+        "12951",
+        &station("BCT"),
+        &station("NDLS"),
+        &Date::new(2026, 1, 23).unwrap(),
+        "3A",
+        "GN",
+    )
+    .await
+    .unwrap();
+    assert_eq!(seats.waitlist_position, Some(30));
+    let date = |day: u8| Date::new(2026, 1, day).unwrap();
+    assert_eq!(seats.waitlist_position_on(&date(23)), Some(30));
+    assert_eq!(seats.available_seats_on(&date(24)), Some(4));
+    assert_eq!(seats.waitlist_position_on(&date(24)), None);
+
+    let json = serde_json::to_value(&seats).unwrap();
+    assert_eq!(
+        json["availability"][0]["seat_status"],
+        json!({"WaitingList": 30})
+    );
+    let cached: SeatAvailability = serde_json::from_value(json).unwrap();
+    assert_eq!(cached.waitlist_position_on(&date(23)), Some(30));
+}
@@ -0,0 +1,159 @@
+use super::rapidapi::{IRCTC_HOST, MAX_CODE_LEN, Station, flag};
+use crate::api_requests::rapidapi::{ApiClient, RapidAPIClient};
+use crate::{error::TravelError, utils::sanitize_query_param};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[gemini_schema]
+///Facilities of a railway station. `false` means not known to be there.
+pub struct StationFacilities {
+    ///Lifts to the platforms
+    #[serde(default, alias = "lift", deserialize_with = "flag")]
+    pub has_elevator: bool,
+    ///Rooms to rest in between trains, booked with a PNR
+    #[serde(default, alias = "retiring_rooms", deserialize_with = "flag")]
+    pub has_retiring_room: bool,
+    #[serde(default, alias = "cloak_room", deserialize_with = "flag")]
+    pub has_cloak_room: bool,
+    #[serde(default, alias = "food_stall", deserialize_with = "flag")]
+    pub has_food_stall: bool,
+    ///Ramps and wheelchairs for passengers who can't use stairs
+    #[serde(default, alias = "wheelchair", deserialize_with = "flag")]
+    pub wheelchair_accessible: bool,
+    #[serde(default, alias = "atm", deserialize_with = "flag")]
+    pub atm_available: bool,
+    #[serde(default, alias = "wifi", deserialize_with = "flag")]
+    pub wifi_available: bool,
+    ///Taken from a list kept by hand instead of IRCTC. The traveller must confirm with the
+    ///station before relying on it, especially for wheelchair access and lifts
+    #[serde(skip_deserializing)]
+    pub unverified: bool,
+}
+
+#[derive(Deserialize)]
+struct StationInfoResponse {
+    data: StationFacilities,
+}
+
+/// Facilities of the busiest stations, used when IRCTC doesn't answer. Every one of them has a
+/// food stall and an ATM. Kept by hand from no official source, so marked `unverified`.
+static MAJOR_STATION_FACILITIES: &[(&str, StationFacilities)] = &[
+    ("NDLS", major_station(true, true)),
+    ("CSMT", major_station(true, true)),
+    ("MMCT", major_station(true, true)),
+    ("LTT", major_station(true, false)),
+    ("HWH", major_station(true, true)),
+    ("SDAH", major_station(true, false)),
+    ("MAS", major_station(true, true)),
+    ("SBC", major_station(true, true)),
+    ("SC", major_station(true, true)),
+    ("PUNE", major_station(true, true)),
+    ("ADI", major_station(true, true)),
+    ("JP", major_station(true, true)),
+    ("LKO", major_station(true, true)),
+    ("BSB", major_station(true, false)),
+    ("MAO", major_station(false, false)),
+];
+
+const fn major_station(has_elevator: bool, has_cloak_room: bool) -> StationFacilities {
+    StationFacilities {
+        has_elevator,
+        has_retiring_room: true,
+        has_cloak_room,
+        has_food_stall: true,
+        wheelchair_accessible: true,
+        atm_available: true,
+        wifi_available: true,
+        unverified: true,
+    }
+}
+
+fn known_facilities(station_code: &str) -> Option<StationFacilities> {
+    MAJOR_STATION_FACILITIES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(station_code))
+        .map(|(_, facilities)| *facilities)
+}
+
+async fn fetch_station_facilities(
+    client: &impl ApiClient,
+    station_code: &str,
+) -> Result<StationFacilities, TravelError> {
+    let path = format!(
+        "/api/v1/getStationInfo?stationCode={}",
+        sanitize_query_param(station_code, MAX_CODE_LEN)?
+    );
+    let body: StationInfoResponse = client.get(&path).await?;
+    Ok(body.data)
+}
+
+/// From IRCTC, falling back to the unverified `MAJOR_STATION_FACILITIES` when it fails.
+pub async fn get_railway_station_facilities(
+    station_code: Station,
+) -> Result<StationFacilities, TravelError> {
+    let code = station_code.to_string();
+    if code.is_empty() {
+        return Err(TravelError::InvalidInput("Station code is empty".into()));
+    }
+    let facilities = match RapidAPIClient::for_host(IRCTC_HOST) {
+        Ok(client) => fetch_station_facilities(&client, &code).await,
+        Err(e) => Err(e),
+    };
+    match facilities {
+        Ok(facilities) => Ok(facilities),
+        Err(e) => known_facilities(&code).ok_or(e),
+    }
+}
+
+#[gemini_function]
+///Get the facilities of a railway station, like elevators, retiring rooms and wheelchair access. Call it for the boarding and arrival stations when the travellers include senior citizens, young children or someone with a disability.
+pub async fn railway_station_facilities(
+    ///Station code (e.g., 'NDLS')
+    station_code: Station,
+) -> Result<StationFacilities, TravelError> {
+    get_railway_station_facilities(station_code).await
+}
+
+#[test]
+fn station_facilities_test() {
+    let response: StationInfoResponse = serde_json::from_value(serde_json::json!({
+        "status": true,
+        "data": {
+            "station_code": "GKP",
+            "lift": "Y",
+            "has_retiring_room": true,
+            "cloak_room": 0,
+            "food_stall": 1,
+            "wheelchair_accessible": "N",
+            "atm": "yes",
+            "unverified": true
+        }
+    }))
+    .unwrap();
+    assert_eq!(
+        response.data,
+        StationFacilities {
+            has_elevator: true,
+            has_retiring_room: true,
+            has_food_stall: true,
+            atm_available: true,
+            ..Default::default()
+        }
+    );
+
+    let new_delhi = known_facilities("ndls").unwrap();
+    assert!(new_delhi.has_elevator && new_delhi.wheelchair_accessible);
+    assert!(new_delhi.unverified);
+    assert!(!known_facilities("MAO").unwrap().has_elevator);
+    assert_eq!(known_facilities("GKP"), None);
+}
+
+#[tokio::test]
+async fn empty_station_code_test() {
+    let station = Station::new(String::new()).unwrap();
+    assert!(matches!(
+        get_railway_station_facilities(station).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+}
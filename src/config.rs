@@ -0,0 +1,169 @@
+use crate::error::TravelError;
+#[cfg(test)]
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+const DEFAULT_GEMINI_MODEL: &str = "gemini-3-flash-preview";
+const DEFAULT_MAX_CONTEXT_SIZE: u8 = 20;
+const DEFAULT_RETRY_MAX: u8 = 3;
+const DEFAULT_TOKEN_REFRESH_BUFFER_SECS: u64 = 30;
+
+/// Loaded on first use. `main` forces it at startup so a misconfigured Lambda fails on cold
+/// start instead of in the middle of a conversation.
+pub static CONFIG: LazyLock<TravelPlannerConfig> = LazyLock::new(|| {
+    TravelPlannerConfig::from_env().unwrap_or_else(|e| panic!("Invalid configuration: {e}"))
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmadeusEnvironment {
+    Test,
+    Production,
+}
+
+impl AmadeusEnvironment {
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            AmadeusEnvironment::Test => "https://test.api.amadeus.com",
+            AmadeusEnvironment::Production => "https://api.amadeus.com",
+        }
+    }
+    /// `path` starts with the API version, e.g. `/v2/shopping/flight-offers`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url())
+    }
+}
+
+impl FromStr for AmadeusEnvironment {
+    type Err = TravelError;
+
+    fn from_str(env: &str) -> Result<Self, Self::Err> {
+        match env.to_ascii_lowercase().as_str() {
+            "test" => Ok(AmadeusEnvironment::Test),
+            "production" => Ok(AmadeusEnvironment::Production),
+            _ => Err(TravelError::InvalidInput(format!(
+                "AMADEUS_ENV must be 'test' or 'production'. Found: {env}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TravelPlannerConfig {
+    pub gemini_api_key: String,
+    pub gemini_model: String,
+    pub rapidapi_key: String,
+    pub amadeus_api_key: String,
+    pub amadeus_api_secret: String,
+    pub google_maps_api_key: String,
+    pub amadeus_env: AmadeusEnvironment,
+    ///History limit of sessions created by the server
+    pub max_context_size: u8,
+    ///Attempts made for a failed upstream request before giving up
+    pub retry_max: u8,
+    ///Cached OAuth tokens are refreshed this long before they expire
+    pub token_refresh_buffer_secs: u64,
+    ///Quote endpoint of the travel insurance partner. Insurance quotes are off without it
+    pub insurance_api_url: Option<String>,
+    pub insurance_api_key: Option<String>,
+}
+
+impl TravelPlannerConfig {
+    pub fn from_env() -> Result<Self, TravelError> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, TravelError> {
+        let required = |key: &str| {
+            lookup(key).ok_or_else(|| TravelError::ApiKeyMissing(format!("{key} not found")))
+        };
+        Ok(Self {
+            gemini_api_key: required("GEMINI_API_KEY")?,
+            gemini_model: lookup("GEMINI_MODEL").unwrap_or_else(|| DEFAULT_GEMINI_MODEL.into()),
+            rapidapi_key: required("RAPIDAPI_KEY")?,
+            amadeus_api_key: required("AMADEUS_API_KEY")?,
+            amadeus_api_secret: required("AMADEUS_API_SECRET")?,
+            google_maps_api_key: required("GOOGLE_MAPS_API_KEY")?,
+            amadeus_env: parse_or(&lookup, "AMADEUS_ENV", AmadeusEnvironment::Test)?,
+            max_context_size: parse_or(&lookup, "MAX_CONTEXT_SIZE", DEFAULT_MAX_CONTEXT_SIZE)?,
+            retry_max: parse_or(&lookup, "RETRY_MAX", DEFAULT_RETRY_MAX)?,
+            token_refresh_buffer_secs: parse_or(
+                &lookup,
+                "TOKEN_REFRESH_BUFFER_SECS",
+                DEFAULT_TOKEN_REFRESH_BUFFER_SECS,
+            )?,
+            insurance_api_url: lookup("INSURANCE_API_URL"),
+            insurance_api_key: lookup("INSURANCE_API_KEY"),
+        })
+    }
+}
+
+fn parse_or<T: FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    default: T,
+) -> Result<T, TravelError> {
+    match lookup(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| TravelError::InvalidInput(format!("Invalid {key}: {value}"))),
+        None => Ok(default),
+    }
+}
+
+#[cfg(test)]
+fn config_from(vars: &[(&str, &str)]) -> Result<TravelPlannerConfig, TravelError> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    TravelPlannerConfig::from_lookup(|key| vars.get(key).cloned())
+}
+
+#[test]
+fn travel_planner_config_test() {
+    let keys = [
+        ("GEMINI_API_KEY", "gemini"),
+        ("RAPIDAPI_KEY", "rapidapi"),
+        ("AMADEUS_API_KEY", "amadeus"),
+        ("AMADEUS_API_SECRET", "secret"),
+        ("GOOGLE_MAPS_API_KEY", "maps"),
+    ];
+    let config = config_from(&keys).unwrap();
+    assert_eq!(config.gemini_model, DEFAULT_GEMINI_MODEL);
+    assert_eq!(config.amadeus_env, AmadeusEnvironment::Test);
+    assert_eq!(config.max_context_size, 20);
+    assert_eq!(config.retry_max, 3);
+    assert_eq!(config.token_refresh_buffer_secs, 30);
+    assert_eq!(config.insurance_api_url, None);
+    assert_eq!(
+        config.amadeus_env.url("/v2/shopping/flight-offers"),
+        "https://test.api.amadeus.com/v2/shopping/flight-offers"
+    );
+
+    let mut overridden = keys.to_vec();
+    overridden.extend([
+        ("GEMINI_MODEL", "gemini-pro"),
+        ("AMADEUS_ENV", "Production"),
+        ("RETRY_MAX", "5"),
+    ]);
+    let config = config_from(&overridden).unwrap();
+    assert_eq!(config.gemini_model, "gemini-pro");
+    assert_eq!(config.amadeus_env.base_url(), "https://api.amadeus.com");
+    assert_eq!(config.retry_max, 5);
+
+    let missing = config_from(&keys[1..]);
+    assert!(
+        matches!(missing, Err(TravelError::ApiKeyMissing(msg)) if msg.contains("GEMINI_API_KEY"))
+    );
+
+    let mut invalid = keys.to_vec();
+    invalid.push(("MAX_CONTEXT_SIZE", "300"));
+    assert!(matches!(
+        config_from(&invalid),
+        Err(TravelError::InvalidInput(_))
+    ));
+    invalid.pop();
+    invalid.push(("AMADEUS_ENV", "staging"));
+    assert!(config_from(&invalid).is_err());
+}
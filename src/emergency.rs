@@ -0,0 +1,128 @@
+use crate::error::TravelError;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[gemini_schema]
+///Emergency numbers of a country and the Indian mission there.
+pub struct EmergencyContacts {
+    pub police: String,
+    pub ambulance: String,
+    pub fire: String,
+    ///None for India itself
+    pub indian_embassy: Option<EmbassyInfo>,
+    pub tourist_helpline: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[gemini_schema]
+pub struct EmbassyInfo {
+    pub address: String,
+    pub phone: String,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CountryContacts {
+    country: String,
+    ///ISO 3166-1 alpha-2 code
+    code: String,
+    ///ISO 4217 code of the local currency
+    currency: String,
+    ///Other names travellers use for the country, including its main destinations
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(flatten)]
+    contacts: EmergencyContacts,
+}
+
+/// Emergency numbers of the countries Indians travel to most, with the Indian embassy or high
+/// commission from the Ministry of External Affairs list of missions. Kept by hand.
+static EMERGENCY_CONTACTS: LazyLock<Vec<CountryContacts>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../data/emergency_contacts.json"))
+        .expect("Invalid data/emergency_contacts.json")
+});
+
+/// `country` is a country name, ISO code like `AE` or a well known destination like `Dubai`.
+fn find_country(country: &str) -> Option<&'static CountryContacts> {
+    let country = country.trim();
+    EMERGENCY_CONTACTS.iter().find(|entry| {
+        entry.country.eq_ignore_ascii_case(country)
+            || entry.code.eq_ignore_ascii_case(country)
+            || entry
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(country))
+    })
+}
+
+/// Full name of `country`, e.g. `United Arab Emirates` for `Dubai`.
+pub fn country_name(country: &str) -> Option<&'static str> {
+    find_country(country).map(|entry| entry.country.as_str())
+}
+
+pub fn contacts_for(country: &str) -> Option<&'static EmergencyContacts> {
+    find_country(country).map(|entry| &entry.contacts)
+}
+
+/// ISO 4217 code of the currency used in `country`, e.g. `THB` for `Thailand`.
+pub fn local_currency(country: &str) -> Option<&'static str> {
+    find_country(country).map(|entry| entry.currency.as_str())
+}
+
+#[gemini_function]
+///Get the local emergency numbers and the Indian embassy contact of a country. Add them to every itinerary abroad.
+pub async fn emergency_contacts(
+    ///Country of the destination (e.g., 'Thailand' or 'TH')
+    destination_country: String,
+) -> Result<EmergencyContacts, TravelError> {
+    contacts_for(&destination_country).cloned().ok_or_else(|| {
+        TravelError::InvalidInput(format!(
+            "No emergency contacts known for {destination_country}"
+        ))
+    })
+}
+
+#[test]
+fn contacts_for_test() {
+    let police = |country: &str| contacts_for(country).map(|contacts| contacts.police.as_str());
+    for (country, expected) in [
+        ("United Arab Emirates", "999"),
+        ("Singapore", "999"),
+        ("Thailand", "191"),
+        ("United Kingdom", "999"),
+        ("United States", "911"),
+        ("Nepal", "100"),
+        ("Sri Lanka", "119"),
+        ("Maldives", "119"),
+        ("Japan", "110"),
+        ("Australia", "000"),
+    ] {
+        assert_eq!(police(country), Some(expected), "{country}");
+        let contacts = contacts_for(country).unwrap();
+        assert!(contacts.indian_embassy.is_some(), "{country}");
+    }
+    assert_eq!(police("uae"), Some("999"));
+    assert_eq!(police(" Dubai "), Some("999"));
+    assert_eq!(police("GB"), Some("999"));
+    assert_eq!(police("Bali"), Some("110"));
+    assert_eq!(police("Atlantis"), None);
+
+    let india = contacts_for("India").unwrap();
+    assert_eq!(india.police, "112");
+    assert!(india.indian_embassy.is_none());
+    let thailand = contacts_for("TH").unwrap();
+    assert_eq!(thailand.ambulance, "1669");
+    assert_eq!(local_currency("Thailand"), Some("THB"));
+    assert_eq!(local_currency("dubai"), Some("AED"));
+    assert_eq!(local_currency("India"), Some("INR"));
+    assert_eq!(local_currency("Atlantis"), None);
+    assert_eq!(country_name("Bali"), Some("Indonesia"));
+    assert!(
+        thailand
+            .indian_embassy
+            .as_ref()
+            .is_some_and(|embassy| embassy.address.contains("Bangkok"))
+    );
+}
@@ -0,0 +1,53 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum TravelError {
+    ///Input from the user or Gemini failed validation
+    InvalidInput(String),
+    ///Text in a format that isn't understood, e.g. a date like 'sometime soon'
+    ParseError(String),
+    ///A required API key or credential is missing or unusable
+    ApiKeyMissing(String),
+    ///An upstream API returned an error or an unexpected payload
+    Api(String),
+    ///A search worked but found nothing, e.g. no flight on any date of a range
+    NoResults(String),
+    ///An upstream API failed repeatedly and is not called again until `retry_in` passes
+    ApiUnavailable { api: String, retry_in: Duration },
+    ///The RapidAPI plan has no calls left until `resets_at`
+    RateLimited { resets_at: Instant },
+    ///Reading or writing persisted state failed
+    Persistence(String),
+    ///A Gemini function call failed, the message starts with the function name
+    FunctionCall(String),
+}
+
+impl Display for TravelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TravelError::InvalidInput(msg) => write!(f, "Invalid input: {msg}"),
+            TravelError::ParseError(msg) => write!(f, "Could not parse: {msg}"),
+            TravelError::ApiKeyMissing(msg) => write!(f, "API key missing: {msg}"),
+            TravelError::Api(msg) => write!(f, "API error: {msg}"),
+            TravelError::NoResults(msg) => write!(f, "No results: {msg}"),
+            TravelError::ApiUnavailable { api, retry_in } => write!(
+                f,
+                "API unavailable: {api} is failing, try again in {}s",
+                retry_in.as_secs().max(1)
+            ),
+            TravelError::RateLimited { resets_at } => write!(
+                f,
+                "Rate limited: no API calls left, try again in {}s",
+                resets_at
+                    .saturating_duration_since(Instant::now())
+                    .as_secs()
+                    .max(1)
+            ),
+            TravelError::Persistence(msg) => write!(f, "Persistence error: {msg}"),
+            TravelError::FunctionCall(msg) => write!(f, "Function call failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for TravelError {}
@@ -0,0 +1,243 @@
+use crate::{
+    api_requests::{flights::amadeus::Flight, site_seen::get_place_image_url},
+    error::TravelError,
+    export::{Export, attach},
+    itinerary::{Timetable, TravelItinerary},
+};
+use gemini_client_api::futures::future::join_all;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use maud::{DOCTYPE, Markup, html};
+
+const HOTEL_IMAGE_WIDTH: u32 = 600;
+
+// Email clients drop `<style>` blocks, so every element is styled inline
+const BODY_STYLE: &str = "margin:0;padding:24px;background:#f4f6f8;font-family:Arial,Helvetica,sans-serif;color:#1f2933;";
+const CONTAINER_STYLE: &str =
+    "max-width:640px;margin:0 auto;background:#ffffff;border-radius:8px;padding:24px;";
+const CARD_STYLE: &str =
+    "border:1px solid #d9e2ec;border-radius:8px;padding:16px;margin:0 0 12px 0;";
+const IMAGE_STYLE: &str = "width:100%;border-radius:6px;margin:0 0 8px 0;";
+const TABLE_STYLE: &str = "width:100%;border-collapse:collapse;font-size:14px;";
+const CELL_STYLE: &str = "border-bottom:1px solid #d9e2ec;padding:6px;text-align:left;";
+const FOOTER_STYLE: &str =
+    "margin:24px 0 0 0;padding:16px;background:#102a43;color:#ffffff;border-radius:8px;";
+
+/// Self-contained HTML email of `itinerary`. `hotel_images` are `(hotel name, image URL)`
+/// pairs, hotels without one get a card without a photo.
+pub fn to_html(itinerary: &TravelItinerary, hotel_images: &[(String, String)]) -> String {
+    let timetable = Timetable::from_itinerary(itinerary);
+    html! {
+        (DOCTYPE)
+        html lang="en" {
+            head {
+                meta charset="utf-8";
+                meta name="viewport" content="width=device-width, initial-scale=1";
+                title { (itinerary.name) }
+            }
+            body style=(BODY_STYLE) {
+                div style=(CONTAINER_STYLE) {
+                    h1 { (itinerary.name) }
+                    p {
+                        (itinerary.source) " → " (itinerary.destination) br;
+                        (itinerary.start_date.to_yyyy_mm_dd()) " to "
+                        (itinerary.end_date.to_yyyy_mm_dd()) " · "
+                        (itinerary.travellers) " travellers"
+                    }
+                    @if !itinerary.flights.is_empty() {
+                        h2 { "Flights" }
+                        @for flight in &itinerary.flights {
+                            (flight_card(flight))
+                        }
+                    }
+                    @if !itinerary.trains.is_empty() {
+                        h2 { "Trains" }
+                        @for train in &itinerary.trains {
+                            div style=(CARD_STYLE) {
+                                strong { (train.train_name) " (" (train.train_number) ")" }
+                                p { "Departs " (train.from_sta) " · Arrives " (train.to_sta) }
+                            }
+                        }
+                    }
+                    @if !itinerary.hotels.is_empty() {
+                        h2 { "Hotels" }
+                        @for hotel in &itinerary.hotels {
+                            div style=(CARD_STYLE) {
+                                @if let Some((_, url)) = hotel_images.iter().find(|(name, _)| name == hotel) {
+                                    img src=(url) alt=(hotel) style=(IMAGE_STYLE);
+                                }
+                                strong { (hotel) }
+                                @if let Some(rating) = itinerary.hotel_rating {
+                                    p { "⭐ " (rating) }
+                                }
+                            }
+                        }
+                    }
+                    @if !timetable.entries.is_empty() {
+                        h2 { "Day by day" }
+                        table style=(TABLE_STYLE) {
+                            tr {
+                                th style=(CELL_STYLE) { "Date" }
+                                th style=(CELL_STYLE) { "Time" }
+                                th style=(CELL_STYLE) { "Plan" }
+                                th style=(CELL_STYLE) { "Where" }
+                            }
+                            @for entry in &timetable.entries {
+                                tr {
+                                    td style=(CELL_STYLE) { (entry.date.to_yyyy_mm_dd()) }
+                                    td style=(CELL_STYLE) { (&entry.time.to_hh_mm_ss()[..5]) }
+                                    td style=(CELL_STYLE) { (entry.description) }
+                                    td style=(CELL_STYLE) { (entry.location) }
+                                }
+                            }
+                        }
+                    }
+                    div style=(FOOTER_STYLE) {
+                        strong { "Total estimated cost: " (itinerary.estimated_cost) }
+                        " for " (itinerary.travellers) " travellers"
+                    }
+                }
+            }
+        }
+    }
+    .into_string()
+}
+
+fn flight_card(flight: &Flight) -> Markup {
+    let numbers: Vec<String> = flight
+        .itineraries
+        .iter()
+        .flat_map(|itinerary| &itinerary.segments)
+        .map(|segment| format!("{}{}", segment.carrier_code, segment.number))
+        .collect();
+    html! {
+        div style=(CARD_STYLE) {
+            strong { "✈️ " (numbers.join(" + ")) }
+            @if let (Some(departure), Some(arrival)) = (flight.departure(), flight.arrival()) {
+                p {
+                    (departure.iata_code) " " (departure.at.replace('T', " ")) " → "
+                    (arrival.iata_code) " " (arrival.at.replace('T', " "))
+                }
+            }
+            p {
+                (flight.price)
+                @if let Some(journey_time) = &flight.journey_time {
+                    " · " (journey_time)
+                }
+            }
+        }
+    }
+}
+
+/// `data:` URI of `html`, which opens in a browser without hosting the file.
+pub fn data_uri(html: &str) -> String {
+    format!("data:text/html;charset=utf-8,{}", urlencoding::encode(html))
+}
+
+#[gemini_function]
+///Export the final itinerary as an HTML email with flight and hotel cards, a day-by-day timeline and the total cost. The app gets the document directly, this returns a short confirmation. Use it when the user wants the plan by email.
+pub async fn export_itinerary_html(itinerary: TravelItinerary) -> Result<String, TravelError> {
+    if itinerary.end_date < itinerary.start_date {
+        return Err(TravelError::InvalidInput(format!(
+            "End date {} is before start date {}",
+            itinerary.end_date.to_yyyy_mm_dd(),
+            itinerary.start_date.to_yyyy_mm_dd()
+        )));
+    }
+    // A missing photo only leaves the card plain
+    let destination = &itinerary.destination;
+    let hotel_images = join_all(itinerary.hotels.iter().map(|hotel| async move {
+        let query = format!("{hotel}, {destination}");
+        let url = get_place_image_url(query, Some(HOTEL_IMAGE_WIDTH))
+            .await
+            .ok()?;
+        Some((hotel.clone(), url))
+    }))
+    .await;
+    let hotel_images: Vec<(String, String)> = hotel_images.into_iter().flatten().collect();
+    let html = to_html(&itinerary, &hotel_images);
+    let file_name = format!("{}.html", itinerary.name.trim());
+    let summary = format!(
+        "Exported '{}' as {file_name} ({} KB) with {} flights, {} trains and {} hotels. The app shows it to the user.",
+        itinerary.name.trim(),
+        html.len().div_ceil(1024),
+        itinerary.flights.len(),
+        itinerary.trains.len(),
+        itinerary.hotels.len()
+    );
+    attach(Export {
+        file_name,
+        data_uri: data_uri(&html),
+    })?;
+    Ok(summary)
+}
+
+#[test]
+fn to_html_test() {
+    use crate::itinerary::sample_itinerary;
+
+    let mut itinerary = sample_itinerary();
+    itinerary.name = "Goa <beach> & forts".into();
+    itinerary.end_date = crate::utils::Date::new(2026, 2, 17).unwrap();
+    let html = to_html(
+        &itinerary,
+        &[(
+            "Taj Fort Aguada".into(),
+            "https://example.com/taj.jpg".into(),
+        )],
+    );
+
+    assert!(html.starts_with("<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\">"));
+    assert!(html.ends_with("</body></html>"));
+    // Names are escaped, not taken as markup
+    assert!(html.contains("<title>Goa &lt;beach&gt; &amp; forts</title>"));
+    assert!(!html.contains("<beach>"));
+    assert!(html.contains("<img src=\"https://example.com/taj.jpg\" alt=\"Taj Fort Aguada\""));
+    assert!(html.contains("<h2>Day by day</h2><table"));
+    assert!(html.contains("<td style=\"border-bottom:1px solid #d9e2ec;padding:6px;text-align:left;\">Visit Baga Beach</td>"));
+    assert!(html.contains("Total estimated cost: ₹42,000"));
+    assert!(!html.contains("<h2>Flights</h2>"));
+    assert_eq!(html.matches("<div").count(), html.matches("</div>").count());
+    assert_eq!(html.matches("<tr>").count(), html.matches("</tr>").count());
+
+    let plain = to_html(&itinerary, &[]);
+    assert!(!plain.contains("<img"));
+    assert!(plain.contains("<strong>Taj Fort Aguada</strong>"));
+
+    let uri = data_uri("<p>Hi & bye</p>");
+    assert_eq!(
+        uri,
+        "data:text/html;charset=utf-8,%3Cp%3EHi%20%26%20bye%3C%2Fp%3E"
+    );
+}
+
+#[tokio::test]
+async fn export_itinerary_html_test() {
+    use crate::export::with_exports;
+    use crate::itinerary::sample_itinerary;
+    use std::sync::{Arc, Mutex};
+
+    let mut itinerary = sample_itinerary();
+    itinerary.name = "Goa by train".into();
+    // Hotel photos need the Places API
+    itinerary.hotels.clear();
+    assert!(matches!(
+        export_itinerary_html(itinerary.clone()).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+
+    let exports = Arc::new(Mutex::new(vec![]));
+    let summary = with_exports(exports.clone(), export_itinerary_html(itinerary))
+        .await
+        .unwrap();
+    assert!(summary.starts_with("Exported 'Goa by train' as Goa by train.html ("));
+    // Gemini only gets the summary, the document goes to the app
+    assert!(!summary.contains("data:") && !summary.contains("<html"));
+    let exports = exports.lock().unwrap();
+    assert_eq!(exports.len(), 1);
+    assert_eq!(exports[0].file_name, "Goa by train.html");
+    assert!(
+        exports[0]
+            .data_uri
+            .starts_with("data:text/html;charset=utf-8,%3C%21DOCTYPE%20html%3E")
+    );
+}
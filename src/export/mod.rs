@@ -0,0 +1,32 @@
+use crate::error::TravelError;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+pub mod html;
+
+/// A document made for the user, sent to the client beside the conversation so that it never
+/// goes through Gemini's context.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Export {
+    ///e.g. 'Goa by train.html'
+    pub file_name: String,
+    ///`data:` URI of the document, opens in a browser without hosting the file
+    pub data_uri: String,
+}
+
+tokio::task_local! {
+    /// Exports made while serving the current `ApiRequest`.
+    static EXPORTS: Arc<Mutex<Vec<Export>>>;
+}
+
+/// Runs `future` collecting what the export Gemini functions make into `exports`.
+pub async fn with_exports<F: Future>(exports: Arc<Mutex<Vec<Export>>>, future: F) -> F::Output {
+    EXPORTS.scope(exports, future).await
+}
+
+/// Hands `export` to the client at the end of the response.
+pub fn attach(export: Export) -> Result<(), TravelError> {
+    EXPORTS
+        .try_with(|exports| exports.lock().unwrap().push(export))
+        .map_err(|_| TravelError::InvalidInput("Exports can't be sent in this request".into()))
+}
@@ -1,72 +1,985 @@
+use crate::error::TravelError;
+#[cfg(test)]
+use crate::gemini_interface::MockGemini;
 use crate::{
     api_requests::{
-        flights::amadeus::{flight_seats_available, flights_between},
-        hotels::amadeus::hotels_in_city,
-        site_seen::get_about_place,
-        trains::rapidapi::{train_seats_available, trains_between},
+        advisory::destination_safety_advisory,
+        buses::redbus::buses_between_cities,
+        currency::currency_tips,
+        events::cricbuzz::cricket_matches_in_city,
+        flights::{
+            amadeus::{
+                cheapest_flight_in_range, confirm_flight_price, flight_fare_rules,
+                flight_seats_available, flights_between, with_price_summary,
+            },
+            lounges::{get_airport_lounge_info, get_terminal_map_url},
+        },
+        holidays::get_holidays,
+        hotels::amadeus::{
+            confirm_hotel_price, hotels_closest_to, hotels_in_city, hotels_near_airport,
+            hotels_near_area, select_hotel_room,
+        },
+        hotels::reviews::hotel_review_summary,
+        insurance::travel_insurance_quotes,
+        site_seen::{
+            get_about_place, get_directions_to_place, get_place_image_url, nearby_attractions,
+            optimize_sightseeing_route, refresh_destination_info, with_markdown,
+        },
+        trains::rapidapi::{
+            get_train_between_paged, train_all_class_availability, train_details,
+            train_seats_available, trains_between_flexible, trains_between_next_available,
+            trains_between_with_availability,
+        },
+        trains::stations::railway_station_facilities,
     },
+    config::CONFIG,
     constants::TRAVEL_PLANNER_SYS_PROMPT,
+    emergency::emergency_contacts,
+    export::html::export_itinerary_html,
+    gemini_interface::{GeminiInterface, ReplyStream},
+    itinerary::{
+        check_itinerary_connections, check_trip_budget, compare_travel_options,
+        get_itinerary_timeline,
+    },
+    loyalty::estimate_loyalty_points,
+    memory::{recall_preference, remember_preference},
+    monsoon::monsoon_forecast,
+    persistence::{get_my_recent_trips, save_my_trip, with_user},
+    preferences::TravelPreferences,
+    streaming::StreamingSession,
+    transport::{estimate_road_trip_cost, suggest_transport_mode},
+    utils::{Date, IndianSeason, indian_airport_city},
+};
+use gemini_client_api::futures::{
+    StreamExt,
+    future::{BoxFuture, join_all},
 };
 use gemini_client_api::gemini::{
     ask::Gemini,
     error::GeminiResponseError,
     types::{
-        request::{Role, Tool},
-        response::GeminiResponseStream,
+        request::{Chat, FunctionCall, PartType, Role, Tool},
         sessions::Session,
     },
-    utils::{GeminiSchema, execute_function_calls},
+    utils::{GeminiSchema, gemini_function},
 };
+use lambda_runtime::tracing;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 
-async fn plan_tour(
-    mut session: Session,
-) -> Result<GeminiResponseStream, (Session, GeminiResponseError)> {
-    let tools = vec![
-        hotels_in_city::gemini_schema(),
-        flights_between::gemini_schema(),
-        flight_seats_available::gemini_schema(),
-        trains_between::gemini_schema(),
-        train_seats_available::gemini_schema(),
-        get_about_place::gemini_schema(),
-    ];
-    let ai = Gemini::new(
-        std::env::var("GEMINI_API_KEY").unwrap(),
-        "gemini-3-flash-preview",
-        Some(TRAVEL_PLANNER_SYS_PROMPT.to_string().into()),
+type FunctionHandler =
+    Box<dyn Fn(Value) -> BoxFuture<'static, Result<Value, TravelError>> + Send + Sync>;
+
+/// Gemini functions by name, along with their declarations in registration order.
+struct FunctionRegistry {
+    schemas: Vec<Value>,
+    handlers: HashMap<&'static str, FunctionHandler>,
+}
+
+impl FunctionRegistry {
+    fn register(&mut self, name: &'static str, schema: Value, handler: FunctionHandler) {
+        self.schemas.push(schema);
+        self.handlers.insert(name, handler);
+    }
+}
+
+/// Registers a `#[gemini_function]` under its own name.
+macro_rules! register_function {
+    ($registry:expr, $function:ident) => {
+        $registry.register(
+            stringify!($function),
+            $function::gemini_schema(),
+            Box::new(|args| {
+                Box::pin(async move {
+                    $function::execute(args).await.map_err(|e| {
+                        TravelError::FunctionCall(format!("{}: {e}", stringify!($function)))
+                    })
+                })
+            }),
+        )
+    };
+}
+
+static FUNCTIONS: LazyLock<FunctionRegistry> = LazyLock::new(|| {
+    let mut registry = FunctionRegistry {
+        schemas: Vec::new(),
+        handlers: HashMap::new(),
+    };
+    register_function!(registry, hotels_in_city);
+    register_function!(registry, flights_between);
+    register_function!(registry, flight_seats_available);
+    register_function!(registry, cheapest_flight_in_range);
+    register_function!(registry, get_train_between_paged);
+    register_function!(registry, train_seats_available);
+    register_function!(registry, get_about_place);
+    register_function!(registry, save_my_trip);
+    register_function!(registry, get_my_recent_trips);
+    register_function!(registry, hotels_near_area);
+    register_function!(registry, compare_travel_options);
+    register_function!(registry, nearby_attractions);
+    register_function!(registry, train_details);
+    register_function!(registry, get_place_image_url);
+    register_function!(registry, get_directions_to_place);
+    register_function!(registry, confirm_flight_price);
+    register_function!(registry, confirm_hotel_price);
+    register_function!(registry, estimate_loyalty_points);
+    register_function!(registry, get_holidays);
+    register_function!(registry, suggest_transport_mode);
+    register_function!(registry, estimate_road_trip_cost);
+    register_function!(registry, select_hotel_room);
+    register_function!(registry, hotel_review_summary);
+    register_function!(registry, trains_between_next_available);
+    register_function!(registry, get_airport_lounge_info);
+    register_function!(registry, refresh_destination_info);
+    register_function!(registry, get_itinerary_timeline);
+    register_function!(registry, check_itinerary_connections);
+    register_function!(registry, cricket_matches_in_city);
+    register_function!(registry, optimize_sightseeing_route);
+    register_function!(registry, hotels_closest_to);
+    register_function!(registry, travel_insurance_quotes);
+    register_function!(registry, emergency_contacts);
+    register_function!(registry, monsoon_forecast);
+    register_function!(registry, remember_preference);
+    register_function!(registry, recall_preference);
+    register_function!(registry, currency_tips);
+    register_function!(registry, get_terminal_map_url);
+    register_function!(registry, destination_safety_advisory);
+    register_function!(registry, generate_packing_list);
+    register_function!(registry, trains_between_with_availability);
+    register_function!(registry, trains_between_flexible);
+    register_function!(registry, flight_fare_rules);
+    register_function!(registry, railway_station_facilities);
+    register_function!(registry, check_trip_budget);
+    register_function!(registry, hotels_near_airport);
+    register_function!(registry, train_all_class_availability);
+    register_function!(registry, buses_between_cities);
+    register_function!(registry, export_itinerary_html);
+    registry
+});
+
+pub fn gemini_function_name(call: &FunctionCall) -> &str {
+    call.name()
+}
+
+pub async fn dispatch_function_call(name: &str, args: Value) -> Result<Value, TravelError> {
+    let handler = FUNCTIONS
+        .handlers
+        .get(name)
+        .ok_or_else(|| TravelError::InvalidInput(format!("Unknown function: {name}")))?;
+    handler(args).await
+}
+
+/// Runs every function call of the last chat concurrently and adds their responses to
+/// `session`. Failures are passed on to Gemini as `{"Error": ...}`.
+async fn execute_function_calls(session: &mut Session, preferences: &TravelPreferences) {
+    let Some(chat) = session.get_last_chat() else {
+        return;
+    };
+    let calls: Vec<(String, Value)> = chat
+        .parts()
+        .iter()
+        .filter_map(|part| match part.data() {
+            PartType::FunctionCall(call) => Some((
+                gemini_function_name(call).to_string(),
+                call.args().clone().unwrap_or_else(|| json!({})),
+            )),
+            _ => None,
+        })
+        .collect();
+
+    let results = join_all(calls.into_iter().map(|(name, args)| async move {
+        let result = dispatch_function_call(&name, args).await;
+        (name, result)
+    }))
+    .await;
+    for (name, result) in results {
+        let response = match result {
+            Ok(response) if name == "flights_between" => {
+                with_price_summary(preferences.filter_response(&name, response))
+            }
+            Ok(response) if name == "get_about_place" => with_markdown(response),
+            Ok(response) => preferences.filter_response(&name, response),
+            Err(e) => json!({"Error": e.to_string()}),
+        };
+        if let Err(e) = session.add_function_response(name.clone(), response) {
+            eprintln!("ERROR: Could not add function response of {name}:\n{e:?}");
+        }
+    }
+}
+
+const PEAK_SEASON_NOTE: &str = "The user is travelling during peak season. Warn them: \"Note: you're traveling during peak season — prices are 30-50% higher.\"";
+
+const MONSOON_NOTE: &str = "The user is travelling to an Indian destination during its monsoon. Call monsoon_forecast for every Indian destination of the trip and, where the travel date is in the monsoon, include its travel_recommendation in the plan.";
+
+/// Arguments naming the place travelled to, e.g. `city_code` of `hotels_in_city`.
+const DESTINATION_ARGS: [&str; 3] = ["destination", "city", "city_code"];
+
+/// Arguments of the functions called so far.
+fn call_args(session: &Session) -> Vec<&Value> {
+    session
+        .get_history()
+        .iter()
+        .flat_map(|chat| chat.parts())
+        .filter_map(|part| match part.data() {
+            PartType::FunctionCall(call) => call.args().as_ref(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Dates passed to functions so far, e.g. `least_departure` of `flights_between`.
+fn travel_dates(session: &Session) -> Vec<Date> {
+    fn collect(value: &Value, dates: &mut Vec<Date>) {
+        match value {
+            Value::Object(map) => match serde_json::from_value::<Date>(value.clone()) {
+                Ok(date) => dates.push(date),
+                Err(_) => map.values().for_each(|value| collect(value, dates)),
+            },
+            Value::Array(values) => values.iter().for_each(|value| collect(value, dates)),
+            _ => {}
+        }
+    }
+    let mut dates = Vec::new();
+    for args in call_args(session) {
+        collect(args, &mut dates);
+    }
+    dates
+}
+
+/// Places passed to functions so far as one of `DESTINATION_ARGS`. Indian airport codes are
+/// given as their city, e.g. 'Goa' for `GOI`.
+fn travel_destinations(session: &Session) -> Vec<String> {
+    fn collect(value: &Value, places: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match value.as_str() {
+                        Some(place) if DESTINATION_ARGS.contains(&key.as_str()) => {
+                            places.push(indian_airport_city(place).unwrap_or(place).to_string())
+                        }
+                        _ => collect(value, places),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect(value, places)),
+            _ => {}
+        }
+    }
+    let mut places = Vec::new();
+    for args in call_args(session) {
+        collect(args, &mut places);
+    }
+    places
+}
+
+fn system_prompt(session: &Session) -> String {
+    let mut prompt = TRAVEL_PLANNER_SYS_PROMPT.to_string();
+    let dates = travel_dates(session);
+    if dates.iter().any(Date::is_peak_travel_season_india) {
+        prompt.push_str("\n\n");
+        prompt.push_str(PEAK_SEASON_NOTE);
+    }
+    if travel_destinations(session)
+        .iter()
+        .any(|place| dates.iter().any(|date| date.is_in_monsoon_season(place)))
+    {
+        prompt.push_str("\n\n");
+        prompt.push_str(MONSOON_NOTE);
+    }
+    prompt
+}
+
+/// Gemini with every travel function. The system prompt is set per turn by `plan_tour`.
+pub fn travel_planner() -> Gemini {
+    Gemini::new(
+        CONFIG.gemini_api_key.clone(),
+        CONFIG.gemini_model.clone(),
+        None,
     )
-    .set_tools(vec![Tool::FunctionDeclarations(tools.clone())]);
-    let results = execute_function_calls!(
-        session,
-        hotels_in_city,
-        flights_between,
-        flight_seats_available,
-        train_seats_available,
-        trains_between,
-        get_about_place
+    .set_tools(vec![Tool::FunctionDeclarations(FUNCTIONS.schemas.clone())])
+}
+
+async fn plan_tour<A: GeminiInterface>(
+    ai: &mut A,
+    mut session: Session,
+    preferences: TravelPreferences,
+) -> Result<A::Stream, (Session, GeminiResponseError)> {
+    ai.set_system_prompt(system_prompt(&session));
+    execute_function_calls(&mut session, &preferences).await;
+    if let Some(chat) = session.get_last_chat()
+        && *chat.role() == Role::Function
+    {
+        println!(
+            "FunctionResponse:\n{}",
+            serde_json::to_string(chat.parts()).unwrap()
+        )
+    }
+    ai.ask_as_stream(session).await
+}
+
+const PREFERENCES_CONTEXT: &str = "My saved travel preferences:";
+
+/// Adds `preferences` to the start of the first user message, so they are known from the first
+/// turn on. Sessions that already carry them or don't start with the user are left as is.
+pub fn inject_context(session: &mut Session, preferences: &TravelPreferences) {
+    if session
+        .get_history()
+        .first()
+        .is_none_or(|chat| *chat.role() != Role::User)
+    {
+        return;
+    }
+    let Some(parts) = session.get_parts_no_mut(0) else {
+        return;
+    };
+    let injected = parts.iter().any(
+        |part| matches!(part.data(), PartType::Text(text) if text.starts_with(PREFERENCES_CONTEXT)),
     );
-    for i in 0..tools.len() {
-        if let Some(Err(e)) = &results[i] {
-            session
-                .add_function_response(
-                    flights_between::name(&tools[i]).unwrap(),
-                    serde_json::json!({"Error":e}),
-                )
-                .unwrap();
+    if !injected && let Ok(preferences) = serde_json::to_string(preferences) {
+        parts.insert(0, format!("{PREFERENCES_CONTEXT} {preferences}").into());
+    }
+}
+
+fn function_names(chat: &Chat, responses: bool) -> Vec<&str> {
+    chat.parts()
+        .iter()
+        .filter_map(|part| match part.data() {
+            PartType::FunctionCall(call) if !responses => Some(call.name().as_str()),
+            PartType::FunctionResponse(response) if responses => Some(response.name().as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks the invariants Gemini rejects a session with a bare 400 for: user and model turns
+/// alternate, with function responses counted as user turns, every function call is answered
+/// in the next turn, and the model is not the last to speak. A trailing model turn with
+/// function calls is allowed, as `handle_request` answers them before asking Gemini.
+pub fn validate_session_schema(session: &Session) -> Result<(), TravelError> {
+    let history = session.get_history();
+    let Some(last) = history.last() else {
+        return Err(TravelError::InvalidInput("Session has no messages".into()));
+    };
+    for (turn, pair) in history.windows(2).enumerate() {
+        let (chat, next) = (pair[0], pair[1]);
+        if (*chat.role() == Role::Model) == (*next.role() == Role::Model) {
+            return Err(TravelError::InvalidInput(format!(
+                "Turns {turn} ({:?}) and {} ({:?}) are from the same side, user and model turns must alternate",
+                chat.role(),
+                turn + 1,
+                next.role()
+            )));
+        }
+        let calls = function_names(chat, false);
+        let responses = function_names(next, true);
+        if *next.role() == Role::Function && calls.is_empty() {
+            return Err(TravelError::InvalidInput(format!(
+                "Turn {} has function responses but turn {turn} has no function call",
+                turn + 1
+            )));
+        }
+        if let Some(call) = calls.iter().find(|call| !responses.contains(call)) {
+            return Err(TravelError::InvalidInput(format!(
+                "Function call {call} in turn {turn} has no response in turn {}",
+                turn + 1
+            )));
         }
     }
-    if let Some(chat) = session.get_last_chat() {
-        if *chat.role() == Role::Function {
-            println!(
-                "FunctionResponse:\n{}",
-                serde_json::to_string(chat.parts()).unwrap()
-            )
+    if *last.role() == Role::Model && function_names(last, false).is_empty() {
+        return Err(TravelError::InvalidInput(
+            "Last turn is from the model, add the user's message after it".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn handle_request<A: GeminiInterface>(
+    ai: &mut A,
+    session: Session,
+    user_id: Option<String>,
+    preferences: TravelPreferences,
+) -> Result<A::Stream, (Session, Box<dyn std::error::Error + Send + Sync>)> {
+    if let Err(e) = validate_session_schema(&session) {
+        return Err((session, e.into()));
+    }
+    match preferences.validated_gemini_model() {
+        Ok(Some(model)) => ai.set_model(model),
+        Ok(None) => {}
+        Err(e) => return Err((session, e.into())),
+    }
+    with_user(user_id, plan_tour(ai, session, preferences))
+        .await
+        .map_err(|(session, e)| (session, e.into()))
+}
+
+/// Answers the session, resolving function calls until Gemini replies without any, and streams
+/// every reply. Returns the finished session, or `None` when the conversation was cut short by
+/// an error or a closed stream.
+pub async fn run_conversation<A: GeminiInterface>(
+    ai: &mut A,
+    mut session: Session,
+    user_id: Option<String>,
+    preferences: &TravelPreferences,
+    stream: &mut StreamingSession,
+) -> Option<Session> {
+    loop {
+        match handle_request(ai, session, user_id.clone(), preferences.clone()).await {
+            Ok(mut response_stream) => {
+                while let Some(gemini_response) = response_stream.next().await {
+                    match gemini_response {
+                        Ok(data) => {
+                            if let Err(e) = stream.send_json(data.get_chat().parts()).await {
+                                eprintln!("ERROR: Stopped streaming:\n{e}");
+                                return None;
+                            }
+                        }
+                        Err(error) => {
+                            eprintln!("ERROR: Did not send stream due to error:\n{error}")
+                        }
+                    }
+                }
+                if !response_stream
+                    .get_session()
+                    .get_last_chat()
+                    .unwrap()
+                    .has_function_call()
+                {
+                    println!("Response streaming completed.");
+                    return Some(response_stream.get_session_owned());
+                }
+                println!("Resolving function calls.");
+                session = response_stream.get_session_owned();
+            }
+            Err((session, e)) => {
+                eprintln!("ERROR: handle_request failed:\n{e}\n{:?}", session);
+                let code = match e.downcast_ref::<TravelError>() {
+                    Some(TravelError::InvalidInput(_)) => "INVALID_SESSION",
+                    _ => "GEMINI_ERROR",
+                };
+                let _ = stream.send_error(code, &e.to_string()).await;
+                return None;
+            }
         }
     }
-    ai.ask_as_stream(session).await
 }
 
-pub async fn handle_request(
+/// `run_conversation` cut short after `timeout`, so a long chain of function calls ends with
+/// an error the client can show instead of the Lambda being killed mid stream.
+pub async fn run_conversation_with_timeout<A: GeminiInterface>(
+    ai: &mut A,
     session: Session,
-) -> Result<GeminiResponseStream, (Session, GeminiResponseError)> {
-    plan_tour(session).await
+    user_id: Option<String>,
+    preferences: &TravelPreferences,
+    stream: &mut StreamingSession,
+    timeout: Duration,
+) -> Option<Session> {
+    let started = Instant::now();
+    let conversation = run_conversation(ai, session, user_id, preferences, &mut *stream);
+    let result = tokio::time::timeout(timeout, conversation).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(session) => {
+            tracing::info!(elapsed_ms, "Conversation finished");
+            session
+        }
+        Err(_) => {
+            tracing::warn!(elapsed_ms, "Conversation timed out");
+            let message = format!(
+                "Planning took longer than {} seconds, ask for fewer things at once",
+                timeout.as_secs()
+            );
+            let _ = stream.send_error("TIMEOUT", &message).await;
+            None
+        }
+    }
+}
+
+const MAX_PACKING_LIST_DAYS: u8 = 90;
+const MAX_PACKING_LIST_ACTIVITIES: usize = 10;
+
+fn packing_list_prompt(
+    destination: &str,
+    duration_days: u8,
+    season: IndianSeason,
+    activities: &[String],
+) -> String {
+    let season = match season {
+        IndianSeason::Summer => "summer (March to May)",
+        IndianSeason::Monsoon => "monsoon (June to September)",
+        IndianSeason::PostMonsoon => "post-monsoon (October and November)",
+        IndianSeason::Winter => "winter (December to February)",
+    };
+    let activities = if activities.is_empty() {
+        "sightseeing".to_string()
+    } else {
+        activities.join(", ")
+    };
+    format!(
+        "Write a concise packing list for an Indian traveller's {duration_days} day trip to {destination} in the Indian {season}. Keep the local climate of {destination} in that season in mind. Planned activities: {activities}. Group the items under the markdown headings Clothing, Toiletries, Electronics and Documents, adding Activity gear only when the activities need it. Use short bullet points and no introduction."
+    )
+}
+
+/// Markdown packing list from a one-off Gemini call, without the planner's tools.
+pub async fn packing_list(
+    ai: &impl GeminiInterface,
+    destination: &str,
+    duration_days: u8,
+    season: IndianSeason,
+    activities: &[String],
+) -> Result<String, TravelError> {
+    if destination.trim().is_empty() {
+        return Err(TravelError::InvalidInput("Destination is empty".into()));
+    }
+    if !(1..=MAX_PACKING_LIST_DAYS).contains(&duration_days) {
+        return Err(TravelError::InvalidInput(format!(
+            "duration_days must be between 1 and {MAX_PACKING_LIST_DAYS}. Found: {duration_days}"
+        )));
+    }
+    if activities.len() > MAX_PACKING_LIST_ACTIVITIES {
+        return Err(TravelError::InvalidInput(format!(
+            "At most {MAX_PACKING_LIST_ACTIVITIES} activities are allowed. Found: {}",
+            activities.len()
+        )));
+    }
+    let mut session = Session::new(2);
+    session.ask_string(packing_list_prompt(
+        destination.trim(),
+        duration_days,
+        season,
+        activities,
+    ));
+    let response = ai
+        .ask(&mut session)
+        .await
+        .map_err(|e| TravelError::Api(format!("Gemini packing list failed: {e}")))?;
+    Ok(response.get_chat().get_text_no_think(""))
+}
+
+#[gemini_function]
+///Generate a packing list grouped into clothing, toiletries, electronics and documents. Call it when the user asks what to pack. Returns markdown to show the user.
+pub async fn generate_packing_list(
+    ///Destination of the trip (e.g., 'Manali')
+    destination: String,
+    ///Length of the trip in days
+    duration_days: u8,
+    ///First day of the trip
+    start_date: Date,
+    ///Planned activities (e.g., 'trekking', 'beach', 'business meetings')
+    activities: Vec<String>,
+) -> Result<String, TravelError> {
+    let ai = Gemini::new(
+        CONFIG.gemini_api_key.clone(),
+        CONFIG.gemini_model.clone(),
+        None,
+    );
+    packing_list(
+        &ai,
+        &destination,
+        duration_days,
+        start_date.indian_season(),
+        &activities,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn dispatch_function_call_test() {
+    assert_eq!(FUNCTIONS.schemas.len(), FUNCTIONS.handlers.len());
+    for schema in &FUNCTIONS.schemas {
+        let name = schema["name"].as_str().unwrap();
+        assert!(
+            FUNCTIONS.handlers.contains_key(name),
+            "{name} is not registered"
+        );
+        // Empty arguments fail before any API is called. `get_my_recent_trips` has no
+        // arguments and fails as there is no signed in user.
+        let result = dispatch_function_call(name, json!({})).await;
+        assert!(
+            matches!(result, Err(TravelError::FunctionCall(ref msg)) if msg.starts_with(name)),
+            "{name}: {result:?}"
+        );
+    }
+    assert!(matches!(
+        dispatch_function_call("book_spaceship", json!({})).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn peak_season_prompt_test() {
+    let mut session = Session::new(10);
+    session.ask_string("Plan a Goa trip");
+    assert!(!system_prompt(&session).contains(PEAK_SEASON_NOTE));
+    assert!(!system_prompt(&session).contains(MONSOON_NOTE));
+
+    let call = |args: Value| {
+        serde_json::from_value::<gemini_client_api::gemini::types::request::Part>(json!({
+            "functionCall": {"name": "hotels_in_city", "args": args}
+        }))
+        .unwrap()
+    };
+    session.reply(vec![call(json!({
+        "filter": {"city_code": "DXB", "check_in": {"year": 2026, "month": 7, "day": 10}}
+    }))]);
+    assert_eq!(travel_dates(&session), [Date::new(2026, 7, 10).unwrap()]);
+    assert_eq!(travel_destinations(&session), ["DXB"]);
+    assert!(!system_prompt(&session).contains(PEAK_SEASON_NOTE));
+    // Not the monsoon abroad
+    assert!(!system_prompt(&session).contains(MONSOON_NOTE));
+
+    session.ask_string("Goa instead");
+    session.reply(vec![call(json!({
+        "filter": {"city_code": "GOI", "check_in": {"year": 2026, "month": 7, "day": 10}}
+    }))]);
+    assert_eq!(travel_destinations(&session), ["DXB", "Goa"]);
+    assert!(system_prompt(&session).contains(MONSOON_NOTE));
+
+    session.ask_string("What about Diwali instead?");
+    session.reply(vec![call(json!({
+        "filter": {"check_in": {"year": 2026, "month": 11, "day": 8}, "adults": 2}
+    }))]);
+    assert!(system_prompt(&session).contains(PEAK_SEASON_NOTE));
+}
+
+#[test]
+fn inject_context_test() {
+    let preferences = TravelPreferences {
+        preferred_airlines: vec!["AI".into()],
+        ..Default::default()
+    };
+    let first_message = |session: &Session| {
+        session.get_history()[0]
+            .parts()
+            .iter()
+            .filter_map(|part| match part.data() {
+                PartType::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut session = Session::new(10);
+    session.ask_string("Plan a Goa trip");
+    inject_context(&mut session, &preferences);
+    assert_eq!(
+        first_message(&session),
+        [
+            r#"My saved travel preferences: {"preferred_airlines":["AI"]}"#,
+            "Plan a Goa trip"
+        ]
+    );
+    assert_eq!(*session.get_history()[0].role(), Role::User);
+
+    session.reply_string("Sure, when?");
+    session.ask_string("Next month");
+    inject_context(&mut session, &preferences);
+    assert_eq!(first_message(&session).len(), 2);
+    assert_eq!(session.get_history_length(), 3);
+
+    let mut empty = Session::new(10);
+    inject_context(&mut empty, &preferences);
+    assert_eq!(empty.get_history_length(), 0);
+}
+
+#[test]
+fn validate_session_schema_test() {
+    let call = |name: &str| {
+        serde_json::from_value::<gemini_client_api::gemini::types::request::Part>(json!({
+            "functionCall": {"name": name, "args": {}}
+        }))
+        .unwrap()
+    };
+    let invalid = |session: &Session| {
+        matches!(
+            validate_session_schema(session),
+            Err(TravelError::InvalidInput(_))
+        )
+    };
+
+    assert!(invalid(&Session::new(10)));
+
+    let mut session = Session::new(10);
+    session.ask_string("Plan a Goa trip");
+    assert!(validate_session_schema(&session).is_ok());
+    session.reply(vec![call("hotels_in_city"), call("get_holidays")]);
+    // Pending calls are answered by `handle_request`
+    assert!(validate_session_schema(&session).is_ok());
+    session
+        .add_function_response("hotels_in_city", json!({"hotels": []}))
+        .unwrap();
+    let mut unanswered = session.clone();
+    unanswered.reply_string("No hotels found");
+    unanswered.ask_string("Try North Goa");
+    assert!(invalid(&unanswered));
+
+    session
+        .add_function_response("get_holidays", json!([]))
+        .unwrap();
+    assert!(validate_session_schema(&session).is_ok());
+    session.reply_string("Here is your plan");
+    assert!(invalid(&session));
+    session.ask_string("Thanks, save it");
+    assert!(validate_session_schema(&session).is_ok());
+
+    // A user message after a model turn whose calls were never answered
+    let mut interrupted = Session::new(10);
+    interrupted.ask_string("Plan a Goa trip");
+    interrupted.reply(vec![call("hotels_in_city")]);
+    interrupted.ask_string("Actually, Kerala");
+    assert!(invalid(&interrupted));
+
+    let corrupted: Session = serde_json::from_value(json!({
+        "history": [
+            {"role": "user", "parts": [{"text": "Plan a Goa trip"}]},
+            {"role": "user", "parts": [{"text": "For 2 adults"}]}
+        ],
+        "history_limit": 10,
+        "chat_no": 2,
+        "remember_reply": true
+    }))
+    .unwrap();
+    assert!(invalid(&corrupted));
+}
+
+#[tokio::test]
+async fn packing_list_test() {
+    let prompt = packing_list_prompt(
+        "Manali",
+        5,
+        IndianSeason::Winter,
+        &["trekking".into(), "skiing".into()],
+    );
+    assert!(prompt.contains("5 day trip to Manali in the Indian winter (December to February)"));
+    assert!(prompt.contains("Planned activities: trekking, skiing."));
+    for heading in ["Clothing", "Toiletries", "Electronics", "Documents"] {
+        assert!(prompt.contains(heading), "{heading}");
+    }
+    assert!(
+        packing_list_prompt("Goa", 3, IndianSeason::Monsoon, &[])
+            .contains("Planned activities: sightseeing.")
+    );
+
+    // Checked before Gemini is called
+    let ai = MockGemini::new(["## Clothing\n- Thermals"]);
+    let invalid = [
+        packing_list(&ai, " ", 5, IndianSeason::Summer, &[]).await,
+        packing_list(&ai, "Goa", 0, IndianSeason::Summer, &[]).await,
+        packing_list(&ai, "Goa", 91, IndianSeason::Summer, &[]).await,
+        packing_list(
+            &ai,
+            "Goa",
+            5,
+            IndianSeason::Summer,
+            &vec!["swimming".into(); 11],
+        )
+        .await,
+    ];
+    for result in invalid {
+        assert!(
+            matches!(result, Err(TravelError::InvalidInput(_))),
+            "{result:?}"
+        );
+    }
+    assert_eq!(ai.remaining(), 1);
+    assert_eq!(
+        packing_list(&ai, "Manali", 5, IndianSeason::Winter, &[])
+            .await
+            .unwrap(),
+        "## Clothing\n- Thermals"
+    );
+}
+
+#[tokio::test]
+async fn run_conversation_test() {
+    use lambda_runtime::streaming::channel;
+
+    let mut ai = MockGemini::new([
+        // Too many trains per page, so the call fails before RapidAPI is called
+        r#"{"functionCall": {"name": "get_train_between_paged", "args": {"source": "NDLS", "destination": "HWH", "date": "2026-02-15", "per_page": 50}}}"#,
+        "The Rajdhani leaves New Delhi at 16:50.",
+    ]);
+    let mut session = Session::new(20);
+    session.ask_string("Trains from Delhi to Kolkata on 15 Feb 2026?");
+    let (tx, rx) = channel();
+    let conversation = tokio::spawn(async move {
+        let mut stream = StreamingSession::new(tx);
+        let session = run_conversation(
+            &mut ai,
+            session,
+            None,
+            &TravelPreferences::default(),
+            &mut stream,
+        )
+        .await;
+        (ai, session)
+    });
+    let body = rx.collect().await.unwrap().to_bytes();
+    let (ai, session) = conversation.await.unwrap();
+    assert_eq!(ai.remaining(), 0);
+    assert!(
+        ai.system_prompt
+            .unwrap()
+            .starts_with(TRAVEL_PLANNER_SYS_PROMPT.as_str())
+    );
+
+    let session = session.unwrap();
+    let history = session.get_history();
+    let roles: Vec<&Role> = history.iter().map(|chat| chat.role()).collect();
+    assert_eq!(
+        roles,
+        [&Role::User, &Role::Model, &Role::Function, &Role::Model]
+    );
+    let PartType::FunctionResponse(response) = history[2].parts()[0].data() else {
+        panic!("Not a function response: {:?}", history[2]);
+    };
+    assert_eq!(response.name(), "get_train_between_paged");
+    assert!(
+        response.response()["Error"]
+            .as_str()
+            .unwrap()
+            .contains("per_page")
+    );
+    assert_eq!(
+        history[3].get_text_no_think(""),
+        "The Rajdhani leaves New Delhi at 16:50."
+    );
+
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("get_train_between_paged"));
+    assert!(lines[1].contains("Rajdhani"));
+}
+
+#[tokio::test]
+async fn run_conversation_text_test() {
+    use lambda_runtime::streaming::channel;
+
+    let mut ai = MockGemini::new(["Goa is lovely in December.", "Unused"]);
+    let mut session = Session::new(20);
+    session.ask_string("When should I visit Goa?");
+    let (tx, rx) = channel();
+    let conversation = tokio::spawn(async move {
+        let mut stream = StreamingSession::new(tx);
+        let session = run_conversation(
+            &mut ai,
+            session,
+            None,
+            &TravelPreferences::default(),
+            &mut stream,
+        )
+        .await;
+        (ai, session)
+    });
+    let body = rx.collect().await.unwrap().to_bytes();
+    let (ai, session) = conversation.await.unwrap();
+    // Text ends the loop without asking again
+    assert_eq!(ai.remaining(), 1);
+    assert_eq!(session.unwrap().get_history_length(), 2);
+    assert_eq!(body, "[{\"text\":\"Goa is lovely in December.\"}]\n");
+
+    // A session ending with the model is rejected before Gemini is asked
+    let mut ai = MockGemini::new(["Unused"]);
+    let mut session = Session::new(20);
+    session.ask_string("Hi").reply_string("Hello");
+    let (tx, rx) = channel();
+    let conversation = tokio::spawn(async move {
+        let mut stream = StreamingSession::new(tx);
+        run_conversation(
+            &mut ai,
+            session,
+            None,
+            &TravelPreferences::default(),
+            &mut stream,
+        )
+        .await
+    });
+    let body = rx.collect().await.unwrap().to_bytes();
+    assert!(conversation.await.unwrap().is_none());
+    assert!(
+        String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("INVALID_SESSION")
+    );
+}
+
+#[tokio::test]
+async fn gemini_model_preference_test() {
+    use lambda_runtime::streaming::channel;
+
+    let converse = |model: &str| {
+        let preferences = TravelPreferences {
+            gemini_model: Some(model.to_string()),
+            ..Default::default()
+        };
+        let mut session = Session::new(20);
+        session.ask_string("Plan Delhi, Jaipur and Agra in 6 days");
+        let (tx, rx) = channel();
+        let conversation = tokio::spawn(async move {
+            let mut ai = MockGemini::new(["Day 1: Delhi"]);
+            let mut stream = StreamingSession::new(tx);
+            let session = run_conversation(&mut ai, session, None, &preferences, &mut stream).await;
+            (ai, session)
+        });
+        async move {
+            let body = rx.collect().await.unwrap().to_bytes();
+            let (ai, session) = conversation.await.unwrap();
+            (ai, session, String::from_utf8(body.to_vec()).unwrap())
+        }
+    };
+
+    let (ai, session, _) = converse("gemini-pro-latest").await;
+    assert_eq!(ai.model.as_deref(), Some("gemini-pro-latest"));
+    assert!(session.is_some());
+
+    // Gemini is never asked with a model outside the allowed ones
+    let (ai, session, body) = converse("gemini-pro-latest:streamGenerateContent?alt=sse#").await;
+    assert_eq!(ai.model, None);
+    assert_eq!(ai.remaining(), 1);
+    assert!(session.is_none());
+    assert!(body.contains("Gemini model must be one of"), "{body}");
+}
+
+#[tokio::test]
+async fn run_conversation_timeout_test() {
+    use lambda_runtime::streaming::channel;
+
+    let converse = |timeout: Duration| {
+        let mut session = Session::new(20);
+        session.ask_string("When should I visit Goa?");
+        let (tx, rx) = channel();
+        let conversation = tokio::spawn(async move {
+            let mut ai = MockGemini::new(["Goa is lovely in December."])
+                .with_delay(Duration::from_millis(50));
+            let mut stream = StreamingSession::new(tx);
+            let session = run_conversation_with_timeout(
+                &mut ai,
+                session,
+                None,
+                &TravelPreferences::default(),
+                &mut stream,
+                timeout,
+            )
+            .await;
+            (ai, session)
+        });
+        async move {
+            let body = rx.collect().await.unwrap().to_bytes();
+            let (ai, session) = conversation.await.unwrap();
+            (ai, session, String::from_utf8(body.to_vec()).unwrap())
+        }
+    };
+
+    let (ai, session, body) = converse(Duration::ZERO).await;
+    assert!(session.is_none());
+    assert_eq!(ai.remaining(), 1);
+    assert!(body.starts_with(r#"{"error":{"code":"TIMEOUT","#), "{body}");
+    assert_eq!(body.lines().count(), 1);
+
+    let (ai, session, body) = converse(Duration::from_secs(25)).await;
+    assert_eq!(session.unwrap().get_history_length(), 2);
+    assert_eq!(ai.remaining(), 0);
+    assert_eq!(body, "[{\"text\":\"Goa is lovely in December.\"}]\n");
 }
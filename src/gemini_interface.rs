@@ -0,0 +1,229 @@
+use gemini_client_api::futures::Stream;
+#[cfg(test)]
+use gemini_client_api::futures::task::{Context, Poll};
+use gemini_client_api::gemini::{
+    ask::Gemini,
+    error::{GeminiResponseError, GeminiResponseStreamError},
+    types::{
+        response::{GeminiResponse, GeminiResponseStream},
+        sessions::Session,
+    },
+};
+#[cfg(test)]
+use serde_json::{Value, json};
+#[cfg(test)]
+use std::{collections::VecDeque, pin::Pin, sync::Mutex, time::Duration};
+
+/// Replies of one turn as they arrive, along with the session they are added to.
+pub trait ReplyStream:
+    Stream<Item = Result<GeminiResponse, GeminiResponseStreamError>> + Unpin + Send
+{
+    fn get_session(&self) -> &Session;
+    fn get_session_owned(self) -> Session;
+}
+
+impl ReplyStream for GeminiResponseStream {
+    fn get_session(&self) -> &Session {
+        GeminiResponseStream::get_session(self)
+    }
+    fn get_session_owned(self) -> Session {
+        GeminiResponseStream::get_session_owned(self)
+    }
+}
+
+/// The parts of the Gemini client the planner uses, so the conversation can be tested
+/// without calling Gemini.
+pub trait GeminiInterface: Send + Sync {
+    type Stream: ReplyStream;
+
+    fn set_system_prompt(&mut self, prompt: String);
+    fn set_model(&mut self, model: &str);
+    /// Adds the reply to `session`.
+    fn ask(
+        &self,
+        session: &mut Session,
+    ) -> impl Future<Output = Result<GeminiResponse, GeminiResponseError>> + Send;
+    /// The reply is added to the session as the stream is read.
+    fn ask_as_stream(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<Self::Stream, (Session, GeminiResponseError)>> + Send;
+}
+
+impl GeminiInterface for Gemini {
+    type Stream = GeminiResponseStream;
+
+    fn set_system_prompt(&mut self, prompt: String) {
+        *self = std::mem::take(self).set_sys_prompt(Some(prompt.into()));
+    }
+    fn set_model(&mut self, model: &str) {
+        *self = std::mem::take(self).set_model(model);
+    }
+    fn ask(
+        &self,
+        session: &mut Session,
+    ) -> impl Future<Output = Result<GeminiResponse, GeminiResponseError>> + Send {
+        Gemini::ask(self, session)
+    }
+    fn ask_as_stream(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<Self::Stream, (Session, GeminiResponseError)>> + Send {
+        Gemini::ask_as_stream(self, session)
+    }
+}
+
+/// Answers with pre-programmed replies in order. A reply that is a JSON part like
+/// `{"functionCall": {...}}` is sent as that part, anything else as text.
+#[cfg(test)]
+pub struct MockGemini {
+    responses: Mutex<VecDeque<String>>,
+    pub system_prompt: Option<String>,
+    pub model: Option<String>,
+    delay: Duration,
+}
+
+#[cfg(test)]
+impl MockGemini {
+    pub fn new<S: Into<String>>(responses: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into_iter().map(Into::into).collect()),
+            system_prompt: None,
+            model: None,
+            delay: Duration::ZERO,
+        }
+    }
+    /// Waits `delay` before every reply, like Gemini thinking.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+    /// Replies not asked for yet.
+    pub fn remaining(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+    fn reply(&self, session: &mut Session) -> Result<GeminiResponse, GeminiResponseError> {
+        use gemini_client_api::gemini::types::request::Role;
+
+        if session
+            .get_last_chat()
+            .is_some_and(|chat| *chat.role() == Role::Model)
+        {
+            return Err(GeminiResponseError::NothingToRespond);
+        }
+        let Some(response) = self.responses.lock().unwrap().pop_front() else {
+            return Err(GeminiResponseError::StatusNotOk(
+                "MockGemini has no replies left".into(),
+            ));
+        };
+        let part = match serde_json::from_str::<Value>(&response) {
+            Ok(part) if part.get("functionCall").is_some() => part,
+            _ => json!({"text": response}),
+        };
+        let reply: GeminiResponse = serde_json::from_value(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [part]},
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {},
+            "modelVersion": "mock"
+        }))
+        .expect("Mock reply is a valid GeminiResponse");
+        session.reply(reply.get_chat().parts().clone());
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+impl GeminiInterface for MockGemini {
+    type Stream = MockReplyStream;
+
+    fn set_system_prompt(&mut self, prompt: String) {
+        self.system_prompt = Some(prompt);
+    }
+    fn set_model(&mut self, model: &str) {
+        self.model = Some(model.to_string());
+    }
+    async fn ask(&self, session: &mut Session) -> Result<GeminiResponse, GeminiResponseError> {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        self.reply(session)
+    }
+    async fn ask_as_stream(
+        &self,
+        mut session: Session,
+    ) -> Result<MockReplyStream, (Session, GeminiResponseError)> {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        match self.reply(&mut session) {
+            Ok(reply) => Ok(MockReplyStream {
+                reply: Some(reply),
+                session,
+            }),
+            Err(e) => Err((session, e)),
+        }
+    }
+}
+
+/// Sends the whole reply as a single chunk.
+#[cfg(test)]
+pub struct MockReplyStream {
+    reply: Option<GeminiResponse>,
+    session: Session,
+}
+
+#[cfg(test)]
+impl Stream for MockReplyStream {
+    type Item = Result<GeminiResponse, GeminiResponseStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.reply.take().map(Ok))
+    }
+}
+
+#[cfg(test)]
+impl ReplyStream for MockReplyStream {
+    fn get_session(&self) -> &Session {
+        &self.session
+    }
+    fn get_session_owned(self) -> Session {
+        self.session
+    }
+}
+
+#[tokio::test]
+async fn mock_gemini_test() {
+    use gemini_client_api::gemini::types::request::PartType;
+
+    let ai = MockGemini::new([
+        "Namaste!",
+        r#"{"functionCall": {"name": "get_holidays", "args": {"year": 2026}}}"#,
+    ]);
+    let mut session = Session::new(10);
+    session.ask_string("Hi");
+    let reply = ai.ask(&mut session).await.unwrap();
+    assert_eq!(reply.get_chat().get_text_no_think(""), "Namaste!");
+    assert_eq!(session.get_history_length(), 2);
+    // Gemini can't answer itself
+    assert!(matches!(
+        ai.ask(&mut session).await,
+        Err(GeminiResponseError::NothingToRespond)
+    ));
+
+    session.ask_string("Which holidays are there in 2026?");
+    let reply = ai.ask(&mut session).await.unwrap();
+    let PartType::FunctionCall(call) = reply.get_chat().parts()[0].data() else {
+        panic!("Not a function call: {:?}", reply.get_chat());
+    };
+    assert_eq!(call.name(), "get_holidays");
+    assert_eq!(call.args(), &Some(json!({"year": 2026})));
+    assert_eq!(ai.remaining(), 0);
+
+    session.ask_string("Thanks");
+    assert!(matches!(
+        ai.ask(&mut session).await,
+        Err(GeminiResponseError::StatusNotOk(_))
+    ));
+}
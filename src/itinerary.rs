@@ -0,0 +1,1561 @@
+use crate::{
+    api_requests::{
+        currency::CurrencyTips,
+        flights::amadeus::{Endpoint, Flight},
+        hotels::amadeus::Hotel,
+        site_seen::{PlaceDetails, look_up_monument_fee, optimize_sites_for_hotel},
+        trains::rapidapi::Train,
+    },
+    emergency::EmergencyContacts,
+    error::TravelError,
+    utils::{Currency, Date, Time, TravelDuration, is_indian_airport},
+};
+use chrono::NaiveDateTime;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[gemini_schema]
+///A complete trip plan put together for the user.
+pub struct TravelItinerary {
+    ///Short title of the plan (e.g., 'Budget Goa trip by train')
+    pub name: String,
+    ///City the trip starts from
+    pub source: String,
+    ///City the trip is headed to
+    pub destination: String,
+    pub start_date: Date,
+    pub end_date: Date,
+    ///Number of adult travellers
+    pub travellers: u8,
+    pub flights: Vec<Flight>,
+    pub trains: Vec<Train>,
+    ///Names of the hotels chosen for the stay
+    pub hotels: Vec<String>,
+    ///Names of the sites planned to be visited
+    pub sites: Vec<String>,
+    ///Estimated cost of the whole trip for all travellers
+    #[serde(default)]
+    pub estimated_cost: Currency,
+    ///Time spent travelling from source to destination in minutes
+    #[serde(default)]
+    pub journey_minutes: u32,
+    ///Star rating of the chosen hotel
+    pub hotel_rating: Option<f32>,
+    ///Emergency numbers and Indian embassy of the destination country, for trips abroad
+    #[serde(default)]
+    pub emergency_contacts: Option<EmergencyContacts>,
+    ///Exchange rate and money advice for the destination, for trips abroad
+    #[serde(default)]
+    pub currency_tips: Option<CurrencyTips>,
+    ///Day by day plan. Leave it empty to have one made from `sites` and `hotels`
+    #[serde(default)]
+    pub days: Vec<ItineraryDay>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[gemini_schema]
+///One day of the trip.
+pub struct ItineraryDay {
+    ///1 for the day the trip starts
+    pub day: u8,
+    pub date: Date,
+    pub activities: Vec<DayActivity>,
+    ///Name of the hotel stayed at that night. Empty on the last day
+    pub hotel: Option<String>,
+    ///Food for all travellers
+    pub meals_estimated: Currency,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[gemini_schema]
+pub struct DayActivity {
+    pub time: Option<Time>,
+    pub place: String,
+    pub description: String,
+    pub duration_minutes: Option<u32>,
+    ///For all travellers
+    pub cost_estimate: Option<Currency>,
+}
+
+/// Getting to the airport, checking in and security before a flight, and collecting bags and
+/// getting out after it.
+const DOMESTIC_TRANSFER_MINUTES: u32 = 60;
+const INTERNATIONAL_TRANSFER_MINUTES: u32 = 2 * 60;
+/// Shortest layover the timeline doesn't warn about, enough to change terminals in India.
+const MIN_CONNECTION_MINUTES: u32 = 90;
+/// Three meals at mid-range restaurants for one traveller.
+const MEALS_PER_DAY_INR: f32 = 1200.0;
+
+impl TravelItinerary {
+    /// Entrance fees of the `sites` with a known fee for all travellers. `None` when no fee is
+    /// known.
+    pub fn estimated_entry_fees(&self) -> Option<Currency> {
+        let fees: Vec<f32> = self
+            .sites
+            .iter()
+            .filter_map(|site| look_up_monument_fee(site))
+            .collect();
+        if fees.is_empty() {
+            return None;
+        }
+        Some(Currency::Inr(
+            fees.iter().sum::<f32>() * f32::from(self.travellers),
+        ))
+    }
+    pub fn transport_mode(&self) -> &'static str {
+        match (self.flights.is_empty(), self.trains.is_empty()) {
+            (false, true) => "Flight",
+            (true, false) => "Train",
+            (false, false) => "Flight + Train",
+            (true, true) => "Not specified",
+        }
+    }
+    /// Time between the arrival of each flight and the departure of the next, in the order of
+    /// `flights`. `None` when either time can't be read, zero when the flights overlap.
+    pub fn layovers(&self) -> Vec<Option<TravelDuration>> {
+        self.flights
+            .windows(2)
+            .map(|legs| {
+                let arrives = legs[0].arrival()?.date_time()?;
+                let departs = legs[1].departure()?.date_time()?;
+                Some(TravelDuration::from_datetimes(
+                    &arrives.and_utc(),
+                    &departs.and_utc(),
+                ))
+            })
+            .collect()
+    }
+    /// `false` when a layover is shorter than `min_minutes` or its length is unknown.
+    pub fn is_connection_time_sufficient(&self, min_minutes: u32) -> bool {
+        self.layovers()
+            .iter()
+            .all(|layover| layover.is_some_and(|layover| layover.total_minutes() >= min_minutes))
+    }
+    /// One day for each date from `start_date` to `end_date`. A site is visited each day from
+    /// the day after arrival, and the first hotel is stayed at every night.
+    pub fn build_days(&self) -> Vec<ItineraryDay> {
+        let travellers = f32::from(self.travellers);
+        let mut sites = self.sites.iter();
+        (0..)
+            .map_while(|day| self.start_date.add_days(day))
+            .take_while(|date| *date <= self.end_date)
+            .zip(1..=u8::MAX)
+            .map(|(date, day)| {
+                let last_day = date == self.end_date;
+                let site = (day > 1 && !last_day).then(|| sites.next()).flatten();
+                ItineraryDay {
+                    day,
+                    activities: site
+                        .map(|site| DayActivity {
+                            time: Some(at_hour(SIGHTSEEING_HOUR)),
+                            place: site.clone(),
+                            description: format!("Visit {site}"),
+                            duration_minutes: None,
+                            cost_estimate: look_up_monument_fee(site)
+                                .map(|fee| Currency::Inr(fee * travellers)),
+                        })
+                        .into_iter()
+                        .collect(),
+                    hotel: self.hotels.first().filter(|_| !last_day).cloned(),
+                    meals_estimated: Currency::Inr(MEALS_PER_DAY_INR * travellers),
+                    date,
+                }
+            })
+            .collect()
+    }
+    /// `days` as planned, or built when none were.
+    pub fn day_plan(&self) -> Vec<ItineraryDay> {
+        if self.days.is_empty() {
+            self.build_days()
+        } else {
+            self.days.clone()
+        }
+    }
+}
+
+fn transfer_minutes(flight: &Flight) -> u32 {
+    let domestic = [flight.departure(), flight.arrival()]
+        .into_iter()
+        .all(|endpoint| endpoint.is_some_and(|endpoint| is_indian_airport(&endpoint.iata_code)));
+    if domestic {
+        DOMESTIC_TRANSFER_MINUTES
+    } else {
+        INTERNATIONAL_TRANSFER_MINUTES
+    }
+}
+
+/// Door to door time of the flights of `itinerary`: every flight, the layovers between them and
+/// the airport transfers of each flight. Flights and layovers of unknown length are left out.
+/// Times are local to each airport, so layovers across time zones are off by the difference.
+pub fn total_travel_time(itinerary: &TravelItinerary) -> TravelDuration {
+    let flights: u32 = itinerary
+        .flights
+        .iter()
+        .filter_map(|flight| flight.duration())
+        .map(TravelDuration::total_minutes)
+        .sum();
+    let layovers: u32 = itinerary
+        .layovers()
+        .into_iter()
+        .flatten()
+        .map(TravelDuration::total_minutes)
+        .sum();
+    let transfers: u32 = itinerary.flights.iter().map(transfer_minutes).sum();
+    TravelDuration::new_from_minutes(flights + layovers + transfers)
+}
+
+#[derive(Serialize, Debug)]
+pub struct ComparisonRow {
+    pub itinerary_name: String,
+    pub transport_mode: String,
+    pub journey_time: Duration,
+    pub total_cost: Currency,
+    pub hotel_rating: Option<f32>,
+}
+
+/// Winners are indices into `rows`.
+#[derive(Serialize, Debug)]
+pub struct TripComparisonReport {
+    pub rows: Vec<ComparisonRow>,
+    pub winner_by_price: usize,
+    pub winner_by_speed: usize,
+    pub winner_by_comfort: usize,
+}
+
+fn index_of_min_by<F: Fn(&ComparisonRow) -> f32>(rows: &[ComparisonRow], key: F) -> usize {
+    rows.iter()
+        .enumerate()
+        .min_by(|a, b| key(a.1).total_cmp(&key(b.1)))
+        .map(|(i, _)| i)
+        .unwrap_or_default()
+}
+
+/// Gemini's `journey_minutes` leaves out layovers and airport transfers, so trips by flight
+/// alone use `total_travel_time` instead.
+fn journey_minutes(itinerary: &TravelItinerary) -> u32 {
+    if itinerary.flights.is_empty() || !itinerary.trains.is_empty() {
+        itinerary.journey_minutes
+    } else {
+        total_travel_time(itinerary).total_minutes()
+    }
+}
+
+/// Winners default to `0` when `itineraries` is empty.
+pub fn compare_itineraries(itineraries: &[TravelItinerary]) -> TripComparisonReport {
+    let rows: Vec<ComparisonRow> = itineraries
+        .iter()
+        .map(|itinerary| ComparisonRow {
+            itinerary_name: itinerary.name.clone(),
+            transport_mode: itinerary.transport_mode().to_string(),
+            journey_time: Duration::from_secs(u64::from(journey_minutes(itinerary)) * 60),
+            total_cost: itinerary.estimated_cost.clone(),
+            hotel_rating: itinerary.hotel_rating,
+        })
+        .collect();
+
+    TripComparisonReport {
+        winner_by_price: index_of_min_by(&rows, |row| row.total_cost.to_amount()),
+        winner_by_speed: index_of_min_by(&rows, |row| row.journey_time.as_secs_f32()),
+        winner_by_comfort: index_of_min_by(&rows, |row| -row.hotel_rating.unwrap_or_default()),
+        rows,
+    }
+}
+
+impl TripComparisonReport {
+    pub fn to_markdown(&self) -> String {
+        let mut table = String::from(
+            "| Option | Transport | Journey time | Total cost | Hotel rating |\n|---|---|---|---|---|\n",
+        );
+        for row in &self.rows {
+            let journey_time =
+                TravelDuration::new_from_minutes((row.journey_time.as_secs() / 60) as u32);
+            let rating = row
+                .hotel_rating
+                .map(|rating| format!("{rating:.1}★"))
+                .unwrap_or_else(|| "-".to_string());
+            table.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                row.itinerary_name,
+                row.transport_mode,
+                journey_time.to_human_string(),
+                row.total_cost,
+                rating
+            ));
+        }
+        if !self.rows.is_empty() {
+            table.push_str(&format!(
+                "\nCheapest: {}\nFastest: {}\nMost comfortable: {}\n",
+                self.rows[self.winner_by_price].itinerary_name,
+                self.rows[self.winner_by_speed].itinerary_name,
+                self.rows[self.winner_by_comfort].itinerary_name
+            ));
+        }
+        table
+    }
+}
+
+#[gemini_function]
+///Compare two or more candidate itineraries (e.g., budget vs luxury, flight vs train) side by side. Returns a markdown table to show the user.
+pub async fn compare_travel_options(
+    itineraries: Vec<TravelItinerary>,
+) -> Result<String, TravelError> {
+    if itineraries.len() < 2 {
+        return Err(TravelError::InvalidInput(
+            "At least 2 itineraries are needed for a comparison".into(),
+        ));
+    }
+    Ok(compare_itineraries(&itineraries).to_markdown())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[gemini_schema]
+///A hotel the user could stay at instead.
+pub struct HotelOption {
+    pub name: String,
+    ///Price of the whole stay
+    pub total_price: Currency,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub enum TourPlanResult {
+    WithinBudget(Box<TravelItinerary>),
+    /// `alternative` is `original` with the cheapest flights and hotel and free sites only.
+    OverBudget {
+        original: Box<TravelItinerary>,
+        alternative: Box<TravelItinerary>,
+        savings: Currency,
+    },
+}
+
+impl TravelItinerary {
+    pub fn total_cost(&self) -> &Currency {
+        &self.estimated_cost
+    }
+    /// Cheapest of `flight_options` on the route of each flight, the cheapest of `hotel_options`
+    /// when the plan has a single hotel, and only the sites without an entrance fee. Options
+    /// priced in another currency than `estimated_cost` are left out.
+    pub fn budget_alternative(
+        &self,
+        flight_options: &[Flight],
+        hotel_options: &[HotelOption],
+    ) -> TravelItinerary {
+        let code = self.estimated_cost.currency_code();
+        let same_currency = |price: &Currency| price.currency_code() == code;
+        let route = |flight: &Flight| {
+            Some((
+                flight.departure()?.iata_code.clone(),
+                flight.arrival()?.iata_code.clone(),
+            ))
+        };
+        let mut alternative = self.clone();
+        let mut savings = 0.0;
+
+        for flight in alternative.flights.iter_mut() {
+            let cheapest = flight_options
+                .iter()
+                .filter(|option| same_currency(&option.price) && route(option) == route(flight))
+                .min_by(|a, b| a.price.to_amount().total_cmp(&b.price.to_amount()));
+            if let Some(cheapest) = cheapest
+                && same_currency(&flight.price)
+                && cheapest.price.to_amount() < flight.price.to_amount()
+            {
+                savings += flight.price.to_amount() - cheapest.price.to_amount();
+                *flight = cheapest.clone();
+            }
+        }
+
+        if let [hotel] = alternative.hotels.as_mut_slice() {
+            let booked = hotel_options.iter().find(|option| {
+                option.name.eq_ignore_ascii_case(hotel) && same_currency(&option.total_price)
+            });
+            let cheapest = hotel_options
+                .iter()
+                .filter(|option| same_currency(&option.total_price))
+                .min_by(|a, b| {
+                    a.total_price
+                        .to_amount()
+                        .total_cmp(&b.total_price.to_amount())
+                });
+            if let (Some(booked), Some(cheapest)) = (booked, cheapest)
+                && cheapest.total_price.to_amount() < booked.total_price.to_amount()
+            {
+                savings += booked.total_price.to_amount() - cheapest.total_price.to_amount();
+                *hotel = cheapest.name.clone();
+            }
+        }
+        if let [hotel] = alternative.hotels.as_slice() {
+            for stay in alternative
+                .days
+                .iter_mut()
+                .filter_map(|day| day.hotel.as_mut())
+            {
+                stay.clone_from(hotel);
+            }
+        }
+
+        let paid_sites = alternative
+            .sites
+            .extract_if(.., |site| {
+                look_up_monument_fee(site).is_some_and(|fee| fee > 0.0)
+            })
+            .collect::<Vec<_>>();
+        if code == "INR" {
+            savings += paid_sites
+                .iter()
+                .filter_map(|site| look_up_monument_fee(site))
+                .sum::<f32>()
+                * f32::from(self.travellers);
+        }
+
+        for day in &mut alternative.days {
+            day.activities
+                .retain(|activity| !paid_sites.contains(&activity.place));
+        }
+
+        alternative.name = format!("{} (budget option)", self.name);
+        alternative.estimated_cost =
+            Currency::from_code(code, (self.estimated_cost.to_amount() - savings).max(0.0))
+                .unwrap_or_else(|_| self.estimated_cost.clone());
+        alternative
+    }
+}
+
+pub fn plan_within_budget(
+    itinerary: TravelItinerary,
+    budget: &Currency,
+    flight_options: &[Flight],
+    hotel_options: &[HotelOption],
+) -> Result<TourPlanResult, TravelError> {
+    let cost = itinerary.total_cost();
+    if budget.currency_code() != cost.currency_code() {
+        return Err(TravelError::InvalidInput(format!(
+            "Budget is in {} but the itinerary costs are in {}",
+            budget.currency_code(),
+            cost.currency_code()
+        )));
+    }
+    if cost.to_amount() <= budget.to_amount() {
+        return Ok(TourPlanResult::WithinBudget(Box::new(itinerary)));
+    }
+    let alternative = itinerary.budget_alternative(flight_options, hotel_options);
+    let savings = Currency::from_code(
+        cost.currency_code(),
+        cost.to_amount() - alternative.total_cost().to_amount(),
+    )?;
+    Ok(TourPlanResult::OverBudget {
+        original: Box::new(itinerary),
+        alternative: Box::new(alternative),
+        savings,
+    })
+}
+
+#[gemini_function]
+///Check a planned itinerary against the user's budget. When it is over budget, a cheaper alternative is returned as well, so present both and let the user choose.
+pub async fn check_trip_budget(
+    itinerary: TravelItinerary,
+    ///Budget for the whole trip, in the currency of the itinerary's `estimated_cost`
+    budget: Currency,
+    ///Flights found for the trip, to pick cheaper ones from
+    flight_options: Vec<Flight>,
+    ///Hotels found for the stay with their price, including the chosen one
+    hotel_options: Vec<HotelOption>,
+) -> Result<TourPlanResult, TravelError> {
+    plan_within_budget(itinerary, &budget, &flight_options, &hotel_options)
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimetableEventType {
+    Departure,
+    Arrival,
+    CheckIn,
+    CheckOut,
+    SightSeeing,
+    Meal,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TimetableEntry {
+    pub date: Date,
+    pub time: Time,
+    pub event_type: TimetableEventType,
+    pub description: String,
+    pub location: String,
+}
+
+/// Every event of a trip in the order they happen.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Timetable {
+    pub entries: Vec<TimetableEntry>,
+}
+
+/// Usual hotel check-in and check-out times in India.
+const CHECK_IN_HOUR: u8 = 14;
+const CHECK_OUT_HOUR: u8 = 11;
+const SIGHTSEEING_HOUR: u8 = 10;
+const DINNER_HOUR: u8 = 20;
+
+fn at_hour(hour: u8) -> Time {
+    Time::new(hour, 0, 0).unwrap()
+}
+
+/// Departure and arrival time of `train` and the days from its departure to its arrival.
+fn train_times(train: &Train) -> Option<(Time, Time, u64)> {
+    let departs = Time::from_hh_mm_ss(&format!("{}:00", train.from_sta)).ok()?;
+    let arrives = Time::from_hh_mm_ss(&format!("{}:00", train.to_sta)).ok()?;
+    let days = match train.journey_minutes {
+        Some(minutes) => {
+            (u64::from(departs.hour()) * 60 + u64::from(departs.minute()) + u64::from(minutes))
+                / (24 * 60)
+        }
+        // Without the journey time only an overnight run can be told apart
+        None => u64::from(arrives <= departs),
+    };
+    Some((departs, arrives, days))
+}
+
+impl Timetable {
+    /// Flights keep their own dates and times. Trains leave on `start_date`, hotels are checked
+    /// into on `start_date` and out of on `end_date`, and activities are taken from the
+    /// `day_plan`. Events whose date or time can't be read are left out.
+    pub fn from_itinerary(itinerary: &TravelItinerary) -> Timetable {
+        let mut entries = Vec::new();
+        let entry = |date: Date, time: Time, event_type, description: String, location: &str| {
+            TimetableEntry {
+                date,
+                time,
+                event_type,
+                description,
+                location: location.to_string(),
+            }
+        };
+
+        for segment in itinerary
+            .flights
+            .iter()
+            .flat_map(|flight| &flight.itineraries)
+            .flat_map(|flight| &flight.segments)
+        {
+            let flight_number = format!("{}{}", segment.carrier_code, segment.number);
+            for (endpoint, event_type, verb) in [
+                (&segment.departure, TimetableEventType::Departure, "departs"),
+                (&segment.arrival, TimetableEventType::Arrival, "arrives"),
+            ] {
+                let date = endpoint
+                    .at
+                    .split_once('T')
+                    .and_then(|(date, _)| Date::from_yyyy_mm_dd(date).ok());
+                if let (Some(date), Some(time)) = (date, endpoint.time()) {
+                    entries.push(entry(
+                        date,
+                        time,
+                        event_type,
+                        format!("Flight {flight_number} {verb}"),
+                        &endpoint.iata_code,
+                    ));
+                }
+            }
+        }
+
+        for train in &itinerary.trains {
+            let Some((departs, arrives, days)) = train_times(train) else {
+                continue;
+            };
+            let description = format!("{} ({})", train.train_name, train.train_number);
+            entries.push(entry(
+                itinerary.start_date.clone(),
+                departs,
+                TimetableEventType::Departure,
+                format!("{description} departs"),
+                &itinerary.source,
+            ));
+            if let Some(date) = itinerary.start_date.add_days(days) {
+                entries.push(entry(
+                    date,
+                    arrives,
+                    TimetableEventType::Arrival,
+                    format!("{description} arrives"),
+                    &itinerary.destination,
+                ));
+            }
+        }
+
+        for hotel in &itinerary.hotels {
+            entries.push(entry(
+                itinerary.start_date.clone(),
+                at_hour(CHECK_IN_HOUR),
+                TimetableEventType::CheckIn,
+                format!("Check in to {hotel}"),
+                &itinerary.destination,
+            ));
+            entries.push(entry(
+                itinerary.end_date.clone(),
+                at_hour(CHECK_OUT_HOUR),
+                TimetableEventType::CheckOut,
+                format!("Check out of {hotel}"),
+                &itinerary.destination,
+            ));
+        }
+
+        for day in itinerary.day_plan() {
+            for activity in day.activities {
+                entries.push(entry(
+                    day.date.clone(),
+                    activity.time.unwrap_or_else(|| at_hour(SIGHTSEEING_HOUR)),
+                    TimetableEventType::SightSeeing,
+                    activity.description,
+                    &activity.place,
+                ));
+            }
+        }
+
+        let nights = (0..)
+            .map_while(|day| itinerary.start_date.add_days(day))
+            .take_while(|date| *date < itinerary.end_date);
+        for date in nights {
+            entries.push(entry(
+                date,
+                at_hour(DINNER_HOUR),
+                TimetableEventType::Meal,
+                "Dinner".to_string(),
+                &itinerary.destination,
+            ));
+        }
+
+        // Stable, so events at the same time keep the order they were added in
+        entries.sort_by(|a, b| (&a.date, &a.time).cmp(&(&b.date, &b.time)));
+        Timetable { entries }
+    }
+
+    /// One heading per day followed by its events.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        let mut day = None;
+        for entry in &self.entries {
+            if day != Some(&entry.date) {
+                if day.is_some() {
+                    markdown.push('\n');
+                }
+                markdown.push_str(&format!("### {}\n", entry.date.to_yyyy_mm_dd()));
+                day = Some(&entry.date);
+            }
+            let time = entry.time.to_hh_mm_ss();
+            markdown.push_str(&format!(
+                "- {} {} ({})\n",
+                &time[..5],
+                entry.description,
+                entry.location
+            ));
+        }
+        markdown
+    }
+}
+
+#[gemini_function]
+///Turn the final itinerary into a day-by-day timeline of departures, arrivals, hotel check-ins, sightseeing and meals. Returns markdown to show the user.
+pub async fn get_itinerary_timeline(itinerary: TravelItinerary) -> Result<String, TravelError> {
+    if itinerary.end_date < itinerary.start_date {
+        return Err(TravelError::InvalidInput(format!(
+            "End date {} is before start date {}",
+            itinerary.end_date.to_yyyy_mm_dd(),
+            itinerary.start_date.to_yyyy_mm_dd()
+        )));
+    }
+    let mut timeline = Timetable::from_itinerary(&itinerary).to_markdown();
+    if let Some(fees) = itinerary.estimated_entry_fees() {
+        timeline.push_str(&format!(
+            "\n🎟️ Estimated entry fees for {} travellers: {fees}\n",
+            itinerary.travellers
+        ));
+    }
+    if !itinerary.is_connection_time_sufficient(MIN_CONNECTION_MINUTES) {
+        timeline.push_str(&format!(
+            "\n⚠️ A connection between flights is shorter than {} or its timing is unknown.\n",
+            TravelDuration::new_from_minutes(MIN_CONNECTION_MINUTES).to_human_string()
+        ));
+    }
+    Ok(timeline)
+}
+
+/// Clearing customs and immigration when a flight crosses a border.
+const CUSTOMS_MINUTES: u32 = 30;
+/// Getting between terminals, or between a station and an airport, on top of the transfer
+/// time of the flight.
+const TERMINAL_CHANGE_MINUTES: u32 = MIN_CONNECTION_MINUTES - DOMESTIC_TRANSFER_MINUTES;
+
+/// Change from one leg of the trip to the next, e.g. from a train to a flight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub arrival_time: NaiveDateTime,
+    pub departure_time: NaiveDateTime,
+    ///Both legs use the same airport terminal
+    pub same_terminal: bool,
+    ///One of the legs is a flight abroad or back to India
+    pub requires_customs: bool,
+}
+
+impl Connection {
+    /// Transfer time of a flight, plus `walking_minutes` to another terminal and the customs
+    /// time when they apply.
+    pub fn required_minutes(&self, walking_minutes: u32) -> u32 {
+        let mut minutes = DOMESTIC_TRANSFER_MINUTES;
+        if !self.same_terminal {
+            minutes += walking_minutes;
+        }
+        if self.requires_customs {
+            minutes += CUSTOMS_MINUTES;
+        }
+        minutes
+    }
+    pub fn available_minutes(&self) -> i64 {
+        (self.departure_time - self.arrival_time).num_minutes()
+    }
+    pub fn is_sufficient(&self, walking_minutes: u32) -> bool {
+        self.available_minutes() >= i64::from(self.required_minutes(walking_minutes))
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConnectionWarning {
+    ///Leg arrived by, e.g. 'Flight 6E512 to DEL'
+    pub arriving: String,
+    ///Leg to catch next
+    pub departing: String,
+    ///Negative when the next leg leaves before the first arrives
+    pub available_minutes: i64,
+    pub required_minutes: u32,
+    pub message: String,
+}
+
+/// A flight or train of the trip with its times.
+struct Leg {
+    name: String,
+    departs: NaiveDateTime,
+    arrives: NaiveDateTime,
+    ///Airport and terminal, `None` for trains
+    departure_terminal: Option<(String, Option<String>)>,
+    arrival_terminal: Option<(String, Option<String>)>,
+    crosses_border: bool,
+}
+
+impl Leg {
+    fn from_flight(flight: &Flight) -> Option<Leg> {
+        let (departure, arrival) = (flight.departure()?, flight.arrival()?);
+        let segment = flight.itineraries.first()?.segments.first()?;
+        let terminal =
+            |endpoint: &Endpoint| Some((endpoint.iata_code.clone(), endpoint.terminal.clone()));
+        Some(Leg {
+            name: format!(
+                "Flight {}{} to {}",
+                segment.carrier_code, segment.number, arrival.iata_code
+            ),
+            departs: departure.date_time()?,
+            arrives: arrival.date_time()?,
+            departure_terminal: terminal(departure),
+            arrival_terminal: terminal(arrival),
+            crosses_border: is_indian_airport(&departure.iata_code)
+                != is_indian_airport(&arrival.iata_code),
+        })
+    }
+    /// Trains carry no date, so they are taken to leave on `start_date` like in the timetable.
+    fn from_train(train: &Train, start_date: &Date) -> Option<Leg> {
+        let (departs, arrives, days) = train_times(train)?;
+        let arrival_date = start_date.add_days(days)?;
+        let at = |date: Date, time: Time| {
+            date.to_naive_date()?
+                .and_hms_opt(time.hour().into(), time.minute().into(), 0)
+        };
+        Some(Leg {
+            name: format!("{} ({})", train.train_name, train.train_number),
+            departs: at(start_date.clone(), departs)?,
+            arrives: at(arrival_date, arrives)?,
+            departure_terminal: None,
+            arrival_terminal: None,
+            crosses_border: false,
+        })
+    }
+}
+
+impl TravelItinerary {
+    /// `sites` grouped along a short route from `starting_hotel`, with the sites whose location
+    /// isn't known at the end in the order given.
+    pub fn optimize_sightseeing_order(
+        sites: &[PlaceDetails],
+        starting_hotel: &Hotel,
+    ) -> Vec<PlaceDetails> {
+        let mut route = sites.to_vec();
+        let unlocated = optimize_sites_for_hotel(starting_hotel, &mut route);
+        route.extend(unlocated);
+        route
+    }
+    /// Warnings for the changes between flights and trains that are too tight, in the order
+    /// the legs depart. Legs whose date or times can't be read are left out. Changing
+    /// terminals within India needs `MIN_CONNECTION_MINUTES` like in the timeline.
+    pub fn validate_connections(&self) -> Vec<ConnectionWarning> {
+        let mut legs: Vec<Leg> = self
+            .flights
+            .iter()
+            .filter_map(Leg::from_flight)
+            .chain(
+                self.trains
+                    .iter()
+                    .filter_map(|train| Leg::from_train(train, &self.start_date)),
+            )
+            .collect();
+        legs.sort_by_key(|leg| leg.departs);
+        legs.windows(2)
+            .filter_map(|legs| {
+                let (arriving, departing) = (&legs[0], &legs[1]);
+                let connection = Connection {
+                    arrival_time: arriving.arrives,
+                    departure_time: departing.departs,
+                    same_terminal: arriving.arrival_terminal.is_some()
+                        && arriving.arrival_terminal == departing.departure_terminal,
+                    requires_customs: arriving.crosses_border || departing.crosses_border,
+                };
+                if connection.is_sufficient(TERMINAL_CHANGE_MINUTES) {
+                    return None;
+                }
+                let available_minutes = connection.available_minutes();
+                let required_minutes = connection.required_minutes(TERMINAL_CHANGE_MINUTES);
+                let message = if available_minutes < 0 {
+                    format!("{} leaves before {} arrives", departing.name, arriving.name)
+                } else {
+                    format!(
+                        "Only {} between {} and {}, at least {} is needed",
+                        TravelDuration::new_from_minutes(available_minutes as u32)
+                            .to_human_string(),
+                        arriving.name,
+                        departing.name,
+                        TravelDuration::new_from_minutes(required_minutes).to_human_string()
+                    )
+                };
+                Some(ConnectionWarning {
+                    arriving: arriving.name.clone(),
+                    departing: departing.name.clone(),
+                    available_minutes,
+                    required_minutes,
+                    message,
+                })
+            })
+            .collect()
+    }
+}
+
+#[gemini_function]
+///Check that there is enough time to change between the flights and trains of an itinerary, including terminal changes and customs. Call it for every itinerary with more than one flight or train and warn the user about each returned connection.
+pub async fn check_itinerary_connections(
+    itinerary: TravelItinerary,
+) -> Result<Vec<ConnectionWarning>, TravelError> {
+    Ok(itinerary.validate_connections())
+}
+
+#[cfg(test)]
+pub fn sample_itinerary() -> TravelItinerary {
+    TravelItinerary {
+        name: "Budget Goa trip".into(),
+        source: "Ranchi".into(),
+        destination: "Goa".into(),
+        start_date: Date::new(2026, 2, 15).unwrap(),
+        end_date: Date::new(2026, 2, 22).unwrap(),
+        travellers: 2,
+        flights: vec![],
+        trains: vec![],
+        hotels: vec!["Taj Fort Aguada".into()],
+        sites: vec!["Baga Beach".into()],
+        estimated_cost: Currency::Inr(42000.0),
+        journey_minutes: 1860,
+        hotel_rating: Some(3.5),
+        emergency_contacts: None,
+        currency_tips: None,
+        days: vec![],
+    }
+}
+
+#[test]
+fn compare_itineraries_test() {
+    let budget = sample_itinerary();
+    let mut luxury = sample_itinerary();
+    luxury.name = "Luxury Goa trip".into();
+    luxury.estimated_cost = Currency::Inr(95000.0);
+    luxury.journey_minutes = 330;
+    luxury.hotel_rating = Some(5.0);
+    let mut unrated = sample_itinerary();
+    unrated.name = "Homestay trip".into();
+    unrated.estimated_cost = Currency::Inr(38000.0);
+    unrated.hotel_rating = None;
+
+    let report = compare_itineraries(&[budget, luxury, unrated]);
+    assert_eq!(report.rows.len(), 3);
+    assert_eq!(report.winner_by_price, 2);
+    assert_eq!(report.winner_by_speed, 1);
+    assert_eq!(report.winner_by_comfort, 1);
+    assert_eq!(report.rows[0].journey_time, Duration::from_secs(1860 * 60));
+
+    let markdown = report.to_markdown();
+    assert!(markdown.starts_with("| Option | Transport |"));
+    assert!(markdown.contains("| Luxury Goa trip | Not specified | 5h 30m | ₹95,000 | 5.0★ |"));
+    assert!(markdown.contains("| Homestay trip | Not specified | 31h 0m | ₹38,000 | - |"));
+    assert!(markdown.contains("Cheapest: Homestay trip"));
+
+    let empty = compare_itineraries(&[]);
+    assert_eq!(empty.winner_by_price, 0);
+    assert!(!empty.to_markdown().contains("Cheapest"));
+}
+
+#[test]
+fn timetable_test() {
+    use crate::api_requests::flights::amadeus::{Endpoint, Itinerary, Segment};
+
+    let segment = |from: &str, departs: &str, to: &str, arrives: &str, number: &str| Segment {
+        departure: Endpoint {
+            iata_code: from.into(),
+            at: departs.into(),
+            terminal: None,
+        },
+        arrival: Endpoint {
+            iata_code: to.into(),
+            at: arrives.into(),
+            terminal: None,
+        },
+        carrier_code: "6E".into(),
+        number: number.into(),
+        duration: "PT2H".into(),
+        operating_carrier: None,
+        operating_flight_number: None,
+    };
+    let mut itinerary = sample_itinerary();
+    itinerary.end_date = Date::new(2026, 2, 18).unwrap();
+    itinerary.sites = vec![
+        "Baga Beach".into(),
+        "Fort Aguada".into(),
+        "Dudhsagar Falls".into(),
+        "Chapora Fort".into(),
+    ];
+    let mut flight: Flight = serde_json::from_value(serde_json::json!({
+        "id": "1",
+        "price": {"Inr": 6000.0},
+        "itineraries": [],
+        "carrier_code": "6E",
+        "overnight": false,
+        "early_morning": false,
+        "journey_time": null
+    }))
+    .unwrap();
+    flight.itineraries = vec![Itinerary {
+        duration: "PT5H".into(),
+        segments: vec![
+            segment(
+                "IXR",
+                "2026-02-15T06:00:00",
+                "DEL",
+                "2026-02-15T08:00:00",
+                "512",
+            ),
+            segment(
+                "DEL",
+                "2026-02-15T10:00:00",
+                "GOI",
+                "2026-02-15T12:30:00",
+                "2131",
+            ),
+        ],
+    }];
+    itinerary.flights = vec![flight];
+
+    let timetable = Timetable::from_itinerary(&itinerary);
+    let events: Vec<(String, TimetableEventType)> = timetable
+        .entries
+        .iter()
+        .map(|entry| (entry.date.to_yyyy_mm_dd(), entry.event_type))
+        .collect();
+    use TimetableEventType::*;
+    let day = |day: u8, event_type| (format!("2026-02-{day}"), event_type);
+    assert_eq!(
+        events,
+        [
+            day(15, Departure),
+            day(15, Arrival),
+            day(15, Departure),
+            day(15, Arrival),
+            day(15, CheckIn),
+            day(15, Meal),
+            day(16, SightSeeing),
+            day(16, Meal),
+            day(17, SightSeeing),
+            day(17, Meal),
+            day(18, CheckOut),
+        ]
+    );
+    assert_eq!(timetable.entries[0].location, "IXR");
+    assert_eq!(timetable.entries[3].description, "Flight 6E2131 arrives");
+    assert_eq!(timetable.entries[6].description, "Visit Baga Beach");
+
+    let markdown = timetable.to_markdown();
+    assert!(markdown.starts_with("### 2026-02-15\n- 06:00 Flight 6E512 departs (IXR)\n"));
+    assert!(markdown.contains("\n\n### 2026-02-16\n- 10:00 Visit Baga Beach (Baga Beach)\n"));
+    assert!(markdown.ends_with("### 2026-02-18\n- 11:00 Check out of Taj Fort Aguada (Goa)\n"));
+}
+
+#[test]
+fn timetable_train_test() {
+    let train = |from_sta: &str, to_sta: &str, journey_minutes| Train {
+        train_number: "12780".into(),
+        train_name: "Goa Express".into(),
+        from_sta: from_sta.into(),
+        to_sta: to_sta.into(),
+        run_days: vec![],
+        train_type: "SF".into(),
+        category: None,
+        journey_minutes,
+        journey_distance_km: None,
+    };
+    let arrival = |train: Train| {
+        let mut itinerary = sample_itinerary();
+        itinerary.hotels.clear();
+        itinerary.sites.clear();
+        itinerary.end_date = itinerary.start_date.clone();
+        itinerary.trains = vec![train];
+        let timetable = Timetable::from_itinerary(&itinerary);
+        assert_eq!(timetable.entries.len(), 2);
+        assert_eq!(
+            timetable.entries[0].event_type,
+            TimetableEventType::Departure
+        );
+        assert_eq!(timetable.entries[0].location, "Ranchi");
+        let arrival = &timetable.entries[1];
+        (arrival.date.to_yyyy_mm_dd(), arrival.time.to_hh_mm_ss())
+    };
+    assert_eq!(
+        arrival(train("08:00", "20:00", None)),
+        ("2026-02-15".to_string(), "20:00:00".to_string())
+    );
+    assert_eq!(
+        arrival(train("15:10", "06:25", None)),
+        ("2026-02-16".to_string(), "06:25:00".to_string())
+    );
+    // 15:10 plus 39h 15m arrives two days later
+    assert_eq!(
+        arrival(train("15:10", "06:25", Some(39 * 60 + 15))),
+        ("2026-02-17".to_string(), "06:25:00".to_string())
+    );
+    assert!(
+        Timetable::from_itinerary(&{
+            let mut itinerary = sample_itinerary();
+            itinerary.trains = vec![train("late", "06:25", None)];
+            itinerary
+        })
+        .entries
+        .iter()
+        .all(|entry| entry.event_type != TimetableEventType::Departure)
+    );
+}
+
+#[cfg(test)]
+fn flight_leg(from: &str, departs: &str, to: &str, arrives: &str, duration: &str) -> Flight {
+    serde_json::from_value(serde_json::json!({
+        "id": "1",
+        "price": {"Inr": 6000.0},
+        "itineraries": [{
+            "duration": duration,
+            "segments": [{
+                "departure": {"iata_code": from, "at": departs},
+                "arrival": {"iata_code": to, "at": arrives},
+                "carrier_code": "6E",
+                "number": "512",
+                "duration": duration
+            }]
+        }],
+        "carrier_code": "6E",
+        "overnight": false,
+        "early_morning": false,
+        "journey_time": null
+    }))
+    .unwrap()
+}
+
+#[test]
+fn total_travel_time_test() {
+    let mut itinerary = sample_itinerary();
+    assert_eq!(total_travel_time(&itinerary).total_minutes(), 0);
+    assert!(itinerary.is_connection_time_sufficient(60));
+
+    // Ranchi to Delhi, a 3 hour layover, then Delhi to Goa
+    itinerary.flights = vec![
+        flight_leg(
+            "IXR",
+            "2026-02-15T06:00:00",
+            "DEL",
+            "2026-02-15T08:00:00",
+            "PT2H",
+        ),
+        flight_leg(
+            "DEL",
+            "2026-02-15T11:00:00",
+            "GOI",
+            "2026-02-15T13:30:00",
+            "PT2H30M",
+        ),
+    ];
+    assert_eq!(
+        itinerary.layovers(),
+        [Some(TravelDuration::new_from_minutes(180))]
+    );
+    // 2h + 3h + 2h 30m of flying and layover, and 1h of transfers for each flight
+    assert_eq!(total_travel_time(&itinerary).to_human_string(), "9h 30m");
+    assert!(itinerary.is_connection_time_sufficient(180));
+    assert!(!itinerary.is_connection_time_sufficient(181));
+    let report = compare_itineraries(&[itinerary.clone(), sample_itinerary()]);
+    assert_eq!(report.rows[0].journey_time, Duration::from_secs(570 * 60));
+    assert_eq!(report.rows[1].journey_time, Duration::from_secs(1860 * 60));
+
+    // On to Dubai the next morning, an international flight
+    itinerary.flights.push(flight_leg(
+        "GOI",
+        "2026-02-16T09:00:00",
+        "DXB",
+        "2026-02-16T11:00:00",
+        "PT3H30M",
+    ));
+    // 19h 30m layover in Goa, 3h 30m flight and 2h of transfers
+    assert_eq!(total_travel_time(&itinerary).to_human_string(), "34h 30m");
+
+    // Overlapping flights can't be connected
+    itinerary.flights[1] = flight_leg(
+        "DEL",
+        "2026-02-15T07:30:00",
+        "GOI",
+        "2026-02-15T10:00:00",
+        "PT2H30M",
+    );
+    assert_eq!(
+        itinerary.layovers()[0],
+        Some(TravelDuration::new_from_minutes(0))
+    );
+    assert!(!itinerary.is_connection_time_sufficient(1));
+
+    // Unknown times are neither counted nor trusted
+    itinerary.flights = vec![
+        flight_leg("IXR", "2026-02-15T06:00:00", "DEL", "soon", "PT2H"),
+        flight_leg(
+            "DEL",
+            "2026-02-15T11:00:00",
+            "GOI",
+            "2026-02-15T13:30:00",
+            "unknown",
+        ),
+    ];
+    assert_eq!(itinerary.layovers(), [None]);
+    assert_eq!(total_travel_time(&itinerary).to_human_string(), "4h 0m");
+    assert!(!itinerary.is_connection_time_sufficient(0));
+}
+
+#[test]
+fn connection_test() {
+    let at = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").unwrap();
+    let mut connection = Connection {
+        arrival_time: at("2026-02-15T08:00"),
+        departure_time: at("2026-02-15T09:30"),
+        same_terminal: true,
+        requires_customs: false,
+    };
+    assert_eq!(connection.available_minutes(), 90);
+    assert_eq!(connection.required_minutes(20), 60);
+    assert!(connection.is_sufficient(20));
+
+    connection.same_terminal = false;
+    assert_eq!(connection.required_minutes(20), 80);
+    assert_eq!(
+        connection.required_minutes(TERMINAL_CHANGE_MINUTES),
+        MIN_CONNECTION_MINUTES
+    );
+    assert!(connection.is_sufficient(30));
+    assert!(!connection.is_sufficient(31));
+
+    connection.requires_customs = true;
+    assert_eq!(connection.required_minutes(20), 110);
+    assert!(!connection.is_sufficient(1));
+    connection.same_terminal = true;
+    assert_eq!(
+        connection.required_minutes(20),
+        DOMESTIC_TRANSFER_MINUTES + CUSTOMS_MINUTES
+    );
+    assert!(connection.is_sufficient(60));
+
+    connection.departure_time = at("2026-02-15T07:00");
+    assert_eq!(connection.available_minutes(), -60);
+    assert!(!connection.is_sufficient(0));
+}
+
+#[tokio::test]
+async fn check_itinerary_connections_test() {
+    let mut itinerary = sample_itinerary();
+    assert!(itinerary.validate_connections().is_empty());
+
+    // Overnight Rajdhani into Delhi at 08:30 on the second day of the trip, then a flight to
+    // Goa at 10:00
+    itinerary.trains = vec![Train {
+        train_number: "12302".into(),
+        train_name: "Howrah Rajdhani".into(),
+        from_sta: "16:50".into(),
+        to_sta: "08:30".into(),
+        run_days: vec![],
+        train_type: "RAJ".into(),
+        category: None,
+        journey_minutes: None,
+        journey_distance_km: None,
+    }];
+    itinerary.flights = vec![flight_leg(
+        "DEL",
+        "2026-02-16T10:00:00",
+        "GOI",
+        "2026-02-16T12:30:00",
+        "PT2H30M",
+    )];
+    // 1h 30m is enough for check-in and getting from the station to the airport
+    assert!(itinerary.validate_connections().is_empty());
+
+    itinerary.flights[0] = flight_leg(
+        "DEL",
+        "2026-02-16T09:00:00",
+        "GOI",
+        "2026-02-16T11:30:00",
+        "PT2H30M",
+    );
+    let warnings = check_itinerary_connections(itinerary.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        warnings,
+        [ConnectionWarning {
+            arriving: "Howrah Rajdhani (12302)".into(),
+            departing: "Flight 6E512 to GOI".into(),
+            available_minutes: 30,
+            required_minutes: 90,
+            message: "Only 0h 30m between Howrah Rajdhani (12302) and Flight 6E512 to GOI, at least 1h 30m is needed".into(),
+        }]
+    );
+
+    // On to Dubai from the same airport 1h 15m after landing in Goa, customs makes it tight
+    itinerary.flights.push(flight_leg(
+        "GOI",
+        "2026-02-16T12:45:00",
+        "DXB",
+        "2026-02-16T14:45:00",
+        "PT3H30M",
+    ));
+    let warnings = itinerary.validate_connections();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[1].available_minutes, 75);
+    assert_eq!(
+        warnings[1].required_minutes,
+        DOMESTIC_TRANSFER_MINUTES + CUSTOMS_MINUTES
+    );
+
+    // The Dubai flight leaves before the Goa flight lands
+    itinerary.flights[1] = flight_leg(
+        "GOI",
+        "2026-02-16T11:00:00",
+        "DXB",
+        "2026-02-16T13:00:00",
+        "PT3H30M",
+    );
+    let warnings = itinerary.validate_connections();
+    assert_eq!(warnings[1].available_minutes, -30);
+    assert_eq!(
+        warnings[1].message,
+        "Flight 6E512 to DXB leaves before Flight 6E512 to GOI arrives"
+    );
+
+    // Straight from the train on day 1 to a flight abroad, customs and the way to the airport
+    // need 2h
+    itinerary.flights = vec![flight_leg(
+        "DEL",
+        "2026-02-16T10:30:00",
+        "DXB",
+        "2026-02-16T12:30:00",
+        "PT3H30M",
+    )];
+    assert!(itinerary.validate_connections().is_empty());
+    itinerary.flights[0] = flight_leg(
+        "DEL",
+        "2026-02-16T10:00:00",
+        "DXB",
+        "2026-02-16T12:00:00",
+        "PT3H30M",
+    );
+    let warnings = itinerary.validate_connections();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].arriving, "Howrah Rajdhani (12302)");
+    assert_eq!(warnings[0].available_minutes, 90);
+    assert_eq!(
+        warnings[0].required_minutes,
+        DOMESTIC_TRANSFER_MINUTES + TERMINAL_CHANGE_MINUTES + CUSTOMS_MINUTES
+    );
+}
+
+#[tokio::test]
+async fn estimated_entry_fees_test() {
+    let mut itinerary = sample_itinerary();
+    assert!(itinerary.estimated_entry_fees().is_none());
+
+    itinerary.destination = "Agra".into();
+    itinerary.sites = vec![
+        "Taj Mahal".into(),
+        "Agra Fort".into(),
+        "Itimad-ud-Daulah's Tomb".into(),
+        "Kinari Bazaar".into(),
+    ];
+    // (50 + 40 + 25) for 2 travellers
+    assert_eq!(
+        itinerary
+            .estimated_entry_fees()
+            .map(|fees| fees.to_amount()),
+        Some(230.0)
+    );
+
+    let timeline = get_itinerary_timeline(itinerary).await.unwrap();
+    assert!(timeline.contains("🎟️ Estimated entry fees for 2 travellers: ₹230"));
+}
+
+#[test]
+fn plan_within_budget_test() {
+    let priced = |price: f32, from: &str, to: &str| {
+        let mut flight = flight_leg(
+            from,
+            "2026-02-15T07:00:00",
+            to,
+            "2026-02-15T09:30:00",
+            "PT2H30M",
+        );
+        flight.price = Currency::Inr(price);
+        flight
+    };
+    let hotel = |name: &str, price: f32| HotelOption {
+        name: name.into(),
+        total_price: Currency::Inr(price),
+    };
+    let mut itinerary = sample_itinerary();
+    itinerary.flights = vec![priced(12000.0, "DEL", "GOI")];
+    itinerary.sites = vec!["Baga Beach".into(), "Taj Mahal".into()];
+    let flights = [
+        priced(9000.0, "DEL", "GOI"),
+        priced(7000.0, "BOM", "GOI"),
+        priced(15000.0, "DEL", "GOI"),
+    ];
+    let hotels = [
+        hotel("Taj Fort Aguada", 30000.0),
+        hotel("Zostel Goa", 6000.0),
+        hotel("Goa Hostel", 4000.0),
+    ];
+
+    let within = plan_within_budget(
+        itinerary.clone(),
+        &Currency::Inr(42000.0),
+        &flights,
+        &hotels,
+    );
+    assert!(
+        matches!(within, Ok(TourPlanResult::WithinBudget(plan)) if plan.name == itinerary.name)
+    );
+
+    let Ok(TourPlanResult::OverBudget {
+        original,
+        alternative,
+        savings,
+    }) = plan_within_budget(
+        itinerary.clone(),
+        &Currency::Inr(20000.0),
+        &flights,
+        &hotels,
+    )
+    else {
+        panic!("Expected an over budget plan");
+    };
+    assert_eq!(original.name, "Budget Goa trip");
+    assert_eq!(alternative.name, "Budget Goa trip (budget option)");
+    assert!(matches!(alternative.flights[0].price, Currency::Inr(price) if price == 9000.0));
+    assert_eq!(alternative.hotels, ["Goa Hostel"]);
+    assert_eq!(alternative.sites, ["Baga Beach"]);
+    // ₹3000 on the flight, ₹26000 on the hotel and ₹50 entry for each of 2 travellers
+    assert!(
+        matches!(savings, Currency::Inr(savings) if savings == 29100.0),
+        "{savings:?}"
+    );
+    assert!(matches!(alternative.estimated_cost, Currency::Inr(cost) if cost == 12900.0));
+
+    // Hotels that can't be compared are kept
+    let Ok(TourPlanResult::OverBudget { alternative, .. }) = plan_within_budget(
+        itinerary.clone(),
+        &Currency::Inr(20000.0),
+        &[],
+        &hotels[1..],
+    ) else {
+        panic!("Expected an over budget plan");
+    };
+    assert_eq!(alternative.hotels, ["Taj Fort Aguada"]);
+    assert!(matches!(alternative.flights[0].price, Currency::Inr(price) if price == 12000.0));
+
+    assert!(matches!(
+        plan_within_budget(itinerary, &Currency::Usd(500.0), &flights, &hotels),
+        Err(TravelError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn itinerary_days_test() {
+    use serde_json::json;
+
+    let mut itinerary = sample_itinerary();
+    itinerary.end_date = Date::new(2026, 2, 18).unwrap();
+    itinerary.sites = vec!["Baga Beach".into(), "Taj Mahal".into()];
+    let days = itinerary.build_days();
+    assert_eq!(days.len(), 4);
+    assert_eq!(
+        days.iter().map(|day| day.day).collect::<Vec<_>>(),
+        [1, 2, 3, 4]
+    );
+    assert!(days[0].activities.is_empty() && days[3].activities.is_empty());
+    assert_eq!(days[1].activities[0].description, "Visit Baga Beach");
+    assert!(days[1].activities[0].cost_estimate.is_none());
+    // ₹50 for each of the two travellers
+    assert_eq!(
+        days[2].activities[0]
+            .cost_estimate
+            .as_ref()
+            .map(Currency::to_amount),
+        Some(100.0)
+    );
+    assert_eq!(days[2].hotel.as_deref(), Some("Taj Fort Aguada"));
+    assert_eq!(days[3].hotel, None);
+    assert_eq!(days[0].meals_estimated.to_amount(), 2400.0);
+
+    let json = serde_json::to_value(&days[1]).unwrap();
+    assert_eq!(json["date"], json!({"year": 2026, "month": 2, "day": 16}));
+    assert_eq!(
+        json["activities"][0]["time"],
+        json!({"hour": 10, "minute": 0, "second": 0})
+    );
+    assert_eq!(json["meals_estimated"], json!({"Inr": 2400.0}));
+
+    // Days planned by Gemini are kept, and they drive the timeline
+    let mut planned = serde_json::to_value(&itinerary).unwrap();
+    planned["days"] = json!([{
+        "day": 2,
+        "date": "2026-02-16",
+        "activities": [{
+            "time": {"hour": 7, "minute": 30, "second": 0},
+            "place": "Dudhsagar Falls",
+            "description": "Jeep safari to Dudhsagar Falls",
+            "duration_minutes": 360,
+            "cost_estimate": {"Inr": 1800.0}
+        }],
+        "hotel": "Taj Fort Aguada",
+        "meals_estimated": {"Inr": 3000.0}
+    }]);
+    let planned: TravelItinerary = serde_json::from_value(planned).unwrap();
+    assert_eq!(planned.day_plan().len(), 1);
+    let timetable = Timetable::from_itinerary(&planned);
+    let sightseeing: Vec<&str> = timetable
+        .entries
+        .iter()
+        .filter(|entry| entry.event_type == TimetableEventType::SightSeeing)
+        .map(|entry| entry.description.as_str())
+        .collect();
+    assert_eq!(sightseeing, ["Jeep safari to Dudhsagar Falls"]);
+
+    let unplanned: TravelItinerary = serde_json::from_value(json!({
+        "name": "Goa",
+        "source": "Ranchi",
+        "destination": "Goa",
+        "start_date": "2026-02-15",
+        "end_date": "2026-02-16",
+        "travellers": 1,
+        "flights": [],
+        "trains": [],
+        "hotels": [],
+        "sites": [],
+        "estimated_cost": {"Inr": 9000.0},
+        "journey_minutes": 120,
+        "hotel_rating": null
+    }))
+    .unwrap();
+    assert!(unplanned.days.is_empty());
+    assert_eq!(unplanned.day_plan().len(), 2);
+}
+
+#[test]
+fn itinerary_without_cost_test() {
+    // Saved before the cost and journey time were added, or left out by Gemini
+    let itinerary: TravelItinerary = serde_json::from_value(serde_json::json!({
+        "name": "Weekend in Puri",
+        "source": "Bhubaneswar",
+        "destination": "Puri",
+        "start_date": "2026-03-07",
+        "end_date": "2026-03-08",
+        "travellers": 2,
+        "flights": [],
+        "trains": [],
+        "hotels": [],
+        "sites": [],
+        "hotel_rating": null
+    }))
+    .unwrap();
+    assert!(matches!(itinerary.estimated_cost, Currency::Inr(cost) if cost == 0.0));
+    assert_eq!(itinerary.journey_minutes, 0);
+}
+
+#[test]
+fn optimize_sightseeing_order_test() {
+    use crate::utils::Coordinates;
+
+    let site = |name: &str, location: Option<(f64, f64)>| -> PlaceDetails {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "address": "Jaipur, Rajasthan",
+            "rating": null,
+            "total_reviews": null,
+            "phone": null,
+            "website": null,
+            "image_url": null,
+            "opening_hours": null,
+            "entrance_fee_inr": null,
+            "location": location.map(|(latitude, longitude)| Coordinates::new(latitude, longitude))
+        }))
+        .unwrap()
+    };
+    let hotel: Hotel = serde_json::from_value(serde_json::json!({
+        "hotel_id": "JAIMIA01",
+        "name": "MI Road Hotel",
+        "latitude": 26.9157,
+        "longitude": 75.8097,
+        "offers": [],
+        "distance_to_center_km": null,
+        "distance_to_airport_km": null
+    }))
+    .unwrap();
+    let sites = [
+        site("Amber Fort", Some((26.9855, 75.8513))),
+        site("Chokhi Dhani", None),
+        site("Hawa Mahal", Some((26.9239, 75.8267))),
+        site("Nahargarh Fort", Some((26.9373, 75.8155))),
+        site("City Palace", Some((26.9258, 75.8237))),
+    ];
+    let order: Vec<String> = TravelItinerary::optimize_sightseeing_order(&sites, &hotel)
+        .into_iter()
+        .map(|site| site.name)
+        .collect();
+    // Around the old city first, then up the hill to the forts
+    assert_eq!(
+        order,
+        [
+            "City Palace",
+            "Hawa Mahal",
+            "Nahargarh Fort",
+            "Amber Fort",
+            "Chokhi Dhani"
+        ]
+    );
+    assert!(TravelItinerary::optimize_sightseeing_order(&[], &hotel).is_empty());
+}
@@ -0,0 +1,209 @@
+use crate::{api_requests::flights::amadeus::Flight, error::TravelError};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use serde::Serialize;
+
+struct RewardProgram {
+    ///IATA airline codes earning in this program
+    codes: &'static [&'static str],
+    airline: &'static str,
+    ///Points per flown km in economy
+    points_per_km: f32,
+    ///Bonus of the first elite tier over the base earn
+    status_bonus_pct: f32,
+    redeemable_for: &'static str,
+}
+
+/// Approximate public earn rates. Revenue based programs are converted to a per km rate using
+/// an average fare.
+static REWARD_PROGRAMS: &[RewardProgram] = &[
+    RewardProgram {
+        // Vistara and Air India Express flights earn Maharaja Club points since the merger
+        codes: &["AI", "UK", "IX"],
+        airline: "Air India (Maharaja Club)",
+        points_per_km: 0.5,
+        status_bonus_pct: 25.0,
+        redeemable_for: "Award flights and upgrades on Air India and Star Alliance partners",
+    },
+    RewardProgram {
+        codes: &["6E"],
+        airline: "IndiGo (BluChip)",
+        points_per_km: 0.3,
+        status_bonus_pct: 0.0,
+        redeemable_for: "IndiGo flight bookings",
+    },
+    RewardProgram {
+        codes: &["SG"],
+        airline: "SpiceJet (SpiceClub)",
+        points_per_km: 0.3,
+        status_bonus_pct: 30.0,
+        redeemable_for: "SpiceJet flights and add-ons",
+    },
+    RewardProgram {
+        codes: &["EK"],
+        airline: "Emirates (Skywards)",
+        points_per_km: 0.4,
+        status_bonus_pct: 25.0,
+        redeemable_for: "Award flights, upgrades and Emirates partner hotels",
+    },
+    RewardProgram {
+        codes: &["QR"],
+        airline: "Qatar Airways (Privilege Club)",
+        points_per_km: 0.4,
+        status_bonus_pct: 50.0,
+        redeemable_for: "Avios for award flights on Qatar Airways and oneworld partners",
+    },
+    RewardProgram {
+        codes: &["SQ"],
+        airline: "Singapore Airlines (KrisFlyer)",
+        points_per_km: 0.45,
+        status_bonus_pct: 25.0,
+        redeemable_for: "Award flights and upgrades on Singapore Airlines and Star Alliance",
+    },
+    RewardProgram {
+        codes: &["LH"],
+        airline: "Lufthansa (Miles & More)",
+        points_per_km: 0.4,
+        status_bonus_pct: 25.0,
+        redeemable_for: "Award flights on Lufthansa Group and Star Alliance partners",
+    },
+    RewardProgram {
+        codes: &["BA"],
+        airline: "British Airways (Executive Club)",
+        points_per_km: 0.35,
+        status_bonus_pct: 25.0,
+        redeemable_for: "Avios for award flights on British Airways and oneworld partners",
+    },
+];
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LoyaltyPoints {
+    pub airline: String,
+    pub base_miles: u32,
+    pub status_bonus_pct: f32,
+    pub redeemable_for: String,
+}
+
+/// `airline` is a code, the airline name (e.g. `IndiGo`) or the name with the program, in any
+/// case.
+fn find_program(airline: &str) -> Option<&'static RewardProgram> {
+    let airline = airline.trim();
+    REWARD_PROGRAMS.iter().find(|program| {
+        let name = program
+            .airline
+            .split_once(" (")
+            .map_or(program.airline, |(name, _)| name);
+        program
+            .codes
+            .iter()
+            .chain([&name, &program.airline])
+            .any(|known| known.eq_ignore_ascii_case(airline))
+    })
+}
+
+fn class_multiplier(class: &str) -> Option<f32> {
+    match class
+        .trim()
+        .to_ascii_lowercase()
+        .replace([' ', '-'], "_")
+        .as_str()
+    {
+        "economy" => Some(1.0),
+        "premium_economy" => Some(1.25),
+        "business" => Some(1.5),
+        "first" => Some(2.0),
+        _ => None,
+    }
+}
+
+impl Flight {
+    /// `airline` is an IATA code (e.g. `6E`) or the airline name. Unknown airlines earn
+    /// nothing and unknown classes earn at the economy rate.
+    pub fn estimate_loyalty_points(airline: &str, distance_km: u32, class: &str) -> LoyaltyPoints {
+        let Some(program) = find_program(airline) else {
+            return LoyaltyPoints {
+                airline: airline.to_string(),
+                base_miles: 0,
+                status_bonus_pct: 0.0,
+                redeemable_for: "No frequent flyer program found".into(),
+            };
+        };
+        let multiplier = class_multiplier(class).unwrap_or(1.0);
+        LoyaltyPoints {
+            airline: program.airline.to_string(),
+            base_miles: (distance_km as f32 * program.points_per_km * multiplier).round() as u32,
+            status_bonus_pct: program.status_bonus_pct,
+            redeemable_for: program.redeemable_for.to_string(),
+        }
+    }
+}
+
+#[gemini_function]
+///Estimate the frequent flyer points the user earns on a flight, e.g. Air India Maharaja Club or IndiGo BluChip.
+pub async fn estimate_loyalty_points(
+    ///IATA carrier code (e.g., 'AI', '6E') or the airline name
+    airline: String,
+    ///Flown distance between the airports in km
+    distance_km: u32,
+    ///Cabin class: 'economy', 'premium_economy', 'business' or 'first'
+    class: String,
+) -> Result<LoyaltyPoints, TravelError> {
+    if find_program(&airline).is_none() {
+        return Err(TravelError::InvalidInput(format!(
+            "No frequent flyer data for airline: {airline}"
+        )));
+    }
+    if class_multiplier(&class).is_none() {
+        return Err(TravelError::InvalidInput(format!(
+            "Unknown cabin class: {class}"
+        )));
+    }
+    Ok(Flight::estimate_loyalty_points(
+        &airline,
+        distance_km,
+        &class,
+    ))
+}
+
+#[test]
+fn estimate_loyalty_points_test() {
+    let air_india = Flight::estimate_loyalty_points("AI", 1150, "economy");
+    assert_eq!(air_india.airline, "Air India (Maharaja Club)");
+    assert_eq!(air_india.base_miles, 575);
+    assert_eq!(air_india.status_bonus_pct, 25.0);
+    assert_eq!(
+        Flight::estimate_loyalty_points("Air India", 1150, "Business").base_miles,
+        863
+    );
+
+    let indigo = Flight::estimate_loyalty_points("6e", 1000, "economy");
+    assert_eq!(indigo.airline, "IndiGo (BluChip)");
+    assert_eq!(indigo.base_miles, 300);
+    assert_eq!(
+        Flight::estimate_loyalty_points("IndiGo", 1000, "economy"),
+        indigo
+    );
+
+    let vistara = Flight::estimate_loyalty_points("UK", 1000, "premium economy");
+    assert_eq!(vistara.airline, "Air India (Maharaja Club)");
+    assert_eq!(vistara.base_miles, 625);
+    assert_eq!(
+        Flight::estimate_loyalty_points("IX", 1000, "economy").airline,
+        "Air India (Maharaja Club)"
+    );
+
+    let unknown = Flight::estimate_loyalty_points("ZZ", 1000, "economy");
+    assert_eq!(unknown.base_miles, 0);
+    assert_eq!(unknown.airline, "ZZ");
+    assert_eq!(
+        Flight::estimate_loyalty_points("", 1000, "economy").base_miles,
+        0
+    );
+    // Only whole names count, "Air" isn't Air India
+    for airline in ["Air", "A", "Indi"] {
+        assert!(find_program(airline).is_none(), "{airline}");
+    }
+    assert_eq!(
+        find_program(" air india (maharaja club) ").map(|program| program.airline),
+        Some("Air India (Maharaja Club)")
+    );
+}
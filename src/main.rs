@@ -1,23 +1,87 @@
 mod api_requests;
+mod config;
 mod constants;
+mod emergency;
+mod error;
+mod export;
 mod function;
+mod gemini_interface;
+mod itinerary;
+mod loyalty;
+mod memory;
+mod monsoon;
+mod persistence;
+mod preferences;
+mod streaming;
+mod transport;
 mod utils;
 
-use crate::function::handle_request;
-use gemini_client_api::{futures::StreamExt, gemini::types::sessions::Session};
+use crate::config::CONFIG;
+use crate::error::TravelError;
+use crate::export::with_exports;
+use crate::function::{inject_context, run_conversation_with_timeout, travel_planner};
+use crate::memory::{TravelMemory, check_memory, with_memory};
+use crate::persistence::{UserSessionStore, merge_session};
+use crate::preferences::TravelPreferences;
+use crate::streaming::StreamingSession;
+use gemini_client_api::gemini::types::sessions::Session;
 use lambda_runtime::{
     LambdaEvent, service_fn,
     streaming::{Body, Response, channel},
     tracing,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::from_str;
+use serde_json::{from_str, json};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-const CHUNK_SEPERATOR: &str = "\n";
+/// Leaves the Lambda a few seconds of its 30 second timeout to report the error and save the
+/// session.
+const DEFAULT_TIMEOUT_SECS: u64 = 25;
 
 #[derive(Serialize, Deserialize)]
 pub struct ApiRequest {
+    /// With a `session_id` this only needs the latest user turn.
+    #[serde(default)]
     pub session: Session,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Loads and saves the conversation in DynamoDB when set.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub preferences: Option<TravelPreferences>,
+    /// Seconds the whole conversation may take, function calls included. Defaults to, and is
+    /// capped at, `DEFAULT_TIMEOUT_SECS`.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// What Gemini remembered in earlier requests. Streamed back as
+    /// `{"travel_memory": {...}}` at the end of the response, to send with the next request.
+    /// Documents exported during the request follow as `{"exports": [...]}`.
+    #[serde(default)]
+    pub travel_memory: TravelMemory,
+}
+
+/// Prepends the stored conversation of `session_id` to the session sent by the client. Fails
+/// when the session belongs to another user.
+async fn load_session(
+    user_id: Option<&str>,
+    session_id: &str,
+    session: Session,
+) -> Result<Session, TravelError> {
+    Ok(match UserSessionStore::load(user_id, session_id).await {
+        Ok(Some(stored)) => merge_session(stored, &session),
+        // Clients that rely on `session_id` may leave out the history limit
+        Ok(None) if session.get_history_limit() == 0 => {
+            merge_session(Session::new(CONFIG.max_context_size.into()), &session)
+        }
+        Ok(None) => session,
+        Err(e @ TravelError::InvalidInput(_)) => return Err(e),
+        Err(e) => {
+            eprintln!("ERROR: Could not load session {session_id}:\n{e}");
+            session
+        }
+    })
 }
 
 #[derive(Deserialize)]
@@ -28,46 +92,52 @@ struct EventBody {
 async fn stream_handler(
     event: LambdaEvent<EventBody>,
 ) -> Result<Response<Body>, lambda_runtime::Error> {
-    let (mut tx, rx) = channel();
+    let (tx, rx) = channel();
     let mut request: ApiRequest = from_str(&event.payload.body)?;
+    check_memory(&request.travel_memory)?;
+    if let Some(session_id) = &request.session_id {
+        request.session =
+            load_session(request.user_id.as_deref(), session_id, request.session).await?;
+    }
+    if let Some(preferences) = &request.preferences {
+        inject_context(&mut request.session, preferences);
+    }
     tokio::spawn(async move {
-        loop {
-            let response = handle_request(request.session).await;
-            match response {
-                Ok(mut response_stream) => {
-                    while let Some(gemini_response) = response_stream.next().await {
-                        match gemini_response {
-                            Ok(data) => {
-                                let response =
-                                    serde_json::to_string(data.get_chat().parts()).unwrap();
-                                println!("{response}");
-                                let chunk = format!("{response}{CHUNK_SEPERATOR}").into();
-                                tx.send_data(chunk).await.unwrap();
-                            }
-                            Err(error) => {
-                                eprintln!("ERROR: Did not send stream due to error:\n{error}")
-                            }
-                        }
-                    }
-                    if !response_stream
-                        .get_session()
-                        .get_last_chat()
-                        .unwrap()
-                        .has_function_call()
-                    {
-                        println!("Response streaming completed.");
-                        break;
-                    } else {
-                        println!("Resolving function calls.");
-                        request.session = response_stream.get_session_owned();
-                    }
-                }
-                Err((session, e)) => {
-                    eprintln!("ERROR: handle_request failed:\n{e}\n{:?}", session);
-                    tx.send_data(e.to_string().into()).await.unwrap();
-                    break;
-                }
-            }
+        let mut stream = StreamingSession::new(tx);
+        let mut ai = travel_planner();
+        let preferences = request.preferences.unwrap_or_default();
+        let timeout = request
+            .timeout_secs
+            .map_or(DEFAULT_TIMEOUT_SECS, |secs| secs.min(DEFAULT_TIMEOUT_SECS));
+        let memory = Arc::new(Mutex::new(request.travel_memory));
+        let exports = Arc::new(Mutex::new(vec![]));
+        let session = with_memory(
+            memory.clone(),
+            with_exports(
+                exports.clone(),
+                run_conversation_with_timeout(
+                    &mut ai,
+                    request.session,
+                    request.user_id.clone(),
+                    &preferences,
+                    &mut stream,
+                    Duration::from_secs(timeout),
+                ),
+            ),
+        )
+        .await;
+        let memory = memory.lock().unwrap().clone();
+        let _ = stream.send_json(&json!({"travel_memory": memory})).await;
+        let exports = std::mem::take(&mut *exports.lock().unwrap());
+        if !exports.is_empty() {
+            let _ = stream.send_json(&json!({"exports": exports})).await;
+        }
+        if let Some(session) = session
+            && let Some(session_id) = &request.session_id
+            && let Err(e) =
+                UserSessionStore::save(request.user_id.as_deref(), session_id, &session).await
+        {
+            eprintln!("ERROR: Could not save session {session_id}:\n{e}");
         }
     });
     Ok(Response::from(rx))
@@ -76,6 +146,7 @@ async fn stream_handler(
 #[tokio::main]
 async fn main() -> Result<(), lambda_runtime::Error> {
     tracing::init_default_subscriber();
+    std::sync::LazyLock::force(&CONFIG);
     match lambda_runtime::run(service_fn(stream_handler)).await {
         Ok(_) => {}
         Err(e) => eprint!("Error:\n{e}"),
@@ -92,7 +163,15 @@ async fn stream_handler_test() {
     let mut session = Session::new(20);
     session.ask_string(r#"I want to travel to goa from ranchi
 I'm planning a 7-day trip for 2 adults starting on February 15th. I prefer a train to save money. I’m looking for a mid-range hotel near North Goa with good Wi-Fi. My budget is roughly ₹50,000 for the whole trip."#);
-    let body = to_string(&ApiRequest { session }).unwrap();
+    let body = to_string(&ApiRequest {
+        session,
+        user_id: None,
+        session_id: None,
+        preferences: None,
+        timeout_secs: None,
+        travel_memory: Default::default(),
+    })
+    .unwrap();
 
     let response = stream_handler(LambdaEvent {
         payload: EventBody { body },
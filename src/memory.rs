@@ -0,0 +1,197 @@
+use crate::{error::TravelError, utils::check_param_length};
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const MAX_KEY_LEN: usize = 64;
+const MAX_VALUE_LEN: usize = 1000;
+const MAX_MEMORY_KEYS: usize = 50;
+
+/// Facts about the trip and the traveller Gemini noted down, e.g. `lower_berth` or
+/// `budget_inr`. Sent by the client with every request and streamed back at its end.
+pub type TravelMemory = HashMap<String, Value>;
+
+tokio::task_local! {
+    /// `travel_memory` of the `ApiRequest` currently being served.
+    static TRAVEL_MEMORY: Arc<Mutex<TravelMemory>>;
+}
+
+/// Runs `future` with `memory` readable and writable by the memory Gemini functions.
+pub async fn with_memory<F: Future>(memory: Arc<Mutex<TravelMemory>>, future: F) -> F::Output {
+    TRAVEL_MEMORY.scope(memory, future).await
+}
+
+/// Checks `memory` sent by the client against the limits of `remember`, so it can't put more
+/// into the conversation than Gemini could have noted down. Values are measured as JSON.
+pub fn check_memory(memory: &TravelMemory) -> Result<(), TravelError> {
+    if memory.len() > MAX_MEMORY_KEYS {
+        return Err(TravelError::InvalidInput(format!(
+            "Travel memory has {} keys, at most {MAX_MEMORY_KEYS} are allowed",
+            memory.len()
+        )));
+    }
+    for (key, value) in memory {
+        check_param_length(key, MAX_KEY_LEN)?;
+        check_param_length(&value.to_string(), MAX_VALUE_LEN)?;
+    }
+    Ok(())
+}
+
+fn current_memory() -> Result<Arc<Mutex<TravelMemory>>, TravelError> {
+    TRAVEL_MEMORY
+        .try_with(Arc::clone)
+        .map_err(|_| TravelError::InvalidInput("No travel memory in this request".into()))
+}
+
+/// Overwrites the value of `key`. `value` is stored as JSON when it parses as JSON, e.g.
+/// `60000` as a number, and as a string otherwise.
+pub fn remember(key: &str, value: &str) -> Result<(), TravelError> {
+    let key = check_param_length(key.trim(), MAX_KEY_LEN)?;
+    if key.is_empty() {
+        return Err(TravelError::InvalidInput("Key is empty".into()));
+    }
+    let value = check_param_length(value.trim(), MAX_VALUE_LEN)?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+
+    let memory = current_memory()?;
+    let mut memory = memory.lock().unwrap();
+    if !memory.contains_key(key) && memory.len() >= MAX_MEMORY_KEYS {
+        return Err(TravelError::InvalidInput(format!(
+            "Travel memory is full, at most {MAX_MEMORY_KEYS} keys can be remembered"
+        )));
+    }
+    memory.insert(key.to_string(), value);
+    Ok(())
+}
+
+/// The value of `key`, or every remembered value without a key. `Value::Null` when `key` was
+/// never remembered.
+pub fn recall(key: Option<&str>) -> Result<Value, TravelError> {
+    let memory = current_memory()?;
+    let memory = memory.lock().unwrap();
+    Ok(match key {
+        Some(key) => memory.get(key.trim()).cloned().unwrap_or(Value::Null),
+        None => json!(*memory),
+    })
+}
+
+#[gemini_function]
+///Remember something about the traveller or the trip for the rest of the conversation, e.g. a berth preference, a food allergy or the budget. Call it as soon as the user mentions one.
+pub async fn remember_preference(
+    ///Short snake_case name (e.g., 'berth_preference', 'allergies', 'budget_inr')
+    key: String,
+    ///Value as text or JSON (e.g., 'lower', '["seafood"]', '60000')
+    value: String,
+) -> Result<String, TravelError> {
+    remember(&key, &value)?;
+    Ok(format!("Remembered {}", key.trim()))
+}
+
+#[gemini_function]
+///Recall what was remembered about the traveller or the trip. Call it without a key at the start of the conversation to get everything.
+pub async fn recall_preference(
+    ///Key passed to remember_preference. Leave it out to get every remembered value
+    key: Option<String>,
+) -> Result<Value, TravelError> {
+    recall(key.as_deref())
+}
+
+#[tokio::test]
+async fn travel_memory_test() {
+    let memory = Arc::new(Mutex::new(TravelMemory::from([(
+        "berth_preference".to_string(),
+        json!("lower"),
+    )])));
+    with_memory(memory.clone(), async {
+        assert_eq!(
+            recall_preference(Some("berth_preference".into()))
+                .await
+                .unwrap(),
+            "lower"
+        );
+        assert_eq!(recall(Some("allergies")).unwrap(), Value::Null);
+
+        remember_preference("allergies".into(), r#"["seafood"]"#.into())
+            .await
+            .unwrap();
+        remember_preference(" budget_inr ".into(), "60000".into())
+            .await
+            .unwrap();
+        remember_preference("berth_preference".into(), "side lower".into())
+            .await
+            .unwrap();
+        assert_eq!(recall(Some("budget_inr")).unwrap(), 60000);
+        assert_eq!(
+            recall_preference(None).await.unwrap(),
+            json!({
+                "berth_preference": "side lower",
+                "allergies": ["seafood"],
+                "budget_inr": 60000
+            })
+        );
+
+        assert!(matches!(
+            remember(" ", "lower"),
+            Err(TravelError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            remember(&"k".repeat(MAX_KEY_LEN + 1), "lower"),
+            Err(TravelError::InvalidInput(_))
+        ));
+    })
+    .await;
+    // Still there for the response once the conversation is over
+    let memory = memory.lock().unwrap();
+    assert_eq!(memory.len(), 3);
+    assert_eq!(memory["allergies"], json!(["seafood"]));
+}
+
+#[tokio::test]
+async fn travel_memory_limits_test() {
+    assert!(matches!(
+        recall_preference(None).await,
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        remember("budget_inr", "60000"),
+        Err(TravelError::InvalidInput(_))
+    ));
+
+    let memory = Arc::new(Mutex::new(
+        (0..MAX_MEMORY_KEYS)
+            .map(|i| (format!("key_{i}"), json!(i)))
+            .collect(),
+    ));
+    with_memory(memory.clone(), async {
+        assert!(matches!(
+            remember("one_more", "1"),
+            Err(TravelError::InvalidInput(_))
+        ));
+        // Keys already remembered can still change
+        remember("key_0", "changed").unwrap();
+    })
+    .await;
+    assert_eq!(memory.lock().unwrap()["key_0"], "changed");
+    assert!(check_memory(&memory.lock().unwrap()).is_ok());
+}
+
+#[test]
+fn check_memory_test() {
+    let memory = |key: String, value: Value| TravelMemory::from([(key, value)]);
+    assert!(check_memory(&TravelMemory::new()).is_ok());
+    assert!(check_memory(&memory("allergies".into(), json!(["seafood"]))).is_ok());
+    for memory in [
+        memory("k".repeat(MAX_KEY_LEN + 1), json!("lower")),
+        memory("notes".into(), json!("x".repeat(MAX_VALUE_LEN))),
+        memory("notes".into(), json!(vec![0; MAX_VALUE_LEN])),
+        (0..=MAX_MEMORY_KEYS)
+            .map(|i| (format!("key_{i}"), json!(i)))
+            .collect(),
+    ] {
+        assert!(matches!(
+            check_memory(&memory),
+            Err(TravelError::InvalidInput(_))
+        ));
+    }
+}
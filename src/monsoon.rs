@@ -0,0 +1,173 @@
+use crate::{error::TravelError, utils::Date};
+use chrono::Datelike;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[gemini_schema]
+///When the monsoon usually arrives at and leaves a destination in a year.
+pub struct MonsoonForecast {
+    pub destination: String,
+    pub monsoon_onset_date: Date,
+    pub monsoon_withdrawal_date: Date,
+    ///Average rainfall of the whole monsoon
+    pub avg_rainfall_mm: f32,
+    ///What travelling there during the monsoon is like
+    pub travel_recommendation: String,
+    ///Whether the travel date asked about falls in the monsoon
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub travel_date_in_monsoon: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct MonsoonCalendar {
+    destination: String,
+    ///Nearby places and states travellers use for the destination
+    #[serde(default)]
+    aliases: Vec<String>,
+    ///Usual onset as `MM-DD`
+    onset: String,
+    ///Usual withdrawal as `MM-DD`, in the same year as the onset
+    withdrawal: String,
+    avg_rainfall_mm: f32,
+    travel_recommendation: String,
+}
+
+/// Normal monsoon dates of popular Indian destinations from the IMD onset and withdrawal maps.
+/// Chennai and Puducherry get most of their rain from the northeast monsoon, so their dates
+/// are of that one. Kept by hand.
+static MONSOON_CALENDAR: LazyLock<Vec<MonsoonCalendar>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../data/monsoon_calendar.json"))
+        .expect("Invalid data/monsoon_calendar.json")
+});
+
+fn find_destination(destination: &str) -> Option<&'static MonsoonCalendar> {
+    let destination = destination.trim();
+    MONSOON_CALENDAR.iter().find(|entry| {
+        entry.destination.eq_ignore_ascii_case(destination)
+            || entry
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(destination))
+    })
+}
+
+/// `month_day` is `MM-DD`.
+fn date_in_year(year: u16, month_day: &str) -> Result<Date, TravelError> {
+    Date::from_yyyy_mm_dd(&format!("{year:04}-{month_day}"))
+        .map_err(|e| TravelError::ParseError(format!("Invalid monsoon date {month_day}: {e}")))
+}
+
+/// `destination` is a city, a state or a well known place like `Munnar`.
+pub fn get_monsoon_forecast(destination: &str, year: u16) -> Result<MonsoonForecast, TravelError> {
+    let entry = find_destination(destination).ok_or_else(|| {
+        TravelError::InvalidInput(format!("No monsoon calendar known for {destination}"))
+    })?;
+    Ok(MonsoonForecast {
+        destination: entry.destination.clone(),
+        monsoon_onset_date: date_in_year(year, &entry.onset)?,
+        monsoon_withdrawal_date: date_in_year(year, &entry.withdrawal)?,
+        avg_rainfall_mm: entry.avg_rainfall_mm,
+        travel_recommendation: entry.travel_recommendation.clone(),
+        travel_date_in_monsoon: None,
+    })
+}
+
+impl MonsoonForecast {
+    pub fn covers(&self, date: &Date) -> bool {
+        (&self.monsoon_onset_date..=&self.monsoon_withdrawal_date).contains(&date)
+    }
+}
+
+impl Date {
+    /// `false` for destinations not in the monsoon calendar.
+    pub fn is_in_monsoon_season(&self, destination: &str) -> bool {
+        let Some(year) = self.to_naive_date().map(|date| date.year() as u16) else {
+            return false;
+        };
+        get_monsoon_forecast(destination, year).is_ok_and(|forecast| forecast.covers(self))
+    }
+}
+
+#[gemini_function]
+///Get when the monsoon arrives at and leaves an Indian destination and what travelling there during it is like. Call it for every Indian destination when the travel dates are between May and December.
+pub async fn monsoon_forecast(
+    ///City, state or well known place (e.g., 'Goa' or 'Munnar')
+    destination: String,
+    travel_date: Date,
+) -> Result<MonsoonForecast, TravelError> {
+    let year = travel_date
+        .to_naive_date()
+        .map(|date| date.year() as u16)
+        .ok_or_else(|| {
+            TravelError::InvalidInput(format!(
+                "Travel date {} doesn't exist",
+                travel_date.to_yyyy_mm_dd()
+            ))
+        })?;
+    let mut forecast = get_monsoon_forecast(&destination, year)?;
+    forecast.travel_date_in_monsoon = Some(forecast.covers(&travel_date));
+    Ok(forecast)
+}
+
+#[test]
+fn goa_monsoon_test() {
+    let date = |y, m, d| Date::new(y, m, d).unwrap();
+    let goa = get_monsoon_forecast("Goa", 2026).unwrap();
+    assert_eq!(goa.monsoon_onset_date, date(2026, 6, 7));
+    assert_eq!(goa.monsoon_withdrawal_date, date(2026, 10, 10));
+    assert!(goa.avg_rainfall_mm > 2000.0);
+    assert_eq!(get_monsoon_forecast(" panjim ", 2026).unwrap(), goa);
+
+    assert!(date(2026, 7, 15).is_in_monsoon_season("Goa"));
+    assert!(date(2026, 6, 7).is_in_monsoon_season("goa"));
+    assert!(date(2026, 10, 10).is_in_monsoon_season("Goa"));
+    assert!(!date(2026, 11, 15).is_in_monsoon_season("Goa"));
+    assert!(!date(2026, 6, 6).is_in_monsoon_season("Goa"));
+}
+
+#[test]
+fn kerala_monsoon_test() {
+    let date = |y, m, d| Date::new(y, m, d).unwrap();
+    let kerala = get_monsoon_forecast("Kochi", 2027).unwrap();
+    assert_eq!(kerala.destination, "Kerala");
+    assert_eq!(kerala.monsoon_onset_date, date(2027, 6, 1));
+    assert_eq!(kerala.monsoon_withdrawal_date, date(2027, 10, 15));
+
+    assert!(date(2027, 6, 1).is_in_monsoon_season("Kerala"));
+    assert!(date(2027, 8, 20).is_in_monsoon_season("Munnar"));
+    assert!(!date(2027, 1, 10).is_in_monsoon_season("Kerala"));
+    assert!(!date(2027, 2, 30).is_in_monsoon_season("Kerala"));
+}
+
+#[tokio::test]
+async fn monsoon_forecast_test() {
+    let date = |y, m, d| Date::new(y, m, d).unwrap();
+    assert_eq!(MONSOON_CALENDAR.len(), 30);
+    for entry in MONSOON_CALENDAR.iter() {
+        let forecast = get_monsoon_forecast(&entry.destination, 2026).unwrap();
+        assert!(
+            forecast.monsoon_onset_date < forecast.monsoon_withdrawal_date,
+            "{}",
+            entry.destination
+        );
+    }
+    assert!(matches!(
+        get_monsoon_forecast("Atlantis", 2026),
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(!date(2026, 7, 15).is_in_monsoon_season("Atlantis"));
+    // Chennai's rain comes with the northeast monsoon
+    assert!(!date(2026, 7, 15).is_in_monsoon_season("Chennai"));
+    assert!(date(2026, 11, 20).is_in_monsoon_season("Chennai"));
+
+    let forecast = monsoon_forecast("Goa".into(), date(2026, 12, 20))
+        .await
+        .unwrap();
+    assert_eq!(forecast.travel_date_in_monsoon, Some(false));
+    let forecast = monsoon_forecast("Goa".into(), date(2026, 7, 20))
+        .await
+        .unwrap();
+    assert_eq!(forecast.travel_date_in_monsoon, Some(true));
+}
@@ -0,0 +1,348 @@
+#[cfg(test)]
+use crate::itinerary::sample_itinerary;
+use crate::{error::TravelError, itinerary::TravelItinerary};
+use aws_sdk_dynamodb::{Client, types::AttributeValue};
+use gemini_client_api::gemini::{
+    types::{request::Role, sessions::Session},
+    utils::{GeminiSchema, gemini_function},
+};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+
+const RECENT_SEARCHES_TABLE: &str = "explorify-recent-searches";
+const SESSIONS_TABLE: &str = "explorify-sessions";
+const MAX_RECENT_SEARCHES: i32 = 5;
+
+static DYNAMO_CLIENT: OnceCell<Client> = OnceCell::const_new();
+
+tokio::task_local! {
+    /// `user_id` of the `ApiRequest` currently being served.
+    static CURRENT_USER: Option<String>;
+}
+
+/// Runs `future` with `user_id` visible to the user scoped Gemini functions.
+pub async fn with_user<F: Future>(user_id: Option<String>, future: F) -> F::Output {
+    CURRENT_USER.scope(user_id, future).await
+}
+
+fn current_user() -> Result<String, TravelError> {
+    CURRENT_USER
+        .try_with(|user| user.clone())
+        .ok()
+        .flatten()
+        .ok_or_else(|| {
+            TravelError::InvalidInput("No user_id in request, user is not signed in".into())
+        })
+}
+
+async fn get_client() -> &'static Client {
+    DYNAMO_CLIENT
+        .get_or_init(|| async {
+            let mut loader = aws_config::from_env();
+            // Points the SDK at DynamoDB local when testing
+            if let Ok(endpoint) = std::env::var("DYNAMODB_ENDPOINT") {
+                loader = loader.endpoint_url(endpoint);
+            }
+            Client::new(&loader.load().await)
+        })
+        .await
+}
+
+fn table_name() -> String {
+    std::env::var("RECENT_SEARCHES_TABLE").unwrap_or_else(|_| RECENT_SEARCHES_TABLE.to_string())
+}
+
+fn sessions_table_name() -> String {
+    std::env::var("SESSIONS_TABLE").unwrap_or_else(|_| SESSIONS_TABLE.to_string())
+}
+
+fn now_millis() -> Result<u128, TravelError> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| TravelError::Persistence(e.to_string()))?
+        .as_millis())
+}
+
+fn to_item(
+    user_id: &str,
+    searched_at: u128,
+    itinerary: &TravelItinerary,
+) -> Result<HashMap<String, AttributeValue>, TravelError> {
+    let itinerary: HashMap<String, AttributeValue> = serde_dynamo::to_item(itinerary)
+        .map_err(|e| TravelError::Persistence(format!("Serializing itinerary failed: {e}")))?;
+    Ok(HashMap::from([
+        (
+            "user_id".to_string(),
+            AttributeValue::S(user_id.to_string()),
+        ),
+        (
+            "searched_at".to_string(),
+            AttributeValue::N(searched_at.to_string()),
+        ),
+        ("itinerary".to_string(), AttributeValue::M(itinerary)),
+    ]))
+}
+
+fn from_item(mut item: HashMap<String, AttributeValue>) -> Result<TravelItinerary, TravelError> {
+    match item.remove("itinerary") {
+        Some(AttributeValue::M(itinerary)) => serde_dynamo::from_item(itinerary)
+            .map_err(|e| TravelError::Persistence(format!("Deserializing itinerary failed: {e}"))),
+        _ => Err(TravelError::Persistence(
+            "Stored search has no itinerary".into(),
+        )),
+    }
+}
+
+/// Stores `itinerary` as the latest search of `user_id`.
+pub async fn save_search(user_id: &str, itinerary: &TravelItinerary) -> Result<(), TravelError> {
+    let searched_at = now_millis()?;
+
+    get_client()
+        .await
+        .put_item()
+        .table_name(table_name())
+        .set_item(Some(to_item(user_id, searched_at, itinerary)?))
+        .send()
+        .await
+        .map_err(|e| TravelError::Persistence(format!("DynamoDB put_item failed: {e}")))?;
+    Ok(())
+}
+
+/// Latest searches of `user_id`, newest first.
+pub async fn get_recent_searches(user_id: &str) -> Result<Vec<TravelItinerary>, TravelError> {
+    let output = get_client()
+        .await
+        .query()
+        .table_name(table_name())
+        .key_condition_expression("user_id = :user_id")
+        .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+        .scan_index_forward(false)
+        .limit(MAX_RECENT_SEARCHES)
+        .send()
+        .await
+        .map_err(|e| TravelError::Persistence(format!("DynamoDB query failed: {e}")))?;
+
+    output
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .map(from_item)
+        .collect()
+}
+
+/// Conversation state keyed by `session_id`, so clients only need to send the latest turn
+/// instead of the whole history. Each session belongs to the `user_id` that first saved it,
+/// requests without one share the empty owner.
+pub struct UserSessionStore;
+
+impl UserSessionStore {
+    /// Fails when `session_id` belongs to another user.
+    pub async fn save(
+        user_id: Option<&str>,
+        session_id: &str,
+        session: &Session,
+    ) -> Result<(), TravelError> {
+        let owner = user_id.unwrap_or_default();
+        get_client()
+            .await
+            .put_item()
+            .table_name(sessions_table_name())
+            .set_item(Some(session_to_item(
+                owner,
+                session_id,
+                now_millis()?,
+                session,
+            )?))
+            .condition_expression("attribute_not_exists(session_id) OR user_id = :user_id")
+            .expression_attribute_values(":user_id", AttributeValue::S(owner.to_string()))
+            .send()
+            .await
+            .map_err(|e| TravelError::Persistence(format!("DynamoDB put_item failed: {e}")))?;
+        Ok(())
+    }
+
+    /// `None` when nothing was saved for `session_id` yet. Fails when it belongs to another
+    /// user.
+    pub async fn load(
+        user_id: Option<&str>,
+        session_id: &str,
+    ) -> Result<Option<Session>, TravelError> {
+        let output = get_client()
+            .await
+            .get_item()
+            .table_name(sessions_table_name())
+            .key("session_id", AttributeValue::S(session_id.to_string()))
+            .send()
+            .await
+            .map_err(|e| TravelError::Persistence(format!("DynamoDB get_item failed: {e}")))?;
+        output
+            .item
+            .map(|item| session_from_item(item, user_id.unwrap_or_default()))
+            .transpose()
+    }
+}
+
+// Stored as JSON since `Session` holds arbitrary function call payloads
+fn session_to_item(
+    user_id: &str,
+    session_id: &str,
+    updated_at: u128,
+    session: &Session,
+) -> Result<HashMap<String, AttributeValue>, TravelError> {
+    let session = serde_json::to_string(session)
+        .map_err(|e| TravelError::Persistence(format!("Serializing session failed: {e}")))?;
+    Ok(HashMap::from([
+        (
+            "session_id".to_string(),
+            AttributeValue::S(session_id.to_string()),
+        ),
+        (
+            "user_id".to_string(),
+            AttributeValue::S(user_id.to_string()),
+        ),
+        (
+            "updated_at".to_string(),
+            AttributeValue::N(updated_at.to_string()),
+        ),
+        ("session".to_string(), AttributeValue::S(session)),
+    ]))
+}
+
+/// Sessions saved before they had an owner count as saved without a `user_id`.
+fn session_from_item(
+    mut item: HashMap<String, AttributeValue>,
+    user_id: &str,
+) -> Result<Session, TravelError> {
+    let owner = match item.get("user_id") {
+        Some(AttributeValue::S(owner)) => owner.as_str(),
+        _ => "",
+    };
+    if owner != user_id {
+        return Err(TravelError::InvalidInput(
+            "The session belongs to another user".into(),
+        ));
+    }
+    match item.remove("session") {
+        Some(AttributeValue::S(session)) => serde_json::from_str(&session)
+            .map_err(|e| TravelError::Persistence(format!("Deserializing session failed: {e}"))),
+        _ => Err(TravelError::Persistence(
+            "Stored item has no session".into(),
+        )),
+    }
+}
+
+/// Appends the user turns of `latest` (what the client sent) to the `stored` session.
+pub fn merge_session(mut stored: Session, latest: &Session) -> Session {
+    for chat in latest.get_history() {
+        if *chat.role() == Role::User {
+            stored.ask(chat.parts().clone());
+        }
+    }
+    stored
+}
+
+#[gemini_function]
+///Save the final itinerary agreed with the user so it can be continued in a later session.
+pub async fn save_my_trip(itinerary: TravelItinerary) -> Result<String, TravelError> {
+    save_search(&current_user()?, &itinerary).await?;
+    Ok("Trip saved".into())
+}
+
+#[gemini_function]
+///Get the trips the user planned in their previous sessions, newest first. Use it to pick up preferences the user already specified.
+pub async fn get_my_recent_trips() -> Result<Vec<TravelItinerary>, TravelError> {
+    get_recent_searches(&current_user()?).await
+}
+
+#[test]
+fn itinerary_item_round_trip_test() {
+    let item = to_item("user-1", 1_700_000_000_000, &sample_itinerary()).unwrap();
+    assert_eq!(item["user_id"], AttributeValue::S("user-1".into()));
+    assert_eq!(
+        item["searched_at"],
+        AttributeValue::N("1700000000000".into())
+    );
+
+    let itinerary = from_item(item).unwrap();
+    assert_eq!(itinerary.name, "Budget Goa trip");
+    assert_eq!(itinerary.hotels, vec!["Taj Fort Aguada".to_string()]);
+}
+
+#[test]
+fn session_item_round_trip_test() {
+    let mut session = Session::new(20);
+    session
+        .ask_string("Plan a trip to Goa")
+        .reply_string("When are you travelling?");
+    let item = session_to_item("user-1", "session-1", 1_700_000_000_000, &session).unwrap();
+    assert_eq!(item["session_id"], AttributeValue::S("session-1".into()));
+    assert_eq!(item["user_id"], AttributeValue::S("user-1".into()));
+
+    // Only its owner gets the session back
+    assert!(matches!(
+        session_from_item(item.clone(), "user-2"),
+        Err(TravelError::InvalidInput(_))
+    ));
+    assert!(session_from_item(item.clone(), "").is_err());
+    let mut latest = Session::new(20);
+    latest.ask_string("From 15th February");
+    let merged = merge_session(session_from_item(item, "user-1").unwrap(), &latest);
+    assert_eq!(merged.get_history_length(), 3);
+    assert_eq!(*merged.get_last_chat().unwrap().role(), Role::User);
+    assert_eq!(merged.get_history_limit(), 20);
+
+    let mut anonymous = session_to_item("", "session-2", 1_700_000_000_000, &session).unwrap();
+    assert!(session_from_item(anonymous.clone(), "").is_ok());
+    anonymous.remove("user_id");
+    assert!(session_from_item(anonymous.clone(), "").is_ok());
+    assert!(session_from_item(anonymous, "user-1").is_err());
+    assert!(session_from_item(HashMap::new(), "").is_err());
+}
+
+#[tokio::test]
+async fn recent_searches_test() {
+    // Needs DynamoDB local: `docker run -p 8000:8000 amazon/dynamodb-local`
+    if std::env::var("DYNAMODB_ENDPOINT").is_err() {
+        println!("Skipping integration test: DYNAMODB_ENDPOINT not found in env");
+        return;
+    }
+    save_search("test-user", &sample_itinerary()).await.unwrap();
+    let searches = get_recent_searches("test-user").await.unwrap();
+    assert_eq!(searches[0].name, "Budget Goa trip");
+}
+
+#[tokio::test]
+async fn user_session_store_test() {
+    // Needs DynamoDB local: `docker run -p 8000:8000 amazon/dynamodb-local`
+    if std::env::var("DYNAMODB_ENDPOINT").is_err() {
+        println!("Skipping integration test: DYNAMODB_ENDPOINT not found in env");
+        return;
+    }
+    let mut session = Session::new(20);
+    session.ask_string("Plan a trip to Goa");
+    UserSessionStore::save(Some("test-user"), "test-session", &session)
+        .await
+        .unwrap();
+    let loaded = UserSessionStore::load(Some("test-user"), "test-session")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(loaded.get_history_length(), 1);
+    assert!(
+        UserSessionStore::load(Some("other-user"), "test-session")
+            .await
+            .is_err()
+    );
+    assert!(
+        UserSessionStore::save(Some("other-user"), "test-session", &session)
+            .await
+            .is_err()
+    );
+    assert!(
+        UserSessionStore::load(Some("test-user"), "missing-session")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
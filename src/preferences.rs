@@ -0,0 +1,251 @@
+use crate::{
+    api_requests::{
+        flights::{amadeus::FlightCollection, low_cost::FlightOffer},
+        hotels::amadeus::Hotel,
+    },
+    error::TravelError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+
+/// Sent by the client along with the session and applied to every function call of the request.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TravelPreferences {
+    /// IATA codes like `AI`. Flights of other airlines are left out of search results. Empty
+    /// allows every airline.
+    #[serde(default)]
+    pub preferred_airlines: Vec<String>,
+    /// Adds the airline meal code to flights and puts hotels with vegetarian meals first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meal_preference: Option<MealPreference>,
+    /// Gemini model to plan with, e.g. a pro model for multi-city trips. Must be one of
+    /// `ALLOWED_GEMINI_MODELS`. Defaults to `GEMINI_MODEL` of the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gemini_model: Option<String>,
+}
+
+/// Models a client may pick. The model name ends up in the request URL, so anything else is
+/// rejected.
+pub const ALLOWED_GEMINI_MODELS: &[&str] = &[
+    "gemini-flash-lite-latest",
+    "gemini-flash-latest",
+    "gemini-pro-latest",
+    "gemini-2.5-flash-lite",
+    "gemini-2.5-flash",
+    "gemini-2.5-pro",
+    "gemini-3-flash-preview",
+    "gemini-3-pro-preview",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MealPreference {
+    Vegetarian,
+    Vegan,
+    Jain,
+}
+
+/// IATA special meal code the airline is asked for when the ticket is booked.
+pub fn amadeus_meal_code(pref: &MealPreference) -> &'static str {
+    match pref {
+        // Asian vegetarian, the usual choice for Indian vegetarians
+        MealPreference::Vegetarian => "AVML",
+        MealPreference::Vegan => "VGML",
+        MealPreference::Jain => "VJML",
+    }
+}
+
+impl TravelPreferences {
+    /// `None` when no model was picked.
+    pub fn validated_gemini_model(&self) -> Result<Option<&str>, TravelError> {
+        let Some(model) = self.gemini_model.as_deref() else {
+            return Ok(None);
+        };
+        if ALLOWED_GEMINI_MODELS.contains(&model) {
+            Ok(Some(model))
+        } else {
+            Err(TravelError::InvalidInput(format!(
+                "Gemini model must be one of {}. Found: {model}",
+                ALLOWED_GEMINI_MODELS.join(", ")
+            )))
+        }
+    }
+    /// Drops the parts of the response of `function` that don't match the preferences, and
+    /// orders or annotates the rest.
+    pub fn filter_response(&self, function: &str, response: Value) -> Value {
+        match function {
+            "flights_between"
+                if !self.preferred_airlines.is_empty() || self.meal_preference.is_some() =>
+            {
+                let Ok(flights) =
+                    serde_json::from_value::<Vec<FlightOffer>>(response["flights"].clone())
+                else {
+                    return response;
+                };
+                let mut flights = FlightCollection(flights);
+                if !self.preferred_airlines.is_empty() {
+                    let preferred: HashSet<String> = self
+                        .preferred_airlines
+                        .iter()
+                        .flat_map(|code| flights.filter_by_airline(code))
+                        .map(|flight| flight.id.clone())
+                        .collect();
+                    flights
+                        .0
+                        .retain(|offer| preferred.contains(&offer.flight().id));
+                }
+                let mut flights = json!(flights.0);
+                if let Some(meal) = &self.meal_preference
+                    && let Some(flights) = flights.as_array_mut()
+                {
+                    for flight in flights.iter_mut().filter_map(Value::as_object_mut) {
+                        flight.insert("special_meal_code".into(), amadeus_meal_code(meal).into());
+                    }
+                }
+                let mut response = response;
+                response["flights"] = flights;
+                response
+            }
+            "hotels_in_city" | "hotels_near_area" if self.meal_preference.is_some() => {
+                let Ok(mut hotels) = serde_json::from_value::<Vec<Hotel>>(response.clone()) else {
+                    return response;
+                };
+                hotels.sort_by_key(|hotel| !hotel.vegetarian_meals_available);
+                json!(hotels)
+            }
+            _ => response,
+        }
+    }
+}
+
+#[test]
+fn filter_response_test() {
+    let flight = |id: &str, carrier: &str| {
+        json!({
+            "id": id,
+            "price": {"Inr": 6000.0},
+            "itineraries": [],
+            "carrier_code": carrier,
+            "journey_time": null
+        })
+    };
+    let response = json!({
+        "flights": [flight("1", "AI"), flight("2", "6E"), flight("3", "UK")],
+        "price_history": null
+    });
+
+    let preferences = TravelPreferences {
+        preferred_airlines: vec!["ai".into(), "UK".into()],
+        ..Default::default()
+    };
+    let filtered = preferences.filter_response("flights_between", response.clone());
+    let ids: Vec<&str> = filtered["flights"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|flight| flight["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["1", "3"]);
+
+    assert_eq!(
+        TravelPreferences::default().filter_response("flights_between", response.clone()),
+        response
+    );
+    assert_eq!(
+        preferences.filter_response("hotels_in_city", response.clone()),
+        response
+    );
+    let error = json!({"Error": "No flights"});
+    assert_eq!(
+        preferences.filter_response("flights_between", error.clone()),
+        error
+    );
+}
+
+#[test]
+fn meal_preference_test() {
+    assert_eq!(amadeus_meal_code(&MealPreference::Vegetarian), "AVML");
+    assert_eq!(amadeus_meal_code(&MealPreference::Vegan), "VGML");
+    assert_eq!(amadeus_meal_code(&MealPreference::Jain), "VJML");
+
+    let preferences: TravelPreferences =
+        serde_json::from_value(json!({"meal_preference": "Jain"})).unwrap();
+    assert_eq!(preferences.meal_preference, Some(MealPreference::Jain));
+
+    let flights = json!({"flights": [{
+        "id": "1",
+        "price": {"Inr": 6000.0},
+        "itineraries": [],
+        "carrier_code": "AI",
+        "journey_time": null
+    }]});
+    let filtered = preferences.filter_response("flights_between", flights);
+    assert_eq!(filtered["flights"][0]["special_meal_code"], "VJML");
+    assert_eq!(filtered["flights"][0]["carrier_code"], "AI");
+
+    let hotel = |id: &str, vegetarian: bool| {
+        json!({
+            "hotel_id": id,
+            "name": id,
+            "latitude": null,
+            "longitude": null,
+            "offers": [],
+            "distance_to_center_km": null,
+            "distance_to_airport_km": null,
+            "vegetarian_meals_available": vegetarian
+        })
+    };
+    let hotels = json!([hotel("A", false), hotel("B", true), hotel("C", false)]);
+    let sorted = preferences.filter_response("hotels_in_city", hotels.clone());
+    let ids: Vec<&str> = sorted
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hotel| hotel["hotel_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["B", "A", "C"]);
+    assert_eq!(
+        TravelPreferences::default().filter_response("hotels_in_city", hotels.clone()),
+        hotels
+    );
+}
+
+#[test]
+fn gemini_model_test() {
+    let preferences = |model: Option<&str>| TravelPreferences {
+        gemini_model: model.map(str::to_string),
+        ..Default::default()
+    };
+    assert_eq!(preferences(None).validated_gemini_model().unwrap(), None);
+    assert_eq!(
+        preferences(Some("gemini-pro-latest"))
+            .validated_gemini_model()
+            .unwrap(),
+        Some("gemini-pro-latest")
+    );
+    for model in [
+        "gemini-ultra",
+        "GEMINI-PRO-LATEST",
+        "gemini-pro-latest:generateContent?key=x",
+        "../tunedModels/mine",
+        "",
+    ] {
+        assert!(
+            matches!(
+                preferences(Some(model)).validated_gemini_model(),
+                Err(TravelError::InvalidInput(_))
+            ),
+            "{model}"
+        );
+    }
+
+    let parsed: TravelPreferences =
+        serde_json::from_value(json!({"gemini_model": "gemini-2.5-flash"})).unwrap();
+    assert_eq!(parsed.gemini_model.as_deref(), Some("gemini-2.5-flash"));
+    assert!(
+        serde_json::to_value(TravelPreferences::default())
+            .unwrap()
+            .get("gemini_model")
+            .is_none()
+    );
+}
@@ -0,0 +1,89 @@
+use lambda_runtime::streaming::Sender;
+use serde::Serialize;
+use serde_json::json;
+use std::fmt::Display;
+
+const CHUNK_SEPERATOR: &str = "\n";
+
+/// The client disconnected, so nothing more can be streamed to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamClosed;
+
+impl Display for StreamClosed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Response stream closed by the client")
+    }
+}
+
+impl std::error::Error for StreamClosed {}
+
+/// Response stream of one request. Each message is sent as a line, and sending waits until
+/// the client is ready for more.
+pub struct StreamingSession {
+    tx: Sender,
+}
+
+impl StreamingSession {
+    pub fn new(tx: Sender) -> Self {
+        Self { tx }
+    }
+
+    pub async fn send_text(&mut self, text: &str) -> Result<(), StreamClosed> {
+        self.tx
+            .send_data(format!("{text}{CHUNK_SEPERATOR}").into())
+            .await
+            .map_err(|_| StreamClosed)
+    }
+
+    pub async fn send_json<T: Serialize>(&mut self, value: &T) -> Result<(), StreamClosed> {
+        match serde_json::to_string(value) {
+            Ok(text) => self.send_text(&text).await,
+            Err(e) => {
+                self.send_error("SERIALIZATION_FAILED", &e.to_string())
+                    .await
+            }
+        }
+    }
+
+    /// Sent as `{"error": {"code": ..., "message": ...}}`.
+    pub async fn send_error(&mut self, code: &str, message: &str) -> Result<(), StreamClosed> {
+        let error = json!({"error": {"code": code, "message": message}});
+        self.send_text(&error.to_string()).await
+    }
+}
+
+#[tokio::test]
+async fn streaming_session_test() {
+    use lambda_runtime::streaming::channel;
+
+    let (tx, rx) = channel();
+    let sender = tokio::spawn(async move {
+        let mut stream = StreamingSession::new(tx);
+        stream.send_text("Planning your trip").await?;
+        stream
+            .send_json(&json!([{"text": "Day 1: Baga Beach"}]))
+            .await?;
+        stream.send_error("GEMINI_ERROR", "Quota exceeded").await
+    });
+    let body = rx.collect().await.unwrap().to_bytes();
+    assert_eq!(sender.await.unwrap(), Ok(()));
+    assert_eq!(
+        body,
+        concat!(
+            "Planning your trip\n",
+            r#"[{"text":"Day 1: Baga Beach"}]"#,
+            "\n",
+            r#"{"error":{"code":"GEMINI_ERROR","message":"Quota exceeded"}}"#,
+            "\n"
+        )
+    );
+
+    let (tx, rx) = channel();
+    drop(rx);
+    let mut disconnected = StreamingSession::new(tx);
+    assert_eq!(disconnected.send_text("Lost").await, Err(StreamClosed));
+    assert_eq!(
+        disconnected.send_error("GEMINI_ERROR", "Lost").await,
+        Err(StreamClosed)
+    );
+}
@@ -0,0 +1,249 @@
+use crate::{
+    api_requests::{geocode::geocode, places::google::GooglePlacesClient},
+    error::TravelError,
+    utils::{Coordinates, Currency},
+};
+use gemini_client_api::futures::future::try_join;
+use gemini_client_api::gemini::utils::{GeminiSchema, gemini_function, gemini_schema};
+use serde::{Deserialize, Serialize};
+
+/// Trains beat flights door to door below this, as airports are far from city centres and
+/// need an early check-in.
+pub const FLIGHT_PREFERRED_ABOVE_KM: f32 = 600.0;
+/// Buses leave more often than trains on short routes and take about as long.
+pub const BUS_PREFERRED_BELOW_KM: f32 = 150.0;
+/// Average petrol price in the metros, used when the user doesn't know the local one.
+const DEFAULT_FUEL_PRICE_INR_PER_LITRE: f32 = 100.0;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportMode {
+    Flight,
+    Train,
+    Bus,
+    ///Picked by distance when the user has no preference
+    Suggested,
+}
+
+impl TransportMode {
+    /// Never returns `Suggested`.
+    pub fn for_distance(distance_km: f32) -> TransportMode {
+        if distance_km < BUS_PREFERRED_BELOW_KM {
+            TransportMode::Bus
+        } else if distance_km <= FLIGHT_PREFERRED_ABOVE_KM {
+            TransportMode::Train
+        } else {
+            TransportMode::Flight
+        }
+    }
+    /// Replaces `Suggested` with the mode for the distance between `source` and `destination`.
+    pub fn resolve(self, source: &Coordinates, destination: &Coordinates) -> TransportMode {
+        match self {
+            TransportMode::Suggested => Self::for_distance(source.distance_km(destination) as f32),
+            mode => mode,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TransportSuggestion {
+    pub mode: TransportMode,
+    ///Straight line distance between the cities
+    pub distance_km: f32,
+}
+
+#[gemini_function]
+///Suggest whether to travel by bus, train or flight between two cities when the user hasn't chosen. Short trips are quicker and cheaper by road or rail.
+pub async fn suggest_transport_mode(
+    ///Name of the city the user travels from (e.g., 'Pune')
+    source: String,
+    ///Name of the city the user travels to (e.g., 'Mumbai')
+    destination: String,
+) -> Result<TransportSuggestion, TravelError> {
+    let (from, to) = try_join(geocode(&source), geocode(&destination)).await?;
+    Ok(TransportSuggestion {
+        mode: TransportMode::Suggested.resolve(&from, &to),
+        distance_km: from.distance_km(&to) as f32,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[gemini_schema]
+#[allow(clippy::upper_case_acronyms)]
+pub enum VehicleType {
+    Hatchback,
+    Sedan,
+    SUV,
+    Motorcycle,
+}
+
+impl VehicleType {
+    /// Typical highway mileage of a petrol vehicle in km per litre.
+    pub fn fuel_efficiency_km_per_litre(self) -> f32 {
+        match self {
+            VehicleType::Hatchback => 18.0,
+            VehicleType::Sedan => 15.0,
+            VehicleType::SUV => 11.0,
+            VehicleType::Motorcycle => 45.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighwayType {
+    Expressway,
+    NationalHighway,
+    StateHighway,
+}
+
+impl HighwayType {
+    /// Short trips mostly run on state highways, long ones on national highways and, above
+    /// 500 km, largely on expressways like Delhi-Mumbai.
+    pub fn for_distance(distance_km: f32) -> HighwayType {
+        if distance_km < 100.0 {
+            HighwayType::StateHighway
+        } else if distance_km < 500.0 {
+            HighwayType::NationalHighway
+        } else {
+            HighwayType::Expressway
+        }
+    }
+    /// Average NHAI and state toll plaza fees spread over the road. Cars, jeeps and vans pay
+    /// the same, and two-wheelers are exempt.
+    pub fn toll_per_km_inr(self, vehicle_type: VehicleType) -> f32 {
+        if vehicle_type == VehicleType::Motorcycle {
+            return 0.0;
+        }
+        match self {
+            HighwayType::Expressway => 2.4,
+            HighwayType::NationalHighway => 1.6,
+            HighwayType::StateHighway => 1.0,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RoadTripCost {
+    pub fuel_cost: Currency,
+    pub toll_estimate: Currency,
+    pub total_estimate: Currency,
+}
+
+/// One way cost of driving `distance_km`.
+pub fn road_trip_cost_for(
+    distance_km: f32,
+    vehicle_type: VehicleType,
+    fuel_price_inr_per_litre: f32,
+) -> RoadTripCost {
+    let fuel_cost =
+        distance_km / vehicle_type.fuel_efficiency_km_per_litre() * fuel_price_inr_per_litre;
+    let toll_estimate =
+        distance_km * HighwayType::for_distance(distance_km).toll_per_km_inr(vehicle_type);
+    RoadTripCost {
+        fuel_cost: Currency::Inr(fuel_cost.round()),
+        toll_estimate: Currency::Inr(toll_estimate.round()),
+        total_estimate: Currency::Inr(fuel_cost.round() + toll_estimate.round()),
+    }
+}
+
+#[gemini_function]
+///Estimate the fuel and toll cost of driving or riding between two places in India. Call it when the user plans a road trip or asks whether driving is cheaper.
+pub async fn estimate_road_trip_cost(
+    ///Name of the place the trip starts from (e.g., 'Bengaluru')
+    source: String,
+    ///Name of the place the trip ends at (e.g., 'Coorg')
+    destination: String,
+    vehicle_type: VehicleType,
+    ///Local petrol price. Leave it out to use ₹100 per litre
+    fuel_price_inr_per_litre: Option<f32>,
+) -> Result<RoadTripCost, TravelError> {
+    let fuel_price = fuel_price_inr_per_litre.unwrap_or(DEFAULT_FUEL_PRICE_INR_PER_LITRE);
+    if !(fuel_price.is_finite() && fuel_price > 0.0) {
+        return Err(TravelError::InvalidInput(format!(
+            "Fuel price must be positive. Found: {fuel_price}"
+        )));
+    }
+    let (from, to) = try_join(geocode(&source), geocode(&destination)).await?;
+    let distance_km = GooglePlacesClient::from_config()
+        .driving_distance_km(from, to)
+        .await?;
+    Ok(road_trip_cost_for(distance_km, vehicle_type, fuel_price))
+}
+
+#[test]
+fn transport_mode_test() {
+    assert_eq!(TransportMode::for_distance(0.0), TransportMode::Bus);
+    assert_eq!(TransportMode::for_distance(149.9), TransportMode::Bus);
+    assert_eq!(TransportMode::for_distance(150.0), TransportMode::Train);
+    assert_eq!(TransportMode::for_distance(600.0), TransportMode::Train);
+    assert_eq!(TransportMode::for_distance(600.1), TransportMode::Flight);
+
+    let pune = Coordinates::new(18.5204, 73.8567);
+    let mumbai = Coordinates::new(19.0760, 72.8777);
+    let delhi = Coordinates::new(28.6139, 77.2090);
+    let agra = Coordinates::new(27.1767, 78.0081);
+    let lucknow = Coordinates::new(26.8467, 80.9462);
+    // ~120 km, ~180 km, ~420 km and ~1150 km
+    assert_eq!(
+        TransportMode::Suggested.resolve(&pune, &mumbai),
+        TransportMode::Bus
+    );
+    assert_eq!(
+        TransportMode::Suggested.resolve(&delhi, &agra),
+        TransportMode::Train
+    );
+    assert_eq!(
+        TransportMode::Suggested.resolve(&delhi, &lucknow),
+        TransportMode::Train
+    );
+    assert_eq!(
+        TransportMode::Suggested.resolve(&delhi, &mumbai),
+        TransportMode::Flight
+    );
+    assert_eq!(
+        TransportMode::Train.resolve(&delhi, &mumbai),
+        TransportMode::Train
+    );
+}
+
+#[test]
+fn road_trip_cost_test() {
+    // Mumbai to Pune is ~150 km on national highways
+    for (vehicle_type, fuel, toll) in [
+        (VehicleType::Hatchback, 833.0, 240.0),
+        (VehicleType::Sedan, 1000.0, 240.0),
+        (VehicleType::SUV, 1364.0, 240.0),
+        (VehicleType::Motorcycle, 333.0, 0.0),
+    ] {
+        let cost = road_trip_cost_for(150.0, vehicle_type, 100.0);
+        assert_eq!(cost.fuel_cost.to_amount(), fuel, "{vehicle_type:?}");
+        assert_eq!(cost.toll_estimate.to_amount(), toll, "{vehicle_type:?}");
+        assert_eq!(
+            cost.total_estimate.to_amount(),
+            fuel + toll,
+            "{vehicle_type:?}"
+        );
+        assert_eq!(cost.total_estimate.currency_code(), "INR");
+    }
+}
+
+#[test]
+fn road_trip_toll_test() {
+    assert_eq!(HighwayType::for_distance(99.9), HighwayType::StateHighway);
+    assert_eq!(
+        HighwayType::for_distance(100.0),
+        HighwayType::NationalHighway
+    );
+    assert_eq!(HighwayType::for_distance(500.0), HighwayType::Expressway);
+
+    // Delhi to Mumbai is ~1400 km, mostly on the expressway
+    let suv = road_trip_cost_for(1400.0, VehicleType::SUV, 95.0);
+    assert_eq!(suv.toll_estimate.to_amount(), 3360.0);
+    assert_eq!(suv.fuel_cost.to_amount(), 12091.0);
+    let bike = road_trip_cost_for(1400.0, VehicleType::Motorcycle, 95.0);
+    assert_eq!(bike.toll_estimate.to_amount(), 0.0);
+
+    let short = road_trip_cost_for(50.0, VehicleType::Sedan, 100.0);
+    assert_eq!(short.toll_estimate.to_amount(), 50.0);
+    let nowhere = road_trip_cost_for(0.0, VehicleType::Hatchback, 100.0);
+    assert_eq!(nowhere.total_estimate.to_amount(), 0.0);
+}
@@ -1,15 +1,30 @@
+use crate::{
+    config::{CONFIG, TravelPlannerConfig},
+    error::TravelError,
+};
+use chrono::{DateTime, Datelike, Days, FixedOffset, Months, NaiveDate, TimeZone, Utc, Weekday};
 use gemini_client_api::gemini::utils::{GeminiSchema, gemini_schema};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
 use std::fmt::Display;
 use std::sync::LazyLock;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use chrono::{DateTime, FixedOffset, Utc};
 
-const AUTH_URL: &str = "https://test.api.amadeus.com/v1/security/oauth2/token";
+pub mod optimizer;
+pub mod sorting;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+const AUTH_PATH: &str = "/v1/security/oauth2/token";
+/// Failures within `CIRCUIT_FAILURE_WINDOW` that open a circuit.
+const CIRCUIT_FAILURE_THRESHOLD: usize = 5;
+const CIRCUIT_FAILURE_WINDOW: Duration = Duration::from_secs(60);
+/// How long an open circuit rejects calls before letting one through to check the API.
+const CIRCUIT_OPEN_DURATION: Duration = Duration::from_secs(30);
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "DateInput")]
 #[gemini_schema]
 pub struct Date {
     year: u16,
@@ -50,15 +65,199 @@ impl Date {
             Err("Too many parameters in data".into())
         }
     }
+    /// `None` for dates that don't exist in the calendar (e.g. 30th February).
+    pub fn to_naive_date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year.into(), self.month.into(), self.day.into())
+    }
+    pub fn weekday(&self) -> Option<Weekday> {
+        self.to_naive_date().map(|date| date.weekday())
+    }
+    pub fn quarter(&self) -> u8 {
+        self.month.clamp(1, 12).div_ceil(3)
+    }
+    /// Oct-Nov (Diwali), Dec-Jan (Christmas and New Year) and Mar-Apr (Holi and summer break).
+    pub fn is_peak_travel_season_india(&self) -> bool {
+        matches!(self.month, 10..=12 | 1 | 3 | 4)
+    }
+    /// Summer is Mar-May, Monsoon Jun-Sep, PostMonsoon Oct-Nov and Winter Dec-Feb.
+    pub fn indian_season(&self) -> IndianSeason {
+        match self.month {
+            3..=5 => IndianSeason::Summer,
+            6..=9 => IndianSeason::Monsoon,
+            10 | 11 => IndianSeason::PostMonsoon,
+            _ => IndianSeason::Winter,
+        }
+    }
+    /// `None` when the date doesn't exist in the calendar.
+    pub fn add_days(&self, days: u64) -> Option<Date> {
+        self.to_naive_date()?
+            .checked_add_days(Days::new(days))
+            .map(Date::from)
+    }
     pub fn now() -> String {
         let utc_now: DateTime<Utc> = Utc::now();
         let ist_offset = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
         let ist_now = utc_now.with_timezone(&ist_offset);
         ist_now.format("%a %b %e %H:%M:%S %Z %Y").to_string()
     }
+    /// Today in India.
+    pub fn today() -> Date {
+        let ist_offset = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        Utc::now().with_timezone(&ist_offset).date_naive().into()
+    }
+    /// Reads dates the way people and Gemini write them: `2026-02-15`, `today`, `tomorrow`,
+    /// `yesterday`, `day after tomorrow`, `in 3 days`, `in 2 weeks`, `in a month`, `next week`,
+    /// `next month`, `end of month`, `next Saturday`, `this Friday`, `Friday`, `15 Feb`,
+    /// `15th February` and `Feb 15`. Days of the week and dates without a year are the next
+    /// ones on or after `reference`, `next Saturday` is the first one after it. Months are
+    /// added the calendar way, so a month after 31st January is 28th or 29th February.
+    pub fn parse_relative(input: &str, reference: &Date) -> Result<Date, TravelError> {
+        let unknown = || TravelError::ParseError(format!("Unknown date format: '{input}'"));
+        let reference_date = reference.to_naive_date().ok_or_else(|| {
+            TravelError::InvalidInput(format!(
+                "Reference date {} does not exist",
+                reference.to_yyyy_mm_dd()
+            ))
+        })?;
+        let days_until = |weekday: Weekday| {
+            u64::from(
+                (7 + weekday.num_days_from_monday()
+                    - reference_date.weekday().num_days_from_monday())
+                    % 7,
+            )
+        };
+        let text = input.trim().to_ascii_lowercase();
+        if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+            return Ok(date.into());
+        }
+        let words: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty())
+            .collect();
+        let date = match words.as_slice() {
+            ["today"] => Some(reference_date),
+            ["tomorrow"] => reference_date.succ_opt(),
+            ["yesterday"] => reference_date.pred_opt(),
+            ["day", "after", "tomorrow"] => reference_date.checked_add_days(Days::new(2)),
+            ["next", "week"] => reference_date.checked_add_days(Days::new(7)),
+            ["next", "month"] => reference_date.checked_add_months(Months::new(1)),
+            ["end", "of", "month"] | ["end", "of", "the", "month"] => reference_date
+                .with_day(1)
+                .and_then(|first| first.checked_add_months(Months::new(1)))
+                .and_then(|next_month| next_month.pred_opt()),
+            ["in", count, unit] => {
+                let count: u32 = match *count {
+                    "a" | "an" | "one" => 1,
+                    count => count.parse().map_err(|_| unknown())?,
+                };
+                match unit.trim_end_matches('s') {
+                    "day" => reference_date.checked_add_days(Days::new(count.into())),
+                    "week" => reference_date.checked_add_days(Days::new(u64::from(count) * 7)),
+                    "month" => reference_date.checked_add_months(Months::new(count)),
+                    _ => return Err(unknown()),
+                }
+            }
+            ["next", weekday] => {
+                let days = days_until(weekday.parse().map_err(|_| unknown())?);
+                reference_date.checked_add_days(Days::new(if days == 0 { 7 } else { days }))
+            }
+            ["this", weekday] | [weekday] if weekday.parse::<Weekday>().is_ok() => {
+                let days = days_until(weekday.parse().map_err(|_| unknown())?);
+                reference_date.checked_add_days(Days::new(days))
+            }
+            [day, month] | [month, day] if parse_month(month).is_some() => {
+                let day: u32 = day
+                    .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                    .parse()
+                    .map_err(|_| unknown())?;
+                let month = parse_month(month).ok_or_else(unknown)?;
+                // 29th February may be a few years away
+                let next = (0..=8)
+                    .filter_map(|years| {
+                        NaiveDate::from_ymd_opt(reference_date.year() + years, month, day)
+                    })
+                    .find(|date| *date >= reference_date);
+                Some(next.ok_or_else(|| {
+                    TravelError::InvalidInput(format!("{input} is not a date in the calendar"))
+                })?)
+            }
+            _ => return Err(unknown()),
+        };
+        date.map(Date::from).ok_or_else(|| {
+            TravelError::InvalidInput(format!(
+                "{input} is too far from {}",
+                reference.to_yyyy_mm_dd()
+            ))
+        })
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// `1` for `jan` or `january`.
+fn parse_month(month: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let month = month.trim_end_matches('.');
+    if month.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|name| name.starts_with(month))
+        .map(|index| index as u32 + 1)
+}
+
+/// Gemini sends dates as `{"year", "month", "day"}` but sometimes as text like `tomorrow`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DateInput {
+    Fields { year: u16, month: u8, day: u8 },
+    Text(String),
+}
+
+impl TryFrom<DateInput> for Date {
+    type Error = String;
+    fn try_from(input: DateInput) -> Result<Self, Self::Error> {
+        match input {
+            DateInput::Fields { year, month, day } => Date::new(year, month, day),
+            DateInput::Text(text) => {
+                Date::parse_relative(&text, &Date::today()).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+impl From<NaiveDate> for Date {
+    fn from(date: NaiveDate) -> Self {
+        Self {
+            year: date.year() as u16,
+            month: date.month() as u8,
+            day: date.day() as u8,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[gemini_schema]
+pub enum IndianSeason {
+    Summer,
+    Monsoon,
+    PostMonsoon,
+    Winter,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Time {
     hour: u8,
     minute: u8,
@@ -81,6 +280,12 @@ impl Time {
             second,
         })
     }
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
     pub fn to_hh_mm_ss(&self) -> String {
         format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
     }
@@ -107,6 +312,21 @@ impl Time {
     }
 }
 
+impl GeminiSchema for Time {
+    fn gemini_schema() -> serde_json::Value {
+        json!({
+            "type": "OBJECT",
+            "description": "Time of day on the 24 hour clock",
+            "properties": {
+                "hour": {"type": "INTEGER"},
+                "minute": {"type": "INTEGER"},
+                "second": {"type": "INTEGER"}
+            },
+            "required": ["hour", "minute", "second"]
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Currency {
     Inr(f32),
@@ -119,21 +339,227 @@ impl Currency {
         amount: &str,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let amount: f32 = amount.parse()?;
-        let cur = match code.to_uppercase().as_str() {
-            "USD" => Self::Usd(amount),
-            "INR" => Self::Inr(amount),
-            "EUR" => Self::Eur(amount),
-            _ => return Err(format!("Unsupported currency: {}", code).into()),
-        };
-        Ok(cur)
+        Ok(Self::from_code(code, amount)?)
+    }
+    pub fn from_code(code: &str, amount: f32) -> Result<Self, TravelError> {
+        match code.to_uppercase().as_str() {
+            "USD" => Ok(Self::Usd(amount)),
+            "INR" => Ok(Self::Inr(amount)),
+            "EUR" => Ok(Self::Eur(amount)),
+            _ => Err(TravelError::InvalidInput(format!(
+                "Unsupported currency: {code}"
+            ))),
+        }
+    }
+    pub fn zero(code: &str) -> Result<Currency, TravelError> {
+        Self::from_code(code, 0.0)
+    }
+    pub fn to_amount(&self) -> f32 {
+        match self {
+            Currency::Inr(amount) | Currency::Usd(amount) | Currency::Eur(amount) => *amount,
+        }
+    }
+    ///ISO 4217 code, e.g. `INR`
+    pub fn currency_code(&self) -> &str {
+        match self {
+            Currency::Inr(_) => "INR",
+            Currency::Usd(_) => "USD",
+            Currency::Eur(_) => "EUR",
+        }
+    }
+    /// Amounts in different currencies are never converted.
+    pub fn add(&self, other: &Currency) -> Result<Currency, TravelError> {
+        match (self, other) {
+            (Currency::Inr(a), Currency::Inr(b)) => Ok(Currency::Inr(a + b)),
+            (Currency::Usd(a), Currency::Usd(b)) => Ok(Currency::Usd(a + b)),
+            (Currency::Eur(a), Currency::Eur(b)) => Ok(Currency::Eur(a + b)),
+            _ => Err(TravelError::InvalidInput(format!(
+                "Cannot add {} and {}",
+                self.currency_code(),
+                other.currency_code()
+            ))),
+        }
+    }
+}
+/// Nothing spent yet, in the currency most trips are planned in.
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::Inr(0.0)
+    }
+}
+impl Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_amount(self))
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Sign, whole units and hundredths of `amount` rounded to 2 decimals.
+fn split_amount(amount: f32) -> (&'static str, u64, u64) {
+    let cents = (f64::from(amount) * 100.0).round();
+    let sign = if cents < 0.0 { "-" } else { "" };
+    let cents = cents.abs() as u64;
+    (sign, cents / 100, cents % 100)
+}
+
+/// Separates the last `first` digits of `whole` and then every `rest` digits before them with
+/// commas.
+fn group_digits(whole: u64, first: usize, rest: usize) -> String {
+    let digits = whole.to_string();
+    if digits.len() <= first {
+        return digits;
+    }
+    let (mut head, tail) = digits.split_at(digits.len() - first);
+    let mut groups = vec![tail];
+    while head.len() > rest {
+        let (before, group) = head.split_at(head.len() - rest);
+        groups.push(group);
+        head = before;
+    }
+    groups.push(head);
+    groups.reverse();
+    groups.join(",")
+}
+
+/// Indian lakh and crore grouping, e.g. `₹12,34,567.50`. Paise are left out when there are none.
+pub fn format_inr(amount: f32) -> String {
+    let (sign, rupees, paise) = split_amount(amount);
+    let rupees = group_digits(rupees, 3, 2);
+    if paise == 0 {
+        format!("{sign}₹{rupees}")
+    } else {
+        format!("{sign}₹{rupees}.{paise:02}")
+    }
+}
+
+/// e.g. `$1,234,567.50`
+pub fn format_usd(amount: f32) -> String {
+    let (sign, dollars, cents) = split_amount(amount);
+    format!("{sign}${}.{cents:02}", group_digits(dollars, 3, 3))
+}
+
+/// e.g. `€1,234,567.50`
+pub fn format_eur(amount: f32) -> String {
+    let (sign, euros, cents) = split_amount(amount);
+    format!("{sign}€{}.{cents:02}", group_digits(euros, 3, 3))
+}
+
+/// The amount with its currency symbol, grouped the way it's written in that currency.
+pub fn format_amount(currency: &Currency) -> String {
+    match currency {
+        Currency::Inr(amount) => format_inr(*amount),
+        Currency::Usd(amount) => format_usd(*amount),
+        Currency::Eur(amount) => format_eur(*amount),
+    }
+}
+impl GeminiSchema for Currency {
+    fn gemini_schema() -> serde_json::Value {
+        json!({
+            "type": "OBJECT",
+            "description": "Amount keyed by exactly one currency (e.g., {\"Inr\": 4500.0})",
+            "properties": {
+                "Inr": {"type": "NUMBER"},
+                "Usd": {"type": "NUMBER"},
+                "Eur": {"type": "NUMBER"}
+            }
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[gemini_schema]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+impl Coordinates {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+        }
+    }
+    /// Great-circle distance using the haversine formula.
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let d_lat = (other.latitude - self.latitude).to_radians();
+        let d_lon = (other.longitude - self.longitude).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2)
+            + self.latitude.to_radians().cos()
+                * other.latitude.to_radians().cos()
+                * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+}
+
+/// Length of a journey in whole minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TravelDuration(u32);
+impl TravelDuration {
+    pub fn new_from_minutes(total_minutes: u32) -> Self {
+        Self(total_minutes)
+    }
+    /// Zero when `end` is before `start`.
+    pub fn from_datetimes<Tz: TimeZone>(start: &DateTime<Tz>, end: &DateTime<Tz>) -> Self {
+        let minutes = end
+            .clone()
+            .signed_duration_since(start.clone())
+            .num_minutes();
+        Self(u32::try_from(minutes).unwrap_or_default())
+    }
+    /// Parses ISO 8601 durations as sent by Amadeus, e.g. `PT5H30M` or `P1DT2H`.
+    pub fn from_iso8601(duration: &str) -> Option<Self> {
+        let duration = duration.strip_prefix('P')?;
+        let (days, time) = duration.split_once('T').unwrap_or((duration, ""));
+        let mut minutes = 0;
+        if !days.is_empty() {
+            minutes += days.strip_suffix('D')?.parse::<u32>().ok()? * 24 * 60;
+        }
+        let mut number = String::new();
+        for c in time.chars() {
+            match c {
+                '0'..='9' => number.push(c),
+                'H' => minutes += std::mem::take(&mut number).parse::<u32>().ok()? * 60,
+                'M' => minutes += std::mem::take(&mut number).parse::<u32>().ok()?,
+                // Seconds are dropped
+                'S' => number.clear(),
+                _ => return None,
+            }
+        }
+        number.is_empty().then_some(Self(minutes))
+    }
+    pub fn total_minutes(self) -> u32 {
+        self.0
+    }
+    pub fn hours(&self) -> u32 {
+        self.0 / 60
+    }
+    pub fn minutes_remainder(&self) -> u32 {
+        self.0 % 60
+    }
+    /// Formats as `5h 30m`.
+    pub fn to_human_string(self) -> String {
+        format!("{}h {}m", self.hours(), self.minutes_remainder())
+    }
+    /// Formats as `PT5H30M`, the inverse of `from_iso8601`.
+    pub fn to_iso8601(self) -> String {
+        format!("PT{}H{}M", self.hours(), self.minutes_remainder())
+    }
+}
+
+/// Indian cities, with their common alternate names, and the IATA code of their main airport.
+/// Gemini often gets the codes of tier-2 cities wrong, e.g. Ranchi is IXR.
+static INDIA_AIRPORT_CODES: LazyLock<Vec<(&str, &str)>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../data/india_airports.json"))
+        .expect("Invalid data/india_airports.json")
+});
+/// Shorter names must match a city exactly, so 'Pa' or a lowercase code like 'del' isn't taken
+/// for the first city it starts.
+const MIN_CITY_PREFIX_LEN: usize = 4;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct IataCode(String);
 impl GeminiSchema for IataCode {
     fn gemini_schema() -> serde_json::Value {
-        json!({"type":"String"})
+        json!({"type":"String", "description": "IATA code, or the name of an Indian city"})
     }
 }
 impl IataCode {
@@ -144,6 +570,59 @@ impl IataCode {
             Err(format!("Invalid ITATA code: {code}"))
         }
     }
+    /// Case-insensitive lookup of an Indian city like 'Ranchi' or 'Ranchi, Jharkhand'. An exact
+    /// name wins over a prefix, so 'Goa' isn't taken for 'Gorakhpur', and prefixes shorter than
+    /// `MIN_CITY_PREFIX_LEN` aren't looked up.
+    pub fn from_indian_city(city: &str) -> Option<IataCode> {
+        let city = city.split(',').next()?.trim().to_lowercase();
+        if city.is_empty() {
+            return None;
+        }
+        let codes = INDIA_AIRPORT_CODES.iter();
+        codes
+            .clone()
+            .find(|(name, _)| name.to_lowercase() == city)
+            .or_else(|| {
+                if city.chars().count() < MIN_CITY_PREFIX_LEN {
+                    return None;
+                }
+                codes
+                    .clone()
+                    .find(|(name, _)| name.to_lowercase().starts_with(&city))
+            })
+            .map(|(_, code)| Self(code.to_string()))
+    }
+    /// The airport is in India, going by `INDIA_AIRPORT_CODES`.
+    pub fn is_domestic_india(&self) -> bool {
+        is_indian_airport(&self.0)
+    }
+}
+
+/// City of an airport in `INDIA_AIRPORT_CODES`, e.g. 'Goa' for `GOI`.
+pub fn indian_airport_city(code: &str) -> Option<&'static str> {
+    INDIA_AIRPORT_CODES
+        .iter()
+        .find(|(_, known)| known.eq_ignore_ascii_case(code.trim()))
+        .map(|(city, _)| *city)
+}
+
+/// Only airports in `INDIA_AIRPORT_CODES` are known, smaller Indian airports are missed.
+pub fn is_indian_airport(code: &str) -> bool {
+    INDIA_AIRPORT_CODES
+        .iter()
+        .any(|(_, known)| known.eq_ignore_ascii_case(code.trim()))
+}
+
+/// City names from Gemini are looked up in `INDIA_AIRPORT_CODES`. Anything else is kept as
+/// sent and left for the API to reject.
+impl<'de> Deserialize<'de> for IataCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Ok(Self::new(code.clone())
+            .ok()
+            .or_else(|| Self::from_indian_city(&code))
+            .unwrap_or(Self(code)))
+    }
 }
 
 impl Display for IataCode {
@@ -152,6 +631,132 @@ impl Display for IataCode {
     }
 }
 
+/// Rejects `input` longer than `max_len` bytes. Use for values which are encoded later on
+/// (e.g. by `reqwest`'s `.query()` or a JSON body).
+pub fn check_param_length(input: &str, max_len: usize) -> Result<&str, TravelError> {
+    if input.len() > max_len {
+        return Err(TravelError::InvalidInput(format!(
+            "Parameter exceeds {max_len} characters: {input}"
+        )));
+    }
+    Ok(input)
+}
+
+/// URL-encodes `input` so it can be safely placed in a hand built query string.
+pub fn sanitize_query_param(input: &str, max_len: usize) -> Result<String, TravelError> {
+    Ok(urlencoding::encode(check_param_length(input, max_len)?).into_owned())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    ///Calls go through
+    Closed,
+    ///Calls are rejected until the given time
+    Open(Instant),
+    ///The open period has passed. A single call decides whether to close or open again
+    HalfOpen,
+}
+
+/// Stops calling a failing API, so concurrent Lambdas don't pile retries onto it.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    state: CircuitState,
+    failures: VecDeque<Instant>,
+    ///When the call checking a half-open API was let through, the others are rejected until its
+    ///result is recorded. A call that never records it is given up on after
+    ///`CIRCUIT_OPEN_DURATION`
+    probe_started_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            failures: VecDeque::new(),
+            probe_started_at: None,
+        }
+    }
+    /// `Err` holds the time left until the API is checked again.
+    pub fn check(&mut self, now: Instant) -> Result<(), Duration> {
+        match self.state {
+            CircuitState::Open(until) if now < until => Err(until - now),
+            CircuitState::HalfOpen
+                if let Some(started_at) = self.probe_started_at
+                    && now < started_at + CIRCUIT_OPEN_DURATION =>
+            {
+                Err(started_at + CIRCUIT_OPEN_DURATION - now)
+            }
+            CircuitState::Open(_) | CircuitState::HalfOpen => {
+                self.state = CircuitState::HalfOpen;
+                self.probe_started_at = Some(now);
+                Ok(())
+            }
+            CircuitState::Closed => Ok(()),
+        }
+    }
+    /// Only closes a half-open circuit. Earlier failures still count until they leave
+    /// `CIRCUIT_FAILURE_WINDOW`.
+    pub fn record_success(&mut self) {
+        if self.state == CircuitState::HalfOpen {
+            self.state = CircuitState::Closed;
+        }
+        self.probe_started_at = None;
+    }
+    pub fn record_failure(&mut self, now: Instant) {
+        self.probe_started_at = None;
+        self.failures.push_back(now);
+        while self
+            .failures
+            .front()
+            .is_some_and(|failure| now.duration_since(*failure) > CIRCUIT_FAILURE_WINDOW)
+        {
+            self.failures.pop_front();
+        }
+        if self.state == CircuitState::HalfOpen || self.failures.len() >= CIRCUIT_FAILURE_THRESHOLD
+        {
+            self.state = CircuitState::Open(now + CIRCUIT_OPEN_DURATION);
+        }
+    }
+    /// `None` is a call that got no response. Transport errors, rate limiting and server errors
+    /// count as failures. Other errors are caused by the request and say nothing about the API's
+    /// health, so they leave the circuit as it is.
+    pub fn record_status(&mut self, status: Option<reqwest::StatusCode>, now: Instant) {
+        match status {
+            Some(status) if status.is_success() => self.record_success(),
+            Some(status) if !status.is_server_error() && status.as_u16() != 429 => {
+                // A half-open circuit lets the next call check the API instead
+                self.probe_started_at = None;
+            }
+            _ => self.record_failure(now),
+        }
+    }
+}
+
+static AMADEUS_CIRCUIT: LazyLock<RwLock<CircuitBreaker>> =
+    LazyLock::new(|| RwLock::new(CircuitBreaker::new()));
+
+async fn check_amadeus_circuit() -> Result<(), TravelError> {
+    AMADEUS_CIRCUIT
+        .write()
+        .await
+        .check(Instant::now())
+        .map_err(|retry_in| TravelError::ApiUnavailable {
+            api: "Amadeus".into(),
+            retry_in,
+        })
+}
+
+async fn record_amadeus_result(result: &Result<reqwest::Response, reqwest::Error>) {
+    let status = match result {
+        Ok(resp) => Some(resp.status()),
+        Err(e) => e.status(),
+    };
+    AMADEUS_CIRCUIT
+        .write()
+        .await
+        .record_status(status, Instant::now());
+}
+
 #[derive(Deserialize, Clone)]
 struct OAuthTokenResponse {
     access_token: String,
@@ -159,6 +764,19 @@ struct OAuthTokenResponse {
     expires_in: u64,
 }
 
+impl OAuthTokenResponse {
+    /// Only bearer tokens are sent with our requests, anything else would fail every call.
+    fn validate(self) -> Result<Self, TravelError> {
+        if self.token_type.to_lowercase() != "bearer" {
+            return Err(TravelError::ApiKeyMissing(format!(
+                "Unexpected token type: {}",
+                self.token_type
+            )));
+        }
+        Ok(self)
+    }
+}
+
 struct TokenCache {
     token: OAuthTokenResponse,
     expiry: Instant,
@@ -170,11 +788,12 @@ pub async fn get_bearer_token(
     client_id: &str,
     client_secret: &str,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let refresh_buffer = Duration::from_secs(CONFIG.token_refresh_buffer_secs);
     {
         let cache = TOKEN_STORAGE.read().await;
         if let Some(ref entry) = *cache {
-            // Buffer of 30s to prevent race conditions near expiry
-            if entry.expiry > Instant::now() + Duration::from_secs(30) {
+            // Buffer to prevent race conditions near expiry
+            if entry.expiry > Instant::now() + refresh_buffer {
                 return Ok(entry.token.access_token.clone());
             }
         }
@@ -183,7 +802,7 @@ pub async fn get_bearer_token(
     let mut cache = TOKEN_STORAGE.write().await;
 
     if let Some(ref entry) = *cache {
-        if entry.expiry > Instant::now() + Duration::from_secs(30) {
+        if entry.expiry > Instant::now() + refresh_buffer {
             return Ok(entry.token.access_token.clone());
         }
     }
@@ -194,14 +813,33 @@ pub async fn get_bearer_token(
         ("client_secret", client_secret),
     ];
 
-    let resp = reqwest::Client::new()
-        .post(AUTH_URL)
-        .form(&params)
-        .send()
-        .await?
-        .error_for_status()?;
+    check_amadeus_circuit().await?;
+    let client = reqwest::Client::new();
+    let mut attempt = 1;
+    // Only transport errors and 5xx are retried, bad credentials will not fix themselves
+    let resp = loop {
+        let result = client
+            .post(CONFIG.amadeus_env.url(AUTH_PATH))
+            .form(&params)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status());
+        match result {
+            Err(e)
+                if attempt < CONFIG.retry_max
+                    && e.status().is_none_or(|status| status.is_server_error()) =>
+            {
+                tokio::time::sleep(Duration::from_millis(200 * u64::from(attempt))).await;
+                attempt += 1;
+            }
+            result => {
+                record_amadeus_result(&result).await;
+                break result?;
+            }
+        }
+    };
 
-    let token: OAuthTokenResponse = resp.json().await?;
+    let token = resp.json::<OAuthTokenResponse>().await?.validate()?;
 
     *cache = Some(TokenCache {
         token: token.clone(),
@@ -210,3 +848,687 @@ pub async fn get_bearer_token(
 
     Ok(token.access_token)
 }
+
+static AMADEUS_HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// For requests without query parameters.
+pub const NO_PARAMS: &[(&str, &str)] = &[];
+
+/// Sends authenticated requests to Amadeus through its circuit breaker. Transport and server
+/// errors are retried up to `retry_max` times.
+#[derive(Clone)]
+pub struct AmadeusClient {
+    http_client: reqwest::Client,
+    config: &'static TravelPlannerConfig,
+}
+
+impl AmadeusClient {
+    pub fn new() -> Self {
+        Self {
+            http_client: AMADEUS_HTTP_CLIENT.clone(),
+            config: &CONFIG,
+        }
+    }
+    /// `path` is relative to the configured environment, e.g. `/v2/shopping/flight-offers`.
+    pub async fn get<T: DeserializeOwned, S: Serialize + Sync>(
+        &self,
+        path: &str,
+        params: &[(&str, S)],
+    ) -> Result<T, TravelError> {
+        let (status, body) = self.send_get(path, params).await?;
+        parse_amadeus_response(status, &body)
+    }
+    /// `None` when Amadeus answers 404, which some endpoints do when nothing matches.
+    pub async fn get_optional<T: DeserializeOwned, S: Serialize + Sync>(
+        &self,
+        path: &str,
+        params: &[(&str, S)],
+    ) -> Result<Option<T>, TravelError> {
+        let (status, body) = self.send_get(path, params).await?;
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        parse_amadeus_response(status, &body).map(Some)
+    }
+    pub async fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &Value,
+    ) -> Result<T, TravelError> {
+        let url = self.config.amadeus_env.url(path);
+        let (status, body) = self.send(|| self.http_client.post(&url).json(body)).await?;
+        parse_amadeus_response(status, &body)
+    }
+    async fn send_get<S: Serialize + Sync>(
+        &self,
+        path: &str,
+        params: &[(&str, S)],
+    ) -> Result<(reqwest::StatusCode, String), TravelError> {
+        let url = self.config.amadeus_env.url(path);
+        self.send(|| self.http_client.get(&url).query(params)).await
+    }
+    /// Bearer token for the configured Amadeus credentials.
+    async fn token(&self) -> Result<String, TravelError> {
+        get_bearer_token(
+            &self.config.amadeus_api_key,
+            &self.config.amadeus_api_secret,
+        )
+        .await
+        .map_err(|e| match e.downcast::<TravelError>() {
+            Ok(e) => *e,
+            Err(e) => TravelError::Api(format!("Amadeus authentication failed: {e}")),
+        })
+    }
+    /// `request` builds the request afresh for every attempt.
+    async fn send(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<(reqwest::StatusCode, String), TravelError> {
+        let token = self.token().await?;
+        check_amadeus_circuit().await?;
+        let mut attempt = 1;
+        let result = loop {
+            let result = request().bearer_auth(&token).send().await;
+            // Transport and server errors are retried
+            let retry = match &result {
+                Ok(resp) => resp.status().is_server_error(),
+                Err(_) => true,
+            };
+            if !retry || attempt >= self.config.retry_max {
+                break result;
+            }
+            tokio::time::sleep(Duration::from_millis(200 * u64::from(attempt))).await;
+            attempt += 1;
+        };
+        // A request counts once for the circuit however often it was retried
+        record_amadeus_result(&result).await;
+        let resp = result.map_err(|e| TravelError::Api(format!("Amadeus request failed: {e}")))?;
+        let status = resp.status();
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| TravelError::Api(format!("Invalid Amadeus response: {e}")))?;
+        Ok((status, body))
+    }
+}
+
+/// Parses the body of an Amadeus response, turning error statuses and the `errors` Amadeus
+/// sends with a 200 into `TravelError::Api`.
+fn parse_amadeus_response<T: DeserializeOwned>(
+    status: reqwest::StatusCode,
+    body: &str,
+) -> Result<T, TravelError> {
+    if !status.is_success() {
+        let errors = serde_json::from_str(body)
+            .ok()
+            .and_then(|value: Value| extract_amadeus_errors(&value));
+        let detail = match errors {
+            Some(errors) => errors
+                .iter()
+                .map(AmadeusError::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+            None => body.to_string(),
+        };
+        return Err(TravelError::Api(format!(
+            "Amadeus API error: {status} - {detail}"
+        )));
+    }
+    validate_amadeus_response(body)?;
+    serde_json::from_str(body)
+        .map_err(|e| TravelError::Api(format!("Invalid Amadeus response: {e}")))
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AmadeusError {
+    #[serde(default)]
+    pub code: u32,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub detail: String,
+    #[serde(default)]
+    pub status: u16,
+}
+
+impl Display for AmadeusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.title, self.code, self.detail)
+    }
+}
+
+/// `None` when `value` has no `errors` array or it is empty.
+pub fn extract_amadeus_errors(value: &Value) -> Option<Vec<AmadeusError>> {
+    let errors: Vec<AmadeusError> = serde_json::from_value(value.get("errors")?.clone()).ok()?;
+    (!errors.is_empty()).then_some(errors)
+}
+
+/// Amadeus sometimes reports errors with a 200 status, in which case `data` is missing and
+/// `errors` explains why.
+pub fn validate_amadeus_response(json: &str) -> Result<(), TravelError> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| TravelError::Api(format!("Invalid Amadeus response: {e}")))?;
+    if value.get("data").is_some() {
+        return Ok(());
+    }
+    match extract_amadeus_errors(&value) {
+        Some(errors) => {
+            let errors: Vec<String> = errors.iter().map(AmadeusError::to_string).collect();
+            Err(TravelError::Api(format!(
+                "Amadeus API error: {}",
+                errors.join("; ")
+            )))
+        }
+        None => Ok(()),
+    }
+}
+
+#[test]
+fn sanitize_query_param_test() {
+    assert_eq!(sanitize_query_param("NDLS", 10).unwrap(), "NDLS");
+    assert_eq!(
+        sanitize_query_param("NDLS&key=stolen", 20).unwrap(),
+        "NDLS%26key%3Dstolen"
+    );
+    assert_eq!(
+        sanitize_query_param("BCT?trainNo=1#x", 20).unwrap(),
+        "BCT%3FtrainNo%3D1%23x"
+    );
+    assert!(sanitize_query_param("NDLS&key=stolen", 10).is_err());
+    assert!(check_param_length(&"A".repeat(201), 200).is_err());
+}
+
+#[test]
+fn haversine_distance_test() {
+    let delhi = Coordinates::new(28.6139, 77.2090);
+    let mumbai = Coordinates::new(19.0760, 72.8777);
+    let distance = delhi.distance_km(&mumbai);
+    assert!((distance - 1153.0).abs() < 5.0, "{distance}");
+    assert_eq!(delhi.distance_km(&delhi), 0.0);
+    assert_eq!(delhi.distance_km(&mumbai), mumbai.distance_km(&delhi));
+
+    // One degree of latitude is ~111.2 km anywhere, a degree of longitude shrinks with latitude
+    let equator = Coordinates::new(0.0, 0.0);
+    let north = Coordinates::new(1.0, 0.0);
+    assert!((equator.distance_km(&north) - 111.19).abs() < 0.01);
+    let east = Coordinates::new(60.0, 1.0);
+    let distance = Coordinates::new(60.0, 0.0).distance_km(&east);
+    assert!((distance - 55.6).abs() < 0.1, "{distance}");
+    let antipode = Coordinates::new(0.0, 180.0);
+    let distance = equator.distance_km(&antipode);
+    assert!((distance - std::f64::consts::PI * EARTH_RADIUS_KM).abs() < 0.001);
+}
+
+#[test]
+fn travel_duration_test() {
+    let duration = TravelDuration::new_from_minutes(330);
+    assert_eq!(duration.hours(), 5);
+    assert_eq!(duration.minutes_remainder(), 30);
+    assert_eq!(duration.to_human_string(), "5h 30m");
+    assert_eq!(
+        TravelDuration::new_from_minutes(45).to_human_string(),
+        "0h 45m"
+    );
+    assert!(TravelDuration::new_from_minutes(90) < duration);
+
+    assert_eq!(TravelDuration::from_iso8601("PT5H30M"), Some(duration));
+    assert_eq!(duration.to_iso8601(), "PT5H30M");
+    assert_eq!(
+        TravelDuration::from_iso8601(&TravelDuration::new_from_minutes(26 * 60).to_iso8601()),
+        Some(TravelDuration::new_from_minutes(26 * 60))
+    );
+    assert_eq!(
+        TravelDuration::from_iso8601("P1DT2H"),
+        Some(TravelDuration::new_from_minutes(26 * 60))
+    );
+    assert_eq!(
+        TravelDuration::from_iso8601("PT45M30S"),
+        Some(TravelDuration::new_from_minutes(45))
+    );
+    assert_eq!(TravelDuration::from_iso8601("5H30M"), None);
+    assert_eq!(TravelDuration::from_iso8601("PT5X"), None);
+    assert_eq!(TravelDuration::from_iso8601("PT5"), None);
+
+    let ist = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+    let start = ist.with_ymd_and_hms(2026, 2, 15, 22, 10, 0).unwrap();
+    let end = ist.with_ymd_and_hms(2026, 2, 16, 3, 40, 0).unwrap();
+    assert_eq!(TravelDuration::from_datetimes(&start, &end), duration);
+    assert_eq!(
+        TravelDuration::from_datetimes(&end, &start),
+        TravelDuration::new_from_minutes(0)
+    );
+}
+
+#[test]
+fn validate_amadeus_response_test() {
+    let fixture = r#"{
+        "errors": [
+            {"status": 400, "code": 477, "title": "INVALID FORMAT",
+             "detail": "departureDate must be in the future", "source": {"parameter": "departureDate"}},
+            {"status": 400, "code": 32171, "title": "MANDATORY DATA MISSING"}
+        ]
+    }"#;
+    let errors = extract_amadeus_errors(&serde_json::from_str(fixture).unwrap()).unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0],
+        AmadeusError {
+            code: 477,
+            title: "INVALID FORMAT".into(),
+            detail: "departureDate must be in the future".into(),
+            status: 400,
+        }
+    );
+    assert!(errors[1].detail.is_empty());
+    assert!(matches!(
+        validate_amadeus_response(fixture),
+        Err(TravelError::Api(msg)) if msg.contains("INVALID FORMAT (477)") && msg.contains("32171")
+    ));
+
+    assert!(validate_amadeus_response(r#"{"meta": {"count": 1}, "data": []}"#).is_ok());
+    assert!(validate_amadeus_response(r#"{"data": [], "errors": [{"code": 1}]}"#).is_ok());
+    assert!(extract_amadeus_errors(&json!({"errors": []})).is_none());
+    assert!(extract_amadeus_errors(&json!({"data": []})).is_none());
+    assert!(validate_amadeus_response("<html>").is_err());
+}
+
+#[test]
+fn parse_amadeus_response_test() {
+    use reqwest::StatusCode;
+
+    let data: Value = parse_amadeus_response(StatusCode::OK, r#"{"data": [{"id": "1"}]}"#).unwrap();
+    assert_eq!(data["data"][0]["id"], "1");
+
+    let errors = r#"{"errors": [{"status": 400, "code": 477, "title": "INVALID FORMAT"}]}"#;
+    assert!(matches!(
+        parse_amadeus_response::<Value>(StatusCode::BAD_REQUEST, errors),
+        Err(TravelError::Api(msg)) if msg == "Amadeus API error: 400 Bad Request - INVALID FORMAT (477): "
+    ));
+    assert!(matches!(
+        parse_amadeus_response::<Value>(StatusCode::OK, errors),
+        Err(TravelError::Api(msg)) if msg.contains("INVALID FORMAT (477)")
+    ));
+    assert!(matches!(
+        parse_amadeus_response::<Value>(StatusCode::BAD_GATEWAY, "<html>"),
+        Err(TravelError::Api(msg)) if msg == "Amadeus API error: 502 Bad Gateway - <html>"
+    ));
+
+    assert!(matches!(
+        parse_amadeus_response::<Vec<String>>(StatusCode::OK, r#"{"data": [1]}"#),
+        Err(TravelError::Api(msg)) if msg.starts_with("Invalid Amadeus response")
+    ));
+}
+
+#[test]
+fn currency_arithmetic_test() {
+    let codes = ["INR", "USD", "EUR"];
+    for code in codes {
+        let zero = Currency::zero(code).unwrap();
+        assert_eq!(zero.currency_code(), code);
+        assert_eq!(zero.to_amount(), 0.0);
+        let total = Currency::from_code(code, 1200.5)
+            .unwrap()
+            .add(&Currency::from_code(code, 99.5).unwrap())
+            .unwrap();
+        assert_eq!(total.currency_code(), code);
+        assert_eq!(total.to_amount(), 1300.0);
+        assert_eq!(zero.add(&total).unwrap().to_amount(), 1300.0);
+
+        for other in codes.iter().filter(|other| **other != code) {
+            let result = zero.add(&Currency::zero(other).unwrap());
+            assert!(
+                matches!(&result, Err(TravelError::InvalidInput(msg)) if *msg == format!("Cannot add {code} and {other}")),
+                "{result:?}"
+            );
+        }
+    }
+    assert_eq!(Currency::zero("inr").unwrap().currency_code(), "INR");
+    assert!(Currency::zero("GBP").is_err());
+    assert_eq!(
+        Currency::parse_currency("usd", "12.25")
+            .unwrap()
+            .to_amount(),
+        12.25
+    );
+    assert!(Currency::parse_currency("GBP", "12.25").is_err());
+}
+
+#[test]
+fn format_inr_test() {
+    for (amount, expected) in [
+        (0.0, "₹0"),
+        (5.0, "₹5"),
+        (999.0, "₹999"),
+        (1000.0, "₹1,000"),
+        (99999.0, "₹99,999"),
+        (100000.0, "₹1,00,000"),
+        (999999.0, "₹9,99,999"),
+        (1000000.0, "₹10,00,000"),
+        (9999999.0, "₹99,99,999"),
+        (10000000.0, "₹1,00,00,000"),
+        (12345678.0, "₹1,23,45,678"),
+        (1234567.5, "₹12,34,567.50"),
+        (1234.25, "₹1,234.25"),
+        (0.05, "₹0.05"),
+        (999.999, "₹1,000"),
+        (-150000.0, "-₹1,50,000"),
+    ] {
+        assert_eq!(format_inr(amount), expected, "{amount}");
+    }
+}
+
+#[test]
+fn format_amount_test() {
+    assert_eq!(format_usd(0.0), "$0.00");
+    assert_eq!(format_usd(999.5), "$999.50");
+    assert_eq!(format_usd(1000.0), "$1,000.00");
+    assert_eq!(format_usd(1_234_567.5), "$1,234,567.50");
+    assert_eq!(format_eur(100_000.25), "€100,000.25");
+    assert_eq!(format_eur(-12.0), "-€12.00");
+
+    assert_eq!(format_amount(&Currency::Inr(250_000.0)), "₹2,50,000");
+    assert_eq!(format_amount(&Currency::Usd(250_000.0)), "$250,000.00");
+    assert_eq!(format_amount(&Currency::Eur(250_000.0)), "€250,000.00");
+    assert_eq!(Currency::Inr(4500.75).to_string(), "₹4,500.75");
+}
+
+#[test]
+fn indian_season_test() {
+    let date = |month: u8, day: u8| Date::new(2026, month, day).unwrap();
+    assert_eq!(date(1, 1).quarter(), 1);
+    assert_eq!(date(3, 31).quarter(), 1);
+    assert_eq!(date(4, 1).quarter(), 2);
+    assert_eq!(date(9, 30).quarter(), 3);
+    assert_eq!(date(10, 1).quarter(), 4);
+    assert_eq!(date(12, 31).quarter(), 4);
+
+    assert_eq!(date(2, 28).indian_season(), IndianSeason::Winter);
+    assert_eq!(date(3, 1).indian_season(), IndianSeason::Summer);
+    assert_eq!(date(5, 31).indian_season(), IndianSeason::Summer);
+    assert_eq!(date(6, 1).indian_season(), IndianSeason::Monsoon);
+    assert_eq!(date(9, 30).indian_season(), IndianSeason::Monsoon);
+    assert_eq!(date(10, 1).indian_season(), IndianSeason::PostMonsoon);
+    assert_eq!(date(11, 30).indian_season(), IndianSeason::PostMonsoon);
+    assert_eq!(date(12, 1).indian_season(), IndianSeason::Winter);
+
+    let peak: Vec<u8> = (1..=12)
+        .filter(|month| date(*month, 15).is_peak_travel_season_india())
+        .collect();
+    assert_eq!(peak, [1, 3, 4, 10, 11, 12]);
+}
+
+#[test]
+fn parse_relative_test() {
+    let date = |date: &str| Date::from_yyyy_mm_dd(date).unwrap();
+    let parse = |input: &str, reference: &str| {
+        Date::parse_relative(input, &date(reference))
+            .unwrap_or_else(|e| panic!("{input}: {e}"))
+            .to_yyyy_mm_dd()
+    };
+
+    // 2026-10-15 is a Thursday
+    let cases = [
+        ("2026-11-02", "2026-11-02"),
+        (" Today ", "2026-10-15"),
+        ("tomorrow", "2026-10-16"),
+        ("Yesterday", "2026-10-14"),
+        ("day after tomorrow", "2026-10-17"),
+        ("in 0 days", "2026-10-15"),
+        ("in 1 day", "2026-10-16"),
+        ("in 3 days", "2026-10-18"),
+        ("in a week", "2026-10-22"),
+        ("in 2 weeks", "2026-10-29"),
+        ("in 3 months", "2027-01-15"),
+        ("next week", "2026-10-22"),
+        ("next month", "2026-11-15"),
+        ("end of month", "2026-10-31"),
+        ("end of the month", "2026-10-31"),
+        ("next Saturday", "2026-10-17"),
+        ("next mon", "2026-10-19"),
+        ("next Thursday", "2026-10-22"),
+        ("this Thursday", "2026-10-15"),
+        ("Friday", "2026-10-16"),
+        ("this wednesday", "2026-10-21"),
+        ("20 Oct", "2026-10-20"),
+        ("15 Oct", "2026-10-15"),
+        ("14 Oct", "2027-10-14"),
+        ("3rd Jan", "2027-01-03"),
+        ("1st, November", "2026-11-01"),
+        ("Feb 15", "2027-02-15"),
+        ("May 5", "2027-05-05"),
+        ("25 december", "2026-12-25"),
+        ("31 Dec", "2026-12-31"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(parse(input, "2026-10-15"), expected, "{input}");
+    }
+
+    // Month and year boundaries
+    assert_eq!(parse("tomorrow", "2026-12-31"), "2027-01-01");
+    assert_eq!(parse("next friday", "2026-12-31"), "2027-01-01");
+    assert_eq!(parse("in 1 month", "2026-12-31"), "2027-01-31");
+    assert_eq!(parse("end of month", "2026-12-31"), "2026-12-31");
+    assert_eq!(parse("in 2 weeks", "2026-12-30"), "2027-01-13");
+    assert_eq!(parse("next Wednesday", "2026-12-30"), "2027-01-06");
+    assert_eq!(parse("next month", "2026-01-31"), "2026-02-28");
+    assert_eq!(parse("in a month", "2026-01-31"), "2026-02-28");
+    assert_eq!(parse("end of month", "2026-02-10"), "2026-02-28");
+    assert_eq!(parse("end of month", "2028-02-10"), "2028-02-29");
+    assert_eq!(parse("tomorrow", "2028-02-28"), "2028-02-29");
+    assert_eq!(parse("tomorrow", "2026-02-28"), "2026-03-01");
+    assert_eq!(parse("yesterday", "2026-03-01"), "2026-02-28");
+    assert_eq!(parse("yesterday", "2027-01-01"), "2026-12-31");
+    assert_eq!(parse("1 Jan", "2026-12-31"), "2027-01-01");
+    assert_eq!(parse("29 Feb", "2026-10-15"), "2028-02-29");
+
+    let error = |input: &str| Date::parse_relative(input, &date("2026-10-15")).unwrap_err();
+    for input in [
+        "",
+        "sometime soon",
+        "in many days",
+        "in 3 fortnights",
+        "next holiday",
+        "2026-13-01",
+        "ma 5",
+        "5th",
+    ] {
+        assert!(
+            matches!(error(input), TravelError::ParseError(_)),
+            "{input}"
+        );
+    }
+    assert!(matches!(error("31 Feb"), TravelError::InvalidInput(_)));
+    assert!(matches!(error("32 Jan"), TravelError::InvalidInput(_)));
+    assert!(matches!(
+        Date::parse_relative("tomorrow", &Date::new(2026, 2, 30).unwrap()),
+        Err(TravelError::InvalidInput(_))
+    ));
+
+    // Gemini may send either form
+    let from_json = |value: Value| serde_json::from_value::<Date>(value);
+    assert_eq!(
+        from_json(json!({"year": 2026, "month": 2, "day": 15})).unwrap(),
+        date("2026-02-15")
+    );
+    assert_eq!(from_json(json!("2026-02-15")).unwrap(), date("2026-02-15"));
+    assert_eq!(
+        from_json(json!("tomorrow")).unwrap(),
+        Date::today().add_days(1).unwrap()
+    );
+    assert!(from_json(json!("whenever")).is_err());
+    assert!(from_json(json!({"year": 2026, "month": 13, "day": 1})).is_err());
+}
+
+#[test]
+fn circuit_breaker_test() {
+    let start = Instant::now();
+    let secs = |secs: u64| start + Duration::from_secs(secs);
+    let mut circuit = CircuitBreaker::new();
+
+    // Failures spread over more than a minute never open the circuit
+    for i in 0..8 {
+        circuit.record_failure(secs(i * 20));
+        assert_eq!(circuit.state, CircuitState::Closed);
+    }
+    for i in 1..5 {
+        circuit.record_failure(secs(200 + i));
+    }
+    assert_eq!(circuit.check(secs(204)), Ok(()));
+    circuit.record_failure(secs(205));
+    assert_eq!(circuit.state, CircuitState::Open(secs(235)));
+    assert_eq!(circuit.check(secs(225)), Err(Duration::from_secs(10)));
+
+    // A failed check after the open period opens the circuit again
+    assert_eq!(circuit.check(secs(235)), Ok(()));
+    assert_eq!(circuit.state, CircuitState::HalfOpen);
+    // Only one call checks the API at a time
+    assert_eq!(circuit.check(secs(235)), Err(Duration::from_secs(30)));
+    circuit.record_failure(secs(236));
+    assert_eq!(circuit.state, CircuitState::Open(secs(266)));
+
+    // A successful one closes it
+    assert_eq!(circuit.check(secs(270)), Ok(()));
+    assert!(circuit.check(secs(271)).is_err());
+    circuit.record_success();
+    assert_eq!(circuit.check(secs(271)), Ok(()));
+    assert_eq!(circuit.state, CircuitState::Closed);
+    circuit.record_failure(secs(271));
+    assert_eq!(circuit.state, CircuitState::Closed);
+
+    // A check that never reports back is given up on
+    for i in 0..5 {
+        circuit.record_failure(secs(280 + i));
+    }
+    assert_eq!(circuit.check(secs(315)), Ok(()));
+    assert!(circuit.check(secs(344)).is_err());
+    assert_eq!(circuit.check(secs(345)), Ok(()));
+
+    let error = TravelError::ApiUnavailable {
+        api: "Amadeus".into(),
+        retry_in: Duration::from_millis(12_400),
+    };
+    assert_eq!(
+        error.to_string(),
+        "API unavailable: Amadeus is failing, try again in 12s"
+    );
+}
+
+#[test]
+fn circuit_breaker_status_test() {
+    use reqwest::StatusCode;
+
+    let start = Instant::now();
+    let secs = |secs: u64| start + Duration::from_secs(secs);
+    let mut circuit = CircuitBreaker::new();
+
+    // Successes and bad requests in between don't reset the failures within the window
+    for (i, status) in [
+        Some(StatusCode::INTERNAL_SERVER_ERROR),
+        Some(StatusCode::OK),
+        Some(StatusCode::BAD_GATEWAY),
+        Some(StatusCode::NOT_FOUND),
+        None,
+        Some(StatusCode::BAD_REQUEST),
+        Some(StatusCode::OK),
+        Some(StatusCode::TOO_MANY_REQUESTS),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        circuit.record_status(status, secs(i as u64 * 5));
+        assert_eq!(circuit.state, CircuitState::Closed);
+    }
+    circuit.record_status(Some(StatusCode::SERVICE_UNAVAILABLE), secs(40));
+    assert_eq!(circuit.state, CircuitState::Open(secs(70)));
+
+    // A bad request while half-open neither closes nor opens the circuit
+    assert_eq!(circuit.check(secs(70)), Ok(()));
+    circuit.record_status(Some(StatusCode::BAD_REQUEST), secs(71));
+    assert_eq!(circuit.state, CircuitState::HalfOpen);
+    assert_eq!(circuit.check(secs(72)), Ok(()));
+    circuit.record_status(Some(StatusCode::OK), secs(73));
+    assert_eq!(circuit.state, CircuitState::Closed);
+
+    // Old failures age out, so a single new one doesn't open the circuit again
+    circuit.record_status(Some(StatusCode::INTERNAL_SERVER_ERROR), secs(130));
+    assert_eq!(circuit.state, CircuitState::Closed);
+}
+
+#[test]
+fn iata_code_from_indian_city_test() {
+    assert!(INDIA_AIRPORT_CODES.len() >= 50);
+    let code = |city: &str| IataCode::from_indian_city(city).map(|code| code.to_string());
+    for (city, expected) in [
+        ("Ranchi", "IXR"),
+        ("Varanasi", "VNS"),
+        ("Jaipur", "JAI"),
+        ("Kochi", "COK"),
+        ("Mangaluru", "IXE"),
+        ("Madurai", "IXM"),
+        ("Chandigarh", "IXC"),
+        ("Leh", "IXL"),
+        ("Port Blair", "IXZ"),
+        ("Prayagraj", "IXD"),
+    ] {
+        assert_eq!(code(city).as_deref(), Some(expected), "{city}");
+    }
+    assert_eq!(code("  bangalore ").as_deref(), Some("BLR"));
+    assert_eq!(code("Trivandrum, Kerala").as_deref(), Some("TRV"));
+    assert_eq!(code("Goa").as_deref(), Some("GOI"));
+    assert_eq!(code("visakha").as_deref(), Some("VTZ"));
+    assert_eq!(code("Atlantis"), None);
+    assert_eq!(code(""), None);
+    // Too short for a prefix
+    assert_eq!(code("Pa"), None);
+    assert_eq!(code("del"), None);
+    assert_eq!(code("Leh").as_deref(), Some("IXL"));
+    assert_eq!(code("Chan").as_deref(), Some("IXC"));
+
+    let parsed: IataCode = serde_json::from_value(json!("Varanasi")).unwrap();
+    assert_eq!(parsed.to_string(), "VNS");
+    let parsed: IataCode = serde_json::from_value(json!("JFK")).unwrap();
+    assert_eq!(parsed.to_string(), "JFK");
+    let parsed: IataCode = serde_json::from_value(json!("Atlantis")).unwrap();
+    assert_eq!(parsed.to_string(), "Atlantis");
+    let parsed: IataCode = serde_json::from_value(json!("del")).unwrap();
+    assert_eq!(parsed.to_string(), "del");
+}
+
+#[test]
+fn is_domestic_india_test() {
+    let code = |code: &str| IataCode::new(code.to_string()).unwrap();
+    assert!(code("DEL").is_domestic_india());
+    assert!(code("BOM").is_domestic_india());
+    assert!(code("IXZ").is_domestic_india());
+    assert!(!code("JFK").is_domestic_india());
+    assert!(!code("DXB").is_domestic_india());
+    assert_eq!(indian_airport_city(" goi"), Some("Goa"));
+    assert_eq!(indian_airport_city("DXB"), None);
+}
+
+#[test]
+fn oauth_token_type_test() {
+    let token = |token_type: &str| -> OAuthTokenResponse {
+        serde_json::from_value(json!({
+            "type": "amadeusOAuth2Token",
+            "username": "dev@explorify.in",
+            "application_name": "explorify",
+            "client_id": "client",
+            "token_type": token_type,
+            "access_token": "AnP3xJ0ZBBjdfc7NXyTNtW5J9Jxy",
+            "expires_in": 1799,
+            "state": "approved",
+            "scope": ""
+        }))
+        .unwrap()
+    };
+    assert_eq!(
+        token("Bearer").validate().unwrap().access_token,
+        "AnP3xJ0ZBBjdfc7NXyTNtW5J9Jxy"
+    );
+    assert!(token("bearer").validate().is_ok());
+    assert!(matches!(
+        token("mac").validate(),
+        Err(TravelError::ApiKeyMissing(msg)) if msg == "Unexpected token type: mac"
+    ));
+}
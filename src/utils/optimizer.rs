@@ -0,0 +1,69 @@
+use crate::utils::Coordinates;
+
+/// Indices of `stops` in the order a greedy nearest neighbour route from `start` visits them,
+/// always going to the closest stop not visited yet. Ties go to the stop listed first. Not the
+/// shortest route, but close to it for the handful of sites seen on a trip.
+pub fn nearest_neighbor_order(start: Coordinates, stops: &[Coordinates]) -> Vec<usize> {
+    let mut unvisited: Vec<usize> = (0..stops.len()).collect();
+    let mut order = Vec::with_capacity(stops.len());
+    let mut current = start;
+    while let Some(closest) = (0..unvisited.len()).min_by(|&a, &b| {
+        current
+            .distance_km(&stops[unvisited[a]])
+            .total_cmp(&current.distance_km(&stops[unvisited[b]]))
+    }) {
+        let stop = unvisited.remove(closest);
+        current = stops[stop];
+        order.push(stop);
+    }
+    order
+}
+
+/// Straight line length of going from `start` through `stops` in order, without returning.
+pub fn route_distance_km(start: Coordinates, stops: &[Coordinates]) -> f64 {
+    std::iter::once(&start)
+        .chain(stops)
+        .zip(stops)
+        .map(|(from, to)| from.distance_km(to))
+        .sum()
+}
+
+#[test]
+fn nearest_neighbor_order_test() {
+    // Stops along the equator
+    let stop = |longitude| Coordinates::new(0.0, longitude);
+    let stops = [stop(3.0), stop(-1.0), stop(0.8), stop(6.0), stop(-2.5)];
+    assert_eq!(nearest_neighbor_order(stop(0.0), &stops), [2, 1, 4, 0, 3]);
+    // Equally far, so the first listed
+    assert_eq!(
+        nearest_neighbor_order(stop(0.0), &[stop(2.0), stop(-2.0)]),
+        [0, 1]
+    );
+    assert!(nearest_neighbor_order(stop(0.0), &[]).is_empty());
+
+    let degree_km = stop(0.0).distance_km(&stop(1.0));
+    let ordered: Vec<Coordinates> = [2, 1, 4, 0, 3].map(|i| stops[i]).to_vec();
+    let length = route_distance_km(stop(0.0), &ordered);
+    assert!((length - 12.6 * degree_km).abs() < 1e-6);
+    assert!(length < route_distance_km(stop(0.0), &stops));
+    assert_eq!(route_distance_km(stop(0.0), &[]), 0.0);
+}
+
+#[test]
+fn goa_sightseeing_order_test() {
+    let calangute = Coordinates::new(15.5439, 73.7553);
+    let sites = [
+        // Dudhsagar Falls, Basilica of Bom Jesus, Baga Beach, Fort Aguada, Chapora Fort, Anjuna
+        Coordinates::new(15.3144, 74.3143),
+        Coordinates::new(15.5009, 73.9116),
+        Coordinates::new(15.5553, 73.7517),
+        Coordinates::new(15.4925, 73.7737),
+        Coordinates::new(15.6062, 73.7364),
+        Coordinates::new(15.5733, 73.7407),
+    ];
+    // North Goa's beaches and forts first, then inland to Old Goa and the falls
+    assert_eq!(
+        nearest_neighbor_order(calangute, &sites),
+        [2, 5, 4, 3, 1, 0]
+    );
+}
@@ -0,0 +1,39 @@
+use crate::{api_requests::hotels::amadeus::Hotel, utils::Coordinates};
+
+/// Closest first. Hotels without a known location go last, in the order they were in.
+pub fn sort_hotels_by_distance_to(hotels: &mut [Hotel], target: Coordinates) {
+    let distance = |hotel: &Hotel| {
+        hotel
+            .coordinates()
+            .map_or(f64::INFINITY, |location| location.distance_km(&target))
+    };
+    hotels.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+}
+
+#[test]
+fn sort_hotels_by_distance_to_test() {
+    let hotel = |id: &str, latitude: Option<f64>, longitude: Option<f64>| -> Hotel {
+        serde_json::from_value(serde_json::json!({
+            "hotel_id": id,
+            "name": id,
+            "latitude": latitude,
+            "longitude": longitude,
+            "offers": [],
+            "distance_to_center_km": null,
+            "distance_to_airport_km": null
+        }))
+        .unwrap()
+    };
+    let mut hotels = vec![
+        hotel("PANJIM", Some(15.4989), Some(73.8278)),
+        hotel("UNKNOWN", None, None),
+        hotel("CALANGUTE", Some(15.5439), Some(73.7553)),
+        hotel("MARGAO", Some(15.2832), Some(73.9862)),
+        hotel("BAGA", Some(15.5553), Some(73.7517)),
+    ];
+    let baga_beach = Coordinates::new(15.5567, 73.7517);
+
+    sort_hotels_by_distance_to(&mut hotels, baga_beach);
+    let ids: Vec<&str> = hotels.iter().map(|hotel| hotel.hotel_id.as_str()).collect();
+    assert_eq!(ids, ["BAGA", "CALANGUTE", "PANJIM", "MARGAO", "UNKNOWN"]);
+}